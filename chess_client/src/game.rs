@@ -0,0 +1,363 @@
+use crate::types::{Move, PlayerColor};
+use nanoserde::{DeBin, SerBin};
+use std::fmt;
+
+/// Events emitted by a Game as it progresses, meant to be broadcast to players and spectators
+#[derive(Clone, Debug, PartialEq, Eq, DeBin, SerBin)]
+pub enum GameEvent {
+    MovePlayed { player: PlayerColor, move_: Move },
+    ClockUpdate { white_ms: u64, black_ms: u64 },
+    DrawOffered(PlayerColor),
+    GameEnded(GameResult),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DeBin, SerBin)]
+pub enum GameResult {
+    Won {
+        winner: PlayerColor,
+        reason: WinReason,
+    },
+    Drawn,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DeBin, SerBin)]
+pub enum WinReason {
+    Resignation,
+    Timeout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameError {
+    /// The game already has a result, so no further actions are accepted
+    GameOver,
+    /// The given player tried to move while it was the opponent's turn
+    NotYourTurn(PlayerColor),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "game is already over"),
+            Self::NotYourTurn(color) => write!(f, "{:?} tried to move out of turn", color),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// Receives every event emitted by a Game, in order
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+impl<F: FnMut(&GameEvent)> GameObserver for F {
+    fn on_event(&mut self, event: &GameEvent) {
+        self(event)
+    }
+}
+
+/// Chess clock with a per-move increment.
+///
+/// Time is passed in by the caller as milliseconds since any fixed point, since there is no
+/// monotonic clock available on every target the client is built for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clock {
+    remaining_ms: [u64; 2],
+    increment_ms: u64,
+    running: Option<(PlayerColor, u64)>,
+}
+
+impl Clock {
+    pub fn new(initial_ms: u64, increment_ms: u64) -> Self {
+        Self {
+            remaining_ms: [initial_ms; 2],
+            increment_ms,
+            running: None,
+        }
+    }
+
+    /// Time left for the given player as of now_ms, including the current running period
+    pub fn remaining_ms(&self, color: PlayerColor, now_ms: u64) -> u64 {
+        let remaining = self.remaining_ms[Self::index(color)];
+        match self.running {
+            Some((running, since_ms)) if running == color => {
+                remaining.saturating_sub(now_ms.saturating_sub(since_ms))
+            }
+            _ => remaining,
+        }
+    }
+
+    /// Returns the player whose clock is running and has run out of time
+    pub fn flagged(&self, now_ms: u64) -> Option<PlayerColor> {
+        let (running, _) = self.running?;
+        (self.remaining_ms(running, now_ms) == 0).then_some(running)
+    }
+
+    pub fn start(&mut self, color: PlayerColor, now_ms: u64) {
+        self.stop(now_ms);
+        self.running = Some((color, now_ms));
+    }
+
+    pub fn stop(&mut self, now_ms: u64) {
+        if let Some((running, _)) = self.running {
+            self.remaining_ms[Self::index(running)] = self.remaining_ms(running, now_ms);
+            self.running = None;
+        }
+    }
+
+    /// Ends the running player's turn: charges the elapsed time, adds the increment and starts
+    /// the opponent's clock
+    pub fn press(&mut self, now_ms: u64) {
+        if let Some((running, _)) = self.running {
+            self.stop(now_ms);
+            self.remaining_ms[Self::index(running)] += self.increment_ms;
+            self.running = Some((!running, now_ms));
+        }
+    }
+
+    fn index(color: PlayerColor) -> usize {
+        match color {
+            PlayerColor::White => 0,
+            PlayerColor::Black => 1,
+        }
+    }
+}
+
+/// A live game between two players. Every change is reported to the registered observers as a
+/// GameEvent, so a server only has to forward the events to connected clients.
+pub struct Game {
+    active_color: PlayerColor,
+    history: Vec<Move>,
+    clock: Clock,
+    draw_offer: Option<PlayerColor>,
+    result: Option<GameResult>,
+    observers: Vec<Box<dyn GameObserver + Send>>,
+}
+
+impl Game {
+    /// Creates a game with white to move and white's clock running from now_ms
+    pub fn new(mut clock: Clock, now_ms: u64) -> Self {
+        clock.start(PlayerColor::White, now_ms);
+        Self {
+            active_color: PlayerColor::White,
+            history: Vec::new(),
+            clock,
+            draw_offer: None,
+            result: None,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: impl GameObserver + Send + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn active_color(&self) -> PlayerColor {
+        self.active_color
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Records a move by the given player and passes the turn to the opponent. A move made after
+    /// the player's flag has fallen ends the game on time instead.
+    pub fn play_move(
+        &mut self,
+        player: PlayerColor,
+        move_: Move,
+        now_ms: u64,
+    ) -> Result<(), GameError> {
+        self.check_ongoing(now_ms)?;
+        if player != self.active_color {
+            return Err(GameError::NotYourTurn(player));
+        }
+        self.clock.press(now_ms);
+        self.history.push(move_);
+        self.active_color = !player;
+        if self.draw_offer == Some(!player) {
+            self.draw_offer = None;
+        }
+        self.emit(GameEvent::MovePlayed { player, move_ });
+        self.emit_clock(now_ms);
+        Ok(())
+    }
+
+    /// Offers a draw to the opponent, or accepts the opponent's pending offer
+    pub fn offer_draw(&mut self, player: PlayerColor, now_ms: u64) -> Result<(), GameError> {
+        self.check_ongoing(now_ms)?;
+        if self.draw_offer == Some(!player) {
+            self.end(GameResult::Drawn, now_ms);
+        } else if self.draw_offer.is_none() {
+            self.draw_offer = Some(player);
+            self.emit(GameEvent::DrawOffered(player));
+        }
+        Ok(())
+    }
+
+    pub fn resign(&mut self, player: PlayerColor, now_ms: u64) -> Result<(), GameError> {
+        self.check_ongoing(now_ms)?;
+        self.end(
+            GameResult::Won {
+                winner: !player,
+                reason: WinReason::Resignation,
+            },
+            now_ms,
+        );
+        Ok(())
+    }
+
+    /// Broadcasts the current clock times, ending the game if the running player's flag has
+    /// fallen. Meant to be called periodically by the owner of the game.
+    pub fn tick(&mut self, now_ms: u64) -> Result<(), GameError> {
+        self.check_ongoing(now_ms)?;
+        self.emit_clock(now_ms);
+        Ok(())
+    }
+
+    fn check_ongoing(&mut self, now_ms: u64) -> Result<(), GameError> {
+        if self.result.is_none() {
+            if let Some(flagged) = self.clock.flagged(now_ms) {
+                self.end(
+                    GameResult::Won {
+                        winner: !flagged,
+                        reason: WinReason::Timeout,
+                    },
+                    now_ms,
+                );
+            }
+        }
+        match self.result {
+            Some(_) => Err(GameError::GameOver),
+            None => Ok(()),
+        }
+    }
+
+    fn end(&mut self, result: GameResult, now_ms: u64) {
+        self.clock.stop(now_ms);
+        self.result = Some(result);
+        self.draw_offer = None;
+        self.emit_clock(now_ms);
+        self.emit(GameEvent::GameEnded(result));
+    }
+
+    fn emit_clock(&mut self, now_ms: u64) {
+        self.emit(GameEvent::ClockUpdate {
+            white_ms: self.clock.remaining_ms(PlayerColor::White, now_ms),
+            black_ms: self.clock.remaining_ms(PlayerColor::Black, now_ms),
+        });
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for observer in self.observers.iter_mut() {
+            observer.on_event(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Square;
+    use std::sync::{Arc, Mutex};
+
+    fn e2e4() -> Move {
+        Move {
+            from: Square { rank: 6, file: 4 },
+            to: Square { rank: 4, file: 4 },
+            promotion: None,
+        }
+    }
+
+    fn recorded_game(clock: Clock) -> (Game, Arc<Mutex<Vec<GameEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut game = Game::new(clock, 0);
+        let recorder = Arc::clone(&events);
+        game.add_observer(move |event: &GameEvent| recorder.lock().unwrap().push(event.clone()));
+        (game, events)
+    }
+
+    #[test]
+    fn test_clock_press_adds_increment() {
+        let mut clock = Clock::new(60_000, 2_000);
+        clock.start(PlayerColor::White, 0);
+        clock.press(5_000);
+        let output = (
+            clock.remaining_ms(PlayerColor::White, 8_000),
+            clock.remaining_ms(PlayerColor::Black, 8_000),
+        );
+        let expected = (57_000, 57_000);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_play_move_emits_move_and_clock() {
+        let (mut game, events) = recorded_game(Clock::new(60_000, 0));
+        game.play_move(PlayerColor::White, e2e4(), 1_500).unwrap();
+        let output = events.lock().unwrap().clone();
+        let expected = vec![
+            GameEvent::MovePlayed {
+                player: PlayerColor::White,
+                move_: e2e4(),
+            },
+            GameEvent::ClockUpdate {
+                white_ms: 58_500,
+                black_ms: 60_000,
+            },
+        ];
+        assert_eq!(output, expected);
+        assert_eq!(game.active_color(), PlayerColor::Black);
+    }
+
+    #[test]
+    fn test_play_move_out_of_turn() {
+        let (mut game, events) = recorded_game(Clock::new(60_000, 0));
+        let output = game.play_move(PlayerColor::Black, e2e4(), 0);
+        let expected = Err(GameError::NotYourTurn(PlayerColor::Black));
+        assert_eq!(output, expected);
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_draw_offer_accepted_by_opponent() {
+        let (mut game, events) = recorded_game(Clock::new(60_000, 0));
+        game.offer_draw(PlayerColor::White, 0).unwrap();
+        game.offer_draw(PlayerColor::Black, 0).unwrap();
+        let output = events.lock().unwrap().last().cloned();
+        let expected = Some(GameEvent::GameEnded(GameResult::Drawn));
+        assert_eq!(output, expected);
+        assert_eq!(game.result(), Some(GameResult::Drawn));
+    }
+
+    #[test]
+    fn test_tick_ends_game_on_timeout() {
+        let (mut game, events) = recorded_game(Clock::new(1_000, 0));
+        let output = game.tick(1_000);
+        let expected = Err(GameError::GameOver);
+        assert_eq!(output, expected);
+        let output = events.lock().unwrap().last().cloned();
+        let expected = Some(GameEvent::GameEnded(GameResult::Won {
+            winner: PlayerColor::Black,
+            reason: WinReason::Timeout,
+        }));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_event_bin_round_trip() {
+        let input = GameEvent::GameEnded(GameResult::Won {
+            winner: PlayerColor::White,
+            reason: WinReason::Resignation,
+        });
+        let output = GameEvent::deserialize_bin(&input.serialize_bin()).unwrap();
+        assert_eq!(output, input);
+    }
+}
@@ -28,6 +28,7 @@ impl MouseState {
                         gamestate.get_square(clicked_square).map(|_| Move {
                             from: clicked_square,
                             to: s,
+                            promotion: None,
                         })
                     });
                 gamestate.set_visibility(clicked_square, true);
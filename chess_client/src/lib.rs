@@ -1 +1,3 @@
+pub mod game;
+pub mod link;
 pub mod types;
@@ -0,0 +1,279 @@
+//! Game links: the moves of a game packed into a string that can go in a URL as is.
+//!
+//! A move is three characters of the URL safe base64 alphabet, `A` to `Z`, `a` to `z`, `0` to
+//! `9`, `-` and `_` standing for 0 to 63:
+//!
+//! 1. the from square, as rank * 8 + file of its Square, which counts ranks from the top of the
+//!    board as it's drawn for white, so a8 is 0, h8 is 7 and h1 is 63
+//! 2. the to square, the same way
+//! 3. the promotion: 0 for none, 1 to 4 for a knight, bishop, rook or queen
+//!
+//! e2e4 is `0kA` and e7e8=Q is `MEE`. The link has no header or padding, so its length is three
+//! times the number of moves, in the order they were played.
+
+use crate::types::{Move, PromotionPiece, Square};
+use std::fmt;
+
+/// URL safe base64 alphabet (RFC 4648 section 5)
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Number of base64 characters used by a single move
+const CHARS_PER_MOVE: usize = 3;
+
+/// Promotion pieces in the order of their link values. 0 means the move isn't a promotion
+const PROMOTION_PIECES: [PromotionPiece; 4] = [
+    PromotionPiece::Knight,
+    PromotionPiece::Bishop,
+    PromotionPiece::Rook,
+    PromotionPiece::Queen,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkEncodeError {
+    /// The square's rank or file is outside the board
+    SquareOutOfRange(Square),
+}
+
+impl fmt::Display for LinkEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SquareOutOfRange(square) => write!(
+                f,
+                "square {:?} should have rank and file in range 0..8",
+                square
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LinkEncodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkDecodeError {
+    /// Every move takes three characters, so a valid link's length is a multiple of three
+    InvalidLength(usize),
+    /// The character at the given index is not part of the URL safe base64 alphabet
+    InvalidChar(usize, char),
+    /// The character at the given index doesn't stand for a promotion piece
+    InvalidPromotion(usize, char),
+}
+
+impl fmt::Display for LinkDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(len) => write!(
+                f,
+                "game link has length {} but should have a multiple of {}",
+                len, CHARS_PER_MOVE
+            ),
+            Self::InvalidChar(index, c) => {
+                write!(f, "game link has invalid char {:?} at index {}", c, index)
+            }
+            Self::InvalidPromotion(index, c) => write!(
+                f,
+                "game link has invalid promotion {:?} at index {}",
+                c, index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LinkDecodeError {}
+
+/// Encodes a sequence of moves into a compact URL safe string.
+///
+/// Each move takes three base64 characters: the from square, the to square and the promotion
+/// piece. A square is stored as its 6 bit index (rank * 8 + file), and the promotion as 0 for none
+/// or 1 to 4 for a knight, bishop, rook or queen. No padding is needed, so the output can be
+/// embedded in a link as is.
+pub fn encode_moves(moves: &[Move]) -> Result<String, LinkEncodeError> {
+    let mut encoded = String::with_capacity(moves.len() * CHARS_PER_MOVE);
+    for move_ in moves {
+        encoded.push(encode_square(move_.from)?);
+        encoded.push(encode_square(move_.to)?);
+        encoded.push(encode_promotion(move_.promotion));
+    }
+    Ok(encoded)
+}
+
+/// Reconstructs the sequence of moves encoded by encode_moves
+pub fn decode_moves(link: &str) -> Result<Vec<Move>, LinkDecodeError> {
+    let chars: Vec<char> = link.chars().collect();
+    // usize::is_multiple_of is only stable since Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    if chars.len() % CHARS_PER_MOVE != 0 {
+        return Err(LinkDecodeError::InvalidLength(chars.len()));
+    }
+    let mut moves = Vec::with_capacity(chars.len() / CHARS_PER_MOVE);
+    for (i, chunk) in chars.chunks(CHARS_PER_MOVE).enumerate() {
+        let index = i * CHARS_PER_MOVE;
+        moves.push(Move {
+            from: decode_square(index, chunk[0])?,
+            to: decode_square(index + 1, chunk[1])?,
+            promotion: decode_promotion(index + 2, chunk[2])?,
+        });
+    }
+    Ok(moves)
+}
+
+fn encode_square(square: Square) -> Result<char, LinkEncodeError> {
+    if square.rank >= 8 || square.file >= 8 {
+        return Err(LinkEncodeError::SquareOutOfRange(square));
+    }
+    Ok(ALPHABET[(square.rank * 8 + square.file) as usize] as char)
+}
+
+fn encode_promotion(promotion: Option<PromotionPiece>) -> char {
+    let value = promotion.map_or(0, |piece| {
+        PROMOTION_PIECES
+            .iter()
+            .position(|&p| p == piece)
+            .expect("every promotion piece should be in PROMOTION_PIECES")
+            + 1
+    });
+    ALPHABET[value] as char
+}
+
+fn decode_value(index: usize, c: char) -> Result<usize, LinkDecodeError> {
+    ALPHABET
+        .iter()
+        .position(|&a| a as char == c)
+        .ok_or(LinkDecodeError::InvalidChar(index, c))
+}
+
+fn decode_square(index: usize, c: char) -> Result<Square, LinkDecodeError> {
+    let value = decode_value(index, c)? as u32;
+    Ok(Square {
+        rank: value / 8,
+        file: value % 8,
+    })
+}
+
+fn decode_promotion(index: usize, c: char) -> Result<Option<PromotionPiece>, LinkDecodeError> {
+    match decode_value(index, c)? {
+        0 => Ok(None),
+        value => PROMOTION_PIECES
+            .get(value - 1)
+            .copied()
+            .map(Some)
+            .ok_or(LinkDecodeError::InvalidPromotion(index, c)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(rank: u32, file: u32) -> Square {
+        Square { rank, file }
+    }
+
+    #[test]
+    fn test_encode_moves_empty() {
+        let output = encode_moves(&[]);
+        let expected = Ok(String::new());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_encode_moves_uses_three_chars_per_move() {
+        let input = [
+            Move {
+                from: square(6, 4),
+                to: square(4, 4),
+                promotion: None,
+            },
+            Move {
+                from: square(1, 4),
+                to: square(3, 4),
+                promotion: None,
+            },
+        ];
+        let output = encode_moves(&input);
+        let expected = Ok("0kAMcA".to_owned());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_encode_moves_format() {
+        let input = [
+            Move {
+                from: square(6, 4),
+                to: square(4, 4),
+                promotion: None,
+            },
+            Move {
+                from: square(1, 4),
+                to: square(0, 4),
+                promotion: Some(PromotionPiece::Queen),
+            },
+        ];
+        let output = encode_moves(&input);
+        let expected = Ok("0kAMEE".to_owned());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_encode_moves_square_out_of_range() {
+        let input = [Move {
+            from: square(6, 4),
+            to: square(8, 4),
+            promotion: None,
+        }];
+        let output = encode_moves(&input);
+        let expected = Err(LinkEncodeError::SquareOutOfRange(square(8, 4)));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_decode_moves_round_trip() {
+        let input: Vec<Move> = (0..64)
+            .map(|i| Move {
+                from: square(i / 8, i % 8),
+                to: square(7 - i / 8, 7 - i % 8),
+                promotion: None,
+            })
+            .collect();
+        let output = decode_moves(&encode_moves(&input).unwrap());
+        let expected = Ok(input);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_decode_moves_round_trip_promotions() {
+        let input: Vec<Move> = [None]
+            .into_iter()
+            .chain(PROMOTION_PIECES.map(Some))
+            .enumerate()
+            .map(|(file, promotion)| Move {
+                from: square(1, file as u32),
+                to: square(0, file as u32),
+                promotion,
+            })
+            .collect();
+        let output = decode_moves(&encode_moves(&input).unwrap());
+        let expected = Ok(input);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_decode_moves_invalid_length() {
+        let output = decode_moves("0kAM");
+        let expected = Err(LinkDecodeError::InvalidLength(4));
+        assert_eq!(output.map(|m| m.len()), expected);
+    }
+
+    #[test]
+    fn test_decode_moves_invalid_char() {
+        let output = decode_moves("0kA+cA");
+        let expected = Err(LinkDecodeError::InvalidChar(3, '+'));
+        assert_eq!(output.map(|m| m.len()), expected);
+    }
+
+    #[test]
+    fn test_decode_moves_invalid_promotion() {
+        let output = decode_moves("0kF");
+        let expected = Err(LinkDecodeError::InvalidPromotion(2, 'F'));
+        assert_eq!(output.map(|m| m.len()), expected);
+    }
+}
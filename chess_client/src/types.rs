@@ -23,10 +23,20 @@ pub enum PlayerColor {
     Black,
 }
 
-#[derive(Clone, Copy, Debug, DeBin, SerBin)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DeBin, SerBin)]
 pub struct Move {
     pub from: Square,
     pub to: Square,
+    pub promotion: Option<PromotionPiece>,
+}
+
+/// The piece a pawn turns into when it reaches the last rank
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DeBin, SerBin)]
+pub enum PromotionPiece {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, DeBin, SerBin)]
@@ -0,0 +1,46 @@
+//! Strength self-test: searches the bundled WAC positions and prints the share solved, along
+//! with the change since the last run at the same depth. Runs are logged to
+//! target/selftest_runs.log, relative to where it's started from.
+//!
+//! `cargo run --release -p chess_engine --example selftest [depth]`
+
+use std::path::Path;
+
+use chess_engine::{
+    search::Searcher,
+    selftest::{load_runs, run_suite, save_run, DEFAULT_SELFTEST_DEPTH, WAC},
+};
+
+const LOG: &str = "target/selftest_runs.log";
+
+fn main() {
+    let depth = match std::env::args().nth(1) {
+        Some(depth) => depth.parse().expect("depth should be a number of plies"),
+        None => DEFAULT_SELFTEST_DEPTH,
+    };
+    let run = run_suite(&WAC, depth, Searcher::new).expect("the bundled suite should parse");
+    for id in &run.failed {
+        println!("failed {}", id);
+    }
+    let summary = run.summary();
+    println!(
+        "WAC at depth {}: {}/{} solved ({:.1}%)",
+        depth,
+        summary.solved,
+        summary.total,
+        summary.percentage()
+    );
+
+    let log = Path::new(LOG);
+    let earlier = load_runs(log).unwrap_or_else(|err| {
+        eprintln!("can't read {}: {}", LOG, err);
+        Vec::new()
+    });
+    match summary.trend(&earlier) {
+        Some(trend) => println!("{:+.1} points since the last run", trend),
+        None => println!("no earlier run at depth {} to compare with", depth),
+    }
+    if let Err(err) = save_run(log, summary) {
+        eprintln!("can't log the run to {}: {}", LOG, err);
+    }
+}
@@ -0,0 +1,29 @@
+//! Lazy SMP scaling report: time to a fixed depth on 1, 2, 4 and 8 threads over the standard
+//! perft positions, with the speedup and efficiency of each thread count against one thread
+//!
+//! `cargo run --release -p chess_engine --example smp_scaling [depth]`
+
+use chess_engine::{perft::PERFT_SUITE, search::time_to_depth};
+
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+fn main() {
+    let depth = match std::env::args().nth(1) {
+        Some(depth) => depth.parse().expect("depth should be a number of plies"),
+        None => 10,
+    };
+    // the first six are the positions of the Chess Programming Wiki perft page
+    let fens: Vec<&str> = PERFT_SUITE.iter().take(6).map(|case| case.fen).collect();
+    println!("time to depth {} over {} positions", depth, fens.len());
+    println!("threads  time (ms)       nodes  speedup  efficiency");
+    for result in time_to_depth(&fens, depth, &THREAD_COUNTS) {
+        println!(
+            "{:>7}  {:>9}  {:>10}  {:>7.2}  {:>10.2}",
+            result.threads,
+            result.time.as_millis(),
+            result.nodes,
+            result.speedup,
+            result.efficiency
+        );
+    }
+}
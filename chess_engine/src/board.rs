@@ -1,5 +1,16 @@
 // TODO: when bitboard errors are removed, remove pub keyword
+pub mod attacks;
 pub mod bitboard;
+mod builder;
+mod castling;
+mod diff;
+mod display;
+mod draw;
+mod edit;
+mod make_move;
+mod position;
+mod see;
+mod status;
 use crate::{
     error::{BoardFENParseError, RankFENParseError},
     gamestate::NUM_BOARD_SQUARES,
@@ -7,11 +18,18 @@ use crate::{
     squares::{Square, Square64},
     util::{Color, File, Rank},
 };
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
 use bitboard::BitBoard;
-use std::{
-    collections::HashMap,
-    fmt::{self, write},
+pub use builder::PositionBuilder;
+pub use castling::STANDARD_CASTLING_ROOK_FILES;
+use core::fmt::{self, write};
+pub use diff::SquareChange;
+pub use display::{DisplayStyle, Glyphs, PositionDisplay};
+pub use make_move::Undo;
+pub use position::{
+    Position, FIFTY_MOVE_RULE_HALF_MOVES, SEVENTY_FIVE_MOVE_RULE_HALF_MOVES, START_FEN,
 };
+pub use status::{DrawReason, GameStatus};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
 
@@ -67,171 +85,202 @@ impl Board {
         BitBoard((self.pawns[0]).0 | (self.pawns[1]).0)
     }
 
+    /// Generates a Board instance corresponding to the board section of a FEN string, failing
+    /// with the first problem parse_fen finds
+    fn gen_board_from_fen(value: &str) -> Result<Self, BoardFENParseError> {
+        let (board, mut problems) = Self::parse_fen(value);
+        match problems.is_empty() {
+            true => Ok(board),
+            false => Err(problems.remove(0).1),
+        }
+    }
+
     // TODO: Look for bishops trapped behind non-enemy pawns (or behind any 3 pawns)
     // TODO: Check for non-jumpers in impossible positions
-    /// Generates a Board instance corresponding to the board section of a FEN string
-    fn gen_board_from_fen(value: &str) -> Result<Self, BoardFENParseError> {
+    /// Parses the board section of a FEN string and collects every problem found instead of
+    /// stopping at the first one. Each problem comes with the char index (relative to the start
+    /// of value) it was found at. Ranks are checked from rank 1 up, so problems are in that order.
+    /// The returned board is only meaningful if there are no problems.
+    pub(crate) fn parse_fen(value: &str) -> (Self, Vec<(usize, BoardFENParseError)>) {
         let mut board = Board::new();
-        let mut freq_counter: HashMap<char, usize> = HashMap::with_capacity(Piece::COUNT);
-        let ranks: Vec<&str> = value.split('/').collect();
-        // Check that we have the right number of ranks and for each valid rank update board accordingly
-        match ranks.len() {
-            Rank::COUNT => {
-                // NOTE: FEN is in reverse order compared with our internal board representation
-                // with regards to rank (chars within rank are in correct order)
-                for (rank, rank_str) in ranks.iter().rev().enumerate() {
-                    // does rank validation in separate function that will return Some(Piece)s or Nones in an array if valid
-                    // use ? to convert from RankFENParseError to BoardFENParseError automatically and throw Err if not Ok
-                    let rank_pieces = Self::gen_rank_from_fen(rank_str)?;
-                    // if the rank is valid update the board
-                    for (file, &piece) in rank_pieces.iter().enumerate() {
-                        // get square from file and rank and use it to update board's pieces array
-                        let square = Square::from_file_and_rank(
-                            File::try_from(file).expect("file should be in range 0..=7"),
-                            Rank::try_from(rank).expect("rank should be in range 0..=7"),
-                        );
-                        board.pieces[square as usize] = piece;
-
-                        // for each piece in rank we got back do other updates that can be done for any
-                        // piece type (piece_count, piece_list, big/major/minor_piece_count)
-                        if let Some(p) = piece {
-                            // update freq_counter
-                            freq_counter
-                                .entry(char::from(p))
-                                .and_modify(|count| *count += 1)
-                                .or_insert(1);
-
-                            // update piece_list
-                            let piece_type_index = p as usize; // outer/row (i) index for piece_list
-                                                               // inner/column (j) index for piece_list
-                            let piece_index = *freq_counter.get(&char::from(p)).expect(
-                                "value to be at least 1 since freq_counter was just updated",
-                            ) - 1;
-
-                            // check for max amount of piece type leq max allowed for that piece
-                            if piece_index >= p.get_max_num_allowed() as usize {
-                                return Err(BoardFENParseError::InvalidNumOfPiece(
-                                    value.to_owned(),
-                                    char::from(p),
-                                ));
-                            }
-
-                            board.piece_list[piece_type_index][piece_index] = Some(square);
+        let mut problems: Vec<(usize, BoardFENParseError)> = Vec::new();
+        let mut freq_counter: [usize; Piece::COUNT] = [0; Piece::COUNT];
+
+        // keep the char index (relative to the start of value) each rank starts at
+        let mut index = 0;
+        let ranks: Vec<(usize, &str)> = value
+            .split('/')
+            .map(|rank_str| {
+                let start = index;
+                // skip over the rank and its / delimiter
+                index += rank_str.chars().count() + 1;
+                (start, rank_str)
+            })
+            .collect();
+
+        // Check that we have the right number of ranks
+        if ranks.len() != Rank::COUNT {
+            problems.push((
+                0,
+                BoardFENParseError::WrongNumRanks(value.to_owned(), ranks.len()),
+            ));
+        }
 
-                            // update piece counts
-                            let color = p.get_color();
-                            let is_big = p.is_big();
-                            let is_major = p.is_major();
-                            let is_minor = p.is_minor();
+        // NOTE: FEN is in reverse order compared with our internal board representation
+        // with regards to rank (chars within rank are in correct order)
+        for (rank, &(index, rank_str)) in ranks.iter().rev().enumerate() {
+            // does rank validation in separate function that will return Some(Piece)s or Nones in an array
+            let mut rank_problems = Vec::new();
+            let rank_pieces = Self::scan_rank_fen(rank_str, &mut rank_problems);
+            problems.extend(
+                rank_problems
+                    .into_iter()
+                    .map(|(offset, error)| (index + offset, error.into())),
+            );
+            // ranks past the eighth have nowhere to go, but their pieces still count
+            let rank = Rank::try_from(rank).ok();
+
+            // for each piece in rank we got back do other updates that can be done for any
+            // piece type (piece_count, piece_list, big/major/minor_piece_count)
+            for (file, &(offset, p)) in rank_pieces
+                .iter()
+                .enumerate()
+                .filter_map(|(file, square)| square.as_ref().map(|square| (file, square)))
+            {
+                // update freq_counter
+                freq_counter[p as usize] += 1;
+
+                // inner/column (j) index for piece_list
+                // value is at least 1 since freq_counter was just updated
+                let piece_index = freq_counter[p as usize] - 1;
+
+                // check for max amount of piece type leq max allowed for that piece, only
+                // reporting the first piece over the limit
+                if piece_index >= p.get_max_num_allowed() as usize {
+                    if piece_index == p.get_max_num_allowed() as usize {
+                        problems.push((
+                            index + offset,
+                            BoardFENParseError::InvalidNumOfPiece(value.to_owned(), char::from(p)),
+                        ));
+                    }
+                    continue;
+                }
 
-                            board.piece_count[p as usize] += 1;
-                            if is_big {
-                                board.big_piece_count[color as usize] += 1;
-                            }
-                            if is_major {
-                                board.major_piece_count[color as usize] += 1;
-                            }
-                            if is_minor {
-                                board.minor_piece_count[color as usize] += 1;
-                            }
+                let Some(rank) = rank else {
+                    continue;
+                };
+                // get square from file and rank and use it to update board's pieces array
+                let square = Square::from_file_and_rank(
+                    File::try_from(file).expect("file should be in range 0..=7"),
+                    rank,
+                );
+                board.pieces[square as usize] = Some(p);
+
+                // update piece_list
+                let piece_type_index = p as usize; // outer/row (i) index for piece_list
+                board.piece_list[piece_type_index][piece_index] = Some(square);
+
+                // update piece counts
+                let color = p.color();
+                let is_big = p.is_big();
+                let is_major = p.is_major();
+                let is_minor = p.is_minor();
+
+                board.piece_count[p as usize] += 1;
+                if is_big {
+                    board.big_piece_count[color as usize] += 1;
+                }
+                if is_major {
+                    board.major_piece_count[color as usize] += 1;
+                }
+                if is_minor {
+                    board.minor_piece_count[color as usize] += 1;
+                }
 
-                            // TODO: check that there aren't any pawns in first or last rank
-                            // update fields of board that are dependent on the piece type
-                            // (pawns, kings_square)
-                            // TODO: check that there aren't more promoted pieces than missing
-                            // pawns
-                            // TODO: it's impossible to have more than 6 pawns in a single file
-                            // TODO: Check minimum number of enemy missing pieces doesn't contradict number of pawns in a single file
-                            // TODO: if there are white pawns in A2 and A3 there can't be one in B2... Generalize
-                            match p {
-                                Piece::WhitePawn => board.pawns[p.get_color() as usize]
-                                    .set_bit(Square64::from(square)),
-                                Piece::BlackPawn => board.pawns[p.get_color() as usize]
-                                    .set_bit(Square64::from(square)),
-                                Piece::WhiteKing => {
-                                    board.kings_square[p.get_color() as usize] = Some(square)
-                                }
-                                Piece::BlackKing => {
-                                    board.kings_square[p.get_color() as usize] = Some(square)
-                                }
-                                _ => (),
-                            }
-                        }
+                // TODO: check that there aren't any pawns in first or last rank
+                // update fields of board that are dependent on the piece type
+                // (pawns, kings_square)
+                // TODO: check that there aren't more promoted pieces than missing
+                // pawns
+                // TODO: it's impossible to have more than 6 pawns in a single file
+                // TODO: Check minimum number of enemy missing pieces doesn't contradict number of pawns in a single file
+                // TODO: if there are white pawns in A2 and A3 there can't be one in B2... Generalize
+                match p {
+                    Piece::WhitePawn => {
+                        board.pawns[p.color() as usize].set_bit(square.to_square64())
+                    }
+                    Piece::BlackPawn => {
+                        board.pawns[p.color() as usize].set_bit(square.to_square64())
                     }
+                    Piece::WhiteKing => board.kings_square[p.color() as usize] = Some(square),
+                    Piece::BlackKing => board.kings_square[p.color() as usize] = Some(square),
+                    _ => (),
                 }
             }
-            _ => {
-                return Err(BoardFENParseError::WrongNumRanks(
-                    value.to_owned(),
-                    ranks.len(),
-                ))
-            }
         }
+
         // TODO: Check that kings are separated by at least 1 square
         // check freq counter for kings
-        if freq_counter.get(&'k') != Some(&1) || freq_counter.get(&'K') != Some(&1) {
-            return Err(BoardFENParseError::InvalidKingNum(value.to_owned()));
+        if freq_counter[Piece::BlackKing as usize] != 1
+            || freq_counter[Piece::WhiteKing as usize] != 1
+        {
+            problems.push((0, BoardFENParseError::InvalidKingNum(value.to_owned())));
         }
 
         // TODO: Check that non-active player is not in check
         // TODO: Check that active color is checked less than 3 times (if 2 can't be by (pawn + (pawn || bishop || knight) || (bishop + bishop) || (knight + knight)))
-        Ok(board)
+        (board, problems)
     }
 
     /// Takes a &str that corresponds to a portion of a FEN string for a specific Rank (e.g. rnbqkbnr)
-    /// and generates a corresponding Option<Piece> array
+    /// and generates a corresponding Option<Piece> array, failing with the first problem found
     fn gen_rank_from_fen(
         fen_rank: &str,
     ) -> Result<[Option<Piece>; File::COUNT], RankFENParseError> {
-        // TODO: get rid of is_empty use pattern match if let instead
+        let mut problems = Vec::new();
+        let rank = Self::scan_rank_fen(fen_rank, &mut problems);
+        match problems.is_empty() {
+            true => Ok(rank.map(|square| square.map(|(_, piece)| piece))),
+            false => Err(problems.remove(0).1),
+        }
+    }
+
+    /// Scans a portion of a FEN string for a specific Rank and pushes every problem found onto
+    /// problems, along with the char index (relative to the start of fen_rank) it was found at.
+    /// Each occupied square holds its piece and the char index it came from.
+    fn scan_rank_fen(
+        fen_rank: &str,
+        problems: &mut Vec<(usize, RankFENParseError)>,
+    ) -> [Option<(usize, Piece)>; File::COUNT] {
+        let mut rank: [Option<(usize, Piece)>; File::COUNT] = [None; File::COUNT];
         if fen_rank.is_empty() {
-            return Err(RankFENParseError::Empty);
+            problems.push((0, RankFENParseError::Empty));
+            return rank;
         }
 
-        let mut rank: [Option<Piece>; File::COUNT] = [None; File::COUNT];
-        let mut square_counter: u8 = 0;
+        let mut square_counter: usize = 0;
         let mut is_last_char_digit: bool = false;
+        let mut too_many_squares = false;
         // NOTE: Rank order is reversed in FEN but not char order within rank
-        for char in fen_rank.chars() {
+        for (index, char) in fen_rank.chars().enumerate() {
             match char.to_digit(10) {
                 Some(digit) => {
                     // check if there two digits in row to catch an invalid string like
                     // "ppp12pp"
-                    match is_last_char_digit {
-                        true => {
-                            return Err(RankFENParseError::TwoConsecutiveDigits(
-                                fen_rank.to_owned(),
-                            ))
-                        }
-                        false => {
-                            is_last_char_digit = true;
-                        }
+                    if is_last_char_digit {
+                        problems.push((
+                            index,
+                            RankFENParseError::TwoConsecutiveDigits(fen_rank.to_owned()),
+                        ));
                     }
-                    // if the char is a digit in the range (1..=8) we need to check
-                    // that it's not pushing us past our 8 square limit
-                    match digit {
-                        d if (1..=File::COUNT).contains(&(digit as usize)) => {
-                            // push Nones if there is space in rank array
-                            match (square_counter + (d as u8) - 1 < File::COUNT as u8) {
-                                true => {
-                                    for i in square_counter..(square_counter + (d as u8)) {
-                                        rank[i as usize] = None;
-                                    }
-                                }
-                                false => {
-                                    return Err(RankFENParseError::InvalidNumSquares(
-                                        fen_rank.to_owned(),
-                                    ))
-                                }
-                            }
-                            square_counter += d as u8;
-                        }
-                        _ => {
-                            return Err(RankFENParseError::InvalidDigit(
-                                fen_rank.to_owned(),
-                                digit as usize,
-                            ))
-                        }
+                    is_last_char_digit = true;
+                    // a digit has to be in the range (1..=8), the squares it skips stay None
+                    match (1..=File::COUNT).contains(&(digit as usize)) {
+                        true => square_counter += digit as usize,
+                        false => problems.push((
+                            index,
+                            RankFENParseError::InvalidDigit(fen_rank.to_owned(), digit as usize),
+                        )),
                     }
                 }
                 // Not a digit so we need to check if char represents a valid piece
@@ -241,32 +290,36 @@ impl Board {
 
                     match Piece::try_from(char) {
                         Ok(piece) => {
-                            // push Some(piece) onto rank if there is space
-                            match square_counter {
-                                sq_count if sq_count < File::COUNT as u8 => {
-                                    rank[sq_count as usize] = Some(piece)
-                                }
-                                _ => {
-                                    return Err(RankFENParseError::InvalidNumSquares(
-                                        fen_rank.to_owned(),
-                                    ))
-                                }
+                            // put Some(piece) in rank if there is space
+                            if let Some(square) = rank.get_mut(square_counter) {
+                                *square = Some((index, piece));
                             }
-
                             square_counter += 1;
                         }
-                        Err(_) => {
-                            return Err(RankFENParseError::InvalidChar(fen_rank.to_owned(), char))
-                        }
+                        Err(_) => problems.push((
+                            index,
+                            RankFENParseError::InvalidChar(fen_rank.to_owned(), char),
+                        )),
                     }
                 }
             }
+            // only report the char that first takes the rank past 8 squares
+            if square_counter > File::COUNT && !too_many_squares {
+                too_many_squares = true;
+                problems.push((
+                    index,
+                    RankFENParseError::InvalidNumSquares(fen_rank.to_owned()),
+                ));
+            }
         }
-        // check the square_counter is exactly 8
-        match square_counter {
-            8 => Ok(rank),
-            _ => Err(RankFENParseError::InvalidNumSquares(fen_rank.to_owned())),
+        // too few squares gets reported at the / (or the end) that closes the rank
+        if square_counter < File::COUNT {
+            problems.push((
+                fen_rank.chars().count(),
+                RankFENParseError::InvalidNumSquares(fen_rank.to_owned()),
+            ));
         }
+        rank
     }
 
     /// Returns FEN based on board position
@@ -312,9 +365,9 @@ impl Board {
 // TODO: use shorter version of rank and file names
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Reverse ranks so the board is presented like a chess board typically is
-        // with white at the bottom
-        for rank in Rank::iter().rev() {
+        // Start from black's back rank so the board is presented like a chess board
+        // typically is with white at the bottom
+        for rank in Rank::iter_from(Color::Black) {
             for file in File::iter() {
                 let square = Square::from_file_and_rank(file, rank);
                 let piece = self.pieces[square as usize];
@@ -0,0 +1,244 @@
+//! Attack sets for every piece type, as BitBoards of the squares a piece attacks.
+//!
+//! Knights, kings and pawns always attack the same squares from a given square, so their attacks
+//! are looked up in tables built at compile time. Sliding pieces depend on the occupancy, so their
+//! rays are walked until they hit the first blocker.
+// TODO: replace ray walking with magic bitboards once they're generated
+
+use super::bitboard::BitBoard;
+use crate::{squares::Square64, util::Color};
+use strum::EnumCount;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const WHITE_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, 1), (1, 1)];
+const BLACK_PAWN_OFFSETS: [(i8, i8); 2] = [(-1, -1), (1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+const KNIGHT_ATTACKS: [u64; Square64::COUNT] = leaper_attacks(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [u64; Square64::COUNT] = leaper_attacks(&KING_OFFSETS);
+/// Indexed by `color as usize` then by square
+const PAWN_ATTACKS: [[u64; Square64::COUNT]; Color::COUNT] = [
+    leaper_attacks(&WHITE_PAWN_OFFSETS),
+    leaper_attacks(&BLACK_PAWN_OFFSETS),
+];
+
+/// Bit of the square reached by moving (file, rank) steps from index, or 0 off the board
+const fn offset_bit(index: usize, (file_offset, rank_offset): (i8, i8)) -> u64 {
+    let file = (index % 8) as i8 + file_offset;
+    let rank = (index / 8) as i8 + rank_offset;
+    if file < 0 || file > 7 || rank < 0 || rank > 7 {
+        return 0;
+    }
+    1 << (rank * 8 + file)
+}
+
+const fn leaper_attacks(offsets: &[(i8, i8)]) -> [u64; Square64::COUNT] {
+    let mut attacks = [0u64; Square64::COUNT];
+    let mut index = 0;
+    while index < Square64::COUNT {
+        let mut i = 0;
+        while i < offsets.len() {
+            attacks[index] |= offset_bit(index, offsets[i]);
+            i += 1;
+        }
+        index += 1;
+    }
+    attacks
+}
+
+/// Squares along each direction up to and including the first occupied square
+fn slider_attacks(square: Square64, occupancy: BitBoard, directions: &[(i8, i8); 4]) -> BitBoard {
+    let mut attacks = 0;
+    for &direction in directions {
+        let mut index = square as usize;
+        loop {
+            let bit = offset_bit(index, direction);
+            if bit == 0 {
+                break;
+            }
+            attacks |= bit;
+            if occupancy.0 & bit != 0 {
+                break;
+            }
+            index = bit.trailing_zeros() as usize;
+        }
+    }
+    BitBoard(attacks)
+}
+
+/// Squares strictly between a and b if they share a rank, file or diagonal, otherwise empty
+pub fn between(a: Square64, b: Square64) -> BitBoard {
+    if a == b || !aligned(a, b) {
+        return BitBoard(0);
+    }
+    let (a, b) = (a as i8, b as i8);
+    let file_step = (b % 8 - a % 8).signum();
+    let rank_step = (b / 8 - a / 8).signum();
+    let mut squares = 0;
+    let mut index = a as usize;
+    loop {
+        let bit = offset_bit(index, (file_step, rank_step));
+        if bit.trailing_zeros() as i8 == b {
+            break;
+        }
+        squares |= bit;
+        index = bit.trailing_zeros() as usize;
+    }
+    BitBoard(squares)
+}
+
+/// Whole rank, file or diagonal through a and b from edge to edge, or empty if they don't share
+/// one
+pub fn line(a: Square64, b: Square64) -> BitBoard {
+    if a == b || !aligned(a, b) {
+        return BitBoard(0);
+    }
+    let (ai, bi) = (a as i8, b as i8);
+    let step = ((bi % 8 - ai % 8).signum(), (bi / 8 - ai / 8).signum());
+    let mut squares = 1 << a as u8;
+    for direction in [step, (-step.0, -step.1)] {
+        let mut index = a as usize;
+        loop {
+            let bit = offset_bit(index, direction);
+            if bit == 0 {
+                break;
+            }
+            squares |= bit;
+            index = bit.trailing_zeros() as usize;
+        }
+    }
+    BitBoard(squares)
+}
+
+/// Returns true if a and b share a rank, file or diagonal
+fn aligned(a: Square64, b: Square64) -> bool {
+    let (a, b) = (a as i8, b as i8);
+    a % 8 == b % 8 || a / 8 == b / 8 || (b % 8 - a % 8).abs() == (b / 8 - a / 8).abs()
+}
+
+pub fn knight_attacks(square: Square64) -> BitBoard {
+    BitBoard(KNIGHT_ATTACKS[square as usize])
+}
+
+pub fn king_attacks(square: Square64) -> BitBoard {
+    BitBoard(KING_ATTACKS[square as usize])
+}
+
+/// Squares a pawn of the given color attacks diagonally, which doesn't include its pushes
+pub fn pawn_attacks(color: Color, square: Square64) -> BitBoard {
+    BitBoard(PAWN_ATTACKS[color as usize][square as usize])
+}
+
+pub fn rook_attacks(square: Square64, occupancy: BitBoard) -> BitBoard {
+    slider_attacks(square, occupancy, &ROOK_DIRECTIONS)
+}
+
+pub fn bishop_attacks(square: Square64, occupancy: BitBoard) -> BitBoard {
+    slider_attacks(square, occupancy, &BISHOP_DIRECTIONS)
+}
+
+pub fn queen_attacks(square: Square64, occupancy: BitBoard) -> BitBoard {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knight_attacks_corner() {
+        let output = knight_attacks(Square64::A1);
+        let expected = BitBoard(1 << 10 | 1 << 17);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_king_attacks_edge() {
+        let output = king_attacks(Square64::E1);
+        let expected = BitBoard(1 << 3 | 1 << 5 | 1 << 11 | 1 << 12 | 1 << 13);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pawn_attacks() {
+        let output = (
+            pawn_attacks(Color::White, Square64::A2),
+            pawn_attacks(Color::Black, Square64::E7),
+        );
+        let expected = (BitBoard(1 << 17), BitBoard(1 << 43 | 1 << 45));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rook_attacks_stop_at_blockers() {
+        // blockers on D6 and F4, the rook is on D4
+        let input = BitBoard(1 << 43 | 1 << 29);
+        let output = rook_attacks(Square64::D4, input);
+        let expected = BitBoard(
+            1 << 35 | 1 << 43 // up to D6
+            | 1 << 28 | 1 << 29 // right to F4
+            | 1 << 19 | 1 << 11 | 1 << 3 // down to D1
+            | 1 << 26 | 1 << 25 | 1 << 24, // left to A4
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_bishop_attacks_empty_board() {
+        let output = bishop_attacks(Square64::A1, BitBoard(0));
+        let expected = BitBoard(0x8040_2010_0804_0200);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_line() {
+        let output = [
+            line(Square64::C1, Square64::E3),
+            line(Square64::D4, Square64::D2),
+            line(Square64::B1, Square64::C3),
+        ];
+        let expected = [
+            BitBoard(1 << 2 | 1 << 11 | 1 << 20 | 1 << 29 | 1 << 38 | 1 << 47),
+            BitBoard(0x0808_0808_0808_0808),
+            BitBoard(0),
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_between() {
+        let output = [
+            between(Square64::A1, Square64::D4),
+            between(Square64::E8, Square64::E5),
+            between(Square64::B1, Square64::C3),
+            between(Square64::G1, Square64::H1),
+        ];
+        let expected = [
+            BitBoard(1 << 9 | 1 << 18),
+            BitBoard(1 << 52 | 1 << 44),
+            BitBoard(0),
+            BitBoard(0),
+        ];
+        assert_eq!(output, expected);
+    }
+}
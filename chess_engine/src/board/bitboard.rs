@@ -1,9 +1,12 @@
 use crate::{
     error::ChessError as Error,
     squares::{Square, Square64},
-    util::{File, Rank, SQUARE_120_TO_64, SQUARE_64_TO_120},
+    util::{File, Rank},
+};
+use core::{
+    fmt,
+    ops::{BitAnd, BitOr},
 };
-use std::{fmt, ops::BitAnd};
 
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
@@ -52,7 +55,7 @@ impl BitBoard {
     /// Sets the first set LSB to 0 and returns the index corresponding to it
     // NOTE: this is slow in comparison to magic bitboard implementation which
     // has a very real effect on performance of move generation and thus on bot ability
-    fn pop_bit(&mut self) -> Option<Square64> {
+    pub fn pop_bit(&mut self) -> Option<Square64> {
         let lsb_index = self.0.trailing_zeros();
         match lsb_index {
             // all zeros
@@ -89,7 +92,7 @@ impl BitBoard {
     }
 
     /// Sets bit at index to 0
-    fn unset_bit(&mut self, index: Square64) {
+    pub fn unset_bit(&mut self, index: Square64) {
         // XOR will toggle value at index so we should only call it
         // if the bit at index was already set
         if self.check_bit(index) {
@@ -110,13 +113,21 @@ impl From<BitBoard> for u64 {
     }
 }
 
-// impl BitAnd for BitBoard {
-//     type Output = Self;
+impl BitAnd for BitBoard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
 
-//     fn bitand(self, rhs: Self) -> Self::Output {
-//         Self(self.0 & rhs.0)
-//     }
-// }
+impl BitOr for BitBoard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
 
 impl fmt::Display for BitBoard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -0,0 +1,174 @@
+use super::Position;
+use crate::{
+    castle_perms::CastlingRights, error::PositionValidationError, pieces::Piece, squares::Square64,
+    util::Color,
+};
+use strum::{EnumCount, IntoEnumIterator};
+
+/// Builds a Position piece by piece instead of from a FEN, e.g.
+///
+/// ```
+/// # use chess_engine::{board::PositionBuilder, pieces::Piece, squares::Square64};
+/// let position = PositionBuilder::new()
+///     .piece(Square64::E1, Piece::WhiteKing)
+///     .piece(Square64::E8, Piece::BlackKing)
+///     .piece(Square64::D2, Piece::WhitePawn)
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Starts from an empty board with white to move, no castling rights and fullmove number 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionBuilder {
+    pieces: [Option<Piece>; Square64::COUNT],
+    side_to_move: Color,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square64>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PositionBuilder {
+    pub fn new() -> Self {
+        Self {
+            pieces: [None; Square64::COUNT],
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::empty(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    /// Puts piece on square, replacing whatever was there before
+    pub fn piece(mut self, square: Square64, piece: Piece) -> Self {
+        self.pieces[square as usize] = Some(piece);
+        self
+    }
+
+    /// Empties square
+    pub fn clear(mut self, square: Square64) -> Self {
+        self.pieces[square as usize] = None;
+        self
+    }
+
+    pub fn side_to_move(mut self, color: Color) -> Self {
+        self.side_to_move = color;
+        self
+    }
+
+    pub fn castling_rights(mut self, castling_rights: CastlingRights) -> Self {
+        self.castling_rights = castling_rights;
+        self
+    }
+
+    pub fn en_passant(mut self, square: Option<Square64>) -> Self {
+        self.en_passant = square;
+        self
+    }
+
+    pub fn halfmove_clock(mut self, halfmove_clock: u32) -> Self {
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    pub fn fullmove_number(mut self, fullmove_number: u32) -> Self {
+        self.fullmove_number = fullmove_number;
+        self
+    }
+
+    /// Builds the position, failing if it doesn't pass Position::validate
+    pub fn build(&self) -> Result<Position, PositionValidationError> {
+        let mut position = Position::new();
+        for square in Square64::iter() {
+            if let Some(piece) = self.pieces[square as usize] {
+                position.add_piece(piece, square);
+            }
+        }
+        position.side_to_move = self.side_to_move;
+        position.castling_rights = self.castling_rights;
+        position.en_passant = self.en_passant;
+        position.halfmove_clock = self.halfmove_clock;
+        position.fullmove_number = self.fullmove_number;
+        position.key = position.compute_key();
+        position.update_check_info();
+        position.validate()?;
+        if !position.has_en_passant_capture() {
+            position.en_passant = None;
+            position.key = position.compute_key();
+        }
+        Ok(position)
+    }
+}
+
+/// Starts a builder from an existing position, e.g. to tweak a position parsed from a FEN
+impl From<&Position> for PositionBuilder {
+    fn from(position: &Position) -> Self {
+        let mut builder = Self::new()
+            .side_to_move(position.side_to_move)
+            .castling_rights(position.castling_rights)
+            .en_passant(position.en_passant)
+            .halfmove_clock(position.halfmove_clock)
+            .fullmove_number(position.fullmove_number);
+        for square in Square64::iter() {
+            builder.pieces[square as usize] = position.piece_on(square);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+
+    #[test]
+    fn test_position_builder_matches_fen() {
+        let input = PositionBuilder::new()
+            .piece(Square64::E1, Piece::WhiteKing)
+            .piece(Square64::H1, Piece::WhiteRook)
+            .piece(Square64::E8, Piece::BlackKing)
+            .side_to_move(Color::Black)
+            .castling_rights(CastlingRights::WHITE_KING)
+            .halfmove_clock(7)
+            .fullmove_number(40);
+        let output = input.build();
+        let expected = Position::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 7 40");
+        assert_eq!(output.map_err(Into::into), expected);
+    }
+
+    #[test]
+    fn test_position_builder_validates() {
+        let input = PositionBuilder::new()
+            .piece(Square64::E1, Piece::WhiteKing)
+            .piece(Square64::E8, Piece::BlackKing)
+            .piece(Square64::E2, Piece::BlackQueen);
+        let output = input.build().map(|p| p.to_fen());
+        let expected = Ok("4k3/8/8/8/8/8/4q3/4K3 w - - 0 1".into());
+        assert_eq!(output, expected);
+
+        let output = input.side_to_move(Color::Black).build();
+        let expected = Err(PositionValidationError::OpponentInCheck(Color::White));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_position_builder_from_position_round_trip() {
+        let input = Position::start();
+        let output = PositionBuilder::from(&input)
+            .clear(Square64::E2)
+            .piece(Square64::E4, Piece::WhitePawn)
+            .build()
+            .unwrap()
+            .to_fen();
+        let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(output, expected);
+        assert_eq!(PositionBuilder::from(&input).build(), Ok(input));
+    }
+}
@@ -0,0 +1,291 @@
+//! Castling fields of Chess960 FENs.
+//!
+//! Standard FEN can't say which rook a castling right belongs to once the rooks don't start on
+//! the A and H files, so Position keeps the file of each castling rook and understands the two
+//! common extensions: Shredder-FEN, which always writes the rook file (e.g. HAha), and X-FEN, which
+//! keeps KQkq for the outermost rooks and only writes the file when another rook is meant.
+
+use super::Position;
+use crate::{
+    castle_perms::CastlingRights,
+    pieces::{Piece, PieceType},
+    squares::Square64,
+    util::{Color, File, Rank},
+};
+use alloc::string::String;
+use strum::IntoEnumIterator;
+
+/// Every castling right with its color, in the order Position::castling_rook_files is indexed by
+pub(crate) const CASTLING_RIGHTS: [(CastlingRights, Color); 4] = [
+    (CastlingRights::WHITE_KING, Color::White),
+    (CastlingRights::WHITE_QUEEN, Color::White),
+    (CastlingRights::BLACK_KING, Color::Black),
+    (CastlingRights::BLACK_QUEEN, Color::Black),
+];
+
+/// Rook files of standard chess, indexed like CASTLING_RIGHTS
+pub const STANDARD_CASTLING_ROOK_FILES: [File; 4] =
+    [File::FileH, File::FileA, File::FileH, File::FileA];
+
+fn is_king_side(index: usize) -> bool {
+    matches!(index, 0 | 2)
+}
+
+fn castling_index(color: Color, king_side: bool) -> usize {
+    2 * color as usize + usize::from(!king_side)
+}
+
+/// Squares the king and rook of a castle move between. The king always ends on the G or C
+/// file and the rook next to it on the F or D file, wherever they started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Castle {
+    pub(crate) king_from: Square64,
+    pub(crate) king_to: Square64,
+    pub(crate) rook_from: Square64,
+    pub(crate) rook_to: Square64,
+}
+
+impl Position {
+    /// File of the rook the given castling right castles with
+    pub fn castling_rook_file(&self, right: CastlingRights) -> Option<File> {
+        CASTLING_RIGHTS
+            .iter()
+            .position(|&(other, _)| other == right)
+            .map(|index| self.castling_rook_files[index])
+    }
+
+    /// Sets castling rights and rook files from a FEN castling field in standard, X-FEN or
+    /// Shredder-FEN notation. Returns None if the field is malformed or names a rook file on the
+    /// same file as the king.
+    pub(crate) fn apply_castling_field(&mut self, field: &str) -> Option<()> {
+        let mut rights = CastlingRights::empty();
+        let mut rook_files = STANDARD_CASTLING_ROOK_FILES;
+        if field != "-" {
+            for c in field.chars() {
+                let color = match c.is_ascii_uppercase() {
+                    true => Color::White,
+                    false => Color::Black,
+                };
+                let king_file = self.back_rank_king_file(color);
+                let (index, rook_file) = match c.to_ascii_lowercase() {
+                    'k' => (0, self.outermost_rook_file(color, 0, king_file)),
+                    'q' => (1, self.outermost_rook_file(color, 1, king_file)),
+                    file @ 'a'..='h' => {
+                        let file = File::try_from((file as u8 - b'a') as usize).ok()?;
+                        match king_file? {
+                            king_file if file as usize > king_file as usize => (0, file),
+                            king_file if (file as usize) < king_file as usize => (1, file),
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                };
+                let index = index + 2 * (color as usize);
+                let (right, _) = CASTLING_RIGHTS[index];
+                if rights.contains(right) {
+                    return None;
+                }
+                rights.insert(right);
+                rook_files[index] = rook_file;
+            }
+        }
+        self.castling_rights = rights;
+        self.castling_rook_files = rook_files;
+        Some(())
+    }
+
+    /// Castling field in X-FEN, which is plain KQkq for standard chess
+    pub(crate) fn x_fen_castling_field(&self) -> String {
+        self.castling_field(|position, index, color, rook_file| {
+            let king_file = position.back_rank_king_file(color);
+            position.outermost_rook_file(color, index % 2, king_file) != rook_file
+        })
+    }
+
+    /// Castling field in Shredder-FEN, where every right is written as its rook's file
+    pub(crate) fn shredder_castling_field(&self) -> String {
+        self.castling_field(|_, _, _, _| true)
+    }
+
+    fn castling_field(&self, use_file: impl Fn(&Self, usize, Color, File) -> bool) -> String {
+        let mut field = String::new();
+        for (index, &(right, color)) in CASTLING_RIGHTS.iter().enumerate() {
+            if !self.castling_rights.contains(right) {
+                continue;
+            }
+            let rook_file = self.castling_rook_files[index];
+            let c = match (use_file(self, index, color, rook_file), is_king_side(index)) {
+                (true, _) => char::from(rook_file),
+                (false, true) => 'K',
+                (false, false) => 'Q',
+            };
+            field.push(match color {
+                Color::White => c,
+                Color::Black => c.to_ascii_lowercase(),
+            });
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// Castle of the given color and side if it still has the right to it, with the king and rook
+    /// where they have to be. Whether the squares in between are empty and safe is left to
+    /// move generation.
+    pub(crate) fn castle(&self, color: Color, king_side: bool) -> Option<Castle> {
+        let index = castling_index(color, king_side);
+        let (right, _) = CASTLING_RIGHTS[index];
+        if !self.castling_rights.contains(right) || !self.has_castling_pieces(index) {
+            return None;
+        }
+        let (rook_from, rook_to) = self.castle_rook_squares(color, king_side);
+        let king_file = match king_side {
+            true => File::FileG,
+            false => File::FileC,
+        };
+        Some(Castle {
+            king_from: self.king_square(color)?,
+            king_to: Square64::from_file_and_rank(king_file, Rank::Rank1.relative(color)),
+            rook_from,
+            rook_to,
+        })
+    }
+
+    /// Squares the rook of the given castle moves from and to. Unlike Position::castle this
+    /// doesn't check the right, so it also works while making and unmaking the castle.
+    pub(crate) fn castle_rook_squares(
+        &self,
+        color: Color,
+        king_side: bool,
+    ) -> (Square64, Square64) {
+        let back_rank = Rank::Rank1.relative(color);
+        let rook_file = self.castling_rook_files[castling_index(color, king_side)];
+        let rook_to = match king_side {
+            true => File::FileF,
+            false => File::FileD,
+        };
+        (
+            Square64::from_file_and_rank(rook_file, back_rank),
+            Square64::from_file_and_rank(rook_to, back_rank),
+        )
+    }
+
+    /// Removes the castling rights lost by moving a piece from one square to another: both
+    /// rights of a king that moves and the right of a castling rook that moves or is captured.
+    /// Has to be called before the move is made, while the king is still on its square.
+    pub(crate) fn update_castling_rights(&mut self, from: Square64, to: Square64) {
+        if self.castling_rights.is_empty() {
+            return;
+        }
+        for (index, &(right, color)) in CASTLING_RIGHTS.iter().enumerate() {
+            let back_rank = Rank::Rank1.relative(color);
+            let rook = Square64::from_file_and_rank(self.castling_rook_files[index], back_rank);
+            if from == rook || to == rook || self.king_square(color) == Some(from) {
+                self.castling_rights.remove(right);
+            }
+        }
+    }
+
+    /// Returns true if the king of the castling right at index is on its back rank and its rook
+    /// is on the stored file, on the right side of the king
+    pub(crate) fn has_castling_pieces(&self, index: usize) -> bool {
+        let (_, color) = CASTLING_RIGHTS[index];
+        let Some(king_file) = self.back_rank_king_file(color) else {
+            return false;
+        };
+        let rook_file = self.castling_rook_files[index];
+        let rook_square = Square64::from_file_and_rank(rook_file, Rank::Rank1.relative(color));
+        let on_side = match is_king_side(index) {
+            true => rook_file as usize > king_file as usize,
+            false => (rook_file as usize) < king_file as usize,
+        };
+        on_side && self.piece_on(rook_square) == Some(Piece::new(color, PieceType::Rook))
+    }
+
+    fn back_rank_king_file(&self, color: Color) -> Option<File> {
+        let king = self.king_square(color)?;
+        let back_rank = Rank::Rank1.relative(color) as usize;
+        match king as usize / 8 == back_rank {
+            true => File::try_from(king as usize % 8).ok(),
+            false => None,
+        }
+    }
+
+    /// File of the rook furthest from the king on the given side of the back rank, falling back to
+    /// the standard file when there's no such rook so that rights without a rook still parse and
+    /// are left for validate to reject
+    fn outermost_rook_file(&self, color: Color, side: usize, king_file: Option<File>) -> File {
+        let rook = Piece::new(color, PieceType::Rook);
+        let back_rank = Rank::Rank1.relative(color);
+        let is_rook = |file: &File| {
+            self.piece_on(Square64::from_file_and_rank(*file, back_rank)) == Some(rook)
+        };
+        let found = match (side, king_file) {
+            (0, Some(king_file)) => File::iter()
+                .rev()
+                .take_while(|&f| f as usize > king_file as usize)
+                .find(is_rook),
+            (_, Some(king_file)) => File::iter()
+                .take_while(|&f| (f as usize) < king_file as usize)
+                .find(is_rook),
+            _ => None,
+        };
+        found.unwrap_or(STANDARD_CASTLING_ROOK_FILES[side])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::START_FEN, error::ChessError, error::GamestateFENParseError};
+    use alloc::borrow::ToOwned;
+
+    #[test]
+    fn test_standard_castling_rook_files() {
+        let input = Position::start();
+        let output = [
+            input.castling_rook_file(CastlingRights::WHITE_KING),
+            input.castling_rook_file(CastlingRights::BLACK_QUEEN),
+        ];
+        let expected = [Some(File::FileH), Some(File::FileA)];
+        assert_eq!(output, expected);
+        assert_eq!(input.to_shredder_fen(), START_FEN.replace("KQkq", "HAha"));
+    }
+
+    #[test]
+    fn test_shredder_fen_round_trip() {
+        let input = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let position = Position::from_fen(input).unwrap();
+        let output = position.to_shredder_fen();
+        assert_eq!(output, input);
+        // F is the queen side rook for white since the king is on G
+        assert_eq!(
+            position.castling_rook_file(CastlingRights::WHITE_QUEEN),
+            Some(File::FileF)
+        );
+        assert_eq!(position.to_fen(), input.replace("HFhf", "KQkq"));
+    }
+
+    #[test]
+    fn test_x_fen_inner_rook() {
+        // the white king side right belongs to the F rook rather than the outermost H rook
+        let input = "rk2r3/8/8/8/8/8/8/RK3R1R w FAea - 0 1";
+        let position = Position::from_fen(input).unwrap();
+        let output = position.to_fen();
+        let expected = "rk2r3/8/8/8/8/8/8/RK3R1R w FQkq - 0 1";
+        assert_eq!(output, expected);
+        assert_eq!(Position::from_fen(&output).unwrap(), position);
+    }
+
+    #[test]
+    fn test_castling_field_on_king_file_is_invalid() {
+        let input = "4k3/8/8/8/8/8/8/4K2R w E - 0 1";
+        let output = Position::from_fen(input);
+        let expected = Err(ChessError::InvalidFen {
+            position: input.to_owned(),
+            reason: GamestateFENParseError::CastlePerm("E".to_owned()),
+        });
+        assert_eq!(output, expected);
+    }
+}
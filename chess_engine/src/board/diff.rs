@@ -0,0 +1,102 @@
+use super::Position;
+use crate::{castle_perms::CastlingRights, pieces::Piece, squares::Square64, util::Color};
+use alloc::vec::Vec;
+use strum::IntoEnumIterator;
+
+/// One difference between two positions, as listed by Position::diff. Besides the squares whose
+/// occupant changed, this covers the state that isn't visible on the board; the new value is
+/// given in every case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquareChange {
+    Piece {
+        square: Square64,
+        before: Option<Piece>,
+        after: Option<Piece>,
+    },
+    SideToMove(Color),
+    CastlingRights(CastlingRights),
+    EnPassant(Option<Square64>),
+}
+
+impl Position {
+    /// Changes needed to turn self into other: the squares whose occupant differs, ordered from
+    /// A1 to H8, followed by any change of side to move, castling rights and en passant square.
+    /// The move counters are ignored. Useful to update a GUI or an electronic board with as
+    /// little work as possible.
+    pub fn diff(&self, other: &Position) -> Vec<SquareChange> {
+        let mut changes: Vec<SquareChange> = Square64::iter()
+            .filter(|&square| self.piece_on(square) != other.piece_on(square))
+            .map(|square| SquareChange::Piece {
+                square,
+                before: self.piece_on(square),
+                after: other.piece_on(square),
+            })
+            .collect();
+        if self.side_to_move != other.side_to_move {
+            changes.push(SquareChange::SideToMove(other.side_to_move));
+        }
+        if self.castling_rights != other.castling_rights {
+            changes.push(SquareChange::CastlingRights(other.castling_rights));
+        }
+        if self.en_passant != other.en_passant {
+            changes.push(SquareChange::EnPassant(other.en_passant));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_identical() {
+        let input = Position::start();
+        let output = input.diff(&input.clone());
+        assert_eq!(output, []);
+    }
+
+    #[test]
+    fn test_diff_after_move() {
+        let before = Position::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        let after = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let output = before.diff(&after);
+        let expected = [
+            SquareChange::Piece {
+                square: Square64::E2,
+                before: Some(Piece::WhitePawn),
+                after: None,
+            },
+            SquareChange::Piece {
+                square: Square64::E4,
+                before: None,
+                after: Some(Piece::WhitePawn),
+            },
+            SquareChange::SideToMove(Color::Black),
+            SquareChange::EnPassant(Some(Square64::E3)),
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_diff_castling_rights_and_capture() {
+        let before = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let after = Position::from_fen("R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1").unwrap();
+        let output = before.diff(&after);
+        let expected = [
+            SquareChange::Piece {
+                square: Square64::A1,
+                before: Some(Piece::WhiteRook),
+                after: None,
+            },
+            SquareChange::Piece {
+                square: Square64::A8,
+                before: Some(Piece::BlackRook),
+                after: Some(Piece::WhiteRook),
+            },
+            SquareChange::SideToMove(Color::Black),
+            SquareChange::CastlingRights(CastlingRights::WHITE_KING | CastlingRights::BLACK_KING),
+        ];
+        assert_eq!(output, expected);
+    }
+}
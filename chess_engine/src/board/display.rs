@@ -0,0 +1,175 @@
+use super::Position;
+use crate::{
+    squares::Square64,
+    util::{Color, File, Rank},
+};
+use core::fmt;
+use strum::IntoEnumIterator;
+
+/// How pieces are drawn by Position::display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Glyphs {
+    /// Unicode chess symbols, e.g. ♔ and ♚
+    #[default]
+    Unicode,
+    /// FEN letters, e.g. K and k
+    Ascii,
+}
+
+/// Options for Position::display. The default draws unicode pieces with coordinates and white at
+/// the bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayStyle {
+    pub glyphs: Glyphs,
+    /// Label the ranks down the left side and the files along the bottom
+    pub coordinates: bool,
+    /// Draw the board from black's side, with rank 1 at the top and the H file on the left
+    pub flipped: bool,
+}
+
+impl Default for DisplayStyle {
+    fn default() -> Self {
+        Self {
+            glyphs: Glyphs::Unicode,
+            coordinates: true,
+            flipped: false,
+        }
+    }
+}
+
+/// Board drawn by Position::display, written out through its Display impl
+#[derive(Debug, Clone, Copy)]
+pub struct PositionDisplay<'a> {
+    position: &'a Position,
+    style: DisplayStyle,
+}
+
+impl Position {
+    /// Draws the board in the given style, e.g. `println!("{}", position.display(style))`. Empty
+    /// squares are drawn as dots.
+    pub fn display(&self, style: DisplayStyle) -> PositionDisplay<'_> {
+        PositionDisplay {
+            position: self,
+            style,
+        }
+    }
+}
+
+impl fmt::Display for PositionDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let viewer = match self.style.flipped {
+            true => Color::Black,
+            false => Color::White,
+        };
+        let mut files: [File; 8] = core::array::from_fn(|i| {
+            File::try_from(i).expect("there should be a file for every index below 8")
+        });
+        if self.style.flipped {
+            files.reverse();
+        }
+        // the ranks furthest from the viewer come first
+        for (i, rank) in Rank::iter_from(!viewer).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            if self.style.coordinates {
+                write!(f, "{} ", rank as u8 + 1)?;
+            }
+            for (j, file) in files.into_iter().enumerate() {
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                let glyph = match self
+                    .position
+                    .piece_on(Square64::from_file_and_rank(file, rank))
+                {
+                    Some(piece) => match self.style.glyphs {
+                        Glyphs::Unicode => piece.to_unicode(),
+                        Glyphs::Ascii => piece.to_char(),
+                    },
+                    None => '.',
+                };
+                write!(f, "{}", glyph)?;
+            }
+        }
+        if self.style.coordinates {
+            write!(f, "\n ")?;
+            for file in files {
+                write!(f, " {}", char::from(file).to_ascii_lowercase())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_display_ascii_with_coordinates() {
+        let input = Position::start();
+        let output = input
+            .display(DisplayStyle {
+                glyphs: Glyphs::Ascii,
+                ..DisplayStyle::default()
+            })
+            .to_string();
+        let expected = "\
+8 r n b q k b n r
+7 p p p p p p p p
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . . . . .
+3 . . . . . . . .
+2 P P P P P P P P
+1 R N B Q K B N R
+  a b c d e f g h";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_display_unicode_flipped() {
+        let input = Position::from_fen("4k3/8/8/8/8/8/6P1/4K3 w - - 0 1").unwrap();
+        let output = input
+            .display(DisplayStyle {
+                flipped: true,
+                ..DisplayStyle::default()
+            })
+            .to_string();
+        let expected = "\
+1 . . . ♔ . . . .
+2 . ♙ . . . . . .
+3 . . . . . . . .
+4 . . . . . . . .
+5 . . . . . . . .
+6 . . . . . . . .
+7 . . . . . . . .
+8 . . . ♚ . . . .
+  h g f e d c b a";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_display_without_coordinates() {
+        let input = Position::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let output = input
+            .display(DisplayStyle {
+                glyphs: Glyphs::Ascii,
+                coordinates: false,
+                flipped: false,
+            })
+            .to_string();
+        let expected = "\
+. . . . . . . k
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+K . . . . . . .";
+        assert_eq!(output, expected);
+    }
+}
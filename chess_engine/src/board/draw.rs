@@ -0,0 +1,167 @@
+use super::{attacks, bitboard::BitBoard, Position};
+use crate::{
+    pieces::{Piece, PieceType},
+    util::Color,
+};
+
+/// A1 is a dark square, so the dark squares of rank 1 are the even bits
+const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+impl Position {
+    /// Returns true if neither side has the material to ever checkmate, i.e. K vs K, a single
+    /// knight or bishop against a bare king, or any number of bishops that all stand on squares of
+    /// the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy_or_pawns = [PieceType::Pawn, PieceType::Rook, PieceType::Queen]
+            .into_iter()
+            .any(|piece_type| self.pieces_of_type(piece_type) != 0);
+        if heavy_or_pawns {
+            return false;
+        }
+        let knights = self.pieces_of_type(PieceType::Knight);
+        let bishops = self.pieces_of_type(PieceType::Bishop);
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+        knights == 0 && (bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0)
+    }
+
+    /// Stricter than is_insufficient_material: also catches positions with only kings and pawns
+    /// where every pawn is blocked head on, no pawn can capture and neither king can reach an enemy
+    /// pawn that isn't defended by another pawn. Nothing can ever change in such a position except
+    /// the kings walking around, so mate is impossible.
+    ///
+    /// This is conservative: it never reports a position that can still be won as dead, but it
+    /// doesn't catch every dead position either.
+    pub fn is_dead_position(&self) -> bool {
+        if self.is_insufficient_material() {
+            return true;
+        }
+        let only_kings_and_pawns = [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ]
+        .into_iter()
+        .all(|piece_type| self.pieces_of_type(piece_type) == 0);
+        if !only_kings_and_pawns || self.checkers.0 != 0 {
+            return false;
+        }
+
+        let pawns = self.pieces_of_type(PieceType::Pawn);
+        let pawn_attacks = [Color::White, Color::Black].map(|color| self.pawn_attack_set(color));
+        for color in [Color::White, Color::Black] {
+            let ours = self.pieces(Piece::new(color, PieceType::Pawn)).0;
+            let theirs = self.pieces(Piece::new(!color, PieceType::Pawn)).0;
+            let pushes = match color {
+                Color::White => ours << 8,
+                Color::Black => ours >> 8,
+            };
+            // Every pawn must be blocked and unable to capture
+            if pushes & !pawns != 0 || pawn_attacks[color as usize] & theirs != 0 {
+                return false;
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let Some(king) = self.king_square(color) else {
+                return false;
+            };
+            let enemy_attacks = pawn_attacks[!color as usize];
+            let passable = !pawns & !enemy_attacks;
+            let mut region = 1u64 << king as u8;
+            loop {
+                let grown = region | (king_spread(region) & passable);
+                if grown == region {
+                    break;
+                }
+                region = grown;
+            }
+            let undefended = self.pieces(Piece::new(!color, PieceType::Pawn)).0 & !enemy_attacks;
+            if king_spread(region) & undefended != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn pieces_of_type(&self, piece_type: PieceType) -> u64 {
+        self.pieces(Piece::new(Color::White, piece_type)).0
+            | self.pieces(Piece::new(Color::Black, piece_type)).0
+    }
+
+    /// Squares attacked by any pawn of the given color
+    fn pawn_attack_set(&self, color: Color) -> u64 {
+        let mut pawns = self.pieces(Piece::new(color, PieceType::Pawn));
+        let mut attacked = 0;
+        while let Some(square) = pawns.pop_bit() {
+            attacked |= attacks::pawn_attacks(color, square).0;
+        }
+        attacked
+    }
+}
+
+/// Squares a king standing on any square of region could step to
+fn king_spread(region: u64) -> u64 {
+    let mut squares = BitBoard(region);
+    let mut spread = 0;
+    while let Some(square) = squares.pop_bit() {
+        spread |= attacks::king_attacks(square).0;
+    }
+    spread
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+
+    #[test]
+    fn test_insufficient_material() {
+        let inputs = [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KN2 b - - 0 1", true),
+            // bishops on C1 and F8 are both on dark squares
+            ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+            ("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+            ("4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ];
+        for (fen, expected) in inputs {
+            let output = Position::from_fen(fen).unwrap().is_insufficient_material();
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_dead_position_locked_pawns() {
+        let inputs = [
+            "8/8/1k6/p1p1p1p1/P1P1P1P1/8/4K3/8 w - - 0 1",
+            "4k3/8/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1",
+        ];
+        for fen in inputs {
+            let output = Position::from_fen(fen).unwrap().is_dead_position();
+            assert!(output, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_dead_position_not_dead() {
+        let inputs = [
+            // the pawns can still capture each other
+            "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1",
+            // the e pawn can still move
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            // the kings can walk around and take the undefended pawns
+            "4k3/8/8/p7/P7/8/8/4K3 w - - 0 1",
+            START_FEN,
+        ];
+        for fen in inputs {
+            let output = Position::from_fen(fen).unwrap().is_dead_position();
+            assert!(!output, "{}", fen);
+        }
+    }
+}
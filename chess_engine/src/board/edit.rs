@@ -0,0 +1,106 @@
+use super::Position;
+use crate::{
+    castle_perms::CastlingRights, error::PositionValidationError, pieces::Piece, squares::Square64,
+    util::Color, zobrist,
+};
+
+/// Editing a position one change at a time, e.g. in a board editor. Every edit keeps the bitboards,
+/// key and check info up to date, but the position may pass through states that aren't legal,
+/// like a board with a single king, so validation is left to Position::finalize once the edits
+/// are done.
+impl Position {
+    /// Puts piece on square and returns the piece that stood there before
+    pub fn put_piece(&mut self, square: Square64, piece: Piece) -> Option<Piece> {
+        let previous = self.take_square(square);
+        self.add_piece(piece, square);
+        self.update_check_info();
+        previous
+    }
+
+    /// Empties square and returns the piece that stood there
+    pub fn remove_piece(&mut self, square: Square64) -> Option<Piece> {
+        let previous = self.take_square(square);
+        self.update_check_info();
+        previous
+    }
+
+    pub fn set_side_to_move(&mut self, color: Color) {
+        if color != self.side_to_move {
+            self.side_to_move = color;
+            self.key ^= zobrist::side_key();
+            self.update_check_info();
+        }
+    }
+
+    /// Replaces the castling rights, keeping the rook file of each right
+    pub fn set_castling(&mut self, castling_rights: CastlingRights) {
+        self.key ^=
+            zobrist::castling_key(self.castling_rights) ^ zobrist::castling_key(castling_rights);
+        self.castling_rights = castling_rights;
+    }
+
+    /// Checks the position after a series of edits with Position::validate
+    pub fn finalize(&self) -> Result<(), PositionValidationError> {
+        self.validate()
+    }
+
+    fn take_square(&mut self, square: Square64) -> Option<Piece> {
+        let piece = self.piece_on(square)?;
+        self.take_piece(piece, square);
+        Some(piece)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+
+    #[test]
+    fn test_edit_pieces() {
+        let mut input = Position::start();
+        let output = (
+            input.remove_piece(Square64::E2),
+            input.put_piece(Square64::E4, Piece::WhitePawn),
+            input.put_piece(Square64::D8, Piece::BlackKnight),
+        );
+        let expected = (Some(Piece::WhitePawn), None, Some(Piece::BlackQueen));
+        assert_eq!(output, expected);
+        assert_eq!(
+            input.to_fen(),
+            "rnbnkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert_eq!(input.key(), input.compute_key());
+        assert_eq!(input.finalize(), Ok(()));
+    }
+
+    #[test]
+    fn test_edit_state() {
+        let mut input = Position::start();
+        input.set_side_to_move(Color::Black);
+        input.set_castling(CastlingRights::WHITE_KING | CastlingRights::BLACK_QUEEN);
+        let output = input.to_fen();
+        let expected = START_FEN.replace("w KQkq", "b Kq");
+        assert_eq!(output, expected);
+        assert_eq!(input.key(), input.compute_key());
+    }
+
+    #[test]
+    fn test_edit_finalize_validates() {
+        let mut input = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        input.put_piece(Square64::E2, Piece::BlackQueen);
+        assert_eq!(input.checkers().0, 1 << Square64::E2 as u8);
+        assert_eq!(input.finalize(), Ok(()));
+
+        input.set_side_to_move(Color::Black);
+        let output = input.finalize();
+        let expected = Err(PositionValidationError::OpponentInCheck(Color::White));
+        assert_eq!(output, expected);
+
+        input.set_side_to_move(Color::White);
+        input.remove_piece(Square64::E8);
+        let output = input.finalize();
+        let expected = Err(PositionValidationError::InvalidKingNum(Color::Black, 0));
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,499 @@
+use super::{attacks, bitboard::BitBoard, Position};
+use crate::{
+    castle_perms::CastlingRights,
+    moves::{Move, MoveFlag},
+    pieces::{Piece, PieceType},
+    squares::Square64,
+    util::{Color, SQUARE_64_BY_INDEX},
+    zobrist,
+};
+use strum::{EnumCount, IntoEnumIterator};
+
+/// Everything make_move overwrites that can't be recomputed from the move itself, so that
+/// unmake_move can restore the position exactly. The key and check info are kept so unmake
+/// doesn't have to recompute them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    move_: Move,
+    captured: Option<Piece>,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square64>,
+    halfmove_clock: u32,
+    key: u64,
+    checkers: BitBoard,
+    pinned: [BitBoard; Color::COUNT],
+}
+
+impl Undo {
+    pub fn move_(&self) -> Move {
+        self.move_
+    }
+
+    /// Piece removed from the board by the move, if it was a capture
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+}
+
+impl Position {
+    /// Plays a pseudo legal move for the side to move and returns what's needed to take it back.
+    /// The move isn't checked for legality, so it must come from the position it's played in.
+    pub fn make_move(&mut self, move_: Move) -> Undo {
+        let from = move_.from_square();
+        let to = move_.to_square();
+        let color = self.side_to_move;
+        let moving = self
+            .piece_on(from)
+            .expect("make_move should only be called with a piece on the from square");
+        debug_assert_eq!(
+            moving.color(),
+            color,
+            "{} should move a {} piece",
+            move_,
+            color
+        );
+
+        let captured_square = match move_.flag() {
+            MoveFlag::EnPassant => en_passant_capture_square(from, to),
+            _ => to,
+        };
+        let captured = match move_.is_capture() {
+            true => self.piece_on(captured_square),
+            false => None,
+        };
+        let undo = Undo {
+            move_,
+            captured,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+            checkers: self.checkers,
+            pinned: self.pinned,
+        };
+
+        // Pieces hash themselves in add_piece and take_piece, everything else is swapped here
+        self.key ^= zobrist::castling_key(self.castling_rights);
+        if let Some(square) = self.en_passant {
+            self.key ^= zobrist::en_passant_key(square);
+        }
+        self.update_castling_rights(from, to);
+        if let Some(captured) = captured {
+            self.take_piece(captured, captured_square);
+        }
+        // In Chess960 the king may land where its rook stood or the other way round, so both
+        // leave the board before either is put back
+        let rook_move = self.castle_rook_move(move_, color);
+        let rook = Piece::new(color, PieceType::Rook);
+        self.take_piece(moving, from);
+        if let Some((rook_from, _)) = rook_move {
+            self.take_piece(rook, rook_from);
+        }
+        let placed = match move_.promotion() {
+            Some(piece_type) => Piece::new(color, piece_type),
+            None => moving,
+        };
+        self.add_piece(placed, to);
+        if let Some((_, rook_to)) = rook_move {
+            self.add_piece(rook, rook_to);
+        }
+
+        self.key ^= zobrist::castling_key(self.castling_rights) ^ zobrist::side_key();
+        self.halfmove_clock = match moving.is_pawn() || captured.is_some() {
+            true => 0,
+            false => self.halfmove_clock + 1,
+        };
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = !color;
+        self.update_check_info();
+        // a double push the opponent can't take en passant leaves no square, or the position
+        // wouldn't repeat the same one reached without it
+        self.en_passant = match move_.flag() {
+            MoveFlag::DoublePawnPush => Some(SQUARE_64_BY_INDEX[(from as usize + to as usize) / 2]),
+            _ => None,
+        };
+        if !self.has_en_passant_capture() {
+            self.en_passant = None;
+        }
+        if let Some(square) = self.en_passant {
+            self.key ^= zobrist::en_passant_key(square);
+        }
+        undo
+    }
+
+    /// Passes the turn without moving a piece, for null move pruning. The side to move must not be
+    /// in check, since passing would leave its king capturable.
+    pub fn make_null_move(&mut self) -> Undo {
+        debug_assert_eq!(
+            self.checkers,
+            BitBoard(0),
+            "null move should not be made while in check"
+        );
+        let undo = Undo {
+            move_: Move::NULL,
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+            checkers: self.checkers,
+            pinned: self.pinned,
+        };
+        if let Some(square) = self.en_passant.take() {
+            self.key ^= zobrist::en_passant_key(square);
+        }
+        self.key ^= zobrist::side_key();
+        self.halfmove_clock += 1;
+        self.side_to_move = !self.side_to_move;
+        self.update_check_info();
+        undo
+    }
+
+    /// Takes back the null move make_null_move returned undo for
+    pub fn unmake_null_move(&mut self, undo: Undo) {
+        debug_assert_eq!(
+            undo.move_,
+            Move::NULL,
+            "undo should come from make_null_move"
+        );
+        self.side_to_move = !self.side_to_move;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key = undo.key;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+    }
+
+    /// Returns true if the pseudo legal move would put the opponent in check, either directly or
+    /// by uncovering a slider, without actually making the move
+    pub fn gives_check(&self, move_: Move) -> bool {
+        let us = self.side_to_move;
+        let Some(king) = self.king_square(!us) else {
+            return false;
+        };
+        let from = move_.from_square();
+        let to = move_.to_square();
+        let from_bit = 1u64 << from as u8;
+        let to_bit = 1u64 << to as u8;
+        let Some(moving) = self.piece_on(from) else {
+            return false;
+        };
+        let placed = match move_.promotion() {
+            Some(piece_type) => Piece::new(us, piece_type),
+            None => moving,
+        };
+
+        // Our pieces and the occupancy as they'd be after the move
+        let mut ours = [0u64; PieceType::COUNT];
+        for piece_type in PieceType::iter() {
+            ours[piece_type as usize] = self.pieces(Piece::new(us, piece_type)).0 & !from_bit;
+        }
+        ours[placed.piece_type() as usize] |= to_bit;
+        let mut occupancy = (self.occupancy().0 & !from_bit) | to_bit;
+        if move_.is_en_passant() {
+            occupancy &= !(1u64 << en_passant_capture_square(from, to) as u8);
+        }
+        if let Some((rook_from, rook_to)) = self.castle_rook_move(move_, us) {
+            let (rook_from_bit, rook_to_bit) = (1u64 << rook_from as u8, 1u64 << rook_to as u8);
+            ours[PieceType::Rook as usize] =
+                (ours[PieceType::Rook as usize] & !rook_from_bit) | rook_to_bit;
+            // the rook may have stood on the king's to square
+            occupancy = (occupancy & !rook_from_bit) | rook_to_bit | to_bit;
+        }
+
+        let occupancy = BitBoard(occupancy);
+        let queens = ours[PieceType::Queen as usize];
+        let checkers = (attacks::pawn_attacks(!us, king).0 & ours[PieceType::Pawn as usize])
+            | (attacks::knight_attacks(king).0 & ours[PieceType::Knight as usize])
+            | (attacks::bishop_attacks(king, occupancy).0
+                & (ours[PieceType::Bishop as usize] | queens))
+            | (attacks::rook_attacks(king, occupancy).0
+                & (ours[PieceType::Rook as usize] | queens));
+        checkers != 0
+    }
+
+    /// Squares the rook moves from and to when the move is a castle by color
+    fn castle_rook_move(&self, move_: Move, color: Color) -> Option<(Square64, Square64)> {
+        match move_.flag() {
+            MoveFlag::KingCastle => Some(self.castle_rook_squares(color, true)),
+            MoveFlag::QueenCastle => Some(self.castle_rook_squares(color, false)),
+            _ => None,
+        }
+    }
+
+    /// Copy-make alternative to make_move: returns the position after the move and leaves self
+    /// untouched, so there's no Undo to keep track of
+    pub fn make_move_new(&self, move_: Move) -> Position {
+        let mut position = self.clone();
+        position.make_move(move_);
+        position
+    }
+
+    /// Takes back the move make_move returned undo for. Moves must be unmade in the reverse
+    /// order they were made in.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let move_ = undo.move_;
+        let from = move_.from_square();
+        let to = move_.to_square();
+        let color = !self.side_to_move;
+        self.side_to_move = color;
+        if color == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        let rook_move = self.castle_rook_move(move_, color);
+        let rook = Piece::new(color, PieceType::Rook);
+        if let Some((_, rook_to)) = rook_move {
+            self.take_piece(rook, rook_to);
+        }
+        let placed = self
+            .piece_on(to)
+            .expect("unmake_move should find the moved piece on the to square");
+        self.take_piece(placed, to);
+        let moving = match move_.is_promotion() {
+            true => Piece::new(color, PieceType::Pawn),
+            false => placed,
+        };
+        self.add_piece(moving, from);
+        if let Some((rook_from, _)) = rook_move {
+            self.add_piece(rook, rook_from);
+        }
+        if let Some(captured) = undo.captured {
+            let captured_square = match move_.flag() {
+                MoveFlag::EnPassant => en_passant_capture_square(from, to),
+                _ => to,
+            };
+            self.add_piece(captured, captured_square);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key = undo.key;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+    }
+}
+
+/// The pawn taken en passant sits on the to square's file and the from square's rank
+fn en_passant_capture_square(from: Square64, to: Square64) -> Square64 {
+    SQUARE_64_BY_INDEX[(from as usize & !7) | (to as usize & 7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+
+    fn make_and_unmake(fen: &str, move_: Move, expected_fen: &str) {
+        let mut position = Position::from_fen(fen).unwrap();
+        let before = position.clone();
+        let undo = position.make_move(move_);
+        assert_eq!(position.to_fen(), expected_fen);
+        assert_eq!(position.key(), position.compute_key());
+        assert_eq!(position, Position::from_fen(expected_fen).unwrap());
+        position.unmake_move(undo);
+        assert!(position.strict_eq(&before), "{:?}", position);
+    }
+
+    #[test]
+    fn test_make_move_double_pawn_push() {
+        let input = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let expected = "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1";
+        make_and_unmake("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", input, expected);
+        // without a pawn that can take en passant there's no en passant square
+        let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        make_and_unmake(START_FEN, input, expected);
+        // nor with one that can't take without exposing its king
+        let expected = "8/8/8/8/k2pP2R/8/8/4K3 b - - 0 1";
+        make_and_unmake("8/8/8/8/k2p3R/8/4P3/4K3 w - - 0 1", input, expected);
+    }
+
+    #[test]
+    fn test_make_move_quiet_increments_clocks() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let input = Move::new(Square64::G8, Square64::F6, MoveFlag::Quiet);
+        let expected = "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_make_move_capture_removes_castling_right() {
+        let fen = "r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 3 20";
+        let input = Move::new(Square64::G2, Square64::H1, MoveFlag::Capture);
+        let expected = "r3k2r/8/8/8/8/8/8/R3K2b w Qkq - 0 21";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_make_move_castles() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let input = Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle);
+        let expected = "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1";
+        make_and_unmake(fen, input, expected);
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        let input = Move::new(Square64::E8, Square64::C8, MoveFlag::QueenCastle);
+        let expected = "2kr3r/8/8/8/8/8/8/R3K2R w KQ - 1 2";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_make_move_chess960_castles() {
+        // the king and rook swap squares
+        let fen = "4k3/8/8/8/8/8/8/1R3KR1 w GB - 0 1";
+        let input = Move::new(Square64::F1, Square64::G1, MoveFlag::KingCastle);
+        let expected = "4k3/8/8/8/8/8/8/1R3RK1 b - - 1 1";
+        make_and_unmake(fen, input, expected);
+
+        // the king lands on the rook's square and the rook jumps over it
+        let fen = "4k3/8/8/8/8/8/8/2RK4 w C - 0 1";
+        let input = Move::new(Square64::D1, Square64::C1, MoveFlag::QueenCastle);
+        let expected = "4k3/8/8/8/8/8/8/2KR4 b - - 1 1";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_make_move_en_passant() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let input = Move::new(Square64::E5, Square64::D6, MoveFlag::EnPassant);
+        let expected = "4k3/8/3P4/8/8/8/8/4K3 b - - 0 1";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_make_move_promotion_capture() {
+        let fen = "1r2k3/P7/8/8/8/8/8/4K3 w - - 5 30";
+        let input = Move::new(Square64::A7, Square64::B8, MoveFlag::KnightPromotionCapture);
+        let expected = "1N2k3/8/8/8/8/8/8/4K3 b - - 0 30";
+        make_and_unmake(fen, input, expected);
+    }
+
+    #[test]
+    fn test_unmake_move_sequence() {
+        let input = [
+            Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush),
+            Move::new(Square64::D7, Square64::D5, MoveFlag::DoublePawnPush),
+            Move::new(Square64::E4, Square64::D5, MoveFlag::Capture),
+            Move::new(Square64::D8, Square64::D5, MoveFlag::Capture),
+        ];
+        let mut output = Position::start();
+        let undos = input.map(|move_| output.make_move(move_));
+        assert_eq!(
+            output.to_fen(),
+            "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3"
+        );
+        assert_eq!(
+            output.key(),
+            Position::from_fen(&output.to_fen()).unwrap().key()
+        );
+        for undo in undos.into_iter().rev() {
+            output.unmake_move(undo);
+        }
+        let expected = Position::start();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_make_move_new_leaves_original() {
+        let input = Position::start();
+        let move_ = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        let output = input.make_move_new(move_);
+        let mut expected = Position::start();
+        expected.make_move(move_);
+        assert_eq!(output, expected);
+        assert_eq!(input, Position::start());
+    }
+
+    #[test]
+    fn test_gives_check() {
+        let inputs = [
+            // direct check by a knight, or not
+            (
+                "4k3/8/8/8/8/5N2/8/4K3 w - - 0 1",
+                (Square64::F3, Square64::D6, MoveFlag::Quiet),
+                true,
+            ),
+            (
+                "4k3/8/8/8/8/5N2/8/4K3 w - - 0 1",
+                (Square64::F3, Square64::D4, MoveFlag::Quiet),
+                false,
+            ),
+            // discovered check by the rook behind the knight
+            (
+                "4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1",
+                (Square64::E4, Square64::C5, MoveFlag::Quiet),
+                true,
+            ),
+            // only the queen checks along the back rank after promoting
+            (
+                "1k6/4P3/8/8/8/8/8/4K3 w - - 0 1",
+                (Square64::E7, Square64::E8, MoveFlag::QueenPromotion),
+                true,
+            ),
+            (
+                "1k6/4P3/8/8/8/8/8/4K3 w - - 0 1",
+                (Square64::E7, Square64::E8, MoveFlag::KnightPromotion),
+                false,
+            ),
+            // the castled rook checks
+            (
+                "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+                (Square64::E1, Square64::G1, MoveFlag::KingCastle),
+                true,
+            ),
+            // the rook checks from the square the king left
+            (
+                "5k2/8/8/8/8/8/8/1R3KR1 w G - 0 1",
+                (Square64::F1, Square64::G1, MoveFlag::KingCastle),
+                true,
+            ),
+            // en passant removes both pawns from the rook's rank
+            (
+                "8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1",
+                (Square64::E5, Square64::D6, MoveFlag::EnPassant),
+                true,
+            ),
+        ];
+        for (fen, (from, to, flag), expected) in inputs {
+            let input = Position::from_fen(fen).unwrap();
+            let move_ = Move::new(from, to, flag);
+            let output = input.gives_check(move_);
+            let after = input.make_move_new(move_);
+            assert_eq!(output, expected, "{} {}", fen, move_);
+            assert_eq!(output, after.checkers() != BitBoard(0), "{} {}", fen, move_);
+        }
+    }
+
+    #[test]
+    fn test_null_move() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let mut input = Position::from_fen(fen).unwrap();
+        let undo = input.make_null_move();
+        let output = input.to_fen();
+        let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 1";
+        assert_eq!(output, expected);
+        assert_eq!(input.key(), input.compute_key());
+        input.unmake_null_move(undo);
+        assert_eq!(input, Position::from_fen(fen).unwrap());
+    }
+
+    #[test]
+    fn test_halfmove_clock_reaches_fifty_move_rule() {
+        let mut input = Position::from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 96 80").unwrap();
+        let shuffle = [
+            Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet),
+            Move::new(Square64::E8, Square64::D8, MoveFlag::Quiet),
+            Move::new(Square64::F3, Square64::G1, MoveFlag::Quiet),
+            Move::new(Square64::D8, Square64::E8, MoveFlag::Quiet),
+        ];
+        let undos = shuffle.map(|move_| input.make_move(move_));
+        assert!(input.is_fifty_move_draw());
+        input.unmake_move(undos[3]);
+        let output = input.is_fifty_move_draw();
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,960 @@
+use super::{
+    attacks,
+    bitboard::BitBoard,
+    castling::{CASTLING_RIGHTS, STANDARD_CASTLING_ROOK_FILES},
+};
+use crate::{
+    castle_perms::CastlingRights,
+    error::{
+        ChessError, FullmoveCounterFENParseError, GamestateFENParseError,
+        HalfmoveClockFENParseError, PositionValidationError,
+    },
+    gamestate::{Gamestate, NUM_FEN_SECTIONS},
+    moves::{Move, MoveFlag},
+    pieces::{Piece, PieceType},
+    squares::Square64,
+    util::{Color, File, Rank, SQUARE_64_BY_INDEX},
+    zobrist,
+};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::Write,
+    hash::{Hash, Hasher},
+};
+use strum::{EnumCount, IntoEnumIterator};
+
+/// FEN of the standard starting position
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Half moves without a capture or pawn move after which either player may claim a draw
+pub const FIFTY_MOVE_RULE_HALF_MOVES: u32 = 100;
+/// Half moves without a capture or pawn move after which the game is drawn automatically
+pub const SEVENTY_FIVE_MOVE_RULE_HALF_MOVES: u32 = 150;
+
+/// Pawns can never stand on the first or last rank
+const BACK_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+/// Full game state on top of bitboards: where every piece is, whose turn it is, and the
+/// irreversible state (castling rights, en passant, clocks) needed to continue the game.
+///
+/// Equality and hashing follow the repetition rules: two positions are equal when they have the
+/// same pieces on the same squares, the same side to move, castling rights (including the rook
+/// files) and en passant square, which is only kept when a pawn can take there. The halfmove clock and fullmove number don't take part, and
+/// neither does anything derived from the other fields, such as the key or the check info. Use
+/// Position::strict_eq to compare every field.
+#[derive(Debug, Clone)]
+pub struct Position {
+    /// One BitBoard per Piece, indexed by `piece as usize`
+    pub(crate) pieces: [BitBoard; Piece::COUNT],
+    /// Union of every piece of a color, indexed by `color as usize`
+    pub(crate) colors: [BitBoard; Color::COUNT],
+    /// Piece on each square, indexed by `square as usize` and kept in sync with the bitboards so
+    /// piece_on doesn't have to scan them
+    pub(crate) mailbox: [Option<Piece>; Square64::COUNT],
+    pub(crate) side_to_move: Color,
+    pub(crate) castling_rights: CastlingRights,
+    /// File of the rook each castling right castles with, indexed like castling::CASTLING_RIGHTS so
+    /// Chess960 positions keep track of which rook is which
+    pub(crate) castling_rook_files: [File; 4],
+    pub(crate) en_passant: Option<Square64>,
+    pub(crate) halfmove_clock: u32, // number of half moves since the last pawn advance or capture
+    pub(crate) fullmove_number: u32, // starts at 1 and is incremented after black moves
+    pub(crate) key: u64,
+    /// Pieces giving check to the side to move, cached by update_check_info
+    pub(crate) checkers: BitBoard,
+    /// Pieces pinned to their own king, indexed by `color as usize` and cached by
+    /// update_check_info
+    pub(crate) pinned: [BitBoard; Color::COUNT],
+}
+
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.mailbox == other.mailbox
+            && self.side_to_move == other.side_to_move
+            && self.castling_rights == other.castling_rights
+            && self.en_passant == other.en_passant
+            // the rook files of rights that are gone no longer matter
+            && CASTLING_RIGHTS.iter().all(|&(right, _)| {
+                !self.castling_rights.contains(right)
+                    || self.castling_rook_file(right) == other.castling_rook_file(right)
+            })
+    }
+}
+
+impl Eq for Position {}
+
+/// Hashes the zobrist key, which only depends on the fields that take part in equality
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+/// Returns an empty position with white to move
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Position {
+    pub fn new() -> Self {
+        Self {
+            pieces: [BitBoard(0); Piece::COUNT],
+            colors: [BitBoard(0); Color::COUNT],
+            mailbox: [None; Square64::COUNT],
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::empty(),
+            castling_rook_files: STANDARD_CASTLING_ROOK_FILES,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            key: 0,
+            checkers: BitBoard(0),
+            pinned: [BitBoard(0); Color::COUNT],
+        }
+    }
+
+    /// Parses all six fields of a FEN string. Malformed input is reported as
+    /// ChessError::InvalidFen, whose reason says exactly what's wrong (e.g. a bad piece char,
+    /// a rank with the wrong number of squares or an impossible en passant square)
+    ///
+    /// Besides KQkq, the castling field may be given in Shredder-FEN (HAha) or X-FEN, which
+    /// name castling rooks by their file so Chess960 positions can be read too.
+    pub fn from_fen(fen: &str) -> Result<Self, ChessError> {
+        let invalid = |reason| ChessError::InvalidFen {
+            position: fen.to_owned(),
+            reason,
+        };
+        let mut sections: Vec<&str> = fen.trim().split(' ').filter(|s| !s.is_empty()).collect();
+        if sections.len() != NUM_FEN_SECTIONS {
+            return Gamestate::from_fen(fen).map(|gamestate| Self::from(&gamestate));
+        }
+        let field = sections[2];
+        if CastlingRights::try_from(field).is_err() {
+            // Gamestate only knows KQkq, so it parses the rest of the FEN without castling
+            // rights and the field is applied on top once the pieces are placed
+            sections[2] = "-";
+        }
+        // Gamestate caps the counters at what fits its fixed size history, which a Position
+        // doesn't have, so they're parsed here instead
+        let (halfmove_clock, fullmove_number) = (sections[4], sections[5]);
+        sections[4] = "0";
+        sections[5] = "1";
+        let mut position = Gamestate::from_fen(&sections.join(" "))
+            .map(|gamestate| Self::from(&gamestate))
+            .map_err(|err| match err {
+                ChessError::InvalidFen { reason, .. } => invalid(reason),
+                err => err,
+            })?;
+        position
+            .apply_castling_field(field)
+            .ok_or_else(|| invalid(GamestateFENParseError::CastlePerm(field.to_owned())))?;
+        position.halfmove_clock = halfmove_clock
+            .parse()
+            .map_err(|err| invalid(HalfmoveClockFENParseError::ParseIntError(err).into()))?;
+        position.fullmove_number = match fullmove_number.parse() {
+            Ok(0) => Err(FullmoveCounterFENParseError::NotInRange(0)),
+            Ok(n) => Ok(n),
+            Err(err) => Err(FullmoveCounterFENParseError::ParseIntError(err)),
+        }
+        .map_err(|err| invalid(err.into()))?;
+        position.check_move_counters().map_err(invalid)?;
+        // FEN gives the square after every double push, see has_en_passant_capture
+        if !position.has_en_passant_capture() {
+            position.en_passant = None;
+        }
+        position.key = position.compute_key();
+        Ok(position)
+    }
+
+    /// Checks the move counters against each other and the en passant square, like Gamestate does
+    fn check_move_counters(&self) -> Result<(), GamestateFENParseError> {
+        // a pawn has just moved if there's an en passant square
+        if self.en_passant.is_some() && self.halfmove_clock != 0 {
+            return Err(HalfmoveClockFENParseError::NonZeroWhileEnPassant.into());
+        }
+        let half_moves_played = 2 * (self.fullmove_number as u64 - 1) + self.side_to_move as u64;
+        if half_moves_played < self.halfmove_clock as u64 {
+            return Err(
+                FullmoveCounterFENParseError::SmallerThanHalfmoveClockDividedByTwo(
+                    self.fullmove_number,
+                    self.halfmove_clock,
+                )
+                .into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Standard starting position
+    pub fn start() -> Self {
+        Self::from_fen(START_FEN).expect("START_FEN should be a valid FEN")
+    }
+
+    /// Canonical FEN of the position, which parses back into the same Position with from_fen. The
+    /// castling field is written in X-FEN, which is plain KQkq unless a Chess960 castling rook
+    /// isn't the outermost rook on its side of the king.
+    pub fn to_fen(&self) -> String {
+        self.fen_with_castling_field(&self.x_fen_castling_field())
+    }
+
+    /// Like to_fen but with the castling field in Shredder-FEN, e.g. HAha for the start position
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen_with_castling_field(&self.shredder_castling_field())
+    }
+
+    fn fen_with_castling_field(&self, castling_field: &str) -> String {
+        let mut fen = String::new();
+        for rank in Rank::iter().rev() {
+            let mut empty_squares = 0;
+            for file in File::iter() {
+                match self.piece_on(Square64::from_file_and_rank(file, rank)) {
+                    Some(piece) => {
+                        if empty_squares > 0 {
+                            write!(fen, "{}", empty_squares);
+                            empty_squares = 0;
+                        }
+                        fen.push(char::from(piece));
+                    }
+                    None => empty_squares += 1,
+                }
+            }
+            if empty_squares > 0 {
+                write!(fen, "{}", empty_squares);
+            }
+            if rank != Rank::Rank1 {
+                fen.push('/');
+            }
+        }
+        let en_passant = match self.en_passant {
+            Some(square) => square.to_string().to_lowercase(),
+            None => "-".to_string(),
+        };
+        write!(
+            fen,
+            " {} {} {} {} {}",
+            char::from(self.side_to_move),
+            castling_field,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        );
+        fen
+    }
+
+    /// Same position with the colors swapped and the board flipped vertically, so that e.g. a white
+    /// pawn on E4 becomes a black pawn on E5 and black is to move. Evaluation should give the
+    /// mirrored position the same score from the side to move's point of view.
+    pub fn mirrored(&self) -> Position {
+        let mut position = Position::new();
+        for square in Square64::iter() {
+            if let Some(piece) = self.piece_on(square) {
+                let mirrored = Piece::new(!piece.color(), piece.piece_type());
+                position.add_piece(mirrored, square.flip_vertical());
+            }
+        }
+        position.side_to_move = !self.side_to_move;
+        position.castling_rights = self.castling_rights.swap_colors();
+        let [white_king, white_queen, black_king, black_queen] = self.castling_rook_files;
+        position.castling_rook_files = [black_king, black_queen, white_king, white_queen];
+        position.en_passant = self.en_passant.map(Square64::flip_vertical);
+        position.halfmove_clock = self.halfmove_clock;
+        position.fullmove_number = self.fullmove_number;
+        position.key = position.compute_key();
+        position.update_check_info();
+        position
+    }
+
+    /// Like ==, but the move counters have to match too
+    pub fn strict_eq(&self, other: &Self) -> bool {
+        self == other
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_number == other.fullmove_number
+    }
+
+    /// Squares occupied by the given piece
+    pub fn pieces(&self, piece: Piece) -> BitBoard {
+        self.pieces[piece as usize]
+    }
+
+    /// Squares occupied by any piece of the given color
+    pub fn color_occupancy(&self, color: Color) -> BitBoard {
+        self.colors[color as usize]
+    }
+
+    /// Squares occupied by any piece
+    pub fn occupancy(&self) -> BitBoard {
+        BitBoard(self.colors[Color::White as usize].0 | self.colors[Color::Black as usize].0)
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    pub fn en_passant(&self) -> Option<Square64> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Zobrist key of the position, kept up to date incrementally as pieces and moves are made
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Number of times the position occurred before, given the keys of the earlier positions of
+    /// the game with the oldest first. Only the positions since the last capture or pawn move are
+    /// checked, since no position before an irreversible move can come back.
+    pub fn repetitions(&self, history: &[u64]) -> usize {
+        history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            // the same side has to be to move, so only every other position can match
+            .skip(1)
+            .step_by(2)
+            .filter(|&&key| key == self.key)
+            .count()
+    }
+
+    /// Returns true if the position occurred at least twice before, i.e. this is a threefold
+    /// repetition
+    pub fn is_repetition(&self, history: &[u64]) -> bool {
+        self.repetitions(history) >= 2
+    }
+
+    /// Returns true if fifty moves by each side passed without a capture or pawn move, so either
+    /// player may claim a draw. A checkmate delivered on the last of those moves still wins.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= FIFTY_MOVE_RULE_HALF_MOVES
+    }
+
+    /// Returns true if seventy five moves by each side passed without a capture or pawn move, at
+    /// which point the game is drawn without either player having to claim it
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove_clock >= SEVENTY_FIVE_MOVE_RULE_HALF_MOVES
+    }
+
+    /// Computes the Zobrist key from scratch
+    pub fn compute_key(&self) -> u64 {
+        let mut key = 0;
+        for piece in Piece::iter() {
+            let mut bits = self.pieces[piece as usize].0;
+            while bits != 0 {
+                key ^=
+                    zobrist::piece_key(piece, SQUARE_64_BY_INDEX[bits.trailing_zeros() as usize]);
+                bits &= bits - 1;
+            }
+        }
+        key ^= zobrist::castling_key(self.castling_rights);
+        if let Some(square) = self.en_passant {
+            key ^= zobrist::en_passant_key(square);
+        }
+        if self.side_to_move == Color::Black {
+            key ^= zobrist::side_key();
+        }
+        key
+    }
+
+    /// Pieces of the opponent giving check to the side to move
+    pub fn checkers(&self) -> BitBoard {
+        self.checkers
+    }
+
+    /// Pieces of the given color that can't leave the line between their king and an enemy slider
+    /// without exposing the king
+    pub fn pinned(&self, color: Color) -> BitBoard {
+        self.pinned[color as usize]
+    }
+
+    /// Recomputes checkers and pinned, which have to be refreshed whenever pieces are moved
+    pub(crate) fn update_check_info(&mut self) {
+        let us = self.side_to_move;
+        self.checkers = match self.king_square(us) {
+            Some(king) => self.attackers_to(king, self.occupancy()) & self.color_occupancy(!us),
+            None => BitBoard(0),
+        };
+        for color in [Color::White, Color::Black] {
+            self.pinned[color as usize] = self.compute_pinned(color);
+        }
+    }
+
+    fn compute_pinned(&self, color: Color) -> BitBoard {
+        let Some(king) = self.king_square(color) else {
+            return BitBoard(0);
+        };
+        let them = !color;
+        let queens = self.pieces(Piece::new(them, PieceType::Queen));
+        // Enemy sliders that would attack the king on an empty board
+        let mut snipers = (attacks::rook_attacks(king, BitBoard(0))
+            & (self.pieces(Piece::new(them, PieceType::Rook)) | queens))
+            | (attacks::bishop_attacks(king, BitBoard(0))
+                & (self.pieces(Piece::new(them, PieceType::Bishop)) | queens));
+        let mut pinned = BitBoard(0);
+        while let Some(sniper) = snipers.pop_bit() {
+            let blockers = attacks::between(king, sniper) & self.occupancy();
+            if blockers.0.count_ones() == 1 {
+                pinned = pinned | (blockers & self.color_occupancy(color));
+            }
+        }
+        pinned
+    }
+
+    /// Square of the king of the given color, or None if it has no king
+    pub fn king_square(&self, color: Color) -> Option<Square64> {
+        let king = self.pieces(Piece::new(color, PieceType::King)).0;
+        (king != 0).then(|| SQUARE_64_BY_INDEX[king.trailing_zeros() as usize])
+    }
+
+    /// Returns the piece on the given square, if any
+    pub fn piece_on(&self, square: Square64) -> Option<Piece> {
+        self.mailbox[square as usize]
+    }
+
+    /// Checks that the position could arise in a legal game: exactly one king per side, no pawns
+    /// on the first or last rank, the side that just moved is not left in check, the en passant
+    /// square sits behind a pawn that just made a double push, and every castling right still has
+    /// its king on the back rank and its rook on the right file and side of the king
+    pub fn validate(&self) -> Result<(), PositionValidationError> {
+        for color in [Color::White, Color::Black] {
+            let num_kings = self
+                .pieces(Piece::new(color, PieceType::King))
+                .0
+                .count_ones();
+            if num_kings != 1 {
+                return Err(PositionValidationError::InvalidKingNum(color, num_kings));
+            }
+        }
+
+        let pawns =
+            self.pieces[Piece::WhitePawn as usize].0 | self.pieces[Piece::BlackPawn as usize].0;
+        if pawns & BACK_RANKS != 0 {
+            let index = (pawns & BACK_RANKS).trailing_zeros() as usize;
+            return Err(PositionValidationError::PawnOnBackRank(
+                SQUARE_64_BY_INDEX[index],
+            ));
+        }
+
+        let waiting = !self.side_to_move;
+        if self.is_in_check(waiting) {
+            return Err(PositionValidationError::OpponentInCheck(waiting));
+        }
+
+        if let Some(square) = self.en_passant {
+            if !self.is_valid_en_passant(square) {
+                return Err(PositionValidationError::InvalidEnPassant(square));
+            }
+        }
+
+        for (index, &(right, _)) in CASTLING_RIGHTS.iter().enumerate() {
+            if self.castling_rights.contains(right) && !self.has_castling_pieces(index) {
+                return Err(PositionValidationError::CastlingRightsMismatch(right));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if a king of the given color is attacked by the opponent
+    fn is_in_check(&self, color: Color) -> bool {
+        self.king_square(color)
+            .is_some_and(|king| self.is_attacked(king, !color))
+    }
+
+    /// Returns true if any piece of the given color attacks the square
+    pub fn is_attacked(&self, square: Square64, by: Color) -> bool {
+        self.attackers_to(square, self.occupancy()).0 & self.color_occupancy(by).0 != 0
+    }
+
+    /// Pieces of both colors that attack the square, with sliders blocked by the given occupancy
+    /// instead of the position's own, e.g. to look through a piece that's about to move
+    pub fn attackers_to(&self, square: Square64, occupancy: BitBoard) -> BitBoard {
+        let bishops_queens = self.pieces(Piece::WhiteBishop)
+            | self.pieces(Piece::BlackBishop)
+            | self.pieces(Piece::WhiteQueen)
+            | self.pieces(Piece::BlackQueen);
+        let rooks_queens = self.pieces(Piece::WhiteRook)
+            | self.pieces(Piece::BlackRook)
+            | self.pieces(Piece::WhiteQueen)
+            | self.pieces(Piece::BlackQueen);
+        // A pawn attacks the square if a pawn of the other color on the square would attack it
+        (attacks::pawn_attacks(Color::Black, square) & self.pieces(Piece::WhitePawn))
+            | (attacks::pawn_attacks(Color::White, square) & self.pieces(Piece::BlackPawn))
+            | (attacks::knight_attacks(square)
+                & (self.pieces(Piece::WhiteKnight) | self.pieces(Piece::BlackKnight)))
+            | (attacks::king_attacks(square)
+                & (self.pieces(Piece::WhiteKing) | self.pieces(Piece::BlackKing)))
+            | (attacks::bishop_attacks(square, occupancy) & bishops_queens)
+            | (attacks::rook_attacks(square, occupancy) & rooks_queens)
+    }
+
+    /// The en passant square must be empty, on the side-not-to-move's third rank, with that side's
+    /// pawn right in front of it and nothing on the square the pawn came from
+    fn is_valid_en_passant(&self, square: Square64) -> bool {
+        let mover = !self.side_to_move;
+        let forward = match mover {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let on_third_rank = square as usize / 8 == Rank::Rank3.relative(mover) as usize;
+        let pawn_square = offset_square(square, (0, forward));
+        let origin_square = offset_square(square, (0, -forward));
+        on_third_rank
+            && self.piece_on(square).is_none()
+            && pawn_square.and_then(|s| self.piece_on(s))
+                == Some(Piece::new(mover, PieceType::Pawn))
+            && origin_square.is_some_and(|s| self.piece_on(s).is_none())
+    }
+
+    /// Returns true if a pawn of the side to move can legally take on the en passant square. The
+    /// square is only kept when one can, since the position is the same one otherwise.
+    pub(crate) fn has_en_passant_capture(&self) -> bool {
+        let Some(square) = self.en_passant else {
+            return false;
+        };
+        let us = self.side_to_move;
+        let pawns =
+            attacks::pawn_attacks(!us, square) & self.pieces(Piece::new(us, PieceType::Pawn));
+        let mut pawns = pawns.0;
+        while pawns != 0 {
+            let from = SQUARE_64_BY_INDEX[pawns.trailing_zeros() as usize];
+            if self.is_legal(Move::new(from, square, MoveFlag::EnPassant)) {
+                return true;
+            }
+            pawns &= pawns - 1;
+        }
+        false
+    }
+
+    /// Places piece on an empty square
+    pub(crate) fn add_piece(&mut self, piece: Piece, square: Square64) {
+        debug_assert!(
+            !self.occupancy().check_bit(square),
+            "square {} should be empty before placing {:?}",
+            square,
+            piece
+        );
+        self.pieces[piece as usize].set_bit(square);
+        self.colors[piece.color() as usize].set_bit(square);
+        self.mailbox[square as usize] = Some(piece);
+        self.key ^= zobrist::piece_key(piece, square);
+    }
+
+    /// Removes piece from the square it occupies
+    pub(crate) fn take_piece(&mut self, piece: Piece, square: Square64) {
+        debug_assert_eq!(
+            self.mailbox[square as usize],
+            Some(piece),
+            "{:?} should be on {} before removing it",
+            piece,
+            square
+        );
+        self.pieces[piece as usize].unset_bit(square);
+        self.colors[piece.color() as usize].unset_bit(square);
+        self.mailbox[square as usize] = None;
+        self.key ^= zobrist::piece_key(piece, square);
+    }
+}
+
+/// Square reached by moving (file, rank) steps from square, or None if that leaves the board
+fn offset_square(square: Square64, (file_offset, rank_offset): (i8, i8)) -> Option<Square64> {
+    let file = (square as i8 % 8) + file_offset;
+    let rank = (square as i8 / 8) + rank_offset;
+    ((0..8).contains(&file) && (0..8).contains(&rank))
+        .then(|| SQUARE_64_BY_INDEX[(rank * 8 + file) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{BoardFENParseError, GamestateFENParseError, RankFENParseError};
+    use alloc::borrow::ToOwned;
+
+    #[test]
+    fn test_position_new_is_empty() {
+        let output = Position::new();
+        assert_eq!(output.occupancy(), BitBoard(0));
+        assert_eq!(output.side_to_move(), Color::White);
+        assert_eq!(output.castling_rights(), CastlingRights::empty());
+        assert_eq!(output.en_passant(), None);
+        assert_eq!(output.fullmove_number(), 1);
+    }
+
+    #[test]
+    fn test_position_put_piece_updates_occupancy() {
+        let mut input = Position::new();
+        input.add_piece(Piece::WhiteKing, Square64::E1);
+        input.add_piece(Piece::BlackKing, Square64::E8);
+        let output = (
+            input.pieces(Piece::WhiteKing),
+            input.color_occupancy(Color::Black),
+            input.occupancy(),
+        );
+        let expected = (
+            BitBoard(1 << 4),
+            BitBoard(1 << 60),
+            BitBoard(1 << 4 | 1 << 60),
+        );
+        assert_eq!(output, expected);
+        assert_eq!(input.piece_on(Square64::E8), Some(Piece::BlackKing));
+        assert_eq!(input.key(), input.compute_key());
+    }
+
+    #[test]
+    fn test_position_from_fen_start() {
+        let output = Position::from_fen(START_FEN).unwrap();
+        assert_eq!(output.occupancy(), BitBoard(0xFFFF_0000_0000_FFFF));
+        assert_eq!(output.pieces(Piece::WhitePawn), BitBoard(0xFF00));
+        assert_eq!(output.pieces(Piece::BlackKing), BitBoard(1 << 60));
+        assert_eq!(output.side_to_move(), Color::White);
+        assert_eq!(output.castling_rights(), CastlingRights::all());
+        assert_eq!(output.en_passant(), None);
+        assert_eq!((output.halfmove_clock(), output.fullmove_number()), (0, 1));
+        assert_eq!(output.key(), output.compute_key());
+    }
+
+    #[test]
+    fn test_position_from_fen_all_fields() {
+        let input = "rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w Kq c6 0 2";
+        let output = Position::from_fen(input).unwrap();
+        assert_eq!(output.piece_on(Square64::C5), Some(Piece::BlackPawn));
+        assert_eq!(output.piece_on(Square64::D5), Some(Piece::WhitePawn));
+        assert_eq!(
+            output.castling_rights(),
+            CastlingRights::WHITE_KING | CastlingRights::BLACK_QUEEN
+        );
+        assert_eq!(output.en_passant(), Some(Square64::C6));
+        assert_eq!(output.fullmove_number(), 2);
+    }
+
+    #[test]
+    fn test_position_from_fen_invalid() {
+        let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1";
+        let output = Position::from_fen(input);
+        let expected = Err(ChessError::InvalidFen {
+            position: input.to_owned(),
+            reason: GamestateFENParseError::BoardFENParseError(
+                BoardFENParseError::RankFENParseError(RankFENParseError::InvalidChar(
+                    "RNBQKBNX".to_owned(),
+                    'X',
+                )),
+            ),
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_position_to_fen_round_trip() {
+        let inputs = [
+            START_FEN,
+            "rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w Kq c6 0 2",
+            "4k3/8/8/8/8/8/8/4K2R b - - 7 40",
+            // past the move rules and the longest game Gamestate can hold
+            "4k3/8/8/8/8/8/8/4K2R w K - 100 80",
+            "4k3/8/8/8/8/8/8/4K2R w K - 150 80",
+            "4k3/8/8/8/8/8/8/4K2R w K - 99 2000",
+        ];
+        for input in inputs {
+            let output = Position::from_fen(input).unwrap().to_fen();
+            assert_eq!(output, input);
+        }
+    }
+
+    #[test]
+    fn test_position_from_fen_invalid_move_counters() {
+        let inputs = [
+            (
+                "4k3/8/8/8/8/8/8/4K2R w K - 100 50",
+                FullmoveCounterFENParseError::SmallerThanHalfmoveClockDividedByTwo(50, 100).into(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2R w K - 0 0",
+                FullmoveCounterFENParseError::NotInRange(0).into(),
+            ),
+            (
+                "4k3/8/8/8/3pP3/8/8/4K3 b - e3 1 1",
+                HalfmoveClockFENParseError::NonZeroWhileEnPassant.into(),
+            ),
+        ];
+        for (input, reason) in inputs {
+            let output = Position::from_fen(input);
+            let expected = Err(ChessError::InvalidFen {
+                position: input.to_owned(),
+                reason,
+            });
+            assert_eq!(output, expected, "{}", input);
+        }
+        assert!(matches!(
+            Position::from_fen("4k3/8/8/8/8/8/8/4K2R w K - x 1"),
+            Err(ChessError::InvalidFen {
+                reason: GamestateFENParseError::HalfmoveClockFENParseError(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_position_remove_piece() {
+        let mut input = Position::new();
+        input.add_piece(Piece::WhiteQueen, Square64::D1);
+        input.take_piece(Piece::WhiteQueen, Square64::D1);
+        let output = input;
+        let expected = Position::new();
+        assert_eq!(output, expected);
+    }
+
+    fn kings_only() -> Position {
+        let mut position = Position::new();
+        position.add_piece(Piece::WhiteKing, Square64::E1);
+        position.add_piece(Piece::BlackKing, Square64::E8);
+        position
+    }
+
+    #[test]
+    fn test_validate_start() {
+        let output = Position::start().validate();
+        let expected = Ok(());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_validate_king_count() {
+        let mut input = kings_only();
+        input.add_piece(Piece::BlackKing, Square64::A8);
+        let output = input.validate();
+        let expected = Err(PositionValidationError::InvalidKingNum(Color::Black, 2));
+        assert_eq!(output, expected);
+
+        let output = Position::new().validate();
+        let expected = Err(PositionValidationError::InvalidKingNum(Color::White, 0));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_validate_pawn_on_back_rank() {
+        let mut input = kings_only();
+        input.add_piece(Piece::BlackPawn, Square64::C1);
+        let output = input.validate();
+        let expected = Err(PositionValidationError::PawnOnBackRank(Square64::C1));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_validate_opponent_in_check() {
+        let inputs = [
+            (Piece::WhiteRook, Square64::E4),
+            (Piece::WhiteBishop, Square64::A4),
+            (Piece::WhiteQueen, Square64::H5),
+            (Piece::WhiteKnight, Square64::F6),
+            (Piece::WhitePawn, Square64::D7),
+        ];
+        for (piece, square) in inputs {
+            let mut input = kings_only();
+            input.add_piece(piece, square);
+            let output = input.validate();
+            let expected = Err(PositionValidationError::OpponentInCheck(Color::Black));
+            assert_eq!(output, expected, "{:?} on {}", piece, square);
+        }
+    }
+
+    #[test]
+    fn test_validate_blocked_slider_is_not_check() {
+        let mut input = kings_only();
+        input.add_piece(Piece::WhiteRook, Square64::E4);
+        input.add_piece(Piece::BlackKnight, Square64::E6);
+        let output = input.validate();
+        let expected = Ok(());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_validate_en_passant() {
+        let input = "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2";
+        let output = Position::from_fen(input).unwrap().validate();
+        let expected = Ok(());
+        assert_eq!(output, expected);
+
+        let mut input = kings_only();
+        input.add_piece(Piece::BlackPawn, Square64::C5);
+        input.en_passant = Some(Square64::D6);
+        let output = input.validate();
+        let expected = Err(PositionValidationError::InvalidEnPassant(Square64::D6));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_validate_castling_rights_mismatch() {
+        let input = "rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let output = Position::from_fen(input).unwrap().validate();
+        let expected = Err(PositionValidationError::CastlingRightsMismatch(
+            CastlingRights::BLACK_KING,
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_position_mailbox_matches_bitboards() {
+        let input = Position::from_fen("r3k2r/1b4q1/8/3pP3/8/2N5/6B1/R3K2R w KQkq d6 0 1").unwrap();
+        for square in Square64::iter() {
+            let output = input.piece_on(square);
+            let expected = Piece::iter().find(|&piece| input.pieces(piece).check_bit(square));
+            assert_eq!(output, expected, "{}", square);
+        }
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let input = Position::from_fen("4k3/8/3n4/1b3p2/4P3/2N2R2/8/4K3 w - - 0 1").unwrap();
+        let output = input.attackers_to(Square64::E4, input.occupancy());
+        // the knights on C3 and D6 and the pawn on F5, while the bishop and rook don't see E4
+        let expected = BitBoard(1 << 18 | 1 << 43 | 1 << 37);
+        assert_eq!(output, expected);
+        assert!(input.is_attacked(Square64::E4, Color::Black));
+        assert!(input.is_attacked(Square64::F5, Color::White));
+        assert!(!input.is_attacked(Square64::H8, Color::White));
+    }
+
+    #[test]
+    fn test_attackers_to_with_custom_occupancy() {
+        let input = Position::from_fen("4k3/8/8/8/4r3/8/4P3/4K3 w - - 0 1").unwrap();
+        let output = input.attackers_to(Square64::E1, input.occupancy()).0 & (1 << 28);
+        assert_eq!(output, 0);
+        let occupancy = BitBoard(input.occupancy().0 & !(1 << 12));
+        let output = input.attackers_to(Square64::E1, occupancy);
+        let expected = BitBoard(1 << 28);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_checkers() {
+        let input = Position::from_fen("4k3/8/8/1B6/8/5n2/8/4K3 w - - 0 1").unwrap();
+        let output = input.checkers();
+        let expected = BitBoard(1 << 21);
+        assert_eq!(output, expected);
+        assert_eq!(Position::start().checkers(), BitBoard(0));
+    }
+
+    #[test]
+    fn test_pinned() {
+        // the D7 pawn is pinned by the B5 bishop, and the E7 rook pins nothing while both the
+        // E4 pawn and the E2 knight stand between it and the white king
+        let input = Position::from_fen("4k3/3pr3/8/1B6/4P3/8/4N3/4K3 w - - 0 1").unwrap();
+        let output = (input.pinned(Color::White), input.pinned(Color::Black));
+        let expected = (BitBoard(0), BitBoard(1 << 51));
+        assert_eq!(output, expected);
+
+        let input = Position::from_fen("4k3/3pr3/8/1B6/8/8/4N3/4K3 w - - 0 1").unwrap();
+        let output = input.pinned(Color::White);
+        let expected = BitBoard(1 << 12);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let input =
+            Position::from_fen("r3k2r/pp3ppp/8/3pP3/8/2N5/PPP2PPP/R3K2R w Kq d6 0 12").unwrap();
+        let output = input.mirrored();
+        let expected =
+            Position::from_fen("r3k2r/ppp2ppp/2n5/8/3Pp3/8/PP3PPP/R3K2R b Qk d3 0 12").unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(output.mirrored(), input);
+    }
+
+    #[test]
+    fn test_move_rule_draws() {
+        let inputs = [
+            ("4k3/8/8/8/8/8/8/4K2R w K - 99 80", (false, false)),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 100 80", (true, false)),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 149 80", (true, false)),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 150 80", (true, true)),
+        ];
+        for (input, expected) in inputs {
+            let position = Position::from_fen(input).unwrap();
+            let output = (
+                position.is_fifty_move_draw(),
+                position.is_seventy_five_move_draw(),
+            );
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    fn hash_of(position: &Position) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        position.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_eq_ignores_move_counters() {
+        let input = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let output = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 37 60").unwrap();
+        assert_eq!(output, input);
+        assert_eq!(hash_of(&output), hash_of(&input));
+        assert!(!output.strict_eq(&input));
+        assert!(input.strict_eq(&input.clone()));
+    }
+
+    #[test]
+    fn test_eq_compares_position_state() {
+        let input = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let others = [
+            "4k3/8/8/8/8/8/8/R3K3 b Q - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1R2K3 w - - 0 1",
+        ];
+        for fen in others {
+            let output = Position::from_fen(fen).unwrap();
+            assert_ne!(output, input, "{}", fen);
+        }
+
+        // same pieces and rights, but castling with the B rook instead of the A rook
+        let input = Position::from_fen("4k3/8/8/8/8/8/8/RR2K3 w Q - 0 1").unwrap();
+        let output = Position::from_fen("4k3/8/8/8/8/8/8/RR2K3 w B - 0 1").unwrap();
+        assert_ne!(output, input);
+    }
+
+    #[test]
+    fn test_eq_ignores_en_passant_square_that_cannot_be_taken() {
+        // after 1.e4 no black pawn can take on e3, so the square changes nothing
+        let input =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        let output =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+                .unwrap();
+        assert_eq!(output, input);
+        assert_eq!(hash_of(&output), hash_of(&input));
+        assert_eq!(output.key(), input.key());
+
+        let mut output = Position::start();
+        output.make_move(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush,
+        ));
+        assert_eq!(output, input);
+        assert_eq!(hash_of(&output), hash_of(&input));
+        assert_eq!(output.key(), input.key());
+
+        // a pawn on d4 can, so there it's a different position
+        let input = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        let output = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        assert_ne!(output, input);
+        assert_ne!(output.key(), input.key());
+    }
+}
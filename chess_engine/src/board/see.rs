@@ -0,0 +1,132 @@
+use super::{bitboard::BitBoard, Position};
+use crate::{
+    moves::Move,
+    pieces::{Piece, PieceType},
+};
+use strum::IntoEnumIterator;
+
+impl Position {
+    /// Static exchange evaluation: the material the side to move wins or loses by making the move
+    /// if both sides then keep capturing on its target square with their least valuable piece,
+    /// each stopping once capturing would lose more. Pins are ignored and a king only captures
+    /// when the square isn't defended any more. Castles score 0.
+    pub fn see(&self, move_: Move) -> i32 {
+        if move_.is_castle() {
+            return 0;
+        }
+        let us = self.side_to_move();
+        let (from, to) = (move_.from_square(), move_.to_square());
+        let mut occupancy = self.occupancy().0 ^ (1 << from as u8);
+        let captured = match move_.is_en_passant() {
+            true => {
+                // the captured pawn stands beside the capturing one, on the from square's rank
+                occupancy ^= 1 << (from as u8 & !7 | to as u8 & 7);
+                Some(PieceType::Pawn)
+            }
+            false => self.piece_on(to).map(|piece| piece.piece_type()),
+        };
+        let value = |piece_type| Piece::new(us, piece_type).get_value() as i32;
+        let mut mover = self
+            .piece_on(from)
+            .expect("a move should have a piece on its from square")
+            .piece_type();
+        let mut gains = [0; 32];
+        gains[0] = captured.map_or(0, value);
+        if let Some(promotion) = move_.promotion() {
+            gains[0] += value(promotion) - value(PieceType::Pawn);
+            mover = promotion;
+        }
+        let mut side = !us;
+        let mut depth = 0;
+        loop {
+            let attackers = self.attackers_to(to, BitBoard(occupancy)).0 & occupancy;
+            let ours = attackers & self.color_occupancy(side).0;
+            let Some((piece_type, attacker)) = PieceType::iter().find_map(|piece_type| {
+                let pieces = ours & self.pieces(Piece::new(side, piece_type)).0;
+                (pieces != 0).then_some((piece_type, pieces & pieces.wrapping_neg()))
+            }) else {
+                break;
+            };
+            if piece_type == PieceType::King && attackers & !ours != 0 {
+                break;
+            }
+            depth += 1;
+            gains[depth] = value(mover) - gains[depth - 1];
+            occupancy ^= attacker;
+            mover = piece_type;
+            side = !side;
+        }
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+        gains[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{moves::MoveFlag, squares::Square64};
+
+    #[test]
+    fn test_see() {
+        let inputs = [
+            // the pawn is undefended
+            (
+                "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+                (Square64::E1, Square64::E5, MoveFlag::Capture),
+                100,
+            ),
+            // the queen on H8 joins in once the bishop in front of it has captured
+            (
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                (Square64::D3, Square64::E5, MoveFlag::Capture),
+                -225,
+            ),
+            // the pawn recaptures the knight
+            (
+                "4k3/8/3p4/4n3/3P4/8/8/4K3 w - - 0 1",
+                (Square64::D4, Square64::E5, MoveFlag::Capture),
+                225,
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                (Square64::E5, Square64::D6, MoveFlag::EnPassant),
+                100,
+            ),
+            (
+                "4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1",
+                (Square64::D1, Square64::D5, MoveFlag::Quiet),
+                -1_000,
+            ),
+            // the king recaptures the rook, unless the rook behind it defends the square
+            (
+                "4k3/8/4r3/8/8/8/8/3KR3 b - - 0 1",
+                (Square64::E6, Square64::E1, MoveFlag::Capture),
+                0,
+            ),
+            (
+                "4k3/4r3/4r3/8/8/8/8/3KR3 b - - 0 1",
+                (Square64::E6, Square64::E1, MoveFlag::Capture),
+                550,
+            ),
+            (
+                "4k3/8/8/8/8/8/3q4/3RQ1K1 b - - 0 1",
+                (Square64::D2, Square64::D1, MoveFlag::Capture),
+                -450,
+            ),
+            // promoting wins the queen's value less the pawn's
+            (
+                "4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                (Square64::A7, Square64::A8, MoveFlag::QueenPromotion),
+                900,
+            ),
+        ];
+        for (fen, (from, to, flag), expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let output = position.see(Move::new(from, to, flag));
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::Position;
+use crate::{movegen, util::Color};
+
+/// Whether a game is still going, and if not, why it ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    /// The given color delivered mate and won
+    Checkmate(Color),
+    Stalemate,
+    DrawByRule(DrawReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    InsufficientMaterial,
+    DeadPosition,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+impl Position {
+    /// Status of the game given the keys of the earlier positions, oldest first (see
+    /// Position::repetitions). Mate and stalemate take precedence over the draw rules, so a mate
+    /// on the fiftieth move still wins. Threefold repetition and the fifty move rule are reported
+    /// as soon as they could be claimed.
+    pub fn status(&self, history: &[u64]) -> GameStatus {
+        if !self.has_legal_move() {
+            return match self.checkers.0 {
+                0 => GameStatus::Stalemate,
+                _ => GameStatus::Checkmate(!self.side_to_move),
+            };
+        }
+        let reason = if self.is_insufficient_material() {
+            DrawReason::InsufficientMaterial
+        } else if self.is_dead_position() {
+            DrawReason::DeadPosition
+        } else if self.is_seventy_five_move_draw() {
+            DrawReason::SeventyFiveMoveRule
+        } else if self.is_repetition(history) {
+            DrawReason::ThreefoldRepetition
+        } else if self.is_fifty_move_draw() {
+            DrawReason::FiftyMoveRule
+        } else {
+            return GameStatus::Ongoing;
+        };
+        GameStatus::DrawByRule(reason)
+    }
+
+    fn has_legal_move(&self) -> bool {
+        !movegen::legal_moves(self).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+
+    fn status_of(fen: &str) -> GameStatus {
+        Position::from_fen(fen).unwrap().status(&[])
+    }
+
+    #[test]
+    fn test_status_ongoing() {
+        let output = status_of(START_FEN);
+        let expected = GameStatus::Ongoing;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_checkmate() {
+        // fool's mate
+        let input = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+        let output = status_of(input);
+        let expected = GameStatus::Checkmate(Color::Black);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_check_with_escape() {
+        // the king can take the unprotected queen
+        let input = "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1";
+        let output = status_of(input);
+        let expected = GameStatus::Ongoing;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_stalemate() {
+        let input = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1";
+        let output = status_of(input);
+        let expected = GameStatus::Stalemate;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_en_passant_is_only_legal_move() {
+        // the black king is boxed in and the e pawn is blocked, so only exd3 e.p. avoids stalemate
+        let input = "7k/5K2/5N2/8/3Pp3/4P3/8/8 b - d3 0 1";
+        let output = status_of(input);
+        let expected = GameStatus::Ongoing;
+        assert_eq!(output, expected);
+
+        let input = "7k/5K2/5N2/8/3Pp3/4P3/8/8 b - - 0 1";
+        let output = status_of(input);
+        let expected = GameStatus::Stalemate;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_draw_by_rule() {
+        let output = status_of("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1");
+        let expected = GameStatus::DrawByRule(DrawReason::InsufficientMaterial);
+        assert_eq!(output, expected);
+
+        let mut input = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        input.halfmove_clock = 100;
+        let output = input.status(&[]);
+        let expected = GameStatus::DrawByRule(DrawReason::FiftyMoveRule);
+        assert_eq!(output, expected);
+    }
+}
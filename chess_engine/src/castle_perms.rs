@@ -1,5 +1,12 @@
 use crate::error::CastlePermConversionError;
-use std::fmt;
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use core::{
+    fmt,
+    ops::{BitAnd, BitOr, Not},
+};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display as EnumDisplay, EnumCount as EnumCountMacro, EnumIter, EnumString};
 
@@ -143,6 +150,136 @@ impl fmt::Display for CastlePerm {
     }
 }
 
+/// Castling rights stored as bit flags. The flag values match Castle so that a CastlingRights
+/// can be used directly as an index into tables of size NUM_CASTLE_PERM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights(u8);
+
+impl CastlingRights {
+    /// White can castle king side (K)
+    pub const WHITE_KING: Self = Self(Castle::WhiteKing as u8);
+    /// White can castle queen side (Q)
+    pub const WHITE_QUEEN: Self = Self(Castle::WhiteQueen as u8);
+    /// Black can castle king side (k)
+    pub const BLACK_KING: Self = Self(Castle::BlackKing as u8);
+    /// Black can castle queen side (q)
+    pub const BLACK_QUEEN: Self = Self(Castle::BlackQueen as u8);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn all() -> Self {
+        Self(0x0F)
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every flag set in other is also set in self
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Gives white's rights to black and black's to white, for mirroring a position
+    pub const fn swap_colors(self) -> Self {
+        Self(((self.0 & 0b0011) << 2) | ((self.0 & 0b1100) >> 2))
+    }
+}
+
+impl BitOr for CastlingRights {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for CastlingRights {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for CastlingRights {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0 & Self::all().0)
+    }
+}
+
+impl From<CastlePerm> for CastlingRights {
+    fn from(value: CastlePerm) -> Self {
+        Self(u8::from(value))
+    }
+}
+
+impl From<CastlingRights> for CastlePerm {
+    fn from(value: CastlingRights) -> Self {
+        CastlePerm::try_from(value.0).expect("CastlingRights should only ever hold bits in 0x0F")
+    }
+}
+
+impl From<CastlingRights> for u8 {
+    fn from(value: CastlingRights) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<u8> for CastlingRights {
+    type Error = CastlePermConversionError;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            v if v <= 0x0F => Ok(Self(v)),
+            _ => Err(CastlePermConversionError::FromU8ValueTooLarge(value)),
+        }
+    }
+}
+
+/// Parses the castling section of a FEN, with the same validation as CastlePerm
+impl TryFrom<&str> for CastlingRights {
+    type Error = CastlePermConversionError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        CastlePerm::try_from(value).map(Self::from)
+    }
+}
+
+/// Display in FEN style
+impl fmt::Display for CastlingRights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "-");
+        }
+        for (flag, c) in [
+            (Self::WHITE_KING, 'K'),
+            (Self::WHITE_QUEEN, 'Q'),
+            (Self::BLACK_KING, 'k'),
+            (Self::BLACK_QUEEN, 'q'),
+        ] {
+            if self.contains(flag) {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,4 +420,61 @@ mod tests {
         let expected = "Qk";
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_castling_rights_try_from_str_valid() {
+        let input = "Kq";
+        let output = CastlingRights::try_from(input);
+        let expected = Ok(CastlingRights::WHITE_KING | CastlingRights::BLACK_QUEEN);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_castling_rights_try_from_str_invalid_order() {
+        let input = "qK";
+        let output = CastlingRights::try_from(input);
+        let expected = Err(CastlePermConversionError::FromStr(input.to_owned()));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_castling_rights_display() {
+        let inputs = [
+            CastlingRights::all(),
+            CastlingRights::empty(),
+            CastlingRights::WHITE_QUEEN | CastlingRights::BLACK_KING,
+        ];
+        let output: Vec<String> = inputs.iter().map(|c| c.to_string()).collect();
+        let expected = vec!["KQkq", "-", "Qk"];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_castling_rights_round_trips_castle_perm() {
+        for bits in 0..NUM_CASTLE_PERM as u8 {
+            let rights = CastlingRights::try_from(bits).unwrap();
+            let output = CastlingRights::from(CastlePerm::from(rights));
+            assert_eq!(output, rights);
+        }
+    }
+
+    #[test]
+    fn test_castling_rights_insert_remove_contains() {
+        let mut input = CastlingRights::empty();
+        input.insert(CastlingRights::BLACK_QUEEN);
+        input.insert(CastlingRights::WHITE_KING);
+        input.remove(CastlingRights::WHITE_KING);
+        assert!(input.contains(CastlingRights::BLACK_QUEEN));
+        assert!(!input.contains(CastlingRights::WHITE_KING));
+    }
+
+    #[test]
+    fn test_castling_rights_swap_colors() {
+        let input =
+            CastlingRights::WHITE_KING | CastlingRights::WHITE_QUEEN | CastlingRights::BLACK_QUEEN;
+        let output = input.swap_colors();
+        let expected =
+            CastlingRights::BLACK_KING | CastlingRights::BLACK_QUEEN | CastlingRights::WHITE_QUEEN;
+        assert_eq!(output, expected);
+    }
 }
@@ -0,0 +1,326 @@
+//! Extended Position Description, the format test suites such as WAC and STS are distributed in.
+//!
+//! An EPD record is the first four fields of a FEN followed by operations, each an opcode and
+//! zero or more operands terminated by a semicolon, e.g.
+//! `2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";`.
+//! The opcodes the engine cares about are parsed into fields of Epd and everything else is kept
+//! as is. Moves stay in the SAN they were written in.
+
+use crate::{board::Position, error::EpdParseError};
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+/// Operation with an opcode Epd has no field for, with its operands unquoted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Epd {
+    /// Position of the record. The move counters come from the hmvc and fmvn operations. Without
+    /// hmvc the halfmove clock is 0, and without fmvn the fullmove number is just high enough for
+    /// that many half moves to have been played, i.e. 1 unless hmvc is given.
+    pub position: Position,
+    /// bm: best moves, any of which solves the position
+    pub best_moves: Vec<String>,
+    /// am: moves to avoid
+    pub avoid_moves: Vec<String>,
+    /// id: name of the position within its suite
+    pub id: Option<String>,
+    /// ce: evaluation in centipawns from the side to move's point of view
+    pub centipawn_eval: Option<i32>,
+    /// pv: principal variation, starting with a move of the side to move
+    pub principal_variation: Vec<String>,
+    /// Every other operation, in the order it appeared in
+    pub other: Vec<Operation>,
+}
+
+impl Epd {
+    /// Record of the position without any operations
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+            id: None,
+            centipawn_eval: None,
+            principal_variation: Vec::new(),
+            other: Vec::new(),
+        }
+    }
+}
+
+impl TryFrom<&str> for Epd {
+    type Error = EpdParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut rest = value.trim();
+        let mut fields = Vec::new();
+        for _ in 0..4 {
+            let (field, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return Err(EpdParseError::MissingFields(value.to_owned()));
+            }
+            fields.push(field);
+            rest = remainder.trim_start();
+        }
+
+        let mut epd = Epd::new(Position::new());
+        let (mut halfmove_clock, mut fullmove_number) = (0, None);
+        for (opcode, operands) in parse_operations(rest)? {
+            let invalid = || EpdParseError::InvalidOperand(opcode.to_owned(), operands.join(" "));
+            match opcode {
+                "bm" => epd
+                    .best_moves
+                    .extend(operands.iter().map(|&s| s.to_owned())),
+                "am" => epd
+                    .avoid_moves
+                    .extend(operands.iter().map(|&s| s.to_owned())),
+                "pv" => epd
+                    .principal_variation
+                    .extend(operands.iter().map(|&s| s.to_owned())),
+                "id" => epd.id = Some(single_operand(&operands).ok_or_else(invalid)?.to_owned()),
+                "ce" => {
+                    let operand = single_operand(&operands).ok_or_else(invalid)?;
+                    epd.centipawn_eval = Some(operand.parse().map_err(|_| invalid())?);
+                }
+                "hmvc" => {
+                    let operand = single_operand(&operands).ok_or_else(invalid)?;
+                    halfmove_clock = operand.parse::<u32>().map_err(|_| invalid())?;
+                }
+                "fmvn" => {
+                    let operand = single_operand(&operands).ok_or_else(invalid)?;
+                    fullmove_number = Some(operand.parse::<u32>().map_err(|_| invalid())?);
+                }
+                _ => epd.other.push(Operation {
+                    opcode: opcode.to_owned(),
+                    operands: operands.iter().map(|&s| s.to_owned()).collect(),
+                }),
+            }
+        }
+
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            halfmove_clock,
+            // without fmvn, a move number that leaves room for hmvc half moves with either side
+            // to move
+            fullmove_number.unwrap_or(halfmove_clock.div_ceil(2) + 1)
+        );
+        epd.position = Position::from_fen(&fen)?;
+        Ok(epd)
+    }
+}
+
+/// Writes the record with the operations in a fixed order: bm, am, ce, pv, id, hmvc and fmvn
+/// (only if the counters aren't 0 and 1), then the other operations
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fen = self.position.to_fen();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+        write!(f, "{}", fields.join(" "))?;
+
+        if !self.best_moves.is_empty() {
+            write_operation(f, "bm", &self.best_moves)?;
+        }
+        if !self.avoid_moves.is_empty() {
+            write_operation(f, "am", &self.avoid_moves)?;
+        }
+        if let Some(eval) = self.centipawn_eval {
+            write_operation(f, "ce", &[eval.to_string()])?;
+        }
+        if !self.principal_variation.is_empty() {
+            write_operation(f, "pv", &self.principal_variation)?;
+        }
+        if let Some(id) = &self.id {
+            // id is always quoted by convention
+            write!(f, " id \"{}\";", id)?;
+        }
+        if self.position.halfmove_clock() != 0 {
+            write_operation(f, "hmvc", &[self.position.halfmove_clock().to_string()])?;
+        }
+        if self.position.fullmove_number() != 1 {
+            write_operation(f, "fmvn", &[self.position.fullmove_number().to_string()])?;
+        }
+        for operation in &self.other {
+            write_operation(f, &operation.opcode, &operation.operands)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes " opcode operands;", quoting the operands that wouldn't read back as a single token
+fn write_operation(f: &mut fmt::Formatter<'_>, opcode: &str, operands: &[String]) -> fmt::Result {
+    write!(f, " {}", opcode)?;
+    for operand in operands {
+        match operand.is_empty() || operand.contains(|c: char| c.is_whitespace() || c == ';') {
+            true => write!(f, " \"{}\"", operand)?,
+            false => write!(f, " {}", operand)?,
+        }
+    }
+    write!(f, ";")
+}
+
+fn single_operand<'a>(operands: &[&'a str]) -> Option<&'a str> {
+    match operands {
+        [operand] => Some(operand),
+        _ => None,
+    }
+}
+
+/// Splits the operations of a record into opcodes and operands. Double quoted operands may
+/// contain spaces and semicolons.
+fn parse_operations(mut rest: &str) -> Result<Vec<(&str, Vec<&str>)>, EpdParseError> {
+    let token_end = |s: &str| {
+        s.find(|c: char| c.is_whitespace() || c == ';')
+            .unwrap_or(s.len())
+    };
+    let mut operations = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(operations);
+        }
+        let end = token_end(rest);
+        let opcode = &rest[..end];
+        rest = &rest[end..];
+        let mut operands = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            match rest.chars().next() {
+                None => return Err(EpdParseError::MissingSemicolon(opcode.to_owned())),
+                Some(';') => {
+                    rest = &rest[1..];
+                    break;
+                }
+                Some('"') => {
+                    let close = rest[1..]
+                        .find('"')
+                        .ok_or_else(|| EpdParseError::UnterminatedString(opcode.to_owned()))?;
+                    operands.push(&rest[1..=close]);
+                    rest = &rest[close + 2..];
+                }
+                Some(_) => {
+                    let end = token_end(rest);
+                    operands.push(&rest[..end]);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        // a stray semicolon leaves an empty opcode behind, which isn't an operation
+        if !opcode.is_empty() {
+            operations.push((opcode, operands));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ChessError, GamestateFENParseError};
+
+    const WAC_001: &str =
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+
+    #[test]
+    fn test_epd_parse() {
+        let output = Epd::try_from(WAC_001).unwrap();
+        let expected = Epd {
+            best_moves: ["Qg6".to_owned()].into(),
+            id: Some("WAC.001".to_owned()),
+            ..Epd::new(
+                Position::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1")
+                    .unwrap(),
+            )
+        };
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_epd_round_trip() {
+        let inputs = [
+            WAC_001,
+            "4k3/8/8/8/8/8/8/4K2R w K - bm O-O Rh8+; am Kd2; ce 350; pv O-O Kd7 Rh7+; id \"a; b\"; hmvc 4; fmvn 30; c0 \"king side castle\"; noop;",
+            "4k3/8/8/8/8/8/8/4K2R w K - hmvc 150; fmvn 2000;",
+        ];
+        for input in inputs {
+            let output = Epd::try_from(input).unwrap().to_string();
+            assert_eq!(output, input);
+        }
+    }
+
+    #[test]
+    fn test_epd_move_counters() {
+        let input = "4k3/8/8/8/8/8/8/4K2R w K - hmvc 100;";
+        let output = Epd::try_from(input).unwrap().position;
+        assert_eq!(output.halfmove_clock(), 100);
+        assert_eq!(output.fullmove_number(), 51);
+        assert!(output.is_fifty_move_draw());
+        assert!(!output.is_seventy_five_move_draw());
+
+        let input = "4k3/8/8/8/8/8/8/4K2R b K - hmvc 99;";
+        let output = Epd::try_from(input).unwrap().position;
+        assert_eq!(output.fullmove_number(), 51);
+    }
+
+    #[test]
+    fn test_epd_operations() {
+        let input = "4k3/8/8/8/8/8/8/4K2R b K -   ce -12 ;pv Kd7;; c0 \"x;y\" z;";
+        let output = Epd::try_from(input).unwrap();
+        assert_eq!(output.centipawn_eval, Some(-12));
+        assert_eq!(output.principal_variation, ["Kd7"]);
+        assert_eq!(
+            output.other,
+            [Operation {
+                opcode: "c0".to_owned(),
+                operands: ["x;y".to_owned(), "z".to_owned()].into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_epd_errors() {
+        let inputs = [
+            (
+                "4k3/8/8/8/8/8/8/4K3 w -",
+                EpdParseError::MissingFields("4k3/8/8/8/8/8/8/4K3 w -".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - bm Kd2",
+                EpdParseError::MissingSemicolon("bm".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - id \"open;",
+                EpdParseError::UnterminatedString("id".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - ce high;",
+                EpdParseError::InvalidOperand("ce".to_owned(), "high".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - id a b;",
+                EpdParseError::InvalidOperand("id".to_owned(), "a b".to_owned()),
+            ),
+        ];
+        for (input, expected) in inputs {
+            let output = Epd::try_from(input);
+            assert_eq!(output, Err(expected), "{}", input);
+        }
+
+        let output = Epd::try_from("4k3/8/8/8/8/8/8/4K3 w X - bm Kd2;");
+        assert!(matches!(
+            output,
+            Err(EpdParseError::InvalidPosition(ChessError::InvalidFen {
+                reason: GamestateFENParseError::CastlePerm(_),
+                ..
+            }))
+        ));
+    }
+}
@@ -1,20 +1,113 @@
-use std::num::ParseIntError;
+use alloc::{string::String, vec::Vec};
+use core::{fmt, num::ParseIntError};
 
 use crate::{
-    board::bitboard::BitBoard,
+    castle_perms::CastlingRights,
     gamestate::{HALF_MOVE_MAX, MAX_GAME_MOVES, NUM_FEN_SECTIONS},
     moves::Move,
+    pieces::PieceType,
     squares::{Square, Square64},
     util::{Color, File, Rank},
 };
 use strum::{EnumCount, ParseError as StrumParseError};
+use strum_macros::Display;
 
 use thiserror::Error;
 
+/// Top level error for library users. Each variant covers one domain and keeps the detailed
+/// error that caused it as its source.
 #[derive(Error, Debug, PartialEq)]
 pub enum ChessError {
+    #[error("index is out of range")]
+    InvalidIndex(#[source] IndexError),
+
+    #[error("FEN {position} is invalid")]
+    InvalidFen {
+        position: String,
+        #[source]
+        reason: GamestateFENParseError,
+    },
+
     #[error("illegal move attempted: {0}")]
     IllegalMove(Move),
+
+    #[error("could not parse value")]
+    ParseError(#[source] ParseError),
+
+    #[error("position is not legal")]
+    InvalidPosition(#[from] PositionValidationError),
+}
+
+impl From<IndexError> for ChessError {
+    fn from(value: IndexError) -> Self {
+        Self::InvalidIndex(value)
+    }
+}
+
+impl From<ParseError> for ChessError {
+    fn from(value: ParseError) -> Self {
+        Self::ParseError(value)
+    }
+}
+
+impl From<SquareConversionError> for ChessError {
+    fn from(value: SquareConversionError) -> Self {
+        Self::InvalidIndex(value.into())
+    }
+}
+
+impl From<Square64ConversionError> for ChessError {
+    fn from(value: Square64ConversionError) -> Self {
+        Self::InvalidIndex(value.into())
+    }
+}
+
+impl From<RankConversionError> for ChessError {
+    fn from(value: RankConversionError) -> Self {
+        Self::InvalidIndex(value.into())
+    }
+}
+
+impl From<FileConversionError> for ChessError {
+    fn from(value: FileConversionError) -> Self {
+        Self::InvalidIndex(value.into())
+    }
+}
+
+impl From<PieceConversionError> for ChessError {
+    fn from(value: PieceConversionError) -> Self {
+        Self::ParseError(value.into())
+    }
+}
+
+impl From<CastlePermConversionError> for ChessError {
+    fn from(value: CastlePermConversionError) -> Self {
+        Self::ParseError(value.into())
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum IndexError {
+    #[error(transparent)]
+    Square(#[from] SquareConversionError),
+
+    #[error(transparent)]
+    Square64(#[from] Square64ConversionError),
+
+    #[error(transparent)]
+    Rank(#[from] RankConversionError),
+
+    #[error(transparent)]
+    File(#[from] FileConversionError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseError {
+    #[error(transparent)]
+    Piece(#[from] PieceConversionError),
+
+    #[error(transparent)]
+    CastlePerm(#[from] CastlePermConversionError),
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -85,6 +178,112 @@ pub enum GamestateFENParseError {
     CastlePerm(String),
 }
 
+/// Section of a FEN string, in the order they appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum FenField {
+    PiecePlacement,
+    ActiveColor,
+    CastlingRights,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl FenField {
+    /// Field that the given parse error was found in, or None if the error is about the FEN as a
+    /// whole (e.g. wrong number of sections)
+    pub fn of(error: &GamestateFENParseError) -> Option<Self> {
+        match error {
+            GamestateFENParseError::BoardFENParseError(_) => Some(Self::PiecePlacement),
+            GamestateFENParseError::ActiveColor(_) => Some(Self::ActiveColor),
+            GamestateFENParseError::CastlePerm(_) => Some(Self::CastlingRights),
+            GamestateFENParseError::EnPassantFENParseError(_) => Some(Self::EnPassant),
+            GamestateFENParseError::HalfmoveClockFENParseError(_) => Some(Self::HalfmoveClock),
+            GamestateFENParseError::FullmoveCounterFENParseError(_) => Some(Self::FullmoveNumber),
+            GamestateFENParseError::Empty | GamestateFENParseError::WrongNumFENSections(_) => None,
+        }
+    }
+}
+
+/// A single problem found in a FEN string. index is the char index into the full FEN string
+#[derive(Error, Debug, PartialEq)]
+#[error("char {index}: {reason}")]
+pub struct FenProblem {
+    pub index: usize,
+    pub field: Option<FenField>,
+    #[source]
+    pub reason: GamestateFENParseError,
+}
+
+/// Every problem found in a FEN string, in the order the parser checks for them
+#[derive(Debug, PartialEq)]
+pub struct FenErrorReport {
+    pub fen: String,
+    pub problems: Vec<FenProblem>,
+}
+
+impl fmt::Display for FenErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FEN {} has {} problem(s)", self.fen, self.problems.len())?;
+        for problem in self.problems.iter() {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for FenErrorReport {}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PositionValidationError {
+    #[error("{0} has {1} kings but must have exactly one")]
+    InvalidKingNum(Color, u32),
+
+    #[error("pawn on {0} is on the first or last rank")]
+    PawnOnBackRank(Square64),
+
+    #[error("{0} is in check but it is not their turn")]
+    OpponentInCheck(Color),
+
+    #[error("en passant square {0} is inconsistent with the side to move or pawn placement")]
+    InvalidEnPassant(Square64),
+
+    #[error("castling rights {0} do not match the king and rook placement")]
+    CastlingRightsMismatch(CastlingRights),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SanParseError {
+    #[error("{0} is not a move in Standard Algebraic Notation")]
+    InvalidSan(String),
+
+    #[error("{0} is not a legal move in the position")]
+    IllegalSan(String),
+
+    #[error("{0} could be any of the moves {1:?}, it needs a file or rank to tell them apart")]
+    AmbiguousSan(String, Vec<String>),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EpdParseError {
+    #[error(
+        "EPD record {0} should start with board, side to move, castling and en passant fields"
+    )]
+    MissingFields(String),
+
+    #[error(transparent)]
+    InvalidPosition(#[from] ChessError),
+
+    #[error("operation {0} is not terminated by a semicolon")]
+    MissingSemicolon(String),
+
+    #[error("string operand of operation {0} is never closed")]
+    UnterminatedString(String),
+
+    #[error("operand {1} of operation {0} is invalid")]
+    InvalidOperand(String, String),
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum EnPassantFENParseError {
     #[error("en passant square {0} is invalid")]
@@ -133,18 +332,6 @@ pub enum HalfmoveClockFENParseError {
     NonZeroWhileEnPassant,
 }
 
-#[derive(Error, Debug, PartialEq)]
-pub enum BitBoardError {
-    #[error("cannot check bit at index {0}, which is greater than 63")]
-    BitBoardCheckBitInvalidIndex(u8),
-
-    #[error("cannot set bit at index {0}, which is greater than 63")]
-    BitBoardSetBitInvalidIndex(u8),
-
-    #[error("cannot unset bit at index {0}, which is greater than 63")]
-    BitBoardUnsetBitInvalidIndex(u8),
-}
-
 #[derive(Error, Debug, PartialEq)]
 pub enum PieceConversionError {
     #[error("could not convert char {0} into a Piece")]
@@ -167,10 +354,34 @@ pub enum CastlePermConversionError {
     FromStr(String),
 }
 
+#[derive(Error, Debug, PartialEq)]
+pub enum MoveConversionError {
+    #[error("could not convert u16 {0} into a Move because its flag bits are unused")]
+    FromU16(u16),
+
+    #[error("{0} is not a move in UCI coordinate notation")]
+    InvalidUci(String),
+
+    #[error("{0} is not a legal move in the position")]
+    IllegalUci(String),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PromotionConversionError {
+    #[error("a pawn can't promote to a {0:?}")]
+    FromPieceType(PieceType),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MoveFlagConversionError {
+    #[error("could not convert u8 {0} into a MoveFlag")]
+    FromU8(u8),
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum SquareConversionError {
     #[error("could not convert &str {0} into a Square")]
-    FromStr(#[from] StrumParseError),
+    FromStr(#[cfg_attr(feature = "std", from)] StrumParseError),
 
     #[error("could not convert i8 {0} into a Square")]
     FromI8(i8),
@@ -188,7 +399,7 @@ pub enum SquareConversionError {
 #[derive(Error, Debug, PartialEq)]
 pub enum Square64ConversionError {
     #[error("could not convert &str {0} into a Square64")]
-    FromStr(#[from] StrumParseError),
+    FromStr(#[cfg_attr(feature = "std", from)] StrumParseError),
 
     #[error("could not convert u8 {0} into a Square64")]
     FromU8(u8),
@@ -200,6 +411,22 @@ pub enum Square64ConversionError {
     FromUsize(usize),
 }
 
+// strum's ParseError only implements Error with std, so without it the Square conversion errors
+// can't hold it as a #[from] source and convert from it by hand instead
+#[cfg(not(feature = "std"))]
+impl From<StrumParseError> for SquareConversionError {
+    fn from(value: StrumParseError) -> Self {
+        Self::FromStr(value)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<StrumParseError> for Square64ConversionError {
+    fn from(value: StrumParseError) -> Self {
+        Self::FromStr(value)
+    }
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum RankConversionError {
     #[error("could not convert usize {0} into a Rank")]
@@ -211,3 +438,35 @@ pub enum FileConversionError {
     #[error("could not convert usize {0} into a File")]
     FromUsize(usize),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_chess_error_from_square_conversion_error() {
+        let input = SquareConversionError::FromU8(0);
+        let output = ChessError::from(input);
+        let expected =
+            ChessError::InvalidIndex(IndexError::Square(SquareConversionError::FromU8(0)));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_chess_error_from_piece_conversion_error() {
+        let input = PieceConversionError::FromChar('x');
+        let output = ChessError::from(input);
+        let expected =
+            ChessError::ParseError(ParseError::Piece(PieceConversionError::FromChar('x')));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_chess_error_source_chain() {
+        let input = ChessError::from(RankConversionError::FromUsize(8));
+        let output = input.source().map(|e| e.to_string());
+        let expected = Some(RankConversionError::FromUsize(8).to_string());
+        assert_eq!(output, expected);
+    }
+}
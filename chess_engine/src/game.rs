@@ -0,0 +1,467 @@
+use crate::{
+    board::{GameStatus, Position, Undo},
+    error::ChessError,
+    moves::Move,
+    search::Score,
+    util::Color,
+};
+use alloc::{format, string::String, vec::Vec};
+use core::time::Duration;
+
+/// A Position together with the moves that led to it, so that moves can be taken back and
+/// repetitions detected without the caller keeping an undo stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    position: Position,
+    undos: Vec<Undo>,
+    /// Keys of every earlier position, oldest first
+    keys: Vec<u64>,
+    /// Annotation of each move, see annotate
+    annotations: Vec<MoveAnnotation>,
+    /// Lines played instead of the move at a ply, in the order they were added
+    variations: Vec<(usize, Vec<Move>)>,
+}
+
+/// Eval and clock of a move, which movetext writes in a comment after it as the [%eval] and
+/// [%clk] commands of PGN do, e.g. {[%eval 0.25] [%clk 0:04:52]}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveAnnotation {
+    /// Score of the position after the move from white's point of view
+    pub eval: Option<Score>,
+    /// Time the player who moved had left after the move
+    pub clock: Option<Duration>,
+}
+
+/// How Game::movetext writes the moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovetextOptions {
+    /// Longest line, breaking between moves and comments, or None to write all of it on one
+    /// line. A comment longer than that still gets a line of its own.
+    pub line_width: Option<usize>,
+    /// Write the evals and clocks of the annotated moves
+    pub annotations: bool,
+    /// Write the variations in parentheses after the moves they're played instead of
+    pub variations: bool,
+}
+
+impl Default for MovetextOptions {
+    fn default() -> Self {
+        Self {
+            line_width: None,
+            annotations: true,
+            variations: true,
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Position::start())
+    }
+}
+
+impl Game {
+    /// Starts a game from the given position with no history
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            undos: Vec::new(),
+            keys: Vec::new(),
+            annotations: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Keys of the positions before the current one, oldest first
+    pub fn history(&self) -> &[u64] {
+        &self.keys
+    }
+
+    /// Moves played since the game was created, oldest first
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.undos.iter().map(Undo::move_)
+    }
+
+    /// Plays a pseudo legal move, see Position::make_move
+    pub fn make_move(&mut self, move_: Move) {
+        self.keys.push(self.position.key());
+        self.undos.push(self.position.make_move(move_));
+        self.annotations.push(MoveAnnotation::default());
+    }
+
+    /// Takes back the last move, returning it, or None if no moves were played. Its annotation
+    /// and variations go with it.
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let undo = self.undos.pop()?;
+        self.keys.pop();
+        self.annotations.pop();
+        let ply = self.undos.len();
+        self.variations
+            .retain(|&(variation_ply, _)| variation_ply < ply);
+        self.position.unmake_move(undo);
+        Some(undo.move_())
+    }
+
+    /// Sets the annotation of the last move, if any was played
+    pub fn annotate(&mut self, annotation: MoveAnnotation) {
+        if let Some(last) = self.annotations.last_mut() {
+            *last = annotation;
+        }
+    }
+
+    /// Adds a line played instead of the move at ply, counting the first move of the game as
+    /// ply 0, which must be one of the moves played. The moves have to be legal one after the
+    /// other from the position before that move, or the first one that isn't is returned.
+    pub fn add_variation(&mut self, ply: usize, moves: &[Move]) -> Result<(), ChessError> {
+        assert!(ply < self.undos.len(), "only a played move has variations");
+        let mut position = self.position_at(ply);
+        for &move_ in moves {
+            if !position.is_legal(move_) {
+                return Err(ChessError::IllegalMove(move_));
+            }
+            position.make_move(move_);
+        }
+        self.variations.push((ply, moves.to_vec()));
+        Ok(())
+    }
+
+    /// Position before the move at ply, or the current one at the number of moves played
+    fn position_at(&self, ply: usize) -> Position {
+        let mut position = self.position.clone();
+        for &undo in self.undos[ply..].iter().rev() {
+            position.unmake_move(undo);
+        }
+        position
+    }
+
+    /// Moves played since the game was created in SAN, oldest first
+    pub fn san_history(&self) -> Vec<String> {
+        let mut position = self.position_at(0);
+        self.moves()
+            .map(|move_| {
+                let san = move_.to_san(&position);
+                position.make_move(move_);
+                san
+            })
+            .collect()
+    }
+
+    /// Numbered SAN movetext of the moves played, e.g. 1. e4 e5 2. Nf3, without the tag pairs
+    /// and result of a full PGN. A game that starts with black to move starts with e.g. 1... e5,
+    /// as does a black move after a comment or variation.
+    pub fn movetext(&self, options: &MovetextOptions) -> String {
+        let mut tokens = Vec::new();
+        let mut position = self.position_at(0);
+        let mut numbered = false;
+        for (ply, (move_, annotation)) in self.moves().zip(&self.annotations).enumerate() {
+            push_move(&mut tokens, &position, move_, numbered);
+            numbered = true;
+            if options.annotations {
+                if let Some(comment) = annotation_comment(annotation) {
+                    tokens.push(comment);
+                    numbered = false;
+                }
+            }
+            if options.variations {
+                for (_, moves) in self.variations.iter().filter(|&&(at, _)| at == ply) {
+                    let start = tokens.len();
+                    let mut variation = position.clone();
+                    for (index, &move_) in moves.iter().enumerate() {
+                        push_move(&mut tokens, &variation, move_, index > 0);
+                        variation.make_move(move_);
+                    }
+                    tokens[start].insert(0, '(');
+                    tokens.last_mut().unwrap().push(')');
+                    numbered = false;
+                }
+            }
+            position.make_move(move_);
+        }
+        wrap(&tokens, options.line_width)
+    }
+
+    /// Returns true if the current position has occurred three times
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.position.is_repetition(&self.keys)
+    }
+
+    /// See Position::status
+    pub fn status(&self) -> GameStatus {
+        self.position.status(&self.keys)
+    }
+}
+
+/// Pushes the SAN of a move, after its number if white moves or black's move isn't numbered
+/// yet, so a line never breaks between the two
+fn push_move(tokens: &mut Vec<String>, position: &Position, move_: Move, numbered: bool) {
+    let san = move_.to_san(position);
+    let number = position.fullmove_number();
+    tokens.push(match position.side_to_move() {
+        Color::White => format!("{}. {}", number, san),
+        Color::Black if !numbered => format!("{}... {}", number, san),
+        Color::Black => san,
+    });
+}
+
+/// Comment of the eval and clock of a move, None if it has neither
+fn annotation_comment(annotation: &MoveAnnotation) -> Option<String> {
+    let mut commands = Vec::new();
+    match annotation.eval {
+        Some(Score::Centipawns(centipawns)) => {
+            let sign = if centipawns < 0 { "-" } else { "" };
+            let centipawns = centipawns.unsigned_abs();
+            commands.push(format!(
+                "[%eval {}{}.{:02}]",
+                sign,
+                centipawns / 100,
+                centipawns % 100
+            ));
+        }
+        Some(Score::Mate(moves)) => commands.push(format!("[%eval #{}]", moves)),
+        None => {}
+    }
+    if let Some(clock) = annotation.clock {
+        let seconds = clock.as_secs();
+        commands.push(format!(
+            "[%clk {}:{:02}:{:02}]",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ));
+    }
+    (!commands.is_empty()).then(|| format!("{{{}}}", commands.join(" ")))
+}
+
+/// Joins the tokens with spaces, starting a new line before a token that would make the line
+/// longer than line_width
+fn wrap(tokens: &[String], line_width: Option<usize>) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        let length = token.chars().count();
+        if line_length > 0 {
+            match line_width.is_some_and(|width| line_length + 1 + length > width) {
+                true => {
+                    text.push('\n');
+                    line_length = 0;
+                }
+                false => {
+                    text.push(' ');
+                    line_length += 1;
+                }
+            }
+        }
+        text.push_str(token);
+        line_length += length;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::DrawReason, moves::MoveFlag, squares::Square64};
+
+    const KNIGHT_SHUFFLE: [(Square64, Square64); 4] = [
+        (Square64::G1, Square64::F3),
+        (Square64::G8, Square64::F6),
+        (Square64::F3, Square64::G1),
+        (Square64::F6, Square64::G8),
+    ];
+
+    fn shuffle_knights(game: &mut Game) {
+        for (from, to) in KNIGHT_SHUFFLE {
+            game.make_move(Move::new(from, to, MoveFlag::Quiet));
+        }
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut input = Game::default();
+        shuffle_knights(&mut input);
+        assert_eq!(input.position().repetitions(input.history()), 1);
+        assert!(!input.is_threefold_repetition());
+        shuffle_knights(&mut input);
+        let output = input.is_threefold_repetition();
+        let expected = true;
+        assert_eq!(output, expected);
+        assert_eq!(
+            input.status(),
+            GameStatus::DrawByRule(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn test_threefold_repetition_after_double_push() {
+        // no black pawn can take on e3, so the position after 1.e4 is the one the knights
+        // come back to
+        let mut input = Game::default();
+        input.make_move(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush,
+        ));
+        for _ in 0..2 {
+            for (from, to) in [
+                (Square64::G8, Square64::F6),
+                (Square64::G1, Square64::F3),
+                (Square64::F6, Square64::G8),
+                (Square64::F3, Square64::G1),
+            ] {
+                input.make_move(Move::new(from, to, MoveFlag::Quiet));
+            }
+        }
+        assert_eq!(input.position().repetitions(input.history()), 2);
+        let output = input.is_threefold_repetition();
+        let expected = true;
+        assert_eq!(output, expected);
+        assert_eq!(
+            input.status(),
+            GameStatus::DrawByRule(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn test_repetition_reset_by_irreversible_move() {
+        let mut input = Game::default();
+        shuffle_knights(&mut input);
+        // the repeated positions before the pawn move can't be reached again
+        input.make_move(Move::new(Square64::E2, Square64::E3, MoveFlag::Quiet));
+        input.make_move(Move::new(Square64::E7, Square64::E6, MoveFlag::Quiet));
+        shuffle_knights(&mut input);
+        let output = input.position().repetitions(input.history());
+        let expected = 1;
+        assert_eq!(output, expected);
+    }
+
+    fn play(game: &mut Game, sans: &[&str]) {
+        for san in sans {
+            let move_ = Move::from_san(game.position(), san).unwrap();
+            game.make_move(move_);
+        }
+    }
+
+    #[test]
+    fn test_san_history() {
+        let mut input = Game::default();
+        play(
+            &mut input,
+            &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"],
+        );
+        let output = input.san_history();
+        let expected = ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"];
+        assert_eq!(output, expected);
+        assert_eq!(Game::default().san_history(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_movetext() {
+        let mut input = Game::default();
+        play(&mut input, &["e4", "e5", "Nf3"]);
+        let output = input.movetext(&MovetextOptions::default());
+        assert_eq!(output, "1. e4 e5 2. Nf3");
+        assert_eq!(Game::default().movetext(&MovetextOptions::default()), "");
+
+        // a game that starts with black to move numbers its first move
+        let position =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        let mut input = Game::new(position);
+        play(&mut input, &["c5", "Nf3", "d6"]);
+        let output = input.movetext(&MovetextOptions::default());
+        assert_eq!(output, "1... c5 2. Nf3 d6");
+    }
+
+    #[test]
+    fn test_movetext_line_width() {
+        let mut input = Game::default();
+        play(&mut input, &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        let options = MovetextOptions {
+            line_width: Some(12),
+            ..MovetextOptions::default()
+        };
+        let output = input.movetext(&options);
+        assert_eq!(output, "1. e4 e5\n2. Nf3 Nc6\n3. Bb5 a6");
+        assert!(output.lines().all(|line| line.len() <= 12));
+    }
+
+    #[test]
+    fn test_movetext_annotations() {
+        let mut input = Game::default();
+        play(&mut input, &["e4"]);
+        input.annotate(MoveAnnotation {
+            eval: Some(Score::Centipawns(25)),
+            clock: Some(Duration::from_secs(292)),
+        });
+        play(&mut input, &["e5"]);
+        input.annotate(MoveAnnotation {
+            eval: Some(Score::Centipawns(-105)),
+            clock: None,
+        });
+        play(&mut input, &["Qh5"]);
+        input.annotate(MoveAnnotation {
+            eval: Some(Score::Mate(-3)),
+            clock: Some(Duration::from_secs(3_725)),
+        });
+        let output = input.movetext(&MovetextOptions::default());
+        let expected = "1. e4 {[%eval 0.25] [%clk 0:04:52]} 1... e5 {[%eval -1.05]} \
+                        2. Qh5 {[%eval #-3] [%clk 1:02:05]}";
+        assert_eq!(output, expected);
+        let options = MovetextOptions {
+            annotations: false,
+            ..MovetextOptions::default()
+        };
+        assert_eq!(input.movetext(&options), "1. e4 e5 2. Qh5");
+    }
+
+    #[test]
+    fn test_movetext_variations() {
+        let mut input = Game::default();
+        play(&mut input, &["e4", "e5", "Nf3"]);
+        let sicilian = [
+            Move::from_san(&input.position_at(1), "c5").unwrap(),
+            Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet),
+        ];
+        input.add_variation(1, &sicilian).unwrap();
+        let d4 = Move::new(Square64::D2, Square64::D4, MoveFlag::DoublePawnPush);
+        input.add_variation(0, &[d4]).unwrap();
+        let output = input.movetext(&MovetextOptions::default());
+        assert_eq!(output, "1. e4 (1. d4) 1... e5 (1... c5 2. Nf3) 2. Nf3");
+        let options = MovetextOptions {
+            variations: false,
+            ..MovetextOptions::default()
+        };
+        assert_eq!(input.movetext(&options), "1. e4 e5 2. Nf3");
+
+        // the knight can't go to f3 with black to move
+        let output = input.add_variation(1, &[sicilian[1]]);
+        assert_eq!(output, Err(ChessError::IllegalMove(sicilian[1])));
+        // taking back a move takes its variations with it
+        input.unmake_move();
+        input.unmake_move();
+        assert_eq!(input.movetext(&MovetextOptions::default()), "1. e4 (1. d4)");
+    }
+
+    #[test]
+    fn test_unmake_move_restores_history() {
+        let mut input = Game::default();
+        input.make_move(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush,
+        ));
+        let output = input.unmake_move();
+        let expected = Some(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush,
+        ));
+        assert_eq!(output, expected);
+        assert_eq!(input, Game::default());
+        assert_eq!(input.unmake_move(), None);
+    }
+}
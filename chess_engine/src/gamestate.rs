@@ -1,20 +1,26 @@
-use rand::prelude::*;
-use rand_pcg::Lcg128Xsl64;
-use std::{
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
     default,
     fmt::{self, write},
     num::ParseIntError,
 };
-use strum::EnumCount;
+use rand::prelude::*;
+use rand_pcg::Lcg128Xsl64;
+use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display as EnumDisplay, EnumCount as EnumCountMacro};
 
 use crate::{
-    board::Board,
+    board::{Board, Position, START_FEN},
     castle_perms::{self, CastlePerm, NUM_CASTLE_PERM},
     error::{
-        BoardFENParseError, CastlePermConversionError, EnPassantFENParseError,
-        FullmoveCounterFENParseError, GamestateFENParseError, HalfmoveClockFENParseError,
-        RankFENParseError, SquareConversionError,
+        BoardFENParseError, CastlePermConversionError, ChessError, EnPassantFENParseError,
+        FenErrorReport, FenField, FenProblem, FullmoveCounterFENParseError, GamestateFENParseError,
+        HalfmoveClockFENParseError, RankFENParseError, SquareConversionError,
     },
     pieces::{self, Piece, PieceType},
     squares::{Square, Square64},
@@ -30,7 +36,7 @@ pub const HALF_MOVE_MAX: usize = 100;
 pub const NUM_FEN_SECTIONS: usize = 6;
 /// Number of squares for the internal board (10x12)
 pub const NUM_BOARD_SQUARES: usize = 120;
-const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_FEN: &str = START_FEN;
 // TODO: test to make sure seed is a good choice
 /// Seed used for Zobrist Hashing. Note that many PRNG implementations will behave poorly
 /// if the seed is poorly distributed (it should have roughly equal number of 0s and 1s)
@@ -101,6 +107,26 @@ impl Default for Zobrist {
     }
 }
 
+/// Moves the validated FEN state over to the bitboard based Position
+impl From<&Gamestate> for Position {
+    fn from(gamestate: &Gamestate) -> Self {
+        let mut position = Position::new();
+        for square in Square::iter() {
+            if let Some(piece) = gamestate.board.pieces[square as usize] {
+                position.add_piece(piece, square.to_square64());
+            }
+        }
+        position.side_to_move = gamestate.active_color;
+        position.castling_rights = gamestate.castle_permissions.into();
+        position.en_passant = gamestate.en_passant.map(Square::to_square64);
+        position.halfmove_clock = gamestate.halfmove_clock;
+        position.fullmove_number = gamestate.fullmove_number;
+        position.key = position.compute_key();
+        position.update_check_info();
+        position
+    }
+}
+
 // TODO: consider making the Gamestate with the builder pattern
 // TODO: make Zobrist generate at compile time with proc macro
 #[derive(Debug, PartialEq, Eq)]
@@ -174,6 +200,39 @@ impl Gamestate {
         }
     }
 
+    /// Same as TryFrom<&str>, but wraps any failure in a ChessError that records the offending FEN
+    pub fn from_fen(fen: &str) -> Result<Self, ChessError> {
+        Self::gen_gamestate_from_fen(fen).map_err(|reason| ChessError::InvalidFen {
+            position: fen.to_owned(),
+            reason,
+        })
+    }
+
+    /// Same as TryFrom<&str>, but on failure reports every problem found in the FEN along with the
+    /// char index and field it was found in, rather than just the first one.
+    ///
+    /// Checks that depend on more than one field (e.g. an en passant square needing a pawn in
+    /// front of it) can only run once each field is valid on its own, so they're reported alone.
+    pub fn from_fen_with_report(fen: &str) -> Result<Self, FenErrorReport> {
+        Self::parse_fen(fen).map_err(|problems| FenErrorReport {
+            fen: fen.to_owned(),
+            problems,
+        })
+    }
+
+    /// Splits a FEN into its non-empty sections, keeping the char index each one starts at
+    fn split_fen_sections(fen: &str) -> Vec<(usize, &str)> {
+        let mut index = fen.chars().take_while(|c| c.is_whitespace()).count();
+        let mut sections = Vec::new();
+        for section in fen.trim().split(' ') {
+            if !section.is_empty() {
+                sections.push((index, section));
+            }
+            index += section.chars().count() + 1;
+        }
+        sections
+    }
+
     /// Determine if the provided square is currently under attack
     fn is_square_attacked(&self, square: Square) -> bool {
         // depending on active_color determine which pieces to check
@@ -211,7 +270,7 @@ impl Gamestate {
                 // and early out entirely if we find an attacking piece
                 sliding if piece.is_sliding() => {
                     // Optimization: bishops can never attack a square that is a different color than they are
-                    if (sliding.is_bishop() && (square.get_color() != sliding.get_color())) {
+                    if (sliding.is_bishop() && (square.get_color() != sliding.color())) {
                         continue;
                     }
 
@@ -253,228 +312,252 @@ impl Gamestate {
 
     // TODO: make sure that on the frontend the number of characters that can be passed is limited to something reasonable
     // TODO: check that bishops are on squares that have the same color as them
-    /// Validate full FEN string and generate valid Gamestate object if validation succeeds
+    /// Validate full FEN string and generate valid Gamestate object if validation succeeds,
+    /// failing with the first problem parse_fen finds
     fn gen_gamestate_from_fen(fen: &str) -> Result<Self, GamestateFENParseError> {
-        let fen_sections: Vec<&str> = fen.trim().split(' ').collect();
-        let mut fen_sections_iterator = fen_sections.iter();
+        Self::parse_fen(fen).map_err(|mut problems| problems.remove(0).reason)
+    }
+
+    /// Parses a FEN and collects every problem found, in the order they appear in it. Each field
+    /// is checked on its own first, and the checks that depend on more than one field only run
+    /// once every field is valid.
+    fn parse_fen(fen: &str) -> Result<Self, Vec<FenProblem>> {
+        let sections = Self::split_fen_sections(fen);
 
         // deal with board FEN separately because spaces in the middle will break parse and complicate things
-        let board_str = *fen_sections_iterator
-            .next()
-            .ok_or(GamestateFENParseError::Empty)?;
-        let board = Board::try_from(board_str)?;
-
-        // go through rest of FEN sections and clean up any empty sections aka extra spaces
-        let mut remaining_sections: Vec<&str> = vec![]; // will not include any empty sections
-        for &section in fen_sections_iterator {
-            // TODO: get rid of is_empty and use patten matching if let [first, ..]
-            // Check if current fen section is empty (if so just a space that we can ignore)
-            if !section.is_empty() {
-                remaining_sections.push(section);
+        let Some(&(board_index, board_str)) = sections.first() else {
+            return Err(vec![FenProblem {
+                index: 0,
+                field: None,
+                reason: GamestateFENParseError::Empty,
+            }]);
+        };
+        let (board, board_problems) = Board::parse_fen(board_str);
+        let mut problems: Vec<FenProblem> = board_problems
+            .into_iter()
+            .map(|(index, error)| FenProblem {
+                index: board_index + index,
+                field: Some(FenField::PiecePlacement),
+                reason: error.into(),
+            })
+            .collect();
+
+        if sections.len() != NUM_FEN_SECTIONS {
+            // the other sections can't be matched up with their fields, so only point at the
+            // first extra section, or the end of the FEN if sections are missing
+            let index = sections.get(NUM_FEN_SECTIONS).map_or_else(
+                || sections.last().map_or(0, |&(i, s)| i + s.chars().count()),
+                |&(i, _)| i,
+            );
+            problems.push(FenProblem {
+                index,
+                field: None,
+                reason: GamestateFENParseError::WrongNumFENSections(sections.len()),
+            });
+            return Err(problems);
+        }
+
+        let active_color = Self::parse_fen_field(
+            &sections,
+            FenField::ActiveColor,
+            Self::parse_active_color,
+            &mut problems,
+        );
+        let castle_permissions = Self::parse_fen_field(
+            &sections,
+            FenField::CastlingRights,
+            Self::parse_castle_permissions,
+            &mut problems,
+        );
+        let en_passant = Self::parse_fen_field(
+            &sections,
+            FenField::EnPassant,
+            Self::parse_en_passant,
+            &mut problems,
+        );
+        let halfmove_clock = Self::parse_fen_field(
+            &sections,
+            FenField::HalfmoveClock,
+            Self::parse_halfmove_clock,
+            &mut problems,
+        );
+        let fullmove_number = Self::parse_fen_field(
+            &sections,
+            FenField::FullmoveNumber,
+            Self::parse_fullmove_number,
+            &mut problems,
+        );
+        let (
+            Some(active_color),
+            Some(castle_permissions),
+            Some(en_passant),
+            Some(halfmove_clock),
+            Some(fullmove_number),
+        ) = (
+            active_color,
+            castle_permissions,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        )
+        else {
+            return Err(problems);
+        };
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        // TODO: Check if active color can win in one move and disallow
+        let gamestate = Gamestate {
+            board,
+            active_color,
+            castle_permissions,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            history: Vec::new(),
+            zobrist: Zobrist::default(),
+        };
+        gamestate.check_fen_fields_agree().map_err(|reason| {
+            let field = FenField::of(&reason);
+            let index = field.map_or(0, |field| sections[field as usize].0);
+            vec![FenProblem {
+                index,
+                field,
+                reason,
+            }]
+        })?;
+        Ok(gamestate)
+    }
+
+    /// Parses the section of a FEN that holds the given field, recording a problem if it's invalid
+    fn parse_fen_field<T, E: Into<GamestateFENParseError>>(
+        sections: &[(usize, &str)],
+        field: FenField,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+        problems: &mut Vec<FenProblem>,
+    ) -> Option<T> {
+        let (index, section) = sections[field as usize];
+        match parse(section) {
+            Ok(value) => Some(value),
+            Err(reason) => {
+                problems.push(FenProblem {
+                    index,
+                    field: Some(field),
+                    reason: reason.into(),
+                });
+                None
             }
         }
+    }
 
-        match remaining_sections.len() {
-            len if len == NUM_FEN_SECTIONS - 1 => {
-                let active_color_str = remaining_sections[0];
-                // active_color_str here should be either "w" or "b"
-                let active_color = match active_color_str {
-                    white if white == char::from(Color::White).to_string() => Color::White,
-                    black if black == char::from(Color::Black).to_string() => Color::Black,
-                    _ => {
-                        return Err(GamestateFENParseError::ActiveColor(
-                            active_color_str.to_string(),
-                        ))
-                    }
-                };
-
-                // Check that castling permissions don't contradict position of rooks and kings
-                // TODO: look into X-FEN and Shredder-FEN for Chess960
-                let castle_permissions_str = remaining_sections[1];
-                let castle_permissions = match CastlePerm::try_from(castle_permissions_str) {
-                    Ok(cp) => cp,
-                    Err(_) => {
-                        return Err(GamestateFENParseError::CastlePerm(
-                            castle_permissions_str.to_string(),
-                        ))
-                    }
-                };
-
-                let en_passant_str = remaining_sections[2];
-                let en_passant = match Square::try_from(en_passant_str.to_uppercase().as_str()) {
-                    // en_passant_str must be lowercase
-                    Ok(ep) => match en_passant_str {
-                        uppercase if uppercase == en_passant_str.to_uppercase() => {
-                            return Err(GamestateFENParseError::EnPassantFENParseError(
-                                EnPassantFENParseError::EnPassantUppercase,
-                            ))
-                        }
-                        _ => {
-                            // If active color is black then en_passant rank has to be 3.
-                            let ep_rank = ep.get_rank();
-                            match ep_rank {
-                                Rank::Rank3 => match active_color {
-                                    Color::Black => {
-                                        // check that the en passant square and the one behind it are empty
-                                        let ep_empty = board.pieces[ep as usize].is_none();
-                                        let square_behind =
-                                            Square::from_file_and_rank(ep.get_file(), Rank::Rank2);
-                                        let square_behind_empty =
-                                            board.pieces[square_behind as usize].is_none();
-                                        let square_ahead =
-                                            Square::from_file_and_rank(ep.get_file(), Rank::Rank4);
-                                        match (ep_empty & square_behind_empty) {
-                                            // check that white pawn is in front of en passant square
-                                            true => match board.pawns[0].check_bit(Square64::from(square_ahead)) {
-                                                true => Some(ep),
-                                                false => return Err(GamestateFENParseError::EnPassantFENParseError(EnPassantFENParseError::CorrectPawnNotInFront(Color::White, ep)))
-                                            },
-                                            false => return Err(GamestateFENParseError::EnPassantFENParseError(EnPassantFENParseError::NonEmptySquares)),
-                                        }
-                                    }
-                                    Color::White => {
-                                        return Err(GamestateFENParseError::EnPassantFENParseError(
-                                            EnPassantFENParseError::ColorRankMismatch(
-                                                active_color,
-                                                ep_rank,
-                                            ),
-                                        ))
-                                    }
-                                },
-                                // If active color is white then en_passant rank has to be 6.
-                                Rank::Rank6 => match active_color {
-                                    Color::White => {
-                                        // check that the en passant square and the one behind it are empty
-                                        let ep_empty = board.pieces[ep as usize].is_none();
-                                        let square_behind =
-                                            Square::from_file_and_rank(ep.get_file(), Rank::Rank7);
-                                        let square_behind_empty =
-                                            board.pieces[square_behind as usize].is_none();
-                                        let square_ahead =
-                                            Square::from_file_and_rank(ep.get_file(), Rank::Rank5);
-                                        match (ep_empty & square_behind_empty) {
-                                            // check that black pawn is in front of en passant square
-                                            true => match board.pawns[1].check_bit(Square64::from(square_ahead)) {
-                                                true => Some(ep),
-                                                false => return Err(GamestateFENParseError::EnPassantFENParseError(EnPassantFENParseError::CorrectPawnNotInFront(Color::Black, ep)))
-                                            },
-                                            false => return Err(GamestateFENParseError::EnPassantFENParseError(EnPassantFENParseError::NonEmptySquares)),
-                                        }
-                                    }
-                                    Color::Black => {
-                                        return Err(GamestateFENParseError::EnPassantFENParseError(
-                                            EnPassantFENParseError::ColorRankMismatch(
-                                                active_color,
-                                                ep_rank,
-                                            ),
-                                        ))
-                                    }
-                                },
-                                _ => {
-                                    return Err(GamestateFENParseError::EnPassantFENParseError(
-                                        EnPassantFENParseError::Rank(ep_rank),
-                                    ))
-                                }
-                            }
-                        }
-                    },
-                    Err(_) => match en_passant_str {
-                        "-" => None,
-                        _ => {
-                            return Err(GamestateFENParseError::EnPassantFENParseError(
-                                EnPassantFENParseError::SquareConversionError(
-                                    SquareConversionError::FromStr(
-                                        strum::ParseError::VariantNotFound,
-                                    ),
-                                ),
-                            ))
-                        }
-                    },
-                };
-
-                let halfmove_clock_str = remaining_sections[3];
-                let halfmove_clock = match halfmove_clock_str.parse::<u32>() {
-                    Ok(num) => match num {
-                        // if this num was 100 the game would immediately tie, so this is considered invalid
-                        n if (0..HALF_MOVE_MAX).contains(&(n as usize)) => {
-                            // if there is an en passant square, the half move clock must equal 0 (pawn must have moved for en passant to be active)
-                            // TODO: get rid of is_some
-                            match (n != 0) && en_passant.is_some() {
-                                true => {
-                                    return Err(GamestateFENParseError::HalfmoveClockFENParseError(
-                                        HalfmoveClockFENParseError::NonZeroWhileEnPassant,
-                                    ))
-                                }
-                                false => n,
-                            }
-                        }
-                        _ => {
-                            return Err(GamestateFENParseError::HalfmoveClockFENParseError(
-                                HalfmoveClockFENParseError::ExceedsMax(num),
-                            ))
-                        }
-                    },
-                    Err(e) => {
-                        return Err(GamestateFENParseError::HalfmoveClockFENParseError(
-                            HalfmoveClockFENParseError::ParseIntError(e),
-                        ))
-                    }
-                };
-
-                let fullmove_number_str = remaining_sections[4];
-                // check that fullmove_number is a valid u32
-                let fullmove_number = match fullmove_number_str.parse::<u32>() {
-                    Ok(num) => match num {
-                        // check that fullmove number is less than MAX_GAME_MOVES
-                        n if (1..=MAX_GAME_MOVES).contains(&(n as usize)) => {
-                            // Check that halfmove and fullmove aren't mutually exclusive
-                            let halfmove_clock = halfmove_clock_str.parse::<u32>().expect(
-                                "halfmove_clock_str should be a valid u32 since we check it above",
-                            );
-                            let offset: u32 = match active_color {
-                                Color::White => 0,
-                                Color::Black => 1,
-                            };
-                            match n {
-                                plausible if ((2*(plausible - 1) + offset) >= halfmove_clock) => plausible,
-                                _ => return Err(GamestateFENParseError::FullmoveCounterFENParseError(FullmoveCounterFENParseError::SmallerThanHalfmoveClockDividedByTwo(n, halfmove_clock)))
-                            }
-                        }
-                        _ => {
-                            return Err(GamestateFENParseError::FullmoveCounterFENParseError(
-                                FullmoveCounterFENParseError::NotInRange(num),
-                            ))
-                        }
-                    },
-                    Err(e) => {
-                        return Err(GamestateFENParseError::FullmoveCounterFENParseError(
-                            FullmoveCounterFENParseError::ParseIntError(e),
-                        ))
-                    }
-                };
-
-                let history = Vec::new();
-                let zobrist = Zobrist::default();
-
-                // TODO: Check if active color can win in one move and disallow
-
-                Ok(Gamestate {
-                    board,
-                    active_color,
-                    castle_permissions,
-                    en_passant,
-                    halfmove_clock,
-                    fullmove_number,
-                    history,
-                    zobrist,
-                })
+    /// active_color_str here should be either "w" or "b"
+    fn parse_active_color(active_color_str: &str) -> Result<Color, GamestateFENParseError> {
+        match active_color_str {
+            white if white == char::from(Color::White).to_string() => Ok(Color::White),
+            black if black == char::from(Color::Black).to_string() => Ok(Color::Black),
+            _ => Err(GamestateFENParseError::ActiveColor(
+                active_color_str.to_string(),
+            )),
+        }
+    }
+
+    // TODO: look into X-FEN and Shredder-FEN for Chess960
+    fn parse_castle_permissions(
+        castle_permissions_str: &str,
+    ) -> Result<CastlePerm, GamestateFENParseError> {
+        CastlePerm::try_from(castle_permissions_str)
+            .map_err(|_| GamestateFENParseError::CastlePerm(castle_permissions_str.to_string()))
+    }
+
+    /// en_passant_str must be "-" or a lowercase square on rank 3 or 6. Whether it fits the
+    /// active color and board is checked by check_fen_fields_agree
+    fn parse_en_passant(en_passant_str: &str) -> Result<Option<Square>, EnPassantFENParseError> {
+        match Square::try_from(en_passant_str.to_uppercase().as_str()) {
+            Ok(_) if en_passant_str == en_passant_str.to_uppercase() => {
+                Err(EnPassantFENParseError::EnPassantUppercase)
             }
-            _ => Err(GamestateFENParseError::WrongNumFENSections(
-                fen_sections.len(),
+            Ok(ep) => match ep.get_rank() {
+                Rank::Rank3 | Rank::Rank6 => Ok(Some(ep)),
+                ep_rank => Err(EnPassantFENParseError::Rank(ep_rank)),
+            },
+            Err(_) if en_passant_str == "-" => Ok(None),
+            Err(_) => Err(EnPassantFENParseError::SquareConversionError(
+                SquareConversionError::FromStr(strum::ParseError::VariantNotFound),
             )),
         }
     }
 
+    /// If the halfmove clock was 100 the game would immediately tie, so this is considered invalid
+    fn parse_halfmove_clock(halfmove_clock_str: &str) -> Result<u32, HalfmoveClockFENParseError> {
+        match halfmove_clock_str.parse::<u32>() {
+            Ok(n) if (n as usize) < HALF_MOVE_MAX => Ok(n),
+            Ok(n) => Err(HalfmoveClockFENParseError::ExceedsMax(n)),
+            Err(e) => Err(HalfmoveClockFENParseError::ParseIntError(e)),
+        }
+    }
+
+    /// Checks that fullmove_number is a valid u32 in the range 1..=MAX_GAME_MOVES
+    fn parse_fullmove_number(
+        fullmove_number_str: &str,
+    ) -> Result<u32, FullmoveCounterFENParseError> {
+        match fullmove_number_str.parse::<u32>() {
+            Ok(n) if (1..=MAX_GAME_MOVES).contains(&(n as usize)) => Ok(n),
+            Ok(n) => Err(FullmoveCounterFENParseError::NotInRange(n)),
+            Err(e) => Err(FullmoveCounterFENParseError::ParseIntError(e)),
+        }
+    }
+
+    /// Checks the FEN fields that depend on each other: the en passant square against the active
+    /// color and the board, and the move counters against each other
+    fn check_fen_fields_agree(&self) -> Result<(), GamestateFENParseError> {
+        if let Some(ep) = self.en_passant {
+            // If active color is black then en_passant rank has to be 3, if white it has to be 6
+            let (rank_behind, rank_ahead, pawn_color) = match (ep.get_rank(), self.active_color) {
+                (Rank::Rank3, Color::Black) => (Rank::Rank2, Rank::Rank4, Color::White),
+                (Rank::Rank6, Color::White) => (Rank::Rank7, Rank::Rank5, Color::Black),
+                (ep_rank, active_color) => {
+                    return Err(
+                        EnPassantFENParseError::ColorRankMismatch(active_color, ep_rank).into(),
+                    )
+                }
+            };
+            // check that the en passant square and the one behind it are empty
+            let square_behind = Square::from_file_and_rank(ep.get_file(), rank_behind);
+            if self.board.pieces[ep as usize].is_some()
+                || self.board.pieces[square_behind as usize].is_some()
+            {
+                return Err(EnPassantFENParseError::NonEmptySquares.into());
+            }
+            // check that the pawn that just moved is in front of en passant square
+            let square_ahead = Square::from_file_and_rank(ep.get_file(), rank_ahead);
+            if !self.board.pawns[pawn_color as usize].check_bit(square_ahead.to_square64()) {
+                return Err(EnPassantFENParseError::CorrectPawnNotInFront(pawn_color, ep).into());
+            }
+            // if there is an en passant square, the half move clock must equal 0 (pawn must have
+            // moved for en passant to be active)
+            if self.halfmove_clock != 0 {
+                return Err(HalfmoveClockFENParseError::NonZeroWhileEnPassant.into());
+            }
+        }
+
+        // Check that halfmove and fullmove aren't mutually exclusive
+        let offset: u32 = match self.active_color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        if 2 * (self.fullmove_number - 1) + offset < self.halfmove_clock {
+            return Err(
+                FullmoveCounterFENParseError::SmallerThanHalfmoveClockDividedByTwo(
+                    self.fullmove_number,
+                    self.halfmove_clock,
+                )
+                .into(),
+            );
+        }
+        Ok(())
+    }
+
     fn gen_position_key(&self) -> u64 {
         let mut position_key: u64 = 0;
 
@@ -642,7 +725,7 @@ mod tests {
         );
         // println!("output zobrist:{:?}\nexpected zobrist:{:?}", output.as_ref().unwrap().zobrist, output.as_ref().unwrap().zobrist);
         assert_eq!(output, expected);
-        assert_eq!(default, expected.unwrap());
+        assert_eq!(Ok(default), expected);
     }
 
     // Square Attacks
@@ -1138,6 +1221,106 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_from_fen_invalid_keeps_position() {
+        let input = "rnbqkbnr/pppp1pp1/7p/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq E6 0 3";
+        let output = Gamestate::from_fen(input);
+        let expected = Err(ChessError::InvalidFen {
+            position: input.to_owned(),
+            reason: GamestateFENParseError::EnPassantFENParseError(
+                EnPassantFENParseError::EnPassantUppercase,
+            ),
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_fen_with_report_all_placement_problems() {
+        let input = "rnbqkbnr/ppzppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let output = Gamestate::from_fen_with_report(input).map(|_| ());
+        let expected = Err(FenErrorReport {
+            fen: input.to_owned(),
+            problems: vec![
+                FenProblem {
+                    index: 11,
+                    field: Some(FenField::PiecePlacement),
+                    reason: GamestateFENParseError::BoardFENParseError(
+                        BoardFENParseError::RankFENParseError(RankFENParseError::InvalidChar(
+                            "ppzppppp".to_owned(),
+                            'z',
+                        )),
+                    ),
+                },
+                FenProblem {
+                    index: 17,
+                    field: Some(FenField::PiecePlacement),
+                    reason: GamestateFENParseError::BoardFENParseError(
+                        BoardFENParseError::RankFENParseError(
+                            RankFENParseError::InvalidNumSquares("ppzppppp".to_owned()),
+                        ),
+                    ),
+                },
+            ],
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_fen_with_report_problems_in_several_fields() {
+        let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 0";
+        let output = Gamestate::from_fen_with_report(input).map(|_| ());
+        let expected = Err(FenErrorReport {
+            fen: input.to_owned(),
+            problems: vec![
+                FenProblem {
+                    index: 44,
+                    field: Some(FenField::ActiveColor),
+                    reason: GamestateFENParseError::ActiveColor("x".to_owned()),
+                },
+                FenProblem {
+                    index: 55,
+                    field: Some(FenField::FullmoveNumber),
+                    reason: GamestateFENParseError::FullmoveCounterFENParseError(
+                        FullmoveCounterFENParseError::NotInRange(0),
+                    ),
+                },
+            ],
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_fen_with_report_cross_field_problem() {
+        let input = "rnbqkbnr/ppp2ppp/3p4/3P4/4p3/8/PPPQPPPP/RNB1KBNR w KQkq e6 0 4";
+        let output = Gamestate::from_fen_with_report(input).map(|_| ());
+        let expected = Err(FenErrorReport {
+            fen: input.to_owned(),
+            problems: vec![FenProblem {
+                index: 56,
+                field: Some(FenField::EnPassant),
+                reason: GamestateFENParseError::EnPassantFENParseError(
+                    EnPassantFENParseError::CorrectPawnNotInFront(Color::Black, Square::E6),
+                ),
+            }],
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_fen_with_report_wrong_num_sections() {
+        let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ kq - 0 1";
+        let output = Gamestate::from_fen_with_report(input).map(|_| ());
+        let expected = Err(FenErrorReport {
+            fen: input.to_owned(),
+            problems: vec![FenProblem {
+                index: 56,
+                field: None,
+                reason: GamestateFENParseError::WrongNumFENSections(7),
+            }],
+        });
+        assert_eq!(output, expected);
+    }
+
     // NOTE: enpassant testing for - is done by the tests that use default FENs
     #[test]
     fn test_gamestate_try_from_invalid_en_passant_uppercase() {
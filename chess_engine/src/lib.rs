@@ -1,11 +1,25 @@
+// Builds without the standard library when the std feature is off. The engine still needs an
+// allocator for FEN parsing, error messages and move history.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![forbid(unsafe_code)]
 #![allow(clippy::module_inception)]
 #![allow(unused)]
+
+extern crate alloc;
+
 pub mod board;
 pub mod castle_perms;
+pub mod epd;
 pub mod error;
+pub mod game;
 pub mod gamestate;
+pub mod movegen;
 pub mod moves;
+pub mod perft;
 pub mod pieces;
+pub mod search;
+pub mod selftest;
 pub mod squares;
+pub mod tablebase;
 pub mod util;
+pub mod zobrist;
@@ -0,0 +1,863 @@
+//! Move generation on top of Position and the attack tables.
+//!
+//! Pseudo legal moves follow the movement rules of every piece but may leave the mover's own king
+//! in check. Castling is the exception: it's only generated if the king isn't in check and
+//! doesn't pass through or land on an attacked square, since that can't be told from the position
+//! after the move. Legal moves are generated directly from the checkers and pins instead of
+//! filtering the pseudo legal ones.
+
+use crate::{
+    board::{attacks, bitboard::BitBoard, Position},
+    moves::{Move, MoveFlag, ScoredMove},
+    pieces::{Piece, PieceType},
+    squares::Square64,
+    util::{Color, SQUARE_64_BY_INDEX},
+};
+use strum::EnumCount;
+
+mod history;
+mod legality;
+mod move_list;
+mod picker;
+
+pub use history::{history_bonus, CountermoveTable, HistoryTable, MAX_HISTORY};
+pub use move_list::{MoveList, ScoredMoveList, MAX_MOVES};
+pub use picker::MovePicker;
+
+const RANK_1: u64 = 0x0000_0000_0000_00FF;
+const RANK_8: u64 = 0xFF00_0000_0000_0000;
+
+/// Which moves a generator emits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationMode {
+    All,
+    /// Captures, including en passant, and promotions, which is all quiescence search looks at.
+    /// Quiet moves are never generated in the first place rather than filtered out.
+    Captures,
+    /// Moves out of check: king moves plus captures of the checker and interpositions on the
+    /// squares between it and the king, or only king moves in double check. The side to move has
+    /// to be in check. Legal generation already restricts itself like this in check, but pseudo
+    /// legal generation only does so in this mode.
+    Evasions,
+    /// Non captures that give check, directly or by moving out of the way of a slider, so
+    /// quiescence search can try them too. Promotions are left to Captures and castling with
+    /// check isn't generated.
+    QuietChecks,
+    /// Everything Captures leaves out: non captures that don't promote, including castling
+    Quiets,
+}
+
+/// Every pseudo legal move of the side to move
+pub fn pseudo_legal_moves(position: &Position) -> MoveList {
+    generate_pseudo_legal(position, GenerationMode::All)
+}
+
+/// Every legal move of the side to move. Instead of playing each pseudo legal move and checking
+/// whether the king is left in check, the cached checkers and pins decide which squares each
+/// piece may go to: with two checkers only the king may move, a single check has to be captured
+/// or blocked, and a pinned piece has to stay on the line through its king.
+pub fn legal_moves(position: &Position) -> MoveList {
+    generate_legal(position, GenerationMode::All)
+}
+
+/// Pseudo legal moves of the side to move of the given kind
+pub fn generate_pseudo_legal(position: &Position, mode: GenerationMode) -> MoveList {
+    debug_assert!(
+        mode != GenerationMode::Evasions || position.checkers().0 != 0,
+        "evasions should only be generated in check"
+    );
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_into(position, mode, &mut moves);
+    moves
+}
+
+fn generate_pseudo_legal_into(
+    position: &Position,
+    mode: GenerationMode,
+    moves: &mut impl MoveSink,
+) {
+    let restrictions = Restrictions::pseudo_legal(position, mode);
+    generate_pawn_moves(position, &restrictions, moves);
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ] {
+        generate_piece_moves(position, piece_type, &restrictions, moves);
+    }
+    if matches!(mode, GenerationMode::All | GenerationMode::Quiets) {
+        generate_castles(position, moves);
+    }
+}
+
+/// Legal moves of the side to move of the given kind, see legal_moves
+pub fn generate_legal(position: &Position, mode: GenerationMode) -> MoveList {
+    debug_assert!(
+        mode != GenerationMode::Evasions || position.checkers().0 != 0,
+        "evasions should only be generated in check"
+    );
+    let mut moves = MoveList::new();
+    generate_legal_into(position, mode, &mut moves);
+    moves
+}
+
+/// Legal captures, including en passant, and promotions, the moves of GenerationMode::Captures,
+/// each scored by mvv_lva as it's generated
+pub fn scored_captures(position: &Position) -> ScoredMoveList {
+    let mut moves = ScoredMoveList::new();
+    generate_legal_into(position, GenerationMode::Captures, &mut moves);
+    moves
+}
+
+fn generate_legal_into(position: &Position, mode: GenerationMode, moves: &mut impl MoveSink) {
+    let us = position.side_to_move();
+    let Some(king) = position.king_square(us) else {
+        // without a king nothing can be left in check
+        return generate_pseudo_legal_into(position, mode, moves);
+    };
+    let restrictions = Restrictions::legal(position, king, mode);
+    generate_king_moves(position, king, &restrictions, moves);
+    if position.checkers().0.count_ones() > 1 {
+        return;
+    }
+    generate_pawn_moves(position, &restrictions, moves);
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ] {
+        generate_piece_moves(position, piece_type, &restrictions, moves);
+    }
+    if matches!(mode, GenerationMode::All | GenerationMode::Quiets) {
+        generate_castles(position, moves);
+    }
+}
+
+/// Limits on the squares pieces other than the king may move to
+struct Restrictions {
+    /// King of the side to move when only legal moves are generated
+    king: Option<Square64>,
+    /// Squares that capture or block the checker when in check, otherwise every square
+    check_mask: u64,
+    pinned: u64,
+    mode: GenerationMode,
+    /// Only set for GenerationMode::QuietChecks
+    check_squares: Option<CheckSquares>,
+}
+
+impl Restrictions {
+    fn pseudo_legal(position: &Position, mode: GenerationMode) -> Self {
+        let king = position.king_square(position.side_to_move());
+        let check_mask = match (mode, king) {
+            (GenerationMode::Evasions, Some(king)) => check_mask(position, king),
+            _ => !0,
+        };
+        Self {
+            king: None,
+            check_mask,
+            pinned: 0,
+            mode,
+            check_squares: CheckSquares::for_mode(position, mode),
+        }
+    }
+
+    fn legal(position: &Position, king: Square64, mode: GenerationMode) -> Self {
+        Self {
+            king: Some(king),
+            check_mask: check_mask(position, king),
+            pinned: position.pinned(position.side_to_move()).0,
+            mode,
+            check_squares: CheckSquares::for_mode(position, mode),
+        }
+    }
+
+    /// Squares the piece on from may move to
+    fn targets(&self, from: Square64) -> u64 {
+        match self.king {
+            Some(king) if self.pinned & (1 << from as u8) != 0 => {
+                self.check_mask & attacks::line(king, from).0
+            }
+            _ => self.check_mask,
+        }
+    }
+
+    /// Squares the piece on from has to go to in order to give check, or every square unless only
+    /// quiet checks are generated
+    fn checking_targets(&self, piece_type: PieceType, from: Square64) -> u64 {
+        match &self.check_squares {
+            Some(check_squares) => check_squares.targets(piece_type, from),
+            None => !0,
+        }
+    }
+
+    /// Returns true if the en passant capture doesn't leave the king in check. The captured pawn
+    /// and the capturing one both leave their squares, which neither the check mask nor the pins
+    /// can account for, so the position after the capture is looked at directly.
+    fn allows_en_passant(&self, position: &Position, from: Square64, to: Square64) -> bool {
+        let Some(king) = self.king else {
+            return true;
+        };
+        let captured = match position.side_to_move() {
+            Color::White => to as u8 - 8,
+            Color::Black => to as u8 + 8,
+        };
+        let occupancy =
+            (position.occupancy().0 ^ (1 << from as u8) ^ (1 << captured)) | (1 << to as u8);
+        let attackers = position.attackers_to(king, BitBoard(occupancy)).0
+            & position.color_occupancy(!position.side_to_move()).0
+            & !(1 << captured);
+        attackers == 0
+    }
+}
+
+/// Squares from which each piece type checks the enemy king, plus our pieces that check by moving
+/// off the line between the king and one of our sliders
+struct CheckSquares {
+    their_king: Square64,
+    /// Indexed by `piece_type as usize`
+    direct: [u64; PieceType::COUNT],
+    discoverers: u64,
+}
+
+impl CheckSquares {
+    fn for_mode(position: &Position, mode: GenerationMode) -> Option<Self> {
+        if mode != GenerationMode::QuietChecks {
+            return None;
+        }
+        let us = position.side_to_move();
+        let their_king = position.king_square(!us)?;
+        let occupancy = position.occupancy();
+        let bishop = attacks::bishop_attacks(their_king, occupancy).0;
+        let rook = attacks::rook_attacks(their_king, occupancy).0;
+        let direct = [
+            attacks::pawn_attacks(!us, their_king).0,
+            attacks::knight_attacks(their_king).0,
+            bishop,
+            rook,
+            bishop | rook,
+            0,
+        ];
+
+        let ours = |piece_type| position.pieces(Piece::new(us, piece_type)).0;
+        let queens = ours(PieceType::Queen);
+        let mut snipers = BitBoard(
+            (attacks::rook_attacks(their_king, BitBoard(0)).0 & (ours(PieceType::Rook) | queens))
+                | (attacks::bishop_attacks(their_king, BitBoard(0)).0
+                    & (ours(PieceType::Bishop) | queens)),
+        );
+        let mut discoverers = 0;
+        while let Some(sniper) = snipers.pop_bit() {
+            let blockers = attacks::between(their_king, sniper).0 & occupancy.0;
+            if blockers.count_ones() == 1 && blockers & position.color_occupancy(us).0 != 0 {
+                discoverers |= blockers;
+            }
+        }
+        Some(Self {
+            their_king,
+            direct,
+            discoverers,
+        })
+    }
+
+    fn targets(&self, piece_type: PieceType, from: Square64) -> u64 {
+        let direct = self.direct[piece_type as usize];
+        match self.discoverers & (1 << from as u8) {
+            0 => direct,
+            _ => direct | !attacks::line(self.their_king, from).0,
+        }
+    }
+}
+
+/// Squares that capture or block the piece checking the king: every square when not in check and
+/// none in double check, where only the king can move
+fn check_mask(position: &Position, king: Square64) -> u64 {
+    let checkers = position.checkers().0;
+    match checkers.count_ones() {
+        0 => !0,
+        1 => {
+            let checker = SQUARE_64_BY_INDEX[checkers.trailing_zeros() as usize];
+            attacks::between(king, checker).0 | checkers
+        }
+        _ => 0,
+    }
+}
+
+fn generate_pawn_moves(
+    position: &Position,
+    restrictions: &Restrictions,
+    moves: &mut impl MoveSink,
+) {
+    let us = position.side_to_move();
+    let empty = !position.occupancy().0;
+    let theirs = position.color_occupancy(!us).0;
+    let mut pawns = position.pieces(Piece::new(us, PieceType::Pawn));
+    while let Some(from) = pawns.pop_bit() {
+        let from_bit = 1u64 << from as u8;
+        let (single, double) = match us {
+            Color::White => {
+                let single = (from_bit << 8) & empty;
+                (single, (single << 8) & empty & 0x0000_0000_FF00_0000)
+            }
+            Color::Black => {
+                let single = (from_bit >> 8) & empty;
+                (single, (single >> 8) & empty & 0x0000_00FF_0000_0000)
+            }
+        };
+        let (single, double) = match restrictions.mode {
+            GenerationMode::All | GenerationMode::Evasions => (single, double),
+            GenerationMode::Captures => (single & (RANK_1 | RANK_8), 0),
+            GenerationMode::QuietChecks => {
+                let checking = restrictions.checking_targets(PieceType::Pawn, from);
+                (single & !(RANK_1 | RANK_8) & checking, double & checking)
+            }
+            GenerationMode::Quiets => (single & !(RANK_1 | RANK_8), double),
+        };
+        let allowed = restrictions.targets(from);
+        let attacks = attacks::pawn_attacks(us, from).0;
+        push_pawn_moves(position, from, single & allowed, MoveFlag::Quiet, moves);
+        push_moves(
+            position,
+            from,
+            double & allowed,
+            MoveFlag::DoublePawnPush,
+            moves,
+        );
+        if matches!(
+            restrictions.mode,
+            GenerationMode::QuietChecks | GenerationMode::Quiets
+        ) {
+            continue;
+        }
+        push_pawn_moves(
+            position,
+            from,
+            attacks & theirs & allowed,
+            MoveFlag::Capture,
+            moves,
+        );
+        if let Some(to) = position.en_passant() {
+            if attacks & (1 << to as u8) != 0 && restrictions.allows_en_passant(position, from, to)
+            {
+                moves.push_move(position, Move::new(from, to, MoveFlag::EnPassant));
+            }
+        }
+    }
+}
+
+/// Pushes a move to every target, or all four promotions, queen first, for targets on the last
+/// rank
+fn push_pawn_moves(
+    position: &Position,
+    from: Square64,
+    targets: u64,
+    flag: MoveFlag,
+    moves: &mut impl MoveSink,
+) {
+    let mut targets = BitBoard(targets);
+    while let Some(to) = targets.pop_bit() {
+        let move_ = Move::new(from, to, flag);
+        match (1u64 << to as u8) & (RANK_1 | RANK_8) != 0 {
+            true => move_
+                .promotion_variants()
+                .into_iter()
+                .for_each(|promotion| moves.push_move(position, promotion)),
+            false => moves.push_move(position, move_),
+        }
+    }
+}
+
+fn generate_piece_moves(
+    position: &Position,
+    piece_type: PieceType,
+    restrictions: &Restrictions,
+    moves: &mut impl MoveSink,
+) {
+    let us = position.side_to_move();
+    let own = position.color_occupancy(us).0;
+    let theirs = position.color_occupancy(!us).0;
+    let occupancy = position.occupancy();
+    let mut pieces = position.pieces(Piece::new(us, piece_type));
+    while let Some(from) = pieces.pop_bit() {
+        let targets = match piece_type {
+            PieceType::Knight => attacks::knight_attacks(from),
+            PieceType::Bishop => attacks::bishop_attacks(from, occupancy),
+            PieceType::Rook => attacks::rook_attacks(from, occupancy),
+            PieceType::Queen => attacks::queen_attacks(from, occupancy),
+            PieceType::King => attacks::king_attacks(from),
+            PieceType::Pawn => unreachable!("pawn moves are generated by generate_pawn_moves"),
+        }
+        .0 & !own;
+        // the king isn't bound by the check mask, it gets out of check by stepping away
+        let targets = match piece_type {
+            PieceType::King => targets,
+            _ => targets & restrictions.targets(from),
+        } & restrictions.checking_targets(piece_type, from);
+        if !matches!(
+            restrictions.mode,
+            GenerationMode::QuietChecks | GenerationMode::Quiets
+        ) {
+            push_moves(position, from, targets & theirs, MoveFlag::Capture, moves);
+        }
+        if restrictions.mode != GenerationMode::Captures {
+            push_moves(position, from, targets & !theirs, MoveFlag::Quiet, moves);
+        }
+    }
+}
+
+/// King moves to squares that aren't attacked once the king has left its square, so it can't
+/// step back along the line of a slider checking it
+fn generate_king_moves(
+    position: &Position,
+    king: Square64,
+    restrictions: &Restrictions,
+    moves: &mut impl MoveSink,
+) {
+    let us = position.side_to_move();
+    let theirs = position.color_occupancy(!us).0;
+    let occupancy = BitBoard(position.occupancy().0 ^ (1 << king as u8));
+    let allowed = match restrictions.mode {
+        GenerationMode::All | GenerationMode::Evasions => !position.color_occupancy(us).0,
+        GenerationMode::Captures => theirs,
+        GenerationMode::QuietChecks => {
+            !occupancy.0 & restrictions.checking_targets(PieceType::King, king)
+        }
+        GenerationMode::Quiets => !position.occupancy().0,
+    };
+    let mut targets = BitBoard(attacks::king_attacks(king).0 & allowed);
+    while let Some(to) = targets.pop_bit() {
+        if position.attackers_to(to, occupancy).0 & theirs != 0 {
+            continue;
+        }
+        let flag = match theirs & (1 << to as u8) != 0 {
+            true => MoveFlag::Capture,
+            false => MoveFlag::Quiet,
+        };
+        moves.push_move(position, Move::new(king, to, flag));
+    }
+}
+
+/// Castles, standard or Chess960: the squares the king and rook pass over and land on have
+/// to be empty apart from the two of them, and the king can't be in check, pass through an
+/// attacked square or land on one
+fn generate_castles(position: &Position, moves: &mut impl MoveSink) {
+    let us = position.side_to_move();
+    if position.checkers().0 != 0 {
+        return;
+    }
+    let occupancy = position.occupancy().0;
+    for (king_side, flag) in [(true, MoveFlag::KingCastle), (false, MoveFlag::QueenCastle)] {
+        let Some(castle) = position.castle(us, king_side) else {
+            continue;
+        };
+        let king_path =
+            attacks::between(castle.king_from, castle.king_to).0 | (1 << castle.king_to as u8);
+        let rook_path =
+            attacks::between(castle.rook_from, castle.rook_to).0 | (1 << castle.rook_to as u8);
+        let movers = (1 << castle.king_from as u8) | (1 << castle.rook_from as u8);
+        if (king_path | rook_path) & occupancy & !movers != 0 {
+            continue;
+        }
+        let mut path = BitBoard(king_path);
+        let mut safe = true;
+        while let Some(square) = path.pop_bit() {
+            safe &= !position.is_attacked(square, !us);
+        }
+        // in Chess960 the rook can stand between the king's landing square and an enemy rook or
+        // queen on the back rank, and stops shielding it once it moves
+        let unshielded = BitBoard(occupancy ^ (1 << castle.rook_from as u8));
+        safe &= position.attackers_to(castle.king_to, unshielded).0
+            & position.color_occupancy(!us).0
+            == 0;
+        if safe {
+            moves.push_move(position, Move::new(castle.king_from, castle.king_to, flag));
+        }
+    }
+}
+
+fn push_moves(
+    position: &Position,
+    from: Square64,
+    targets: u64,
+    flag: MoveFlag,
+    moves: &mut impl MoveSink,
+) {
+    let mut targets = BitBoard(targets);
+    while let Some(to) = targets.pop_bit() {
+        moves.push_move(position, Move::new(from, to, flag));
+    }
+}
+
+/// Where the generators put their moves, so the same code fills a plain or a scored move list
+trait MoveSink {
+    fn push_move(&mut self, position: &Position, move_: Move);
+}
+
+impl MoveSink for MoveList {
+    fn push_move(&mut self, _position: &Position, move_: Move) {
+        self.push(move_);
+    }
+}
+
+impl MoveSink for ScoredMoveList {
+    fn push_move(&mut self, position: &Position, move_: Move) {
+        self.push(ScoredMove::new(move_, mvv_lva(position, move_)));
+    }
+}
+
+/// Most valuable victim, least valuable attacker score of a move in the given position: captures
+/// of more valuable pieces score higher and, among those, captures by less valuable pieces. A
+/// promotion adds what the pawn gains by promoting. Other moves score 0 and every capture or
+/// promotion scores more.
+pub fn mvv_lva(position: &Position, move_: Move) -> i32 {
+    let victim = match move_.is_en_passant() {
+        true => Some(PieceType::Pawn),
+        false => position
+            .piece_on(move_.to_square())
+            .map(|piece| piece.piece_type()),
+    };
+    // pieces are worth one more than their index, pawn 1 to king 6, so a promotion to a queen
+    // gains 4 over the pawn
+    let gain = victim.map_or(0, |victim| victim as i32 + 1)
+        + move_.promotion().map_or(0, |promotion| promotion as i32);
+    if gain == 0 {
+        return 0;
+    }
+    let attacker = position
+        .piece_on(move_.from_square())
+        .expect("a capture or promotion should have a piece on its from square");
+    8 * gain - attacker.piece_type() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::START_FEN;
+    use alloc::vec::Vec;
+
+    fn moves_of(fen: &str) -> MoveList {
+        pseudo_legal_moves(&Position::from_fen(fen).unwrap())
+    }
+
+    fn moves_of_legal(fen: &str) -> MoveList {
+        legal_moves(&Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves_start() {
+        let output = moves_of(START_FEN);
+        assert_eq!(output.len(), 20);
+        assert!(output.contains(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush
+        )));
+        assert!(output.contains(Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet)));
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves_kiwipete() {
+        // every one of the 48 moves is legal, including both castles
+        let input = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let output = moves_of(input);
+        assert_eq!(output.len(), 48);
+        assert!(output.contains(Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle)));
+        assert!(output.contains(Move::new(Square64::E1, Square64::C1, MoveFlag::QueenCastle)));
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves_pawns() {
+        // the B pawn can promote by pushing or by taking the rook, and exd6 e.p. is possible
+        let input = "r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let output: Vec<Move> = moves_of(input)
+            .into_iter()
+            .filter(|move_| !matches!(move_.from_square(), Square64::E1))
+            .collect();
+        let expected = [
+            Move::new(Square64::E5, Square64::E6, MoveFlag::Quiet),
+            Move::new(Square64::E5, Square64::D6, MoveFlag::EnPassant),
+            Move::new(Square64::B7, Square64::B8, MoveFlag::QueenPromotion),
+            Move::new(Square64::B7, Square64::B8, MoveFlag::RookPromotion),
+            Move::new(Square64::B7, Square64::B8, MoveFlag::BishopPromotion),
+            Move::new(Square64::B7, Square64::B8, MoveFlag::KnightPromotion),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::QueenPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::RookPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::BishopPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::KnightPromotionCapture),
+        ];
+        assert_eq!(output, expected);
+    }
+
+    /// Legal moves found the slow way, by playing every pseudo legal move
+    fn filtered_moves(position: &Position) -> Vec<Move> {
+        let us = position.side_to_move();
+        let mut moves: Vec<Move> = pseudo_legal_moves(position)
+            .into_iter()
+            .filter(|&move_| {
+                let after = position.make_move_new(move_);
+                after
+                    .king_square(us)
+                    .is_some_and(|king| !after.is_attacked(king, !us))
+            })
+            .collect();
+        moves.sort_by_key(|move_| move_.bits());
+        moves
+    }
+
+    #[test]
+    fn test_legal_moves_match_filtered_pseudo_legal_moves() {
+        let inputs = [
+            (START_FEN, 20),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                48,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 14),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                6,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                44,
+            ),
+            // double check by the knight and the rook, only the king may move
+            ("4k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1", 3),
+            // the bishop is pinned along the diagonal and may only move on it
+            ("8/8/8/4k3/8/2b5/8/B3K3 b - - 0 1", 11),
+        ];
+        for (fen, expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let mut output: Vec<Move> = legal_moves(&position).into_iter().collect();
+            output.sort_by_key(|move_| move_.bits());
+            assert_eq!(output, filtered_moves(&position), "{}", fen);
+            assert_eq!(output.len(), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_en_passant_pins() {
+        // taking en passant would clear the fifth rank between the king and the rook
+        let output = moves_of_legal("8/8/8/KPp4r/8/8/8/7k w - c6 0 1");
+        assert!(!output.iter().any(|move_| move_.is_en_passant()));
+
+        // the pawn giving check can be taken en passant
+        let output = moves_of_legal("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        assert!(output.contains(Move::new(Square64::E4, Square64::D3, MoveFlag::EnPassant)));
+    }
+
+    #[test]
+    fn test_captures_and_quiets_modes() {
+        let inputs = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            // in check by the knight, which the king can take
+            "4k3/8/8/8/8/8/5n2/4K3 w - - 0 1",
+        ];
+        for fen in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let is_capture_or_promotion = |move_: &Move| move_.is_capture() || move_.is_promotion();
+            let output: Vec<Move> = generate_legal(&position, GenerationMode::Captures)
+                .into_iter()
+                .collect();
+            let mut expected: Vec<Move> = legal_moves(&position)
+                .into_iter()
+                .filter(is_capture_or_promotion)
+                .collect();
+            let mut sorted = output.clone();
+            sorted.sort_by_key(|move_| move_.bits());
+            expected.sort_by_key(|move_| move_.bits());
+            assert_eq!(sorted, expected, "{}", fen);
+
+            let output = generate_pseudo_legal(&position, GenerationMode::Captures);
+            assert!(output.iter().all(is_capture_or_promotion), "{}", fen);
+
+            let mut output: Vec<Move> = generate_legal(&position, GenerationMode::Quiets)
+                .into_iter()
+                .collect();
+            output.sort_by_key(|move_| move_.bits());
+            let mut expected: Vec<Move> = legal_moves(&position)
+                .into_iter()
+                .filter(|move_| !is_capture_or_promotion(move_))
+                .collect();
+            expected.sort_by_key(|move_| move_.bits());
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_scored_captures() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let position = Position::from_fen(fen).unwrap();
+        let output = scored_captures(&position);
+        let moves: Vec<Move> = output.iter().map(|scored| scored.move_).collect();
+        let expected: Vec<Move> = generate_legal(&position, GenerationMode::Captures)
+            .into_iter()
+            .collect();
+        assert_eq!(moves, expected);
+        for scored in output.iter() {
+            assert_eq!(scored.score, mvv_lva(&position, scored.move_));
+        }
+        // pawn takes pawn beats knight takes pawn, and both lose out to bishop takes bishop
+        let score = |from, to| mvv_lva(&position, Move::new(from, to, MoveFlag::Capture));
+        assert!(score(Square64::G2, Square64::H3) > score(Square64::E5, Square64::F7));
+        assert!(score(Square64::E2, Square64::A6) > score(Square64::G2, Square64::H3));
+    }
+
+    #[test]
+    fn test_mvv_lva() {
+        let position = Position::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let inputs = [
+            (
+                Move::new(Square64::A7, Square64::A8, MoveFlag::QueenPromotion),
+                32,
+            ),
+            (
+                Move::new(Square64::A7, Square64::B8, MoveFlag::QueenPromotionCapture),
+                48,
+            ),
+            (
+                Move::new(Square64::A7, Square64::B8, MoveFlag::KnightPromotionCapture),
+                24,
+            ),
+            (Move::new(Square64::E1, Square64::E2, MoveFlag::Quiet), 0),
+        ];
+        for (input, expected) in inputs {
+            assert_eq!(mvv_lva(&position, input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_evasions_mode() {
+        // the rook on E6 checks, Bxe6 captures it, Be2, Ne2, Ne4 and Re2 block and the king can
+        // step aside
+        let input = Position::from_fen("4k3/8/4r3/8/2B5/2N5/7R/4K3 w - - 0 1").unwrap();
+        let mut output: Vec<Move> = generate_pseudo_legal(&input, GenerationMode::Evasions)
+            .into_iter()
+            .collect();
+        output.sort_by_key(|move_| move_.bits());
+        let mut expected = [
+            Move::new(Square64::E1, Square64::D1, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::F1, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::D2, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::E2, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::F2, MoveFlag::Quiet),
+            Move::new(Square64::C4, Square64::E6, MoveFlag::Capture),
+            Move::new(Square64::C4, Square64::E2, MoveFlag::Quiet),
+            Move::new(Square64::C3, Square64::E2, MoveFlag::Quiet),
+            Move::new(Square64::C3, Square64::E4, MoveFlag::Quiet),
+            Move::new(Square64::H2, Square64::E2, MoveFlag::Quiet),
+        ];
+        expected.sort_by_key(|move_| move_.bits());
+        assert_eq!(output, expected);
+
+        let mut output: Vec<Move> = generate_legal(&input, GenerationMode::Evasions)
+            .into_iter()
+            .collect();
+        output.sort_by_key(|move_| move_.bits());
+        let expected: Vec<Move> = expected
+            .into_iter()
+            .filter(|move_| {
+                move_.to_square() != Square64::E2 || move_.from_square() != Square64::E1
+            })
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_evasions_mode_double_check() {
+        let input = Position::from_fen("4k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        let output = generate_pseudo_legal(&input, GenerationMode::Evasions);
+        assert!(output
+            .iter()
+            .all(|move_| move_.from_square() == Square64::E8));
+    }
+
+    #[test]
+    fn test_quiet_checks_mode() {
+        let inputs = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                0,
+            ),
+            // Ra8, Qa8, Qc6, Qe4, Qh5 and Qh8
+            ("4k3/8/8/8/8/8/8/R3K1NQ w - - 0 1", 6),
+            // every knight move uncovers the rook
+            ("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1", 8),
+            // d5 checks, and the king uncovers the rook when it leaves the E file
+            ("8/8/4k3/8/3P4/8/4K3/4R3 w - - 0 1", 7),
+            // the knight would uncover the bishop, but it's pinned to its own king
+            ("5r1k/8/5N2/8/3B4/8/8/5K2 w - - 0 1", 0),
+        ];
+        for (fen, expected_len) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let mut output: Vec<Move> = generate_legal(&position, GenerationMode::QuietChecks)
+                .into_iter()
+                .collect();
+            output.sort_by_key(|move_| move_.bits());
+            let mut expected: Vec<Move> = legal_moves(&position)
+                .into_iter()
+                .filter(|&move_| {
+                    !move_.is_capture()
+                        && !move_.is_promotion()
+                        && !move_.is_castle()
+                        && position.gives_check(move_)
+                })
+                .collect();
+            expected.sort_by_key(|move_| move_.bits());
+            assert_eq!(output, expected, "{}", fen);
+            assert_eq!(output.len(), expected_len, "{}", fen);
+        }
+
+        let input = Position::from_fen("5r1k/8/5N2/8/3B4/8/8/5K2 w - - 0 1").unwrap();
+        let output = generate_pseudo_legal(&input, GenerationMode::QuietChecks);
+        assert_eq!(output.len(), 8);
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves_castling_restrictions() {
+        let castles = |fen: &str| -> Vec<Move> {
+            moves_of(fen)
+                .into_iter()
+                .filter(|move_| move_.is_castle())
+                .collect()
+        };
+        // the bishop on A6 covers F1, so only the queen side castle is left
+        let output = castles("4k3/8/b7/8/8/8/8/R3K2R w KQ - 0 1");
+        let expected = [Move::new(Square64::E1, Square64::C1, MoveFlag::QueenCastle)];
+        assert_eq!(output, expected);
+
+        // B1 may be attacked, it only has to be empty
+        let output = castles("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        assert_eq!(output.len(), 1);
+
+        // no castling out of check or with a piece in between
+        assert_eq!(castles("4k3/8/8/8/8/8/8/RN2K1rR w KQ - 0 1"), []);
+        assert_eq!(castles("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1"), []);
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves_chess960_castles() {
+        let castles = |fen: &str| -> Vec<Move> {
+            moves_of(fen)
+                .into_iter()
+                .filter(|move_| move_.is_castle())
+                .collect()
+        };
+        // the king and rook may pass over or land on each other's squares
+        let output = castles("4k3/8/8/8/8/8/8/1R3KR1 w GB - 0 1");
+        let expected = [
+            Move::new(Square64::F1, Square64::G1, MoveFlag::KingCastle),
+            Move::new(Square64::F1, Square64::C1, MoveFlag::QueenCastle),
+        ];
+        assert_eq!(output.len(), 2);
+        assert!(expected.iter().all(|move_| output.contains(move_)));
+
+        // the king already stands on C1, but only the rook on B1 shields it from the queen
+        assert_eq!(castles("4k3/8/8/8/8/8/8/qRK5 w B - 0 1"), []);
+        assert_eq!(castles("4k3/8/8/8/8/8/8/1RK5 w B - 0 1").len(), 1);
+    }
+}
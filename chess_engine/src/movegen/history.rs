@@ -0,0 +1,177 @@
+use crate::{moves::Move, util::Color};
+use alloc::{vec, vec::Vec};
+use strum::EnumCount;
+
+/// Bound on the magnitude of a history score
+pub const MAX_HISTORY: i32 = 16_384;
+
+/// Butterfly history of quiet moves: a score per side to move, from square and to square that
+/// grows whenever the move causes a beta cutoff and shrinks whenever another move does so after
+/// it was tried. Updates are damped by gravity, i.e. pulled towards zero by how large the score
+/// already is, so no score leaves -MAX_HISTORY..=MAX_HISTORY and a move that stops working loses
+/// its score about as quickly as it gained it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryTable {
+    /// Indexed by `color as usize`, then `from as usize * 64 + to as usize`
+    scores: Vec<[i32; 64 * 64]>,
+}
+
+impl HistoryTable {
+    pub fn new() -> Self {
+        Self {
+            scores: vec![[0; 64 * 64]; Color::COUNT],
+        }
+    }
+
+    fn index(move_: Move) -> usize {
+        move_.from_square() as usize * 64 + move_.to_square() as usize
+    }
+
+    pub fn get(&self, color: Color, move_: Move) -> i32 {
+        self.scores[color as usize][Self::index(move_)]
+    }
+
+    /// Adds bonus, which is negative for a penalty, to the score of the move with gravity
+    pub fn update(&mut self, color: Color, move_: Move, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+        let score = &mut self.scores[color as usize][Self::index(move_)];
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY;
+    }
+
+    /// Halves every score, so a new search still profits from the old scores but soon outweighs
+    /// them with its own
+    pub fn age(&mut self) {
+        for score in self.scores.iter_mut().flatten() {
+            *score /= 2;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for score in self.scores.iter_mut().flatten() {
+            *score = 0;
+        }
+    }
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Countermoves: for each move of the opponent, the quiet move that last refuted it with a beta
+/// cutoff, indexed by the side to move and the from and to squares of the opponent's move. A
+/// refutation often works whatever came before the opponent's move, so it's worth trying early.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountermoveTable {
+    /// Indexed like HistoryTable::scores
+    moves: Vec<[Option<Move>; 64 * 64]>,
+}
+
+impl CountermoveTable {
+    pub fn new() -> Self {
+        Self {
+            moves: vec![[None; 64 * 64]; Color::COUNT],
+        }
+    }
+
+    /// Countermove of color to the opponent's previous move
+    pub fn get(&self, color: Color, previous: Move) -> Option<Move> {
+        self.moves[color as usize][HistoryTable::index(previous)]
+    }
+
+    pub fn set(&mut self, color: Color, previous: Move, countermove: Move) {
+        self.moves[color as usize][HistoryTable::index(previous)] = Some(countermove);
+    }
+
+    pub fn clear(&mut self) {
+        for move_ in self.moves.iter_mut().flatten() {
+            *move_ = None;
+        }
+    }
+}
+
+impl Default for CountermoveTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// History bonus of a cutoff depth plies from the horizon. Cutoffs deep in the tree save more,
+/// so they count quadratically more.
+pub fn history_bonus(depth: u32) -> i32 {
+    (depth.min(64) as i32).pow(2) * 32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{moves::MoveFlag, squares::Square64};
+
+    #[test]
+    fn test_history_update() {
+        let mut history = HistoryTable::new();
+        let move_ = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        history.update(Color::White, move_, 1_024);
+        assert_eq!(history.get(Color::White, move_), 1_024);
+        // the other side and other moves keep their own scores
+        assert_eq!(history.get(Color::Black, move_), 0);
+        let other = Move::new(Square64::F3, Square64::G1, MoveFlag::Quiet);
+        assert_eq!(history.get(Color::White, other), 0);
+        // gravity damps the bonus by the score already there
+        history.update(Color::White, move_, 1_024);
+        assert_eq!(history.get(Color::White, move_), 1_984);
+        history.update(Color::White, move_, -1_024);
+        assert_eq!(history.get(Color::White, move_), 836);
+        history.age();
+        assert_eq!(history.get(Color::White, move_), 418);
+        history.clear();
+        assert_eq!(history, HistoryTable::new());
+    }
+
+    #[test]
+    fn test_history_stays_bounded() {
+        let mut history = HistoryTable::new();
+        let move_ = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        for _ in 0..1_000 {
+            history.update(Color::Black, move_, history_bonus(20));
+        }
+        let output = history.get(Color::Black, move_);
+        assert!((MAX_HISTORY * 9 / 10..=MAX_HISTORY).contains(&output));
+        for _ in 0..1_000 {
+            history.update(Color::Black, move_, -history_bonus(64));
+        }
+        let output = history.get(Color::Black, move_);
+        assert!((-MAX_HISTORY..=-MAX_HISTORY * 9 / 10).contains(&output));
+    }
+
+    #[test]
+    fn test_history_bonus() {
+        let inputs = [(1, 32), (4, 512), (10, 3_200), (200, 131_072)];
+        for (depth, expected) in inputs {
+            assert_eq!(history_bonus(depth), expected, "{}", depth);
+        }
+    }
+
+    #[test]
+    fn test_countermoves() {
+        let mut countermoves = CountermoveTable::new();
+        let previous = Move::new(Square64::E7, Square64::E5, MoveFlag::DoublePawnPush);
+        let countermove = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        assert_eq!(countermoves.get(Color::White, previous), None);
+        countermoves.set(Color::White, previous, countermove);
+        assert_eq!(countermoves.get(Color::White, previous), Some(countermove));
+        assert_eq!(countermoves.get(Color::Black, previous), None);
+        // only the from and to squares of the previous move count
+        let same_squares = Move::new(Square64::E7, Square64::E5, MoveFlag::Quiet);
+        assert_eq!(
+            countermoves.get(Color::White, same_squares),
+            Some(countermove)
+        );
+        let newer = Move::new(Square64::B1, Square64::C3, MoveFlag::Quiet);
+        countermoves.set(Color::White, previous, newer);
+        assert_eq!(countermoves.get(Color::White, previous), Some(newer));
+        countermoves.clear();
+        assert_eq!(countermoves, CountermoveTable::new());
+    }
+}
@@ -0,0 +1,167 @@
+use super::{generate_castles, GenerationMode, MoveList, Restrictions, RANK_1, RANK_8};
+use crate::{
+    board::{attacks, bitboard::BitBoard, Position},
+    moves::{Move, MoveFlag},
+    pieces::PieceType,
+    util::Color,
+};
+
+/// Checking a single move, e.g. a hash move or killer that may come from another position,
+/// without generating the whole move list
+impl Position {
+    /// Returns true if the move is one pseudo_legal_moves would generate for this position
+    pub fn is_pseudo_legal(&self, move_: Move) -> bool {
+        let us = self.side_to_move();
+        let (from, to) = (move_.from_square(), move_.to_square());
+        let Some(piece) = self.piece_on(from).filter(|piece| piece.color() == us) else {
+            return false;
+        };
+        let to_bit = 1u64 << to as u8;
+        if move_.is_castle() {
+            // there are at most two castles to generate, which is as cheap as checking by hand
+            let mut castles = MoveList::new();
+            generate_castles(self, &mut castles);
+            return castles.contains(move_);
+        }
+        if piece.piece_type() == PieceType::Pawn {
+            return self.is_pseudo_legal_pawn_move(move_);
+        }
+        if move_.flag() != MoveFlag::Quiet && move_.flag() != MoveFlag::Capture {
+            return false;
+        }
+        let occupancy = self.occupancy();
+        let targets = match piece.piece_type() {
+            PieceType::Knight => attacks::knight_attacks(from),
+            PieceType::Bishop => attacks::bishop_attacks(from, occupancy),
+            PieceType::Rook => attacks::rook_attacks(from, occupancy),
+            PieceType::Queen => attacks::queen_attacks(from, occupancy),
+            PieceType::King => attacks::king_attacks(from),
+            PieceType::Pawn => unreachable!("pawn moves are checked by is_pseudo_legal_pawn_move"),
+        }
+        .0;
+        let allowed = match move_.is_capture() {
+            true => self.color_occupancy(!us).0,
+            false => !occupancy.0,
+        };
+        targets & allowed & to_bit != 0
+    }
+
+    /// Returns true if the move is one legal_moves would generate for this position
+    pub fn is_legal(&self, move_: Move) -> bool {
+        if !self.is_pseudo_legal(move_) {
+            return false;
+        }
+        let us = self.side_to_move();
+        let Some(king) = self.king_square(us) else {
+            return true;
+        };
+        // castles are only pseudo legal if the king doesn't pass through check
+        if move_.is_castle() {
+            return true;
+        }
+        let (from, to) = (move_.from_square(), move_.to_square());
+        if from == king {
+            let occupancy = self.occupancy().0 ^ (1 << king as u8);
+            return self.attackers_to(to, BitBoard(occupancy)).0 & self.color_occupancy(!us).0 == 0;
+        }
+        if self.checkers().0.count_ones() > 1 {
+            return false;
+        }
+        let restrictions = Restrictions::legal(self, king, GenerationMode::All);
+        match move_.is_en_passant() {
+            true => restrictions.allows_en_passant(self, from, to),
+            false => restrictions.targets(from) & (1 << to as u8) != 0,
+        }
+    }
+
+    fn is_pseudo_legal_pawn_move(&self, move_: Move) -> bool {
+        let us = self.side_to_move();
+        let (from, to) = (move_.from_square(), move_.to_square());
+        let (from_bit, to_bit) = (1u64 << from as u8, 1u64 << to as u8);
+        // a move to the last rank has to promote and no other move may
+        if move_.is_promotion() != (to_bit & (RANK_1 | RANK_8) != 0) {
+            return false;
+        }
+        let empty = !self.occupancy().0;
+        let (single, double) = match us {
+            Color::White => {
+                let single = (from_bit << 8) & empty;
+                (single, (single << 8) & empty & 0x0000_0000_FF00_0000)
+            }
+            Color::Black => {
+                let single = (from_bit >> 8) & empty;
+                (single, (single >> 8) & empty & 0x0000_00FF_0000_0000)
+            }
+        };
+        let attacks = attacks::pawn_attacks(us, from).0;
+        let targets = match move_.flag() {
+            MoveFlag::EnPassant => match self.en_passant() {
+                Some(en_passant) if en_passant == to => attacks,
+                _ => 0,
+            },
+            MoveFlag::DoublePawnPush => double,
+            MoveFlag::KingCastle | MoveFlag::QueenCastle => 0,
+            _ if move_.is_capture() => attacks & self.color_occupancy(!us).0,
+            _ => single,
+        };
+        targets & to_bit != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        movegen::{legal_moves, pseudo_legal_moves},
+        squares::Square64,
+        util::SQUARE_64_BY_INDEX,
+    };
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_single_move_checks_match_generation() {
+        let inputs = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/8/8/KPp4r/8/8/8/6k1 w - c6 0 1",
+            "4k3/8/8/8/8/8/3n4/R3K2R w KQ - 0 1",
+        ];
+        for fen in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let pseudo_legal = pseudo_legal_moves(&position);
+            let legal = legal_moves(&position);
+            for from in SQUARE_64_BY_INDEX {
+                for to in SQUARE_64_BY_INDEX {
+                    for flag in MoveFlag::iter() {
+                        let input = Move::new(from, to, flag);
+                        let output = (position.is_pseudo_legal(input), position.is_legal(input));
+                        let expected = (pseudo_legal.contains(input), legal.contains(input));
+                        assert_eq!(output, expected, "{} {} {:?}", fen, input, flag);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_legal_rejects_moves_from_other_positions() {
+        let input = Position::start();
+        let moves = [
+            Move::new(Square64::E7, Square64::E5, MoveFlag::DoublePawnPush),
+            Move::new(Square64::E2, Square64::E4, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle),
+            Move::NULL,
+        ];
+        for move_ in moves {
+            assert!(!input.is_legal(move_), "{}", move_);
+        }
+        assert!(input.is_legal(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush
+        )));
+    }
+}
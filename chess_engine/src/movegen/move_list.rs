@@ -0,0 +1,287 @@
+use crate::moves::{Move, ScoredMove};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// Capacity of a MoveList. No legal position has more than 218 moves, so this leaves room for
+/// the pseudo legal ones too.
+pub const MAX_MOVES: usize = 256;
+
+/// Moves generated for a position, in generation order. The moves are stored inline rather than
+/// in a Vec so generating them during search never allocates.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub const fn new() -> Self {
+        Self {
+            moves: [Move::NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Appends a move. Panics if the list already holds MAX_MOVES moves.
+    pub fn push(&mut self, move_: Move) {
+        self.moves[self.len] = move_;
+        self.len += 1;
+    }
+
+    /// Removes and returns the last move
+    pub fn pop(&mut self) -> Option<Move> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.moves[self.len])
+    }
+
+    pub fn contains(&self, move_: Move) -> bool {
+        self.as_slice().contains(&move_)
+    }
+
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+
+    /// Sorts the moves from the highest score to the lowest. Moves with equal scores keep their
+    /// order, and each move is scored only once.
+    pub fn sort_by_score(&mut self, mut score: impl FnMut(Move) -> i32) {
+        let mut scored = [ScoredMove::default(); MAX_MOVES];
+        for (scored, &move_) in scored.iter_mut().zip(self.as_slice()) {
+            *scored = ScoredMove::new(move_, score(move_));
+        }
+        let scored = &mut scored[..self.len];
+        // insertion sort, which is stable and quick for lists this short
+        for i in 1..scored.len() {
+            let current = scored[i];
+            let mut j = i;
+            while j > 0 && scored[j - 1].score < current.score {
+                scored[j] = scored[j - 1];
+                j -= 1;
+            }
+            scored[j] = current;
+        }
+        for (move_, scored) in self.moves.iter_mut().zip(scored.iter()) {
+            *move_ = scored.move_;
+        }
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl PartialEq for MoveList {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for MoveList {}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl FromIterator<Move> for MoveList {
+    fn from_iter<T: IntoIterator<Item = Move>>(iter: T) -> Self {
+        let mut moves = Self::new();
+        for move_ in iter {
+            moves.push(move_);
+        }
+        moves
+    }
+}
+
+/// Owning iterator over a MoveList, yielding the moves in order
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    list: MoveList,
+    next: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let move_ = *self.list.as_slice().get(self.next)?;
+        self.next += 1;
+        Some(move_)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.list.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            list: self,
+            next: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+/// Moves paired with the ordering scores they were generated with, see scored_captures
+#[derive(Clone)]
+pub struct ScoredMoveList {
+    moves: [ScoredMove; MAX_MOVES],
+    len: usize,
+}
+
+impl ScoredMoveList {
+    pub const fn new() -> Self {
+        Self {
+            moves: [ScoredMove::new(Move::NULL, 0); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Appends a move. Panics if the list already holds MAX_MOVES moves.
+    pub fn push(&mut self, scored: ScoredMove) {
+        self.moves[self.len] = scored;
+        self.len += 1;
+    }
+
+    /// Removes and returns the move with the highest score, the first one generated if several
+    /// tie. Picking one move at a time is cheaper than sorting when a cutoff comes early.
+    pub fn pop_best(&mut self) -> Option<ScoredMove> {
+        let (index, &best) = self
+            .as_slice()
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, scored)| scored.score)?;
+        self.moves.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        Some(best)
+    }
+
+    pub fn as_slice(&self) -> &[ScoredMove] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Default for ScoredMoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ScoredMoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl Deref for ScoredMoveList {
+    type Target = [ScoredMove];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for ScoredMoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{moves::MoveFlag, squares::Square64};
+    use alloc::vec::Vec;
+
+    const MOVES: [Move; 3] = [
+        Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush),
+        Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet),
+        Move::new(Square64::D2, Square64::D3, MoveFlag::Quiet),
+    ];
+
+    #[test]
+    fn test_move_list_push_and_pop() {
+        let mut input = MoveList::new();
+        for move_ in MOVES {
+            input.push(move_);
+        }
+        assert_eq!(input.len(), 3);
+        assert_eq!(&input[..], &MOVES);
+        let output = (input.pop(), input.pop(), input.pop(), input.pop());
+        let expected = (Some(MOVES[2]), Some(MOVES[1]), Some(MOVES[0]), None);
+        assert_eq!(output, expected);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_move_list_into_iter() {
+        let input: MoveList = MOVES.into_iter().collect();
+        let output: Vec<Move> = input.clone().into_iter().collect();
+        assert_eq!(output, MOVES);
+        let output: Vec<Move> = (&input).into_iter().copied().collect();
+        assert_eq!(output, MOVES);
+    }
+
+    #[test]
+    fn test_move_list_sort_by_score() {
+        let mut input: MoveList = MOVES.into_iter().collect();
+        // the two quiets tie, so they keep their order
+        input.sort_by_score(|move_| match move_.is_double_pawn_push() {
+            true => -10,
+            false => 5,
+        });
+        let expected = [MOVES[1], MOVES[2], MOVES[0]];
+        assert_eq!(&input[..], &expected);
+    }
+
+    #[test]
+    fn test_scored_move_list_pop_best() {
+        let mut input = ScoredMoveList::new();
+        for (move_, score) in MOVES.into_iter().zip([5, 10, 5]) {
+            input.push(ScoredMove::new(move_, score));
+        }
+        let output: Vec<Move> = core::iter::from_fn(|| input.pop_best())
+            .map(|scored| scored.move_)
+            .collect();
+        // the two moves scoring 5 come out in the order they were pushed
+        let expected = [MOVES[1], MOVES[0], MOVES[2]];
+        assert_eq!(output, expected);
+        assert!(input.is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+use super::{generate_legal, scored_captures, GenerationMode, HistoryTable, ScoredMoveList};
+use crate::{
+    board::Position,
+    moves::{Move, ScoredMove},
+    pieces::{Piece, PieceType},
+};
+
+/// Stages of MovePicker, in the order they're tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    GoodCaptures,
+    /// The killers and then the countermove
+    Refutations,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Yields the legal moves of a position best first, one stage at a time: the hash move, captures
+/// that don't lose material, the killer moves and the countermove, the remaining quiet moves and
+/// finally the captures that probably lose material. Each stage only generates its moves once the
+/// stages before it are used up, so a search that cuts off on the hash move or a capture never
+/// generates the quiets.
+///
+/// The hash move, killers and countermove come from the search, e.g. the transposition table,
+/// the moves that recently caused cutoffs at the same ply and the move that last refuted the
+/// opponent's previous move. They're checked with Position::is_legal, so a stale or colliding
+/// entry is simply skipped, and no move is ever yielded twice.
+///
+/// As an Iterator the quiets come in generation order. A search picks them with next_move
+/// instead, highest history score first.
+///
+/// MovePicker::probcut only yields the hash move and the captures that win enough material by
+/// static exchange evaluation, for ProbCut to try.
+#[derive(Debug, Clone)]
+pub struct MovePicker<'a> {
+    position: &'a Position,
+    hash_move: Option<Move>,
+    /// The two killers followed by the countermove
+    refutations: [Option<Move>; 3],
+    stage: Stage,
+    /// Good and bad captures with their MVV-LVA scores, the best one popped first
+    good_captures: Option<ScoredMoveList>,
+    bad_captures: ScoredMoveList,
+    quiets: Option<ScoredMoveList>,
+    next_refutation: usize,
+    /// Static exchange evaluation every move has to reach if only captures are yielded
+    see_threshold: Option<i32>,
+}
+
+impl<'a> MovePicker<'a> {
+    pub fn new(
+        position: &'a Position,
+        hash_move: Option<Move>,
+        killers: [Option<Move>; 2],
+    ) -> Self {
+        Self {
+            position,
+            hash_move,
+            refutations: [killers[0], killers[1], None],
+            stage: Stage::HashMove,
+            good_captures: None,
+            bad_captures: ScoredMoveList::new(),
+            quiets: None,
+            next_refutation: 0,
+            see_threshold: None,
+        }
+    }
+
+    /// Picker of the captures and promotions, the hash move among them, whose static exchange
+    /// evaluation is at least see_threshold, best by MVV-LVA first
+    pub fn probcut(position: &'a Position, hash_move: Option<Move>, see_threshold: i32) -> Self {
+        Self {
+            see_threshold: Some(see_threshold),
+            ..Self::new(position, hash_move, [None, None])
+        }
+    }
+
+    /// Also tries the countermove right after the killers, if it's a legal quiet move
+    pub fn with_countermove(mut self, countermove: Option<Move>) -> Self {
+        self.refutations[2] = countermove;
+        self
+    }
+
+    fn is_hash_move(&self, move_: Move) -> bool {
+        self.hash_move == Some(move_)
+    }
+
+    fn is_refutation(&self, move_: Move) -> bool {
+        self.refutations.contains(&Some(move_))
+    }
+
+    /// Whether the move can be yielded at all, which a picker with a see_threshold only does for
+    /// the captures and promotions that reach it
+    fn reaches_threshold(&self, move_: Move) -> bool {
+        self.see_threshold.is_none_or(|threshold| {
+            (move_.is_capture() || move_.is_promotion()) && self.position.see(move_) >= threshold
+        })
+    }
+
+    /// Generates and splits up the captures the first time they're needed. With a see_threshold
+    /// the ones that don't reach it are dropped instead.
+    fn good_captures(&mut self) -> &mut ScoredMoveList {
+        if self.good_captures.is_none() {
+            let mut good = ScoredMoveList::new();
+            for &scored in scored_captures(self.position).iter() {
+                if self.see_threshold.is_some() {
+                    if self.reaches_threshold(scored.move_) {
+                        good.push(scored);
+                    }
+                    continue;
+                }
+                match is_good_capture(self.position, scored.move_) {
+                    true => good.push(scored),
+                    false => self.bad_captures.push(scored),
+                }
+            }
+            self.good_captures = Some(good);
+        }
+        self.good_captures
+            .as_mut()
+            .expect("good captures should have been generated")
+    }
+
+    /// Generates the quiet moves the first time they're needed, scored by the history if there
+    /// is one
+    fn quiets(&mut self, history: Option<&HistoryTable>) -> &mut ScoredMoveList {
+        let position = self.position;
+        self.quiets.get_or_insert_with(|| {
+            let mut quiets = ScoredMoveList::new();
+            for move_ in generate_legal(position, GenerationMode::Quiets) {
+                let score =
+                    history.map_or(0, |history| history.get(position.side_to_move(), move_));
+                quiets.push(ScoredMove::new(move_, score));
+            }
+            quiets
+        })
+    }
+
+    /// Returns the next move like Iterator::next, but orders the quiet moves by their history
+    /// scores, which is looked up once the quiets are generated
+    pub fn next_move(&mut self, history: &HistoryTable) -> Option<Move> {
+        self.pick(Some(history))
+    }
+
+    fn pick(&mut self, history: Option<&HistoryTable>) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GoodCaptures;
+                    match self.hash_move {
+                        Some(move_)
+                            if self.position.is_legal(move_) && self.reaches_threshold(move_) =>
+                        {
+                            return Some(move_)
+                        }
+                        _ => self.hash_move = None,
+                    }
+                }
+                Stage::GoodCaptures => match self.good_captures().pop_best().map(|s| s.move_) {
+                    Some(move_) if self.is_hash_move(move_) => continue,
+                    Some(move_) => return Some(move_),
+                    None if self.see_threshold.is_some() => self.stage = Stage::Done,
+                    None => self.stage = Stage::Refutations,
+                },
+                Stage::Refutations => {
+                    let Some(&refutation) = self.refutations.get(self.next_refutation) else {
+                        self.stage = Stage::Quiets;
+                        continue;
+                    };
+                    self.next_refutation += 1;
+                    let Some(refutation) = refutation else {
+                        continue;
+                    };
+                    if self.refutations[..self.next_refutation - 1].contains(&Some(refutation))
+                        || self.is_hash_move(refutation)
+                    {
+                        continue;
+                    }
+                    // refutations come from other positions, so only quiets legal here count
+                    if !refutation.is_capture()
+                        && !refutation.is_promotion()
+                        && self.position.is_legal(refutation)
+                    {
+                        return Some(refutation);
+                    }
+                }
+                Stage::Quiets => match self.quiets(history).pop_best().map(|s| s.move_) {
+                    Some(move_) if self.is_hash_move(move_) || self.is_refutation(move_) => {
+                        continue
+                    }
+                    Some(move_) => return Some(move_),
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => match self.bad_captures.pop_best().map(|s| s.move_) {
+                    Some(move_) if self.is_hash_move(move_) => continue,
+                    Some(move_) => return Some(move_),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+impl Iterator for MovePicker<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pick(None)
+    }
+}
+
+/// Whether a capture or promotion looks like it doesn't lose material: queen promotions and
+/// captures of a piece worth at least as much as the capturer always do, others only if the
+/// target square isn't defended. Underpromotions count as losing so they're tried last.
+fn is_good_capture(position: &Position, move_: Move) -> bool {
+    let us = position.side_to_move();
+    let to = move_.to_square();
+    let attacker = position
+        .piece_on(move_.from_square())
+        .expect("a generated move should have a piece on its from square");
+    let victim = match move_.is_en_passant() {
+        true => Piece::new(!us, PieceType::Pawn).get_value(),
+        false => position.piece_on(to).map_or(0, |piece| piece.get_value()),
+    };
+    match move_.promotion() {
+        Some(PieceType::Queen) => true,
+        Some(_) => false,
+        None => victim >= attacker.get_value() || !position.is_attacked(to, !us),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::START_FEN, movegen::legal_moves, moves::MoveFlag, squares::Square64, util::Color,
+    };
+    use alloc::{vec, vec::Vec};
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn sorted(mut moves: Vec<Move>) -> Vec<Move> {
+        moves.sort_by_key(|move_| move_.bits());
+        moves
+    }
+
+    #[test]
+    fn test_move_picker_yields_every_legal_move_once() {
+        let inputs = [
+            (START_FEN, None, [None, None]),
+            (
+                KIWIPETE,
+                Some(Move::new(Square64::E2, Square64::A6, MoveFlag::Capture)),
+                [
+                    Some(Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet)),
+                    Some(Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle)),
+                ],
+            ),
+        ];
+        for (fen, hash_move, killers) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let output = sorted(MovePicker::new(&position, hash_move, killers).collect());
+            let expected = sorted(legal_moves(&position).into_iter().collect());
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_move_picker_stage_order() {
+        let position = Position::from_fen(KIWIPETE).unwrap();
+        let hash_move = Move::new(Square64::A2, Square64::A4, MoveFlag::DoublePawnPush);
+        let killer = Move::new(Square64::D5, Square64::D6, MoveFlag::Quiet);
+        let output: Vec<Move> =
+            MovePicker::new(&position, Some(hash_move), [Some(killer), None]).collect();
+        assert_eq!(output[0], hash_move);
+        // the good captures come right after the hash move, then the killer and the quiets, and
+        // queen takes the knight on F6, which is defended by the bishop and queen, comes last with
+        // the other bad captures
+        let index_of = |move_| output.iter().position(|&m| m == move_).unwrap();
+        let good = Move::new(Square64::E2, Square64::A6, MoveFlag::Capture);
+        let bad = Move::new(Square64::F3, Square64::F6, MoveFlag::Capture);
+        assert!(output[1..index_of(killer)].iter().all(|m| m.is_capture()));
+        assert!(index_of(good) < index_of(killer));
+        let last_quiet = output.iter().rposition(|m| !m.is_capture()).unwrap();
+        assert!(last_quiet < index_of(bad));
+    }
+
+    #[test]
+    fn test_move_picker_skips_invalid_hash_move_and_killers() {
+        let position = Position::from_fen(START_FEN).unwrap();
+        let illegal = Move::new(Square64::E2, Square64::E5, MoveFlag::Quiet);
+        let output: Vec<Move> = MovePicker::new(&position, Some(illegal), [Some(illegal), None])
+            .take(1)
+            .collect();
+        // no captures, so the first move is the first generated quiet
+        let expected = [legal_moves(&position)[0]];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_picker_orders_quiets_by_history() {
+        let position = Position::from_fen(START_FEN).unwrap();
+        let mut history = HistoryTable::new();
+        let best = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        let second = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let worst = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        history.update(Color::White, best, 500);
+        history.update(Color::White, second, 200);
+        history.update(Color::White, worst, -200);
+        // black's scores don't matter with white to move
+        history.update(Color::Black, worst, 1_000);
+        let mut picker = MovePicker::new(&position, None, [None, None]);
+        let mut output = Vec::new();
+        while let Some(move_) = picker.next_move(&history) {
+            output.push(move_);
+        }
+        assert_eq!(output[..2], [best, second]);
+        assert_eq!(output.last(), Some(&worst));
+        // the rest keep generation order
+        let expected: Vec<Move> = legal_moves(&position)
+            .into_iter()
+            .filter(|&move_| ![best, second, worst].contains(&move_))
+            .collect();
+        assert_eq!(output[2..output.len() - 1], expected);
+    }
+
+    #[test]
+    fn test_move_picker_tries_countermove_after_killers() {
+        let position = Position::from_fen(START_FEN).unwrap();
+        let killer = Move::new(Square64::B1, Square64::C3, MoveFlag::Quiet);
+        let countermove = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        let inputs = [
+            (Some(countermove), vec![killer, countermove]),
+            // a countermove that's also a killer or illegal here isn't tried twice or at all
+            (Some(killer), vec![killer]),
+            (
+                Some(Move::new(Square64::E2, Square64::E5, MoveFlag::Quiet)),
+                vec![killer],
+            ),
+        ];
+        for (input, expected) in inputs {
+            let picker =
+                MovePicker::new(&position, None, [Some(killer), None]).with_countermove(input);
+            let output: Vec<Move> = picker.collect();
+            assert_eq!(output[..expected.len()], expected);
+            assert_eq!(output.len(), 20);
+        }
+    }
+
+    #[test]
+    fn test_probcut_move_picker() {
+        let position = Position::from_fen(KIWIPETE).unwrap();
+        let hash_move = Move::new(Square64::E2, Square64::A6, MoveFlag::Capture);
+        let inputs = [
+            // every capture that doesn't lose material, the hash move first, and the queen
+            // taking the defended knight on F6 isn't among them
+            (
+                0,
+                Some(hash_move),
+                vec![
+                    hash_move,
+                    Move::new(Square64::G2, Square64::H3, MoveFlag::Capture),
+                    Move::new(Square64::D5, Square64::E6, MoveFlag::Capture),
+                ],
+            ),
+            (300, Some(hash_move), vec![hash_move]),
+            // a quiet hash move is left out, as are captures winning too little, here the
+            // pawn on E6 that's recaptured
+            (
+                100,
+                Some(Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet)),
+                vec![
+                    Move::new(Square64::E2, Square64::A6, MoveFlag::Capture),
+                    Move::new(Square64::G2, Square64::H3, MoveFlag::Capture),
+                ],
+            ),
+            (1_000, Some(hash_move), vec![]),
+        ];
+        for (threshold, hash_move, expected) in inputs {
+            let output: Vec<Move> = MovePicker::probcut(&position, hash_move, threshold).collect();
+            assert_eq!(output, expected, "{}", threshold);
+        }
+    }
+}
@@ -1,30 +1,543 @@
-use std::fmt;
+use crate::{
+    board::Position,
+    error::{MoveConversionError, MoveFlagConversionError, PromotionConversionError},
+    movegen,
+    pieces::{Piece, PieceType},
+    squares::Square64,
+    util::SQUARE_64_BY_INDEX,
+};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use core::fmt;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Move;
+mod san;
+
+const SQUARE_MASK: u16 = 0x3F;
+const TO_SHIFT: u16 = 6;
+const FLAG_SHIFT: u16 = 12;
+
+/// Kind of move, stored in the top 4 bits of a Move. Bit 2 marks captures and bit 3 marks
+/// promotions, with the low 2 bits of a promotion picking the piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum MoveFlag {
+    Quiet = 0,
+    DoublePawnPush = 1,
+    KingCastle = 2,
+    QueenCastle = 3,
+    Capture = 4,
+    EnPassant = 5,
+    KnightPromotion = 8,
+    BishopPromotion = 9,
+    RookPromotion = 10,
+    QueenPromotion = 11,
+    KnightPromotionCapture = 12,
+    BishopPromotionCapture = 13,
+    RookPromotionCapture = 14,
+    QueenPromotionCapture = 15,
+}
+
+impl TryFrom<u8> for MoveFlag {
+    type Error = MoveFlagConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::iter()
+            .find(|f| *f as u8 == value)
+            .ok_or(MoveFlagConversionError::FromU8(value))
+    }
+}
+
+/// Piece a pawn promotes to. The discriminants are the low two bits of the promotion flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+pub enum Promotion {
+    Knight = 0,
+    Bishop = 1,
+    Rook = 2,
+    Queen = 3,
+}
+
+impl Promotion {
+    /// Every promotion, best first, which is also the order move generation emits them in
+    pub const ALL: [Promotion; 4] = [
+        Promotion::Queen,
+        Promotion::Rook,
+        Promotion::Bishop,
+        Promotion::Knight,
+    ];
+}
+
+impl From<Promotion> for PieceType {
+    fn from(value: Promotion) -> Self {
+        match value {
+            Promotion::Knight => PieceType::Knight,
+            Promotion::Bishop => PieceType::Bishop,
+            Promotion::Rook => PieceType::Rook,
+            Promotion::Queen => PieceType::Queen,
+        }
+    }
+}
+
+impl TryFrom<PieceType> for Promotion {
+    type Error = PromotionConversionError;
+
+    fn try_from(value: PieceType) -> Result<Self, Self::Error> {
+        Self::iter()
+            .find(|&promotion| PieceType::from(promotion) == value)
+            .ok_or(PromotionConversionError::FromPieceType(value))
+    }
+}
+
+/// Move packed into 16 bits:
+///   bits 0..=5   from square (Square64 index)
+///   bits 6..=11  to square (Square64 index)
+///   bits 12..=15 MoveFlag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Move(u16);
 
 impl Move {
-    pub fn from_uci(uci: &str) -> Self {
-        todo!()
+    /// All zero move (A1 to A1), used as an empty slot e.g. in the transposition table
+    pub const NULL: Move = Move(0);
+
+    pub const fn new(from: Square64, to: Square64, flag: MoveFlag) -> Self {
+        Self((from as u16) | ((to as u16) << TO_SHIFT) | ((flag as u16) << FLAG_SHIFT))
+    }
+
+    /// Parses a move in UCI coordinate notation, e.g. e2e4 or e7e8q, into the legal move of the
+    /// position it names. The notation has no flags, so the position decides whether the move is
+    /// a capture, a double push or en passant. Castling may be written as the king's two step
+    /// move, e1g1, or as the king taking its own rook, e1h1, which is how UCI writes castling in
+    /// Chess960 mode.
+    pub fn from_uci(position: &Position, uci: &str) -> Result<Self, MoveConversionError> {
+        let invalid = || MoveConversionError::InvalidUci(uci.to_owned());
+        let bytes = uci.as_bytes();
+        if !matches!(bytes.len(), 4 | 5) {
+            return Err(invalid());
+        }
+        let from = parse_square(&bytes[0..2]).ok_or_else(invalid)?;
+        let to = parse_square(&bytes[2..4]).ok_or_else(invalid)?;
+        let promotion = match bytes.get(4) {
+            None => None,
+            Some(b'n') => Some(PieceType::Knight),
+            Some(b'b') => Some(PieceType::Bishop),
+            Some(b'r') => Some(PieceType::Rook),
+            Some(b'q') => Some(PieceType::Queen),
+            Some(_) => return Err(invalid()),
+        };
+
+        let us = position.side_to_move();
+        // the king taking its own castling rook is a castle
+        let castle = [(true, MoveFlag::KingCastle), (false, MoveFlag::QueenCastle)]
+            .into_iter()
+            .find(|&(king_side, _)| {
+                position.king_square(us) == Some(from)
+                    && position.piece_on(to) == Some(Piece::new(us, PieceType::Rook))
+                    && position.castle_rook_squares(us, king_side).0 == to
+            })
+            .map(|(_, flag)| flag);
+        movegen::legal_moves(position)
+            .into_iter()
+            .find(|move_| match castle {
+                Some(flag) => move_.flag() == flag && promotion.is_none(),
+                // a castle written as the king's move only counts if the king moves two files,
+                // in Chess960 a one file castle has to be told apart from the plain king move
+                None => {
+                    move_.from_square() == from
+                        && move_.to_square() == to
+                        && move_.promotion() == promotion
+                        && (!move_.is_castle() || (from as u8 % 8).abs_diff(to as u8 % 8) == 2)
+                }
+            })
+            .ok_or_else(|| MoveConversionError::IllegalUci(uci.to_owned()))
+    }
+
+    /// Writes the move in UCI coordinate notation, the same as its Display impl
+    pub fn to_uci(self) -> String {
+        self.to_string()
+    }
+
+    /// Writes the move in UCI coordinate notation as used in Chess960 mode, where castling is
+    /// written as the king taking its own rook, e.g. e1h1. The position is the one the move is
+    /// played in, which knows where the castling rook stands.
+    pub fn to_uci_chess960(self, position: &Position) -> String {
+        let king_side = match self.flag() {
+            MoveFlag::KingCastle => true,
+            MoveFlag::QueenCastle => false,
+            _ => return self.to_uci(),
+        };
+        let (rook, _) = position.castle_rook_squares(position.side_to_move(), king_side);
+        Self::new(self.from_square(), rook, MoveFlag::Quiet).to_uci()
+    }
+
+    pub const fn from_square(self) -> Square64 {
+        SQUARE_64_BY_INDEX[(self.0 & SQUARE_MASK) as usize]
+    }
+
+    pub const fn to_square(self) -> Square64 {
+        SQUARE_64_BY_INDEX[((self.0 >> TO_SHIFT) & SQUARE_MASK) as usize]
+    }
+
+    pub fn flag(self) -> MoveFlag {
+        MoveFlag::try_from((self.0 >> FLAG_SHIFT) as u8)
+            .expect("Move should only ever be built with a valid MoveFlag")
+    }
+
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub const fn is_capture(self) -> bool {
+        self.0 & (MoveFlag::Capture as u16) << FLAG_SHIFT != 0
+    }
+
+    pub const fn is_promotion(self) -> bool {
+        self.0 & (MoveFlag::KnightPromotion as u16) << FLAG_SHIFT != 0
+    }
+
+    pub fn is_castle(self) -> bool {
+        matches!(self.flag(), MoveFlag::KingCastle | MoveFlag::QueenCastle)
+    }
+
+    pub fn is_en_passant(self) -> bool {
+        self.flag() == MoveFlag::EnPassant
+    }
+
+    pub fn is_double_pawn_push(self) -> bool {
+        self.flag() == MoveFlag::DoublePawnPush
+    }
+
+    /// Piece type the pawn promotes to, if this is a promotion
+    pub fn promotion(self) -> Option<PieceType> {
+        self.promotion_kind().map(PieceType::from)
+    }
+
+    /// Same as Move::promotion but typed so only the four promotion pieces can come back
+    pub fn promotion_kind(self) -> Option<Promotion> {
+        if !self.is_promotion() {
+            return None;
+        }
+        match (self.0 >> FLAG_SHIFT) & 0b11 {
+            0 => Some(Promotion::Knight),
+            1 => Some(Promotion::Bishop),
+            2 => Some(Promotion::Rook),
+            _ => Some(Promotion::Queen),
+        }
+    }
+
+    /// Same move promoting to the given piece, a capture if this move is one
+    pub fn with_promotion(self, promotion: Promotion) -> Self {
+        let capture = match self.is_capture() {
+            true => MoveFlag::Capture as u8,
+            false => 0,
+        };
+        let flag = MoveFlag::try_from(MoveFlag::KnightPromotion as u8 | capture | promotion as u8)
+            .expect("every promotion should have a flag with and without capture");
+        Self::new(self.from_square(), self.to_square(), flag)
+    }
+
+    /// The four promotions of this move in Promotion::ALL order, e.g. for a GUI to offer once a
+    /// pawn is dropped on the last rank
+    pub fn promotion_variants(self) -> [Self; 4] {
+        Promotion::ALL.map(|promotion| self.with_promotion(promotion))
+    }
+}
+
+impl From<Move> for u16 {
+    fn from(value: Move) -> Self {
+        value.0
     }
 }
 
+impl TryFrom<u16> for Move {
+    type Error = MoveConversionError;
+
+    /// Fails if the flag bits hold one of the two unused values (6 and 7)
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        MoveFlag::try_from((value >> FLAG_SHIFT) as u8)
+            .map(|_| Self(value))
+            .map_err(|_| MoveConversionError::FromU16(value))
+    }
+}
+
+/// Displays the move in UCI coordinate notation, e.g. e2e4 or e7e8q
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        todo!()
-        // write!(f, "{}", output_str)
+        write_square(f, self.from_square())?;
+        write_square(f, self.to_square())?;
+        if let Some(piece_type) = self.promotion() {
+            write!(f, "{}", piece_type.to_char().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Square from a lowercase file letter and a rank digit, e.g. e4
+fn parse_square(bytes: &[u8]) -> Option<Square64> {
+    match bytes {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Some(SQUARE_64_BY_INDEX[((rank - b'1') * 8 + file - b'a') as usize])
+        }
+        _ => None,
+    }
+}
+
+fn write_square(f: &mut fmt::Formatter, square: Square64) -> fmt::Result {
+    let index = square as u8;
+    write!(
+        f,
+        "{}{}",
+        (b'a' + index % 8) as char,
+        (b'1' + index / 8) as char
+    )
+}
+
+/// Move paired with an ordering score, used by move lists during search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScoredMove {
+    pub move_: Move,
+    pub score: i32,
+}
+
+impl ScoredMove {
+    pub const fn new(move_: Move, score: i32) -> Self {
+        Self { move_, score }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_from_uci() {
+        let position = Position::from_fen(
+            "r3k2r/p1p1qpb1/bn2pnp1/4N3/1p2P3/2N2Q2/PPPBBPpP/R3K2R b KQkq - 0 1",
+        )
+        .unwrap();
+        let inputs = [
+            (
+                "b4b3",
+                Ok(Move::new(Square64::B4, Square64::B3, MoveFlag::Quiet)),
+            ),
+            (
+                "c7c5",
+                Ok(Move::new(
+                    Square64::C7,
+                    Square64::C5,
+                    MoveFlag::DoublePawnPush,
+                )),
+            ),
+            (
+                "b4c3",
+                Ok(Move::new(Square64::B4, Square64::C3, MoveFlag::Capture)),
+            ),
+            (
+                "e6d5",
+                Err(MoveConversionError::IllegalUci("e6d5".to_owned())),
+            ),
+            (
+                "f6e4",
+                Ok(Move::new(Square64::F6, Square64::E4, MoveFlag::Capture)),
+            ),
+            (
+                "g2h1n",
+                Ok(Move::new(
+                    Square64::G2,
+                    Square64::H1,
+                    MoveFlag::KnightPromotionCapture,
+                )),
+            ),
+            (
+                "g2g1q",
+                Ok(Move::new(
+                    Square64::G2,
+                    Square64::G1,
+                    MoveFlag::QueenPromotion,
+                )),
+            ),
+            (
+                "e8g8",
+                Ok(Move::new(Square64::E8, Square64::G8, MoveFlag::KingCastle)),
+            ),
+            (
+                "e8h8",
+                Ok(Move::new(Square64::E8, Square64::G8, MoveFlag::KingCastle)),
+            ),
+            (
+                "e8a8",
+                Ok(Move::new(Square64::E8, Square64::C8, MoveFlag::QueenCastle)),
+            ),
+            (
+                "g2g1",
+                Err(MoveConversionError::IllegalUci("g2g1".to_owned())),
+            ),
+            (
+                "e2e4",
+                Err(MoveConversionError::IllegalUci("e2e4".to_owned())),
+            ),
+            ("e2", Err(MoveConversionError::InvalidUci("e2".to_owned()))),
+            (
+                "E7E5",
+                Err(MoveConversionError::InvalidUci("E7E5".to_owned())),
+            ),
+            (
+                "g2g1k",
+                Err(MoveConversionError::InvalidUci("g2g1k".to_owned())),
+            ),
+        ];
+        for (input, expected) in inputs {
+            let output = Move::from_uci(&position, input);
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_to_uci() {
+        let standard = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let chess960 = "4k3/8/8/8/8/8/8/1R3KR1 w GB - 0 1";
+        let inputs = [
+            (
+                "4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                Move::new(Square64::A7, Square64::A8, MoveFlag::QueenPromotion),
+                "a7a8q",
+                "a7a8q",
+            ),
+            (
+                standard,
+                Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle),
+                "e1g1",
+                "e1h1",
+            ),
+            (
+                standard,
+                Move::new(Square64::E1, Square64::C1, MoveFlag::QueenCastle),
+                "e1c1",
+                "e1a1",
+            ),
+            (
+                chess960,
+                Move::new(Square64::F1, Square64::G1, MoveFlag::KingCastle),
+                "f1g1",
+                "f1g1",
+            ),
+            (
+                chess960,
+                Move::new(Square64::F1, Square64::C1, MoveFlag::QueenCastle),
+                "f1c1",
+                "f1b1",
+            ),
+        ];
+        for (fen, input, expected, expected_chess960) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            assert_eq!(input.to_uci(), expected);
+            assert_eq!(input.to_uci_chess960(&position), expected_chess960);
+            assert_eq!(Move::from_uci(&position, expected_chess960), Ok(input));
+        }
+    }
+
+    #[test]
+    fn test_from_uci_chess960_one_file_castle() {
+        // the king on B1 castles queen side onto C1, which it can also step to
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/RK6 w A - 0 1").unwrap();
+        let inputs = [
+            (
+                "b1c1",
+                Move::new(Square64::B1, Square64::C1, MoveFlag::Quiet),
+            ),
+            (
+                "b1a1",
+                Move::new(Square64::B1, Square64::C1, MoveFlag::QueenCastle),
+            ),
+        ];
+        for (input, expected) in inputs {
+            let output = Move::from_uci(&position, input);
+            assert_eq!(output, Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_move_accessors() {
+        let input = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let output = (input.from_square(), input.to_square(), input.flag());
+        let expected = (Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        assert_eq!(output, expected);
+        assert!(input.is_double_pawn_push());
+        assert!(!input.is_capture());
+    }
+
+    #[test]
+    fn test_move_every_flag_round_trips() {
+        for flag in MoveFlag::iter() {
+            let output = Move::new(Square64::H8, Square64::A1, flag);
+            assert_eq!(output.flag(), flag);
+            assert_eq!(output.from_square(), Square64::H8);
+            assert_eq!(output.to_square(), Square64::A1);
+            assert_eq!(Move::try_from(u16::from(output)), Ok(output));
+        }
+    }
+
+    #[test]
+    fn test_move_promotion_capture() {
+        let input = Move::new(Square64::B7, Square64::A8, MoveFlag::RookPromotionCapture);
+        let output = (input.is_capture(), input.promotion());
+        let expected = (true, Some(PieceType::Rook));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_promotion_variants() {
+        let input = Move::new(Square64::B7, Square64::A8, MoveFlag::Capture);
+        let output = input.promotion_variants();
+        let expected = [
+            Move::new(Square64::B7, Square64::A8, MoveFlag::QueenPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::RookPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::BishopPromotionCapture),
+            Move::new(Square64::B7, Square64::A8, MoveFlag::KnightPromotionCapture),
+        ];
+        assert_eq!(output, expected);
+        let kinds = output.map(|move_| move_.promotion_kind());
+        assert_eq!(kinds, Promotion::ALL.map(Some));
+
+        let input = Move::new(Square64::B2, Square64::B1, MoveFlag::QueenPromotion);
+        let output = input.with_promotion(Promotion::Knight);
+        let expected = Move::new(Square64::B2, Square64::B1, MoveFlag::KnightPromotion);
+        assert_eq!(output, expected);
+    }
 
-    // #[test]
-    // fn test_from_uci() {
-    //     let ref_string = "e2e4";
-    //     let new_move: Move = Move::from_uci(ref_string);
-    //     let output_string = new_move.to_string();
-    //     assert_eq!(ref_string, output_string);
-    // }
+    #[test]
+    fn test_promotion_piece_type_conversions() {
+        for promotion in Promotion::iter() {
+            assert_eq!(
+                Promotion::try_from(PieceType::from(promotion)),
+                Ok(promotion)
+            );
+        }
+        let output = Promotion::try_from(PieceType::King);
+        let expected = Err(PromotionConversionError::FromPieceType(PieceType::King));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_try_from_u16_invalid_flag() {
+        let input: u16 = 6 << FLAG_SHIFT;
+        let output = Move::try_from(input);
+        let expected = Err(MoveConversionError::FromU16(input));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_display_uci() {
+        let input = [
+            Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush),
+            Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle),
+            Move::new(Square64::E7, Square64::E8, MoveFlag::QueenPromotion),
+            Move::new(Square64::A2, Square64::B1, MoveFlag::KnightPromotionCapture),
+        ];
+        let output = input.map(|m| m.to_string());
+        let expected = ["e2e4", "e1g1", "e7e8q", "a2b1n"];
+        assert_eq!(output, expected);
+    }
 }
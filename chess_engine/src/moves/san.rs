@@ -0,0 +1,442 @@
+use super::{parse_square, Move, MoveFlag};
+use crate::{board::Position, error::SanParseError, movegen, pieces::PieceType};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Standard Algebraic Notation, the notation of PGN files and most chess books, e.g. Nbd7, exd6,
+/// e8=Q+ or O-O#
+impl Move {
+    /// Writes the move in SAN. The move has to be legal in the position, which is needed both to
+    /// name the moving piece and to tell it apart from the other pieces of its kind that could
+    /// go to the same square.
+    pub fn to_san(self, position: &Position) -> String {
+        debug_assert!(
+            position.is_legal(self),
+            "only legal moves can be written in SAN"
+        );
+        let mut san = String::new();
+        match self.flag() {
+            MoveFlag::KingCastle => san.push_str("O-O"),
+            MoveFlag::QueenCastle => san.push_str("O-O-O"),
+            _ => self.write_san_body(position, &mut san),
+        }
+
+        let after = position.make_move_new(self);
+        if after.checkers().0 != 0 {
+            match movegen::legal_moves(&after).is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+        }
+        san
+    }
+
+    /// Parses a move in SAN into the legal move of the position it names. Sloppy SAN is accepted
+    /// too: the check and capture markers and annotations like ! may be left out or be wrong,
+    /// castling may be written with zeros, the = of a promotion may be missing, en passant may be
+    /// marked with e.p. or ep and the from square may be given in full, e.g. Ng1f3.
+    pub fn from_san(position: &Position, san: &str) -> Result<Self, SanParseError> {
+        let invalid = || SanParseError::InvalidSan(san.to_owned());
+        let trimmed = san
+            .trim()
+            .trim_end_matches("e.p.")
+            .trim_end_matches("ep")
+            .trim_end()
+            .trim_end_matches(['+', '#', '!', '?']);
+        let legal = movegen::legal_moves(position);
+
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(MoveFlag::KingCastle),
+            "O-O-O" | "0-0-0" => Some(MoveFlag::QueenCastle),
+            _ => None,
+        };
+        if let Some(flag) = castle {
+            return legal
+                .into_iter()
+                .find(|move_| move_.flag() == flag)
+                .ok_or_else(|| SanParseError::IllegalSan(san.to_owned()));
+        }
+
+        let mut bytes: Vec<u8> = trimmed
+            .bytes()
+            .filter(|&c| !matches!(c, b'x' | b':' | b'-' | b'='))
+            .collect();
+        let promotion = match bytes.last() {
+            Some(&c) if matches!(c, b'N' | b'B' | b'R' | b'Q') && bytes.len() > 2 => {
+                bytes.pop();
+                PieceType::try_from(c as char).ok()
+            }
+            _ => None,
+        };
+        let piece_type = match bytes.first() {
+            Some(&c) if matches!(c, b'N' | b'B' | b'R' | b'Q' | b'K') => {
+                bytes.remove(0);
+                PieceType::try_from(c as char).map_err(|_| invalid())?
+            }
+            _ => PieceType::Pawn,
+        };
+        let to_index = bytes.len().checked_sub(2).ok_or_else(invalid)?;
+        let to = parse_square(&bytes[to_index..]).ok_or_else(invalid)?;
+        // whatever is left narrows down the from square
+        let (mut from_file, mut from_rank) = (None, None);
+        for &c in &bytes[..to_index] {
+            match c {
+                b'a'..=b'h' if from_file.is_none() => from_file = Some(c - b'a'),
+                b'1'..=b'8' if from_rank.is_none() => from_rank = Some(c - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let candidates: Vec<Move> = legal
+            .into_iter()
+            .filter(|move_| {
+                let from = move_.from_square() as u8;
+                move_.to_square() == to
+                    && move_.promotion() == promotion
+                    && !move_.is_castle()
+                    && position
+                        .piece_on(move_.from_square())
+                        .map(|p| p.piece_type())
+                        == Some(piece_type)
+                    && from_file.is_none_or(|file| from % 8 == file)
+                    && from_rank.is_none_or(|rank| from / 8 == rank)
+            })
+            .collect();
+        match candidates[..] {
+            [move_] => Ok(move_),
+            [] => Err(SanParseError::IllegalSan(san.to_owned())),
+            _ => Err(SanParseError::AmbiguousSan(
+                san.to_owned(),
+                candidates.iter().map(|move_| move_.to_string()).collect(),
+            )),
+        }
+    }
+
+    /// Piece, disambiguation, capture, target square and promotion of a move that isn't a castle
+    fn write_san_body(self, position: &Position, san: &mut String) {
+        let (from, to) = (self.from_square(), self.to_square());
+        let piece_type = position
+            .piece_on(from)
+            .expect("a legal move should have a piece on its from square")
+            .piece_type();
+        let file = (b'a' + from as u8 % 8) as char;
+        let rank = (b'1' + from as u8 / 8) as char;
+
+        match piece_type {
+            // pawn captures are always named by their file, which is never ambiguous since the
+            // capture lands on a neighbouring file
+            PieceType::Pawn if self.is_capture() => san.push(file),
+            PieceType::Pawn => {}
+            _ => {
+                san.push(piece_type.to_char());
+                let rivals: movegen::MoveList = movegen::legal_moves(position)
+                    .into_iter()
+                    .filter(|other| {
+                        other.to_square() == to
+                            && other.from_square() != from
+                            && position
+                                .piece_on(other.from_square())
+                                .map(|p| p.piece_type())
+                                == Some(piece_type)
+                    })
+                    .collect();
+                let shares = |same: fn(u8, u8) -> bool| {
+                    rivals
+                        .iter()
+                        .any(|other| same(other.from_square() as u8, from as u8))
+                };
+                let same_file = shares(|a, b| a % 8 == b % 8);
+                let same_rank = shares(|a, b| a / 8 == b / 8);
+                match (rivals.is_empty(), same_file, same_rank) {
+                    (true, _, _) => {}
+                    (false, false, _) => san.push(file),
+                    (false, true, false) => san.push(rank),
+                    (false, true, true) => {
+                        san.push(file);
+                        san.push(rank);
+                    }
+                }
+            }
+        }
+        if self.is_capture() {
+            san.push('x');
+        }
+        san.push((b'a' + to as u8 % 8) as char);
+        san.push((b'1' + to as u8 / 8) as char);
+        if let Some(promotion) = self.promotion() {
+            san.push('=');
+            san.push(promotion.to_char());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squares::Square64;
+    use alloc::string::ToString;
+
+    fn san_of(fen: &str, from: Square64, to: Square64, flag: MoveFlag) -> String {
+        let position = Position::from_fen(fen).unwrap();
+        Move::new(from, to, flag).to_san(&position)
+    }
+
+    #[test]
+    fn test_to_san() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let inputs = [
+            (
+                kiwipete,
+                Square64::E2,
+                Square64::A6,
+                MoveFlag::Capture,
+                "Bxa6",
+            ),
+            (
+                kiwipete,
+                Square64::D5,
+                Square64::E6,
+                MoveFlag::Capture,
+                "dxe6",
+            ),
+            (
+                kiwipete,
+                Square64::A2,
+                Square64::A4,
+                MoveFlag::DoublePawnPush,
+                "a4",
+            ),
+            (
+                kiwipete,
+                Square64::E1,
+                Square64::G1,
+                MoveFlag::KingCastle,
+                "O-O",
+            ),
+            (
+                kiwipete,
+                Square64::E1,
+                Square64::C1,
+                MoveFlag::QueenCastle,
+                "O-O-O",
+            ),
+            (
+                kiwipete,
+                Square64::E5,
+                Square64::F7,
+                MoveFlag::Capture,
+                "Nxf7",
+            ),
+            (
+                kiwipete,
+                Square64::F3,
+                Square64::H3,
+                MoveFlag::Capture,
+                "Qxh3",
+            ),
+            // no other knight reaches these squares
+            (kiwipete, Square64::C3, Square64::D1, MoveFlag::Quiet, "Nd1"),
+            (kiwipete, Square64::E5, Square64::D3, MoveFlag::Quiet, "Nd3"),
+            (kiwipete, Square64::E5, Square64::G4, MoveFlag::Quiet, "Ng4"),
+        ];
+        for (fen, from, to, flag, expected) in inputs {
+            let output = san_of(fen, from, to, flag);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_to_san_disambiguation() {
+        let inputs = [
+            // knights on b8 and f6 both reach d7
+            (
+                "1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1",
+                Square64::B8,
+                Square64::D7,
+                MoveFlag::Quiet,
+                "Nbd7",
+            ),
+            // rooks on a1 and a5 share the file
+            (
+                "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1",
+                Square64::A1,
+                Square64::A3,
+                MoveFlag::Quiet,
+                "R1a3",
+            ),
+            // queens on e4, h4 and h1 all reach e1, h4 shares a file with h1 and a rank with e4
+            (
+                "8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1",
+                Square64::H4,
+                Square64::E1,
+                MoveFlag::Quiet,
+                "Qh4e1",
+            ),
+        ];
+        for (fen, from, to, flag, expected) in inputs {
+            let output = san_of(fen, from, to, flag);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_to_san_promotion_check_and_mate() {
+        let inputs = [
+            (
+                "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                Square64::B7,
+                Square64::B8,
+                MoveFlag::QueenPromotion,
+                "b8=Q+",
+            ),
+            (
+                "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                Square64::B7,
+                Square64::B8,
+                MoveFlag::KnightPromotion,
+                "b8=N",
+            ),
+            (
+                "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                Square64::B7,
+                Square64::A8,
+                MoveFlag::RookPromotionCapture,
+                "bxa8=R+",
+            ),
+            (
+                "6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1",
+                Square64::A1,
+                Square64::A8,
+                MoveFlag::Quiet,
+                "Ra8#",
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                Square64::E5,
+                Square64::D6,
+                MoveFlag::EnPassant,
+                "exd6",
+            ),
+        ];
+        for (fen, from, to, flag, expected) in inputs {
+            let output = san_of(fen, from, to, flag);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_from_san_round_trips_every_legal_move() {
+        let inputs = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ];
+        for fen in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            for move_ in movegen::legal_moves(&position) {
+                let san = move_.to_san(&position);
+                assert_eq!(
+                    Move::from_san(&position, &san),
+                    Ok(move_),
+                    "{} {}",
+                    fen,
+                    san
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_san_sloppy() {
+        let position = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let inputs = [
+            (
+                "Bxa6",
+                Move::new(Square64::E2, Square64::A6, MoveFlag::Capture),
+            ),
+            (
+                "Ba6",
+                Move::new(Square64::E2, Square64::A6, MoveFlag::Capture),
+            ),
+            (
+                "Be2xa6!?",
+                Move::new(Square64::E2, Square64::A6, MoveFlag::Capture),
+            ),
+            (
+                "de6",
+                Move::new(Square64::D5, Square64::E6, MoveFlag::Capture),
+            ),
+            (
+                "Ne5g4",
+                Move::new(Square64::E5, Square64::G4, MoveFlag::Quiet),
+            ),
+            (
+                "0-0",
+                Move::new(Square64::E1, Square64::G1, MoveFlag::KingCastle),
+            ),
+            (
+                "O-O-O+",
+                Move::new(Square64::E1, Square64::C1, MoveFlag::QueenCastle),
+            ),
+        ];
+        for (input, expected) in inputs {
+            let output = Move::from_san(&position, input);
+            assert_eq!(output, Ok(expected), "{}", input);
+        }
+
+        let position = Position::from_fen("r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let inputs = [
+            (
+                "exd6 e.p.",
+                Move::new(Square64::E5, Square64::D6, MoveFlag::EnPassant),
+            ),
+            (
+                "ed6ep",
+                Move::new(Square64::E5, Square64::D6, MoveFlag::EnPassant),
+            ),
+            (
+                "b8Q",
+                Move::new(Square64::B7, Square64::B8, MoveFlag::QueenPromotion),
+            ),
+            (
+                "bxa8=N",
+                Move::new(Square64::B7, Square64::A8, MoveFlag::KnightPromotionCapture),
+            ),
+        ];
+        for (input, expected) in inputs {
+            let output = Move::from_san(&position, input);
+            assert_eq!(output, Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_from_san_errors() {
+        let position = Position::from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let inputs = [
+            (
+                "Nd7",
+                SanParseError::AmbiguousSan(
+                    "Nd7".to_owned(),
+                    ["f6d7".to_owned(), "b8d7".to_owned()].into(),
+                ),
+            ),
+            ("Nd6", SanParseError::IllegalSan("Nd6".to_owned())),
+            ("O-O", SanParseError::IllegalSan("O-O".to_owned())),
+            ("e5", SanParseError::IllegalSan("e5".to_owned())),
+            ("Nz7", SanParseError::InvalidSan("Nz7".to_owned())),
+            ("N", SanParseError::InvalidSan("N".to_owned())),
+            ("", SanParseError::InvalidSan("".to_owned())),
+        ];
+        for (input, expected) in inputs {
+            let output = Move::from_san(&position, input);
+            assert_eq!(output, Err(expected), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Performance test: counting the leaf nodes of the legal move tree to a fixed depth. The counts
+//! of well known positions are published, e.g. 8,902 at depth 3 of the starting position, so a
+//! mismatch shows a bug in move generation or make_move, and timing a run benchmarks both.
+
+use crate::{board::Position, movegen::legal_moves, moves::Move};
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+mod suite;
+
+pub use suite::{run_perft_suite, PerftCase, PerftResult, PERFT_SUITE};
+
+/// Number of leaf nodes of the legal move tree depth plies deep. The position is left as it was.
+///
+/// Depth 1 is counted from the length of the move list rather than by making every move, which
+/// is where most of the time would go otherwise.
+pub fn perft(position: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = legal_moves(position);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for move_ in moves {
+        let undo = position.make_move(move_);
+        nodes += perft(position, depth - 1);
+        position.unmake_move(undo);
+    }
+    nodes
+}
+
+/// Cache of perft counts by Zobrist key and depth for perft_hashed. Transpositions are common
+/// in the move tree, e.g. 1. Nf3 Nf6 2. Nc3 and 1. Nc3 Nf6 2. Nf3, so deep runs find most subtrees
+/// already counted. Each key maps to one slot and a new count always replaces the old one.
+#[derive(Debug, Clone)]
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PerftEntry {
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+impl PerftTable {
+    /// Table of at least the given number of entries, rounded up to a power of two
+    pub fn new(entries: usize) -> Self {
+        Self {
+            entries: vec![PerftEntry::default(); entries.max(1).next_power_of_two()],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    fn get(&self, key: u64, depth: u32) -> Option<u64> {
+        let entry = self.entries[self.index(key)];
+        // depth 0 and 1 are never stored, so an empty slot can't match
+        (entry.key == key && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    fn insert(&mut self, key: u64, depth: u32, nodes: u64) {
+        let index = self.index(key);
+        self.entries[index] = PerftEntry { key, depth, nodes };
+    }
+}
+
+/// perft that looks subtrees up in table before counting them and stores the ones it counts.
+/// The table can be reused between runs on any position.
+pub fn perft_hashed(position: &mut Position, depth: u32, table: &mut PerftTable) -> u64 {
+    if depth <= 1 {
+        return perft(position, depth);
+    }
+    let key = position.key();
+    if let Some(nodes) = table.get(key, depth) {
+        return nodes;
+    }
+    let mut nodes = 0;
+    for move_ in legal_moves(position) {
+        let undo = position.make_move(move_);
+        nodes += perft_hashed(position, depth - 1, table);
+        position.unmake_move(undo);
+    }
+    table.insert(key, depth, nodes);
+    nodes
+}
+
+/// Perft node counts split by root move, as returned by perft_divide. Comparing them to the
+/// counts of a reference engine narrows a wrong total down to the subtree it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divide {
+    /// Every legal root move with the leaf nodes below it, in generation order
+    pub moves: Vec<(Move, u64)>,
+}
+
+impl Divide {
+    /// Leaf nodes of the whole tree, the same as perft at the same depth
+    pub fn nodes(&self) -> u64 {
+        self.moves.iter().map(|&(_, nodes)| nodes).sum()
+    }
+}
+
+/// Writes one "move: nodes" line per root move followed by the total, the format UCI engines
+/// print for `go perft`, so the output can be diffed against theirs line by line
+impl fmt::Display for Divide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (move_, nodes) in &self.moves {
+            writeln!(f, "{}: {}", move_, nodes)?;
+        }
+        write!(f, "\nNodes searched: {}", self.nodes())
+    }
+}
+
+/// Runs perft below each legal move of the position. Depth counts the root move, so the
+/// subtrees are searched depth - 1 plies deep and depth 0 has no moves to split by.
+pub fn perft_divide(position: &mut Position, depth: u32) -> Divide {
+    let mut moves = Vec::new();
+    if depth > 0 {
+        for move_ in legal_moves(position) {
+            let undo = position.make_move(move_);
+            moves.push((move_, perft(position, depth - 1)));
+            position.unmake_move(undo);
+        }
+    }
+    Divide { moves }
+}
+
+/// perft with the root moves shared out between threads, each searching its own copy of the
+/// position. Every thread takes the next unsearched root move once it's done with its last one,
+/// so an uneven split of the work doesn't leave threads idle. A thread count of 0 uses
+/// std::thread::available_parallelism.
+#[cfg(feature = "std")]
+pub fn perft_parallel(position: &Position, depth: u32, threads: usize) -> u64 {
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    if depth <= 1 {
+        return perft(&mut position.clone(), depth);
+    }
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let moves = legal_moves(position);
+    let next_move = AtomicUsize::new(0);
+    let nodes = AtomicU64::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(moves.len()) {
+            scope.spawn(|| {
+                let mut position = position.clone();
+                while let Some(&move_) = moves.get(next_move.fetch_add(1, Ordering::Relaxed)) {
+                    let undo = position.make_move(move_);
+                    nodes.fetch_add(perft(&mut position, depth - 1), Ordering::Relaxed);
+                    position.unmake_move(undo);
+                }
+            });
+        }
+    });
+    nodes.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::START_FEN, moves::MoveFlag, squares::Square64};
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_perft_start_position() {
+        let mut input = Position::start();
+        let output: [u64; 4] = core::array::from_fn(|depth| perft(&mut input, depth as u32));
+        let expected = [1, 20, 400, 8_902];
+        assert_eq!(output, expected);
+        assert!(input.strict_eq(&Position::from_fen(START_FEN).unwrap()));
+    }
+
+    #[test]
+    fn test_perft_reference_positions() {
+        let inputs = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                2,
+                2_039,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2_812),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                3,
+                9_467,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                2,
+                1_486,
+            ),
+        ];
+        for (fen, depth, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = perft(&mut position, depth);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_perft_chess960_positions() {
+        let inputs = [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                12_189,
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                18_002,
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                13_440,
+            ),
+        ];
+        for (fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = perft(&mut position, 3);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let mut input = Position::start();
+        let output = perft_divide(&mut input, 3);
+        assert_eq!(output.moves.len(), 20);
+        assert_eq!(output.nodes(), perft(&mut input, 3));
+        let e2e4 = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        assert!(output.moves.contains(&(e2e4, 600)));
+    }
+
+    #[test]
+    fn test_perft_divide_display() {
+        let mut input = Position::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let output = perft_divide(&mut input, 2).to_string();
+        let expected = "a1b1: 3\na1a2: 3\na1b2: 3\n\nNodes searched: 9";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_perft_parallel_matches_perft() {
+        let input = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        for threads in [0, 1, 3, 64] {
+            let output = (0..4)
+                .map(|depth| perft_parallel(&input, depth, threads))
+                .collect::<Vec<u64>>();
+            assert_eq!(output, [1, 48, 2_039, 97_862], "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn test_perft_hashed_matches_perft() {
+        let mut table = PerftTable::new(1 << 12);
+        let inputs = [
+            (START_FEN, 4, 197_281),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+        ];
+        for (fen, depth, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = perft_hashed(&mut position, depth, &mut table);
+            assert_eq!(output, expected, "{}", fen);
+            // the second run is answered from the table
+            let output = perft_hashed(&mut position, depth, &mut table);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::perft;
+use crate::board::Position;
+use alloc::vec::Vec;
+
+/// Position with its published perft counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerftCase {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// Leaf nodes at depth 1, 2 and so on
+    pub nodes: &'static [u64],
+}
+
+/// The standard positions of the Chess Programming Wiki perft results page followed by smaller
+/// positions that each target a rule that's easy to get wrong, e.g. an en passant capture that
+/// would expose the king along the rank
+pub const PERFT_SUITE: [PerftCase; 21] = [
+    PerftCase {
+        name: "start position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        nodes: &[20, 400, 8_902, 197_281, 4_865_609, 119_060_324],
+    },
+    PerftCase {
+        name: "kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        nodes: &[48, 2_039, 97_862, 4_085_603, 193_690_690],
+    },
+    PerftCase {
+        name: "position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        nodes: &[14, 191, 2_812, 43_238, 674_624, 11_030_083],
+    },
+    PerftCase {
+        name: "position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        nodes: &[6, 264, 9_467, 422_333, 15_833_292],
+    },
+    PerftCase {
+        name: "position 4 mirrored",
+        fen: "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        nodes: &[6, 264, 9_467, 422_333, 15_833_292],
+    },
+    PerftCase {
+        name: "position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        nodes: &[44, 1_486, 62_379, 2_103_487, 89_941_194],
+    },
+    PerftCase {
+        name: "position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        nodes: &[46, 2_079, 89_890, 3_894_594, 164_075_551],
+    },
+    PerftCase {
+        name: "en passant exposing the king",
+        fen: "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+        nodes: &[18, 92, 1_670, 10_138, 185_429, 1_134_888],
+    },
+    PerftCase {
+        name: "en passant pinned on a diagonal",
+        fen: "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1",
+        nodes: &[13, 102, 1_266, 10_276, 135_655, 1_015_133],
+    },
+    PerftCase {
+        name: "en passant giving check",
+        fen: "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        nodes: &[15, 126, 1_928, 13_931, 206_379, 1_440_467],
+    },
+    PerftCase {
+        name: "short castle giving check",
+        fen: "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        nodes: &[15, 66, 1_198, 6_399, 120_330, 661_072],
+    },
+    PerftCase {
+        name: "long castle giving check",
+        fen: "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+        nodes: &[16, 71, 1_286, 7_418, 141_077, 803_711],
+    },
+    PerftCase {
+        name: "castling rights lost",
+        fen: "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        nodes: &[26, 1_141, 27_826, 1_274_206],
+    },
+    PerftCase {
+        name: "castling prevented",
+        fen: "r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1",
+        nodes: &[44, 1_494, 50_509, 1_720_476],
+    },
+    PerftCase {
+        name: "promotion out of check",
+        fen: "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        nodes: &[11, 133, 1_442, 19_174, 266_199, 3_821_001],
+    },
+    PerftCase {
+        name: "discovered check",
+        fen: "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+        nodes: &[29, 165, 5_160, 31_961, 1_004_658],
+    },
+    PerftCase {
+        name: "promotion giving check",
+        fen: "4k3/1P6/8/8/8/8/K7/8 w - - 0 1",
+        nodes: &[9, 40, 472, 2_661, 38_983, 217_342],
+    },
+    PerftCase {
+        name: "underpromotion giving check",
+        fen: "8/P1k5/K7/8/8/8/8/8 w - - 0 1",
+        nodes: &[6, 27, 273, 1_329, 18_135, 92_683],
+    },
+    PerftCase {
+        name: "self stalemate",
+        fen: "K1k5/8/P7/8/8/8/8/8 w - - 0 1",
+        nodes: &[2, 6, 13, 63, 382, 2_217],
+    },
+    PerftCase {
+        name: "stalemate and checkmate",
+        fen: "8/k1P5/8/1K6/8/8/8/8 w - - 0 1",
+        nodes: &[10, 25, 268, 926, 10_857, 43_261, 567_584],
+    },
+    PerftCase {
+        name: "checkmate and stalemate",
+        fen: "8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1",
+        nodes: &[37, 183, 6_559, 23_527],
+    },
+];
+
+/// perft count of one case at one depth next to the published one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerftResult {
+    pub case: PerftCase,
+    pub depth: u32,
+    pub expected: u64,
+    pub nodes: u64,
+}
+
+impl PerftResult {
+    pub fn passed(&self) -> bool {
+        self.nodes == self.expected
+    }
+}
+
+impl PerftCase {
+    /// Runs perft at every depth with a published count, up to max_depth
+    pub fn run(&self, max_depth: u32) -> Vec<PerftResult> {
+        let mut position =
+            Position::from_fen(self.fen).expect("the perft suite should only hold valid FENs");
+        (1..)
+            .zip(self.nodes)
+            .take_while(|&(depth, _)| depth <= max_depth)
+            .map(|(depth, &expected)| PerftResult {
+                case: *self,
+                depth,
+                expected,
+                nodes: perft(&mut position, depth),
+            })
+            .collect()
+    }
+}
+
+/// Runs every case of PERFT_SUITE up to max_depth. Depth 3 covers every case in well under a
+/// second in release builds, while the full depths run to hundreds of millions of nodes.
+pub fn run_perft_suite(max_depth: u32) -> Vec<PerftResult> {
+    PERFT_SUITE
+        .iter()
+        .flat_map(|case| case.run(max_depth))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_suite_fens_are_valid() {
+        for case in PERFT_SUITE {
+            let output = Position::from_fen(case.fen).map(|position| position.to_fen());
+            assert_eq!(output.as_deref(), Ok(case.fen), "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_perft_suite() {
+        let output = run_perft_suite(3);
+        assert_eq!(output.len(), 3 * PERFT_SUITE.len());
+        for result in output {
+            assert!(result.passed(), "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_perft_case_run_stops_at_published_depth() {
+        let input = PERFT_SUITE
+            .iter()
+            .find(|case| case.name == "checkmate and stalemate")
+            .unwrap();
+        let output: Vec<u32> = input.run(10).iter().map(|result| result.depth).collect();
+        assert_eq!(output, [1, 2, 3, 4]);
+    }
+}
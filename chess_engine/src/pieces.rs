@@ -1,8 +1,9 @@
 use crate::{error::PieceConversionError, util::Color};
 
-use std::fmt::{self, write};
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, write};
 use strum::EnumCount;
-use strum_macros::EnumCount as EnumCountMacro;
+use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
 
 // CONSTANTS:
 const PIECE_BIG: [bool; Piece::COUNT] = [
@@ -161,7 +162,7 @@ const KING_DIRECTIONS: [i8; 8] = [
     11,  // Down Right
 ];
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, EnumCountMacro)]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -171,7 +172,7 @@ pub enum PieceType {
     King,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumCountMacro)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, EnumCountMacro)]
 pub enum Piece {
     WhitePawn,
     WhiteKnight,
@@ -187,7 +188,62 @@ pub enum Piece {
     BlackKing,
 }
 
+impl PieceType {
+    /// Returns the uppercase letter used for this piece type in FEN and SAN
+    pub fn to_char(&self) -> char {
+        char::from(*self)
+    }
+}
+
+impl TryFrom<char> for PieceType {
+    type Error = PieceConversionError;
+
+    /// Accepts either case, since the piece type alone carries no color
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value.to_ascii_uppercase() {
+            'P' => Ok(PieceType::Pawn),
+            'N' => Ok(PieceType::Knight),
+            'B' => Ok(PieceType::Bishop),
+            'R' => Ok(PieceType::Rook),
+            'Q' => Ok(PieceType::Queen),
+            'K' => Ok(PieceType::King),
+            _ => Err(PieceConversionError::FromChar(value)),
+        }
+    }
+}
+
+impl From<PieceType> for char {
+    fn from(value: PieceType) -> Self {
+        match value {
+            PieceType::Pawn => 'P',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Rook => 'R',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+}
+
 impl Piece {
+    /// Builds the piece of a given color and type
+    pub fn new(color: Color, piece_type: PieceType) -> Self {
+        match (color, piece_type) {
+            (Color::White, PieceType::Pawn) => Piece::WhitePawn,
+            (Color::White, PieceType::Knight) => Piece::WhiteKnight,
+            (Color::White, PieceType::Bishop) => Piece::WhiteBishop,
+            (Color::White, PieceType::Rook) => Piece::WhiteRook,
+            (Color::White, PieceType::Queen) => Piece::WhiteQueen,
+            (Color::White, PieceType::King) => Piece::WhiteKing,
+            (Color::Black, PieceType::Pawn) => Piece::BlackPawn,
+            (Color::Black, PieceType::Knight) => Piece::BlackKnight,
+            (Color::Black, PieceType::Bishop) => Piece::BlackBishop,
+            (Color::Black, PieceType::Rook) => Piece::BlackRook,
+            (Color::Black, PieceType::Queen) => Piece::BlackQueen,
+            (Color::Black, PieceType::King) => Piece::BlackKing,
+        }
+    }
+
     pub fn is_big(&self) -> bool {
         PIECE_BIG[*self as usize]
     }
@@ -203,7 +259,7 @@ impl Piece {
     pub fn get_value(&self) -> u32 {
         PIECE_VALUE[*self as usize]
     }
-    pub fn get_color(&self) -> Color {
+    pub fn color(&self) -> Color {
         PIECE_COLOR[*self as usize]
     }
     pub fn get_max_num_allowed(&self) -> u8 {
@@ -228,15 +284,38 @@ impl Piece {
     pub fn is_king(&self) -> bool {
         PIECE_KING[*self as usize]
     }
-    pub fn get_piece_type(&self) -> PieceType {
+    pub fn piece_type(&self) -> PieceType {
         PIECE_TYPE[*self as usize]
     }
 
+    /// Returns the FEN letter for the piece (uppercase for White, lowercase for Black)
+    pub fn to_char(&self) -> char {
+        char::from(*self)
+    }
+
+    /// Returns the unicode chess glyph for the piece
+    pub fn to_unicode(&self) -> char {
+        match self {
+            Piece::WhitePawn => '♙',
+            Piece::WhiteKnight => '♘',
+            Piece::WhiteBishop => '♗',
+            Piece::WhiteRook => '♖',
+            Piece::WhiteQueen => '♕',
+            Piece::WhiteKing => '♔',
+            Piece::BlackPawn => '♟',
+            Piece::BlackKnight => '♞',
+            Piece::BlackBishop => '♝',
+            Piece::BlackRook => '♜',
+            Piece::BlackQueen => '♛',
+            Piece::BlackKing => '♚',
+        }
+    }
+
     // TODO: Test performance
     pub fn get_attack_directions(&self) -> Vec<i8> {
         let mut attack_directions: Vec<i8> = vec![];
-        match self.get_piece_type() {
-            PieceType::Pawn => match self.get_color() {
+        match self.piece_type() {
+            PieceType::Pawn => match self.color() {
                 Color::White => attack_directions.extend_from_slice(&WHITE_PAWN_ATTACK_DIRECTIONS),
                 Color::Black => attack_directions.extend_from_slice(&BLACK_PAWN_ATTACK_DIRECTIONS),
             },
@@ -296,26 +375,14 @@ impl From<Piece> for char {
 
 impl fmt::Display for Piece {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Piece::WhitePawn => write!(f, "♙"),
-            Piece::WhiteRook => write!(f, "♖"),
-            Piece::WhiteBishop => write!(f, "♗"),
-            Piece::WhiteKnight => write!(f, "♘"),
-            Piece::WhiteQueen => write!(f, "♕"),
-            Piece::WhiteKing => write!(f, "♔"),
-            Piece::BlackPawn => write!(f, "♟"),
-            Piece::BlackRook => write!(f, "♜"),
-            Piece::BlackBishop => write!(f, "♝"),
-            Piece::BlackKnight => write!(f, "♞"),
-            Piece::BlackQueen => write!(f, "♛"),
-            Piece::BlackKing => write!(f, "♚"),
-        }
+        write!(f, "{}", self.to_unicode())
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use strum::IntoEnumIterator;
 
     #[test]
     fn test_piece_is_big_true() {
@@ -374,13 +441,60 @@ mod test {
     }
 
     #[test]
-    fn test_piece_get_color() {
+    fn test_piece_color() {
         let input = Piece::WhitePawn;
-        let output = input.get_color();
+        let output = input.color();
         let expected = Color::White;
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_piece_piece_type() {
+        let input = Piece::BlackQueen;
+        let output = input.piece_type();
+        let expected = PieceType::Queen;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_new_round_trips_color_and_type() {
+        for piece in Piece::iter() {
+            let output = Piece::new(piece.color(), piece.piece_type());
+            assert_eq!(output, piece);
+        }
+    }
+
+    #[test]
+    fn test_piece_to_char_round_trips_try_from_char() {
+        for piece in Piece::iter() {
+            let output = Piece::try_from(piece.to_char());
+            assert_eq!(output, Ok(piece));
+        }
+    }
+
+    #[test]
+    fn test_piece_type_try_from_char_ignores_case() {
+        let output = (PieceType::try_from('n'), PieceType::try_from('N'));
+        let expected = (Ok(PieceType::Knight), Ok(PieceType::Knight));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_type_try_from_char_invalid_input() {
+        let input = 'x';
+        let output = PieceType::try_from(input);
+        let expected = Err(PieceConversionError::FromChar(input));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_to_unicode() {
+        let input = Piece::WhiteKnight;
+        let output = input.to_unicode();
+        let expected = '♘';
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_piece_try_from_char_valid_input() {
         let input = 'P';
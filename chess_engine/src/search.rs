@@ -0,0 +1,2291 @@
+//! Game tree search: fail-soft negamax with alpha-beta pruning over the legal moves. Scores are
+//! in centipawns from the point of view of the side to move, with mates scored by their distance
+//! from the root so a quicker mate is preferred.
+
+use crate::{
+    board::Position,
+    movegen::{history_bonus, legal_moves, mvv_lva, scored_captures, MoveList, MovePicker},
+    moves::Move,
+    pieces::{Piece, PieceType},
+    tablebase::{Tablebases, Wdl},
+};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+mod limits;
+pub mod mcts;
+mod pruning;
+mod smp;
+mod tt;
+
+pub use limits::SearchLimits;
+use mcts::Mcts;
+use pruning::{material_gain, non_pawn_material, reduction_table, ReductionTable};
+pub use pruning::{
+    CheckExtensions, DeltaPruning, Futility, HistoryPruning, InternalIterativeDeepening,
+    LateMovePruning, Lmr, NullMove, ProbCut, Razoring, ReverseFutility, SingleReplyExtensions,
+    SingularExtensions,
+};
+pub use smp::SearchThread;
+#[cfg(feature = "std")]
+pub use smp::{time_to_depth, ScalingResult};
+use tt::excluded_key;
+pub use tt::{Bound, TranspositionTable, TtEntry, DEFAULT_HASH_MB};
+
+/// Bound no score reaches, used as the initial alpha-beta window
+pub const INFINITY: i32 = 32_000;
+/// Score of the side to move being checkmated at the root. Being mated n plies from the root
+/// scores -(MATE - n) and mating scores MATE - n.
+pub const MATE: i32 = 31_000;
+/// Score of a position the endgame tables say the side to move wins at the root, less a point
+/// a ply as for mates. It's below every mate score, as the tables don't say how far the mate is.
+pub const TB_WIN: i32 = MATE - 2 * MAX_PLY as i32;
+/// Deepest ply searched, a bound on how far quiescence search can go on capturing and checking
+pub const MAX_PLY: u32 = 128;
+
+const PV_SIZE: usize = MAX_PLY as usize + 1;
+
+/// Returns true if the score is a mate for either side found within MAX_PLY plies
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE - MAX_PLY as i32
+}
+
+/// What a position without legal moves is worth to its side to move, which is where variants
+/// with standard moves differ. Both searches score such positions through it, see
+/// Searcher::set_terminal_scoring and Mcts::set_terminal_scoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerminalScoring {
+    /// Checkmate loses and stalemate draws
+    #[default]
+    Standard,
+    /// Stalemate loses like checkmate, as in some historical rules
+    StalemateLoses,
+    /// Having no legal move wins, checkmated or not, as in antichess
+    NoMovesWins,
+}
+
+impl TerminalScoring {
+    /// Score of a position without legal moves ply plies from the root. A loss is being mated at
+    /// the ply, and a win is scored as mating at the next one, which keeps it inside the bounds
+    /// of mate distance pruning.
+    pub fn score(self, in_check: bool, ply: u32) -> i32 {
+        match (self, in_check) {
+            (Self::Standard, false) => 0,
+            (Self::Standard | Self::StalemateLoses, _) => -(MATE - ply as i32),
+            (Self::NoMovesWins, _) => MATE - ply as i32 - 1,
+        }
+    }
+
+    /// Value of a position without legal moves for Mcts, from -1 to 1
+    pub fn value(self, in_check: bool) -> f32 {
+        match self.score(in_check, 0) {
+            0 => 0.0,
+            score => score.signum() as f32,
+        }
+    }
+}
+
+/// Search score as it's reported, with mates told apart from material by the number of moves to
+/// them rather than by how close to MATE they score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Moves of the side to move until it mates, or minus the moves until it's mated. Mate(0) is
+    /// the side to move being checkmated already.
+    Mate(i32),
+}
+
+impl From<i32> for Score {
+    fn from(score: i32) -> Self {
+        if !is_mate_score(score) {
+            return Self::Centipawns(score);
+        }
+        // the mating side makes the last move, so a mate n plies away takes n / 2 moves of the
+        // side getting mated and one more of the side mating
+        let plies = MATE - score.abs();
+        match score > 0 {
+            true => Self::Mate((plies + 1) / 2),
+            false => Self::Mate(-plies / 2),
+        }
+    }
+}
+
+/// Displays the score as UCI reports it, e.g. cp 25 or mate -3
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Centipawns(centipawns) => write!(f, "cp {}", centipawns),
+            Self::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+/// Material balance of the position from the point of view of the side to move, kings excluded,
+/// the sum of the EvalTerms
+pub fn evaluate(position: &Position) -> i32 {
+    EvalTerm::iter().map(|term| term.score(position)).sum()
+}
+
+/// Term of evaluate, each scored on its own so a wrong one can be told from the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum EvalTerm {
+    PawnMaterial,
+    KnightMaterial,
+    BishopMaterial,
+    RookMaterial,
+    QueenMaterial,
+}
+
+impl EvalTerm {
+    /// Score of the term alone, from the point of view of the side to move
+    pub fn score(self, position: &Position) -> i32 {
+        let piece_type = match self {
+            Self::PawnMaterial => PieceType::Pawn,
+            Self::KnightMaterial => PieceType::Knight,
+            Self::BishopMaterial => PieceType::Bishop,
+            Self::RookMaterial => PieceType::Rook,
+            Self::QueenMaterial => PieceType::Queen,
+        };
+        let us = position.side_to_move();
+        let material = |color| {
+            let piece = Piece::new(color, piece_type);
+            (position.pieces(piece).0.count_ones() * piece.get_value()) as i32
+        };
+        material(us) - material(!us)
+    }
+}
+
+/// Outcome of a search: the best root move and its score. The move is None if the root has no
+/// legal moves, in which case the score tells checkmate from stalemate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    /// Principal variation: the line both sides are expected to play, starting with best_move.
+    /// It's empty if there's no best move and usually depth moves long, but can be shorter if it
+    /// ends in mate or a draw, or longer if it was extended or the transposition table knows
+    /// more of it.
+    pub pv: Vec<Move>,
+    pub depth: u32,
+    /// Selective depth: the deepest ply searched, quiescence search and extensions included
+    pub seldepth: u32,
+    /// Positions visited, the root included
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// Reply of the opponent the principal variation expects, which the engine can ponder on
+    pub fn ponder_move(&self) -> Option<Move> {
+        self.pv.get(1).copied()
+    }
+}
+
+/// Progress of a search at a completed depth, see SearchObserver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchInfo<'a> {
+    /// Result of the depth: its best move, score and principal variation
+    pub result: &'a SearchResult,
+    /// Nodes the thread reporting has searched so far, across its depths
+    pub nodes: u64,
+    /// Time since the search started. Telling the time needs std, so without it this and nps
+    /// are None.
+    pub time: Option<Duration>,
+    /// Nodes per second
+    pub nps: Option<u64>,
+    /// Per mille of the transposition table filled by this search
+    pub hashfull: u32,
+}
+
+impl SearchInfo<'_> {
+    pub fn score(&self) -> Score {
+        Score::from(self.result.score)
+    }
+}
+
+/// Writes the UCI info line of the progress, e.g. `info depth 2 seldepth 3 score cp 0 nodes 74
+/// nps 61666 time 1 hashfull 1 pv e2e4 e7e5`. Without a clock there's no nps or time.
+impl fmt::Display for SearchInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let result = self.result;
+        write!(
+            f,
+            "info depth {} seldepth {}",
+            result.depth, result.seldepth
+        )?;
+        write!(f, " score {} nodes {}", self.score(), self.nodes)?;
+        if let (Some(nps), Some(time)) = (self.nps, self.time) {
+            write!(f, " nps {} time {}", nps, time.as_millis())?;
+        }
+        write!(f, " hashfull {}", self.hashfull)?;
+        if !result.pv.is_empty() {
+            write!(f, " pv")?;
+            for move_ in &result.pv {
+                write!(f, " {}", move_)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Receives the progress of a search while it runs, e.g. to show it in a GUI without parsing
+/// UCI info lines. Every method does nothing by default, so an observer only implements those
+/// it needs, and () observes nothing.
+pub trait SearchObserver {
+    /// Called with every depth iterative deepening completes
+    fn on_iteration(&mut self, info: &SearchInfo) {}
+
+    /// Called before each root move of a depth is searched, the number of the first being 1.
+    /// A depth repeated with a wider aspiration window goes through the moves again.
+    fn on_current_move(&mut self, depth: u32, move_: Move, number: usize) {}
+}
+
+impl SearchObserver for () {}
+
+/// Observer that only passes the result of each depth on to a closure
+struct OnIteration<F>(F);
+
+impl<F: FnMut(&SearchResult)> SearchObserver for OnIteration<F> {
+    fn on_iteration(&mut self, info: &SearchInfo) {
+        (self.0)(info.result);
+    }
+}
+
+/// Window iterative deepening searches each depth with, around the score of the depth before.
+/// A narrow window prunes more, and if the score falls outside of it the search is repeated with
+/// the window widened on the side it failed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aspiration {
+    /// First depth searched with a narrow window, the shallower ones are searched with the full
+    /// window since their scores still swing too much
+    pub min_depth: u32,
+    /// Distance of alpha and beta from the previous score
+    pub margin: i32,
+    /// Factor the margin on the side that failed grows by for the next try, at least 2
+    pub growth: i32,
+}
+
+impl Default for Aspiration {
+    fn default() -> Self {
+        Self {
+            min_depth: 4,
+            margin: 25,
+            growth: 4,
+        }
+    }
+}
+
+/// State of the search at one ply
+#[derive(Debug, Clone, Copy, Default)]
+struct Frame {
+    /// The ply was reached by a null move, so it mustn't pass straight back
+    null_move: bool,
+    /// The node is the verification search of a null move fail high, which mustn't pass again
+    verifying: bool,
+    /// The last two quiet moves that failed high at the ply, newest first. Sibling positions
+    /// tend to be refuted by the same move, so they're tried right after the good captures.
+    killers: [Option<Move>; 2],
+    /// Move being searched at the ply, which the ply below looks up its countermove by. None
+    /// while passing.
+    current_move: Option<Move>,
+    /// Static evaluation of the node, the material balance without searching any moves. None in
+    /// check, where none of the pruning it's used for applies.
+    static_eval: Option<i32>,
+    /// Plies the line from the root to the ply has been extended by
+    extensions: u32,
+    /// Move the next search of the ply leaves out, to see whether it's singular
+    excluded_move: Option<Move>,
+}
+
+/// Searches positions to a fixed depth. The keys of the game before the root are needed to see
+/// repetitions of positions that were played rather than only searched. The transposition table
+/// is kept between searches, so searching the positions of one game with the same Searcher
+/// reuses what earlier searches found. Clones share the table, so Searchers on several threads
+/// can help each other.
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    /// State of the thread the searcher searches on
+    thread: SearchThread,
+    /// States of the helper threads, one fewer than the threads search_with_limits searches with
+    helpers: Vec<SearchThread>,
+    tt: Arc<TranspositionTable>,
+    /// None to always search with the full window
+    aspiration: Option<Aspiration>,
+    /// false to search every move with the full window, see set_pvs
+    pvs: bool,
+    /// Lines multi_pv_search searches
+    multi_pv: usize,
+    /// Root moves searches are restricted to, see set_search_moves
+    search_moves: Vec<Move>,
+    /// None to never prune by passing
+    null_move: Option<NullMove>,
+    /// None to search every move to the full depth
+    lmr: Option<Lmr>,
+    /// Plies of late move reduction by depth and move number
+    reductions: ReductionTable,
+    /// None to never prune by history
+    history_pruning: Option<HistoryPruning>,
+    /// None to never prune quiet moves by how many were searched before
+    late_move_pruning: Option<LateMovePruning>,
+    /// None to never prune futile moves
+    futility: Option<Futility>,
+    /// None to never fail high by static evaluation alone
+    reverse_futility: Option<ReverseFutility>,
+    /// None to never drop into quiescence search early
+    razoring: Option<Razoring>,
+    /// None to search every capture in quiescence that doesn't lose material
+    delta_pruning: Option<DeltaPruning>,
+    /// None to never search deeper than the depth asked for
+    check_extensions: Option<CheckExtensions>,
+    /// None to never extend forced evasions beyond the check extension
+    single_reply_extensions: Option<SingleReplyExtensions>,
+    /// None to never extend singular hash moves
+    singular_extensions: Option<SingularExtensions>,
+    /// None to never fail high by a shallower search of the good captures
+    probcut: Option<ProbCut>,
+    /// None to search nodes without a hash move in whatever order the moves come
+    iid: Option<InternalIterativeDeepening>,
+    limits: SearchLimits,
+    /// The root of the search has a single move to search, see SearchLimits::movetime
+    only_move: bool,
+    #[cfg(feature = "std")]
+    started: Option<std::time::Instant>,
+    /// Set from another thread to stop the search, see stop_handle
+    stop: Arc<AtomicBool>,
+    /// Set while the search ponders, see ponder_handle
+    ponder: Arc<AtomicBool>,
+    /// Every search starts from scratch on one thread without the clock, see set_deterministic
+    deterministic: bool,
+    /// Scores of positions without legal moves, see set_terminal_scoring
+    terminal_scoring: TerminalScoring,
+    /// None to never probe endgame tables, see set_tablebases
+    tablebases: Option<Arc<Tablebases>>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    /// Searcher with a transposition table of DEFAULT_HASH_MB
+    pub fn new() -> Self {
+        Self::with_hash_size(DEFAULT_HASH_MB)
+    }
+
+    /// Searcher with a transposition table of at most size_mb megabytes
+    pub fn with_hash_size(size_mb: usize) -> Self {
+        Self {
+            thread: SearchThread::new(Vec::new()),
+            helpers: Vec::new(),
+            tt: Arc::new(TranspositionTable::new(size_mb)),
+            aspiration: Some(Aspiration::default()),
+            pvs: true,
+            multi_pv: 1,
+            search_moves: Vec::new(),
+            null_move: Some(NullMove::default()),
+            lmr: Some(Lmr::default()),
+            reductions: reduction_table(&Lmr::default()),
+            history_pruning: Some(HistoryPruning::default()),
+            late_move_pruning: Some(LateMovePruning::default()),
+            futility: Some(Futility::default()),
+            reverse_futility: Some(ReverseFutility::default()),
+            razoring: Some(Razoring::default()),
+            delta_pruning: Some(DeltaPruning::default()),
+            check_extensions: Some(CheckExtensions::default()),
+            single_reply_extensions: Some(SingleReplyExtensions::default()),
+            singular_extensions: Some(SingularExtensions::default()),
+            probcut: Some(ProbCut::default()),
+            iid: Some(InternalIterativeDeepening::default()),
+            limits: SearchLimits::default(),
+            only_move: false,
+            #[cfg(feature = "std")]
+            started: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            ponder: Arc::new(AtomicBool::new(false)),
+            deterministic: false,
+            terminal_scoring: TerminalScoring::Standard,
+            tablebases: None,
+        }
+    }
+
+    /// Searcher for a game with the given earlier positions, oldest first, e.g. Game::history
+    pub fn with_history(history: &[u64]) -> Self {
+        let mut searcher = Self::new();
+        searcher.thread.history = history.to_vec();
+        searcher
+    }
+
+    /// Replaces the transposition table with an empty one of at most size_mb megabytes, which
+    /// clones made before no longer share
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+    }
+
+    pub fn tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    /// Starts a search within limits: the transposition table and the threads' histories age
+    /// their entries, or are cleared if the searcher is deterministic, and the killers of
+    /// earlier searches are forgotten. The limits only apply once a first depth is done.
+    fn new_search(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+        if self.deterministic {
+            self.tt.clear();
+            self.thread.clear_history();
+            self.limits.movetime = None;
+        }
+        self.tt.new_search();
+        self.thread.new_search();
+        for helper in &mut self.helpers {
+            helper.new_search();
+        }
+        #[cfg(feature = "std")]
+        {
+            self.started = Some(std::time::Instant::now());
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        self.ponder.store(limits.ponder, Ordering::Relaxed);
+    }
+
+    /// Sets the aspiration windows of iterative deepening, or turns them off with None
+    pub fn set_aspiration(&mut self, aspiration: Option<Aspiration>) {
+        self.aspiration = aspiration;
+    }
+
+    /// Turns principal variation search on or off. Off, every move is searched with the full
+    /// window to the full depth like plain alpha-beta does, so late move reductions need it on.
+    pub fn set_pvs(&mut self, pvs: bool) {
+        self.pvs = pvs;
+    }
+
+    /// Sets the number of best root moves multi_pv_search reports a line of, at least 1
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+    }
+
+    /// Restricts the root of every search to the given moves, as UCI go searchmoves does, or
+    /// lifts the restriction with none. Moves that aren't legal at a root are ignored, and a root
+    /// none of them is legal at is searched in full.
+    pub fn set_search_moves(&mut self, search_moves: &[Move]) {
+        self.search_moves = search_moves.to_vec();
+    }
+
+    /// Makes every search depend on nothing but the position, the game history before it and the
+    /// limits, so it returns the same result with the same node counts each time, as regression
+    /// tests need: each search starts with an empty transposition table and history, searches
+    /// on this thread only and ignores the movetime. Nothing in the search is random, and the
+    /// stop flag still stops it.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    /// Sets what positions without legal moves score, for the variants that differ in it
+    pub fn set_terminal_scoring(&mut self, terminal_scoring: TerminalScoring) {
+        self.terminal_scoring = terminal_scoring;
+    }
+
+    /// Endgame tables to play their endings perfectly by. A root they know only searches the
+    /// moves that keep its result, and a position a capture or pawn move leads into takes the
+    /// score they give it, see TB_WIN. The tables only hold with the standard terminal scoring.
+    pub fn set_tablebases(&mut self, tablebases: Option<Arc<Tablebases>>) {
+        self.tablebases = tablebases;
+    }
+
+    /// Sets null-move pruning, or turns it off with None
+    pub fn set_null_move(&mut self, null_move: Option<NullMove>) {
+        self.null_move = null_move;
+    }
+
+    /// Sets late move reductions, or turns them off with None
+    pub fn set_lmr(&mut self, lmr: Option<Lmr>) {
+        if let Some(lmr) = &lmr {
+            self.reductions = reduction_table(lmr);
+        }
+        self.lmr = lmr;
+    }
+
+    /// Sets history pruning, or turns it off with None
+    pub fn set_history_pruning(&mut self, history_pruning: Option<HistoryPruning>) {
+        self.history_pruning = history_pruning;
+    }
+
+    /// Sets late move pruning, or turns it off with None
+    pub fn set_late_move_pruning(&mut self, late_move_pruning: Option<LateMovePruning>) {
+        self.late_move_pruning = late_move_pruning;
+    }
+
+    /// Sets futility pruning, or turns it off with None
+    pub fn set_futility(&mut self, futility: Option<Futility>) {
+        self.futility = futility;
+    }
+
+    /// Sets reverse futility pruning, or turns it off with None
+    pub fn set_reverse_futility(&mut self, reverse_futility: Option<ReverseFutility>) {
+        self.reverse_futility = reverse_futility;
+    }
+
+    /// Sets razoring, or turns it off with None
+    pub fn set_razoring(&mut self, razoring: Option<Razoring>) {
+        self.razoring = razoring;
+    }
+
+    /// Sets delta pruning, or turns it off with None
+    pub fn set_delta_pruning(&mut self, delta_pruning: Option<DeltaPruning>) {
+        self.delta_pruning = delta_pruning;
+    }
+
+    /// Sets check extensions, or turns them off with None
+    pub fn set_check_extensions(&mut self, check_extensions: Option<CheckExtensions>) {
+        self.check_extensions = check_extensions;
+    }
+
+    /// Sets single reply extensions, or turns them off with None
+    pub fn set_single_reply_extensions(
+        &mut self,
+        single_reply_extensions: Option<SingleReplyExtensions>,
+    ) {
+        self.single_reply_extensions = single_reply_extensions;
+    }
+
+    /// Sets singular extensions, or turns them off with None
+    pub fn set_singular_extensions(&mut self, singular_extensions: Option<SingularExtensions>) {
+        self.singular_extensions = singular_extensions;
+    }
+
+    /// Sets ProbCut, or turns it off with None
+    pub fn set_probcut(&mut self, probcut: Option<ProbCut>) {
+        self.probcut = probcut;
+    }
+
+    /// Sets internal iterative deepening, or turns it off with None
+    pub fn set_iid(&mut self, iid: Option<InternalIterativeDeepening>) {
+        self.iid = iid;
+    }
+
+    /// Searches the position depth plies deep and returns the best move. The position is left
+    /// as it was.
+    pub fn search(&mut self, position: &mut Position, depth: u32) -> SearchResult {
+        self.new_search(SearchLimits::default());
+        self.search_root(position, depth, None, &[], -INFINITY, INFINITY, &mut ())
+    }
+
+    /// Searches the position one ply deeper at a time up to max_depth, calling on_iteration with
+    /// the result of each depth, and returns the result of the deepest one. Each iteration tries
+    /// the best move of the one before first, which is usually still best and makes the pruning
+    /// of the other moves more effective, and from Aspiration::min_depth on is searched with an
+    /// aspiration window. Depth 0 is only searched if max_depth is 0, and a root without legal
+    /// moves has nothing to deepen, so it's searched once.
+    pub fn iterative_deepening(
+        &mut self,
+        position: &mut Position,
+        max_depth: u32,
+        on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let limits = SearchLimits {
+            depth: Some(max_depth),
+            ..SearchLimits::default()
+        };
+        self.search_with_limits(position, limits, on_iteration)
+    }
+
+    /// Iterative deepening like iterative_deepening until one of the limits is reached. A
+    /// depth cut short by the node or time limit is thrown away, so the result is always that
+    /// of the last depth searched in full. With more than one thread, see set_threads, the
+    /// other threads search along as helpers until this one is done.
+    pub fn search_with_limits(
+        &mut self,
+        position: &mut Position,
+        limits: SearchLimits,
+        on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.search_with_observer(position, limits, &mut OnIteration(on_iteration))
+    }
+
+    /// Searches like search_with_limits, telling the observer how the search goes. Only this
+    /// thread reports, the helpers search silently.
+    pub fn search_with_observer(
+        &mut self,
+        position: &mut Position,
+        limits: SearchLimits,
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        self.new_search(limits);
+        self.only_move = self.root_moves(position).len() == 1;
+        let first_depth = limits.depth.unwrap_or(1).min(1);
+        #[cfg(feature = "std")]
+        if !self.helpers.is_empty() && !self.deterministic {
+            return self.lazy_smp(position, first_depth, observer);
+        }
+        self.deepen(position, first_depth, observer)
+    }
+
+    /// Iterative deepening from first_depth on until the limits are reached
+    fn deepen(
+        &mut self,
+        position: &mut Position,
+        first_depth: u32,
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        let (alpha, beta) = (-INFINITY, INFINITY);
+        let mut result = self.search_root(position, first_depth, None, &[], alpha, beta, observer);
+        self.thread.depth_completed = true;
+        observer.on_iteration(&self.info(&result));
+        while !self.limits_reached(&result) {
+            let next = self.search_iteration(position, result.depth + 1, &result, &[], observer);
+            if self.thread.stopped {
+                break;
+            }
+            result = next;
+            observer.on_iteration(&self.info(&result));
+        }
+        self.wait_while_pondering();
+        result
+    }
+
+    /// Progress of the search at the result of a depth
+    fn info<'a>(&self, result: &'a SearchResult) -> SearchInfo<'a> {
+        let nodes = self.thread.nodes();
+        #[cfg(feature = "std")]
+        let time = self.started.map(|started| started.elapsed());
+        #[cfg(not(feature = "std"))]
+        let time = None;
+        SearchInfo {
+            result,
+            nodes,
+            time,
+            nps: time.map(|time| (nodes as u128 * 1_000_000 / time.as_micros().max(1)) as u64),
+            hashfull: self.tt.hashfull(),
+        }
+    }
+
+    /// Iterative deepening of the best multi_pv root moves, see set_multi_pv, each being the
+    /// best of the root moves the lines before it leave. Every depth searches each line in turn
+    /// without the moves of the lines before, so as for iterative_deepening on_iteration is
+    /// called with the lines of each depth, best first, and the lines of the deepest one are
+    /// returned. There are fewer lines if the root has fewer moves to search, and only one with
+    /// no best move if it has no legal ones.
+    pub fn multi_pv_search(
+        &mut self,
+        position: &mut Position,
+        max_depth: u32,
+        mut on_iteration: impl FnMut(&[SearchResult]),
+    ) -> Vec<SearchResult> {
+        let limits = SearchLimits {
+            depth: Some(max_depth),
+            ..SearchLimits::default()
+        };
+        self.new_search(limits);
+        let count = self.multi_pv.min(self.root_moves(position).len()).max(1);
+        let mut lines: Vec<SearchResult> = Vec::with_capacity(count);
+        let mut excluded = Vec::with_capacity(count);
+        let depth = max_depth.min(1);
+        for _ in 0..count {
+            let (alpha, beta) = (-INFINITY, INFINITY);
+            let line = self.search_root(position, depth, None, &excluded, alpha, beta, &mut ());
+            excluded.extend(line.best_move);
+            lines.push(line);
+        }
+        sort_lines(&mut lines);
+        self.thread.depth_completed = true;
+        on_iteration(&lines);
+        while !self.limits_reached(&lines[0]) {
+            let depth = lines[0].depth + 1;
+            excluded.clear();
+            let mut next = Vec::with_capacity(count);
+            for line in &lines {
+                let line = self.search_iteration(position, depth, line, &excluded, &mut ());
+                excluded.extend(line.best_move);
+                next.push(line);
+            }
+            if self.thread.stopped {
+                break;
+            }
+            lines = next;
+            sort_lines(&mut lines);
+            on_iteration(&lines);
+        }
+        lines
+    }
+
+    /// Searches the next depth of a line of iterative deepening after its previous result
+    /// without the excluded root moves, from Aspiration::min_depth on with an aspiration window
+    fn search_iteration(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        previous: &SearchResult,
+        excluded: &[Move],
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        match self.aspiration {
+            // a mate score won't move by a margin, it only gets found sooner or later
+            Some(aspiration) if depth >= aspiration.min_depth && !is_mate_score(previous.score) => {
+                self.aspiration_search(position, depth, previous, excluded, aspiration, observer)
+            }
+            _ => self.search_root(
+                position,
+                depth,
+                previous.best_move,
+                excluded,
+                -INFINITY,
+                INFINITY,
+                observer,
+            ),
+        }
+    }
+
+    /// Searches depth plies deep with a window around the score of the previous iteration,
+    /// widening it until the score falls inside. The node count and selective depth cover every
+    /// try.
+    fn aspiration_search(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        previous: &SearchResult,
+        excluded: &[Move],
+        aspiration: Aspiration,
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        let growth = aspiration.growth.max(2);
+        let (mut below, mut above) = (aspiration.margin.max(1), aspiration.margin.max(1));
+        let mut first_move = previous.best_move;
+        let (mut nodes, mut seldepth) = (0, 0);
+        loop {
+            let alpha = previous.score.saturating_sub(below).max(-INFINITY);
+            let beta = previous.score.saturating_add(above).min(INFINITY);
+            let mut result =
+                self.search_root(position, depth, first_move, excluded, alpha, beta, observer);
+            nodes += result.nodes;
+            seldepth = seldepth.max(result.seldepth);
+            if self.thread.stopped {
+                return result;
+            }
+            if result.score <= alpha && alpha > -INFINITY {
+                below = below.saturating_mul(growth);
+            } else if result.score >= beta && beta < INFINITY {
+                above = above.saturating_mul(growth);
+                // the move that failed high is at least as good as the old best one
+                first_move = result.best_move;
+            } else {
+                result.nodes = nodes;
+                result.seldepth = seldepth;
+                return result;
+            }
+        }
+    }
+
+    /// Searches the root moves within the window, trying first_move before the others if it's
+    /// one of them. With the full window the score is exact, otherwise it may be a bound as in
+    /// negamax. The node count is per call, and the depth is at most MAX_PLY - 1. Only the
+    /// root_moves are searched, and the excluded ones of them are left out, which must leave at
+    /// least one unless there's none. Either way the result is then kept out of the
+    /// transposition table since it isn't the root's.
+    #[allow(clippy::too_many_arguments)]
+    fn search_root(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        first_move: Option<Move>,
+        excluded: &[Move],
+        mut alpha: i32,
+        beta: i32,
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        let depth = depth.min(MAX_PLY - 1);
+        self.thread.searched_nodes += self.thread.nodes;
+        self.thread.nodes = 1;
+        self.thread.seldepth = 0;
+        self.thread.pv_len[0] = 0;
+        let mut moves: MoveList = self
+            .root_moves(position)
+            .into_iter()
+            .filter(|move_| !excluded.contains(move_))
+            .collect();
+        if moves.is_empty() || depth == 0 {
+            let score = match moves.is_empty() {
+                true => self.terminal_score(position, 0),
+                false => evaluate(position),
+            };
+            return SearchResult {
+                best_move: None,
+                score,
+                pv: Vec::new(),
+                depth,
+                seldepth: 0,
+                nodes: self.thread.nodes,
+            };
+        }
+        let first_move = first_move.or_else(|| {
+            self.tt
+                .probe(position.key(), 0)
+                .and_then(|entry| entry.best_move)
+        });
+        order_moves(position, &mut moves, first_move);
+        let original_alpha = alpha;
+        let mut best = (moves[0], -INFINITY);
+        for (index, move_) in moves.into_iter().enumerate() {
+            observer.on_current_move(depth, move_, index + 1);
+            let score = self.search_pvs(position, move_, index == 0, depth - 1, 0, 1, alpha, beta);
+            if score > best.1 {
+                best = (move_, score);
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(0, move_);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        if self.thread.pv_len[0] == 0 {
+            // every move failed low, so the best one found is all there is of a line
+            self.thread.pv[0][0] = best.0;
+            self.thread.pv_len[0] = 1;
+        }
+        let bound = match best.1 {
+            score if score >= beta => Bound::Lower,
+            score if score > original_alpha => Bound::Exact,
+            _ => Bound::Upper,
+        };
+        if excluded.is_empty() && self.search_moves.is_empty() && !self.thread.stopped {
+            self.tt
+                .store(position.key(), 0, depth, bound, best.1, Some(best.0));
+        }
+        SearchResult {
+            best_move: Some(best.0),
+            score: best.1,
+            pv: self.principal_variation(position, depth),
+            depth,
+            seldepth: self.thread.seldepth,
+            nodes: self.thread.nodes,
+        }
+    }
+
+    /// Legal moves of the root a search goes through, those of set_search_moves if any of them
+    /// is legal
+    fn root_moves(&self, position: &Position) -> MoveList {
+        let moves = legal_moves(position);
+        let moves = match moves.iter().any(|move_| self.search_moves.contains(move_)) {
+            true => moves
+                .into_iter()
+                .filter(|move_| self.search_moves.contains(move_))
+                .collect(),
+            false => moves,
+        };
+        // the moves that keep the result of the root, if the tables know it
+        let Some(tablebases) = self
+            .tablebases
+            .as_ref()
+            .filter(|_| self.probes_tablebases())
+        else {
+            return moves;
+        };
+        let Some(wdl) = tablebases.probe(position) else {
+            return moves;
+        };
+        let keeping_result: MoveList = moves
+            .iter()
+            .copied()
+            .filter(|&move_| {
+                let next = position.make_move_new(move_);
+                let next_wdl = match next.is_insufficient_material() {
+                    true => Some(Wdl::Draw),
+                    false => tablebases.probe(&next),
+                };
+                next_wdl.map(|next_wdl| -next_wdl) == Some(wdl)
+            })
+            .collect();
+        match keeping_result.is_empty() {
+            true => moves,
+            false => keeping_result,
+        }
+    }
+
+    /// The tables are only right about the standard rules
+    fn probes_tablebases(&self) -> bool {
+        self.tablebases.is_some() && self.terminal_scoring == TerminalScoring::Standard
+    }
+
+    /// Score the endgame tables give a position just reached by a capture or pawn move, the
+    /// only moves that go from one ending to another. Within an ending the search finds its own
+    /// way to the result, which the root moves already keep to.
+    fn probe_tablebases(&self, position: &Position, ply: u32) -> Option<i32> {
+        if position.halfmove_clock() != 0 || !self.probes_tablebases() {
+            return None;
+        }
+        let score = TB_WIN - ply as i32;
+        match self.tablebases.as_ref()?.probe(position)? {
+            Wdl::Win => Some(score),
+            Wdl::Draw => Some(0),
+            Wdl::Loss => Some(-score),
+        }
+    }
+
+    fn search_move(
+        &mut self,
+        position: &mut Position,
+        move_: Move,
+        depth: u32,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.thread.stack[ply as usize - 1].current_move = Some(move_);
+        self.thread.history.push(position.key());
+        let undo = position.make_move(move_);
+        let score = match depth {
+            0 => self.quiescence(position, ply, alpha, beta),
+            depth => self.negamax(position, depth, ply, alpha, beta),
+        };
+        position.unmake_move(undo);
+        self.thread.history.pop();
+        score
+    }
+
+    /// Principal variation search of one move of a node, returning its score for the side to move
+    /// at the node. Only the first move is searched with the full window. The moves after it are
+    /// expected to be worse, so a zero window just above alpha only has to prove they don't
+    /// raise it, which prunes much more, and a move that does raise it without failing high is
+    /// searched again with the full window for its exact score. A move after the first can be
+    /// reduced by some plies, which only holds while it doesn't raise alpha.
+    #[allow(clippy::too_many_arguments)]
+    fn search_pvs(
+        &mut self,
+        position: &mut Position,
+        move_: Move,
+        first: bool,
+        depth: u32,
+        reduction: u32,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if !first && self.pvs {
+            let mut score =
+                -self.search_move(position, move_, depth - reduction, ply, -alpha - 1, -alpha);
+            if score > alpha && reduction > 0 {
+                score = -self.search_move(position, move_, depth, ply, -alpha - 1, -alpha);
+            }
+            if score <= alpha || score >= beta {
+                return score;
+            }
+        }
+        -self.search_move(position, move_, depth, ply, -beta, -alpha)
+    }
+
+    /// Fail-soft negamax: the score is exact if it lies strictly inside alpha and beta, otherwise
+    /// it's a bound on the exact score that may lie beyond the window. A transposition table
+    /// entry searched at least as deep answers the position right away if its bound settles it,
+    /// and otherwise its best move is tried first.
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.thread.nodes += 1;
+        self.thread.seldepth = self.thread.seldepth.max(ply);
+        self.thread.pv_len[ply as usize] = ply as usize;
+        let verifying = mem::take(&mut self.thread.stack[ply as usize].verifying);
+        let excluded = mem::take(&mut self.thread.stack[ply as usize].excluded_move);
+        self.check_limits();
+        if self.thread.stopped {
+            return 0;
+        }
+        if position.repetitions(&self.thread.history) > 0 || position.is_insufficient_material() {
+            return 0;
+        }
+        // mate distance pruning: no line through the node scores better than mating right after
+        // it or worse than being mated at it, so a quicker mate found elsewhere settles it
+        alpha = alpha.max(-(MATE - ply as i32));
+        let beta = beta.min(MATE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+        if let Some(score) = self.probe_tablebases(position, ply) {
+            return score;
+        }
+        let in_check = position.checkers().0 != 0;
+        let extension = self.check_extension(in_check, depth, ply);
+        let extension =
+            extension + self.single_reply_extension(position, in_check, depth + extension, ply);
+        self.thread.stack[ply as usize].extensions =
+            self.thread.stack[ply as usize - 1].extensions + extension;
+        let mut depth = depth + extension;
+        let key = match excluded {
+            Some(excluded) => excluded_key(position.key(), excluded),
+            None => position.key(),
+        };
+        let mut entry = self.tt.probe(key, ply);
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            let settled = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if settled {
+                return entry.score;
+            }
+        }
+        // unless the move that reached it mated, which the moves below find out
+        if position.is_fifty_move_draw() && !legal_moves(position).is_empty() {
+            return 0;
+        }
+        let static_eval = (!in_check).then(|| evaluate(position));
+        self.thread.stack[ply as usize].static_eval = static_eval;
+        let pv_node = beta - alpha > 1;
+        if let Some(static_eval) = static_eval.filter(|_| !pv_node) {
+            if let Some(score) = self.reverse_futility_score(depth, static_eval, beta) {
+                return score;
+            }
+            if let Some(score) = self.razor(position, depth, ply, alpha, static_eval) {
+                return score;
+            }
+        }
+        if !verifying && excluded.is_none() {
+            if let Some(score) = self.null_move_search(position, depth, ply, alpha, beta) {
+                return score;
+            }
+        }
+        if let Some(static_eval) = static_eval.filter(|_| !pv_node && excluded.is_none()) {
+            let hash_move = entry.and_then(|entry| entry.best_move);
+            if let Some(score) = self.probcut(position, depth, ply, beta, static_eval, hash_move) {
+                return score;
+            }
+        }
+        if excluded.is_none() && entry.and_then(|entry| entry.best_move).is_none() {
+            match pv_node {
+                true => {
+                    entry = self
+                        .internal_iterative_deepening(position, depth, ply, alpha, beta)
+                        .or(entry)
+                }
+                false => depth -= self.internal_iterative_reduction(depth),
+            }
+        }
+        let hash_move = entry.and_then(|entry| entry.best_move);
+        let singular = match excluded {
+            Some(_) => 0,
+            None => self.singular_extension(position, depth, ply, entry),
+        };
+        let killers = self.thread.stack[ply as usize].killers;
+        let previous = self.thread.stack[ply as usize - 1].current_move;
+        let countermove = previous.and_then(|previous| {
+            self.thread
+                .countermoves
+                .get(position.side_to_move(), previous)
+        });
+        // the picker needs a position of its own, the moves are made on this one
+        let picker_position = position.clone();
+        let mut moves =
+            MovePicker::new(&picker_position, hash_move, killers).with_countermove(countermove);
+        let original_alpha = alpha;
+        let mut best = (hash_move, -INFINITY);
+        let mut index = 0;
+        let mut quiets_tried = MoveList::new();
+        while let Some(move_) = moves.next_move(&self.thread.quiet_history) {
+            if Some(move_) == excluded {
+                continue;
+            }
+            let quiet = !move_.is_capture() && !move_.is_promotion();
+            // pruning needs a searched move that isn't getting mated, or the node could end up
+            // with no score or a wrong mate score
+            if quiet && index > 0 && !pv_node && !is_mate_score(best.1) {
+                if let Some(static_eval) = static_eval {
+                    if self.late_move_prunes(position, move_, depth, quiets_tried.len()) {
+                        continue;
+                    }
+                    if self.history_prunes(position, move_, depth) {
+                        continue;
+                    }
+                    if let Some(futility) = self
+                        .futility_value(position, move_, depth, static_eval)
+                        .filter(|&futility| futility <= alpha && !is_mate_score(alpha))
+                    {
+                        // the skipped move is expected to score no more than this
+                        best.1 = best.1.max(futility);
+                        continue;
+                    }
+                }
+            }
+            let extension = match Some(move_) == hash_move {
+                true => singular,
+                false => 0,
+            };
+            let reduction = match in_check || extension > 0 {
+                true => 0,
+                false => self.late_move_reduction(position, move_, depth, index, pv_node),
+            };
+            self.thread.stack[ply as usize].extensions += extension;
+            let score = self.search_pvs(
+                position,
+                move_,
+                index == 0,
+                depth - 1 + extension,
+                reduction,
+                ply + 1,
+                alpha,
+                beta,
+            );
+            self.thread.stack[ply as usize].extensions -= extension;
+            index += 1;
+            if score > best.1 {
+                best.1 = score;
+                if score > alpha {
+                    best.0 = Some(move_);
+                    alpha = score;
+                    self.update_pv(ply as usize, move_);
+                    if alpha >= beta {
+                        if quiet {
+                            self.store_killer(ply as usize, move_);
+                            self.update_history(position, depth, move_, &quiets_tried);
+                            if let Some(previous) = previous {
+                                self.thread.countermoves.set(
+                                    position.side_to_move(),
+                                    previous,
+                                    move_,
+                                );
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            if quiet {
+                quiets_tried.push(move_);
+            }
+        }
+        if best.1 == -INFINITY {
+            // every score beats -INFINITY, so no move was searched. Without the excluded move
+            // that's no mate, the node just has nothing else to offer.
+            return match excluded {
+                Some(_) => alpha,
+                None => self.terminal_score(position, ply),
+            };
+        }
+        let bound = match best.1 {
+            score if score >= beta => Bound::Lower,
+            score if score > original_alpha => Bound::Exact,
+            _ => Bound::Upper,
+        };
+        if !self.thread.stopped {
+            self.tt.store(key, ply, depth, bound, best.1, best.0);
+        }
+        best.1
+    }
+
+    /// Makes the quiet move the newest killer of the ply, unless it already is
+    fn store_killer(&mut self, ply: usize, move_: Move) {
+        let killers = &mut self.thread.stack[ply].killers;
+        if killers[0] != Some(move_) {
+            killers[1] = killers[0];
+            killers[0] = Some(move_);
+        }
+    }
+
+    /// Rewards the quiet move that failed high at depth and penalizes the quiet moves tried before
+    /// it, which didn't
+    fn update_history(&mut self, position: &Position, depth: u32, cutoff: Move, tried: &MoveList) {
+        let us = position.side_to_move();
+        let bonus = history_bonus(depth);
+        self.thread.quiet_history.update(us, cutoff, bonus);
+        for &move_ in tried.as_slice() {
+            self.thread.quiet_history.update(us, move_, -bonus);
+        }
+    }
+
+    /// Search past the horizon until the position is quiet, so a leaf isn't scored in the
+    /// middle of an exchange. The side to move may stand pat, i.e. take the static evaluation
+    /// rather than capture, and only tries the captures and promotions that don't lose material
+    /// by static exchange evaluation and could raise alpha by delta pruning, best MVV-LVA first. In
+    /// check every evasion is searched instead, since standing pat in check could hide a mate.
+    fn quiescence(&mut self, position: &mut Position, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        self.thread.nodes += 1;
+        self.thread.seldepth = self.thread.seldepth.max(ply);
+        // captures past the horizon aren't part of the principal variation
+        self.thread.pv_len[ply as usize] = ply as usize;
+        self.check_limits();
+        if self.thread.stopped {
+            return 0;
+        }
+        if position.repetitions(&self.thread.history) > 0 || position.is_insufficient_material() {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(position);
+        }
+        if position.checkers().0 != 0 {
+            let moves = legal_moves(position);
+            if moves.is_empty() {
+                return self.terminal_score(position, ply);
+            }
+            let mut best = -INFINITY;
+            for move_ in moves {
+                let score = -self.search_move(position, move_, 0, ply + 1, -beta, -alpha);
+                if score > best {
+                    best = score;
+                    alpha = alpha.max(score);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+            return best;
+        }
+        let mut best = evaluate(position);
+        if best >= beta {
+            return best;
+        }
+        alpha = alpha.max(best);
+        let delta_pruning = self
+            .delta_pruning
+            .filter(|delta| non_pawn_material(position) > delta.endgame_material);
+        let stand_pat = best;
+        let mut captures = scored_captures(position);
+        while let Some(scored) = captures.pop_best() {
+            if let Some(delta) = delta_pruning {
+                let optimistic = stand_pat + material_gain(position, scored.move_) + delta.margin;
+                if optimistic <= alpha {
+                    best = best.max(optimistic);
+                    continue;
+                }
+            }
+            if position.see(scored.move_) < 0 {
+                continue;
+            }
+            let score = -self.search_move(position, scored.move_, 0, ply + 1, -beta, -alpha);
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// Makes the move followed by the line below it the principal variation from ply on
+    fn update_pv(&mut self, ply: usize, move_: Move) {
+        let child_len = self.thread.pv_len[ply + 1].max(ply + 1);
+        let (row, rows_below) = self.thread.pv.split_at_mut(ply + 1);
+        row[ply][ply] = move_;
+        row[ply][ply + 1..child_len].copy_from_slice(&rows_below[0][ply + 1..child_len]);
+        self.thread.pv_len[ply] = child_len;
+    }
+
+    /// Principal variation of the root from the triangular table. A transposition table cutoff
+    /// cuts the line short where it happened, so it's continued with the best moves the table
+    /// has for the positions after it, as long as they're legal and don't repeat a position of the
+    /// line, up to depth moves.
+    fn principal_variation(&self, position: &Position, depth: u32) -> Vec<Move> {
+        let mut pv = self.thread.pv[0][..self.thread.pv_len[0]].to_vec();
+        let mut position = position.clone();
+        let mut keys = Vec::new();
+        for &move_ in &pv {
+            keys.push(position.key());
+            position.make_move(move_);
+        }
+        while pv.len() < depth as usize && !keys.contains(&position.key()) {
+            let Some(move_) = self
+                .tt
+                .probe(position.key(), 0)
+                .and_then(|entry| entry.best_move)
+                .filter(|&move_| position.is_legal(move_))
+            else {
+                break;
+            };
+            keys.push(position.key());
+            position.make_move(move_);
+            pv.push(move_);
+        }
+        pv
+    }
+
+    /// Score of a position without legal moves, ply plies from the root, see TerminalScoring
+    fn terminal_score(&self, position: &Position, ply: u32) -> i32 {
+        self.terminal_scoring.score(position.checkers().0 != 0, ply)
+    }
+}
+
+/// Sorts the lines of a multi_pv_search from the highest score to the lowest, keeping the order
+/// of equal ones
+fn sort_lines(lines: &mut [SearchResult]) {
+    lines.sort_by_key(|line| core::cmp::Reverse(line.score));
+}
+
+/// Sorts the moves by MVV-LVA with first_move, e.g. the hash move, ahead of all of them
+fn order_moves(position: &Position, moves: &mut MoveList, first_move: Option<Move>) {
+    moves.sort_by_score(|move_| match Some(move_) == first_move {
+        true => i32::MAX,
+        false => mvv_lva(position, move_),
+    });
+}
+
+/// Search algorithm an engine plays with, chosen at runtime, e.g. by a UCI option, see
+/// Engine::new. Both share the Position and move generation, so switching only changes how the
+/// moves are weighed.
+#[derive(Debug)]
+pub enum Engine {
+    /// Boxed since a Searcher's stack takes a lot more room than Mcts
+    AlphaBeta(Box<Searcher>),
+    Mcts(Mcts),
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::AlphaBeta(Box::default())
+    }
+}
+
+/// Names of the kinds of Engine, alpha_beta and mcts as strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum EngineKind {
+    AlphaBeta,
+    Mcts,
+}
+
+impl Engine {
+    /// Engine of the kind with a hash of size_mb, the transposition table of a Searcher or the
+    /// tree of Mcts
+    pub fn new(kind: EngineKind, size_mb: usize) -> Self {
+        match kind {
+            EngineKind::AlphaBeta => Self::AlphaBeta(Box::new(Searcher::with_hash_size(size_mb))),
+            EngineKind::Mcts => {
+                let mut mcts = Mcts::default();
+                mcts.set_hash_size(size_mb);
+                Self::Mcts(mcts)
+            }
+        }
+    }
+
+    pub fn kind(&self) -> EngineKind {
+        match self {
+            Self::AlphaBeta(_) => EngineKind::AlphaBeta,
+            Self::Mcts(_) => EngineKind::Mcts,
+        }
+    }
+
+    /// Searches the position within the limits, see Searcher::search_with_limits and
+    /// Mcts::search for which of them apply
+    pub fn search(&mut self, position: &mut Position, limits: SearchLimits) -> SearchResult {
+        match self {
+            Self::AlphaBeta(searcher) => searcher.search_with_limits(position, limits, |_| {}),
+            Self::Mcts(mcts) => mcts.search(position, limits),
+        }
+    }
+
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        match self {
+            Self::AlphaBeta(searcher) => searcher.stop_handle(),
+            Self::Mcts(mcts) => mcts.stop_handle(),
+        }
+    }
+}
+
+/// Searches the position depth plies deep without any game history, see Searcher::search
+pub fn search(position: &mut Position, depth: u32) -> SearchResult {
+    Searcher::new().search(position, depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::START_FEN, epd::Epd, moves::MoveFlag, perft::PERFT_SUITE, selftest::WAC,
+        squares::Square64, tablebase::Ending, util::Color,
+    };
+
+    /// Plain negamax without pruning, which alpha-beta has to agree with, down to a full window
+    /// quiescence search at the leaves
+    fn minimax(searcher: &mut Searcher, position: &mut Position, depth: u32, ply: u32) -> i32 {
+        if depth == 0 {
+            return searcher.quiescence(position, ply, -INFINITY, INFINITY);
+        }
+        let moves = legal_moves(position);
+        if moves.is_empty() {
+            return searcher.terminal_score(position, ply);
+        }
+        let mut best = -INFINITY;
+        for move_ in moves {
+            let undo = position.make_move(move_);
+            best = best.max(-minimax(searcher, position, depth - 1, ply + 1));
+            position.unmake_move(undo);
+        }
+        best
+    }
+
+    /// Searcher without the pruning that can change scores, as plain alpha-beta would find them
+    pub(super) fn exact_searcher(size_mb: usize) -> Searcher {
+        let mut searcher = Searcher::with_hash_size(size_mb);
+        searcher.set_null_move(None);
+        searcher.set_lmr(None);
+        searcher.set_history_pruning(None);
+        searcher.set_late_move_pruning(None);
+        searcher.set_futility(None);
+        searcher.set_reverse_futility(None);
+        searcher.set_razoring(None);
+        searcher.set_delta_pruning(None);
+        searcher.set_check_extensions(None);
+        searcher.set_single_reply_extensions(None);
+        searcher.set_singular_extensions(None);
+        searcher.set_probcut(None);
+        searcher.set_iid(None);
+        searcher
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let inputs = [
+            (START_FEN, 0),
+            ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 1_000),
+            ("4k3/8/8/8/8/8/8/3QK3 b - - 0 1", -1_000),
+            ("rn2k3/8/8/8/8/8/8/4K2B w - - 0 1", -550),
+        ];
+        for (fen, expected) in inputs {
+            let output = evaluate(&Position::from_fen(fen).unwrap());
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    /// Every term of the evaluation scores a position and its mirror, see Position::mirrored,
+    /// the same from the side to move's point of view, so it's antisymmetric in the colors. Each
+    /// term is checked on its own, which names the one that breaks it rather than only finding
+    /// that the sum does.
+    #[test]
+    fn test_evaluation_is_color_symmetric() {
+        let fens = [
+            "rn2k3/8/8/8/8/8/8/4K2B w - - 0 1",
+            "4k3/pppppppp/8/8/8/8/8/QQQQK3 b - - 0 1",
+        ];
+        let corpus = PERFT_SUITE
+            .iter()
+            .map(|case| case.fen)
+            .chain(fens)
+            .map(|fen| Position::from_fen(fen).unwrap())
+            .chain(
+                WAC.iter()
+                    .map(|record| Epd::try_from(*record).unwrap().position),
+            );
+        for input in corpus {
+            let mirrored = input.mirrored();
+            for term in EvalTerm::iter() {
+                let output = term.score(&mirrored);
+                let expected = term.score(&input);
+                assert_eq!(output, expected, "{} of {}", term, input.to_fen());
+            }
+            assert_eq!(evaluate(&mirrored), evaluate(&input), "{}", input.to_fen());
+        }
+    }
+
+    #[test]
+    fn test_evaluation_terms() {
+        let input = Position::from_fen("rn2k3/8/8/8/8/8/8/4K2B w - - 0 1").unwrap();
+        let output: Vec<(EvalTerm, i32)> = EvalTerm::iter()
+            .map(|term| (term, term.score(&input)))
+            .collect();
+        let expected = [
+            (EvalTerm::PawnMaterial, 0),
+            (EvalTerm::KnightMaterial, -325),
+            (EvalTerm::BishopMaterial, 325),
+            (EvalTerm::RookMaterial, -550),
+            (EvalTerm::QueenMaterial, 0),
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_finds_mate_in_one() {
+        let mut input = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let output = search(&mut input, 3);
+        let expected = Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet);
+        assert_eq!(output.best_move, Some(expected));
+        assert_eq!(output.score, MATE - 1);
+    }
+
+    #[test]
+    fn test_search_wins_material() {
+        // the rook takes the queen, which can't be recaptured
+        let mut input = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let output = search(&mut input, 2);
+        let expected = Move::new(Square64::D1, Square64::D5, MoveFlag::Capture);
+        assert_eq!(output.best_move, Some(expected));
+        assert_eq!(output.score, 550);
+    }
+
+    #[test]
+    fn test_search_without_legal_moves() {
+        let inputs = [
+            // stalemate
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 0),
+            // checkmate
+            ("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", -MATE),
+        ];
+        for (fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = search(&mut position, 3);
+            assert_eq!(
+                (output.best_move, output.score),
+                (None, expected),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_terminal_scoring() {
+        let stalemate = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1";
+        let checkmate = "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1";
+        let inputs = [
+            (TerminalScoring::Standard, stalemate, 0),
+            (TerminalScoring::Standard, checkmate, -MATE),
+            (TerminalScoring::StalemateLoses, stalemate, -MATE),
+            (TerminalScoring::StalemateLoses, checkmate, -MATE),
+            (TerminalScoring::NoMovesWins, stalemate, MATE - 1),
+            (TerminalScoring::NoMovesWins, checkmate, MATE - 1),
+        ];
+        for (terminal_scoring, fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = Searcher::new();
+            searcher.set_terminal_scoring(terminal_scoring);
+            let output = searcher.search(&mut position, 3);
+            assert_eq!(output.score, expected, "{:?} {}", terminal_scoring, fen);
+        }
+
+        // Qf7 stalemates and Qf8 mates, so under NoMovesWins white wants neither
+        let fen = "7k/8/5QK1/8/8/8/8/8 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_terminal_scoring(TerminalScoring::StalemateLoses);
+        assert_eq!(searcher.search(&mut position, 2).score, MATE - 1);
+        searcher.set_terminal_scoring(TerminalScoring::NoMovesWins);
+        let output = searcher.search(&mut position, 2);
+        position.make_move(output.best_move.unwrap());
+        assert!(!legal_moves(&position).is_empty());
+        assert!(!is_mate_score(output.score));
+    }
+
+    #[test]
+    fn test_tablebases() {
+        let tablebases = Arc::new(Tablebases::generate(&[Ending::Kpk]));
+        // only the king moves that keep the opposition win
+        let mut position = Position::from_fen("8/3k4/8/4K3/4P3/8/8/8 w - - 0 1").unwrap();
+        let moves = legal_moves(&position);
+        let winning: Vec<Move> = moves
+            .iter()
+            .copied()
+            .filter(|&move_| tablebases.probe(&position.make_move_new(move_)) == Some(Wdl::Loss))
+            .collect();
+        assert!(!winning.is_empty() && winning.len() < moves.len());
+        let mut searcher = Searcher::new();
+        assert_eq!(searcher.root_moves(&position).as_slice(), moves.as_slice());
+        searcher.set_tablebases(Some(tablebases.clone()));
+        assert_eq!(searcher.root_moves(&position).as_slice(), winning);
+        let output = searcher.search(&mut position, 4);
+        assert!(winning.contains(&output.best_move.unwrap()));
+
+        // taking the rook leaves a won KQK, which only the tables see the end of this soon
+        let fen = "r3k3/8/8/Q7/8/8/8/4K3 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        assert_eq!(searcher.search(&mut position, 2).score, TB_WIN - 1);
+        let mut searcher = Searcher::new();
+        searcher.set_tablebases(Some(tablebases));
+        searcher.set_terminal_scoring(TerminalScoring::StalemateLoses);
+        assert!(searcher.search(&mut position, 2).score < TB_WIN - MAX_PLY as i32);
+    }
+
+    #[test]
+    fn test_search_matches_minimax() {
+        let inputs = [
+            (START_FEN, 3),
+            ("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 3),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+        ];
+        for (fen, depth) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = exact_searcher(DEFAULT_HASH_MB).search(&mut position, depth);
+            let expected = minimax(&mut exact_searcher(0), &mut position, depth, 0);
+            assert_eq!(output.score, expected, "{}", fen);
+            assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_multi_pv_search() {
+        let inputs = [
+            (START_FEN, 3),
+            ("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 3),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+        ];
+        for (fen, depth) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_multi_pv(3);
+            let mut depths = Vec::new();
+            let output = searcher.multi_pv_search(&mut position, depth, |lines| {
+                depths.push(lines.iter().map(|line| line.depth).collect::<Vec<_>>())
+            });
+            assert_eq!(
+                depths,
+                (1..=depth).map(|depth| vec![depth; 3]).collect::<Vec<_>>()
+            );
+            // each line scores what minimax gives its move, best first
+            let mut expected: Vec<i32> = legal_moves(&position)
+                .into_iter()
+                .map(|move_| {
+                    let undo = position.make_move(move_);
+                    let score = -minimax(&mut exact_searcher(0), &mut position, depth - 1, 1);
+                    position.unmake_move(undo);
+                    score
+                })
+                .collect();
+            expected.sort_by_key(|&score| core::cmp::Reverse(score));
+            let scores: Vec<i32> = output.iter().map(|line| line.score).collect();
+            assert_eq!(scores, expected[..3], "{}", fen);
+            for (index, line) in output.iter().enumerate() {
+                assert_eq!(line.pv.first().copied(), line.best_move, "{}", fen);
+                assert!(!output[..index]
+                    .iter()
+                    .any(|other| other.best_move == line.best_move));
+                play_pv(&position, &line.pv);
+            }
+            assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_multi_pv_search_with_few_moves() {
+        // the king has to take the queen and has nothing else to report
+        let mut position = Position::from_fen("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_multi_pv(4);
+        let output = searcher.multi_pv_search(&mut position, 3, |_| {});
+        let capture = Move::new(Square64::A1, Square64::B2, MoveFlag::Capture);
+        assert_eq!(output.len(), 1);
+        assert_eq!((output[0].best_move, output[0].score), (Some(capture), 0));
+        // checkmated
+        let mut position = Position::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let output = searcher.multi_pv_search(&mut position, 3, |_| {});
+        assert_eq!(output.len(), 1);
+        assert_eq!((output[0].best_move, output[0].score), (None, -MATE));
+        // a single line is what iterative deepening finds
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        searcher.set_multi_pv(0);
+        let output = searcher.multi_pv_search(&mut position, 4, |_| {});
+        let expected = Searcher::new().iterative_deepening(&mut position, 4, |_| {});
+        assert_eq!(output.len(), 1);
+        assert_eq!(
+            (output[0].best_move, output[0].score),
+            (expected.best_move, expected.score)
+        );
+    }
+
+    #[test]
+    fn test_search_moves() {
+        // the rook takes the queen unless it's not allowed to
+        let mut position = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let capture = Move::new(Square64::D1, Square64::D5, MoveFlag::Capture);
+        let king_moves = [
+            Move::new(Square64::E1, Square64::E2, MoveFlag::Quiet),
+            Move::new(Square64::E1, Square64::F2, MoveFlag::Quiet),
+        ];
+        let mut searcher = Searcher::new();
+        searcher.set_search_moves(&king_moves);
+        let output = searcher.search(&mut position, 3);
+        assert!(king_moves.contains(&output.best_move.unwrap()));
+        assert!(output.score < 0);
+        // the restricted result doesn't pass for the root's in the table
+        assert_eq!(searcher.tt.probe(position.key(), 0), None);
+        searcher.set_multi_pv(3);
+        let output = searcher.multi_pv_search(&mut position, 3, |_| {});
+        let mut moves: Vec<Move> = output.iter().flat_map(|line| line.best_move).collect();
+        moves.sort_by_key(|move_| move_.bits());
+        assert_eq!(moves, king_moves);
+        // illegal moves are ignored, and so is a restriction with nothing legal
+        let illegal = Move::new(Square64::E1, Square64::E3, MoveFlag::Quiet);
+        searcher.set_search_moves(&[illegal, king_moves[0]]);
+        let output = searcher.search(&mut position, 3);
+        assert_eq!(output.best_move, Some(king_moves[0]));
+        searcher.set_search_moves(&[illegal]);
+        let output = searcher.search(&mut position, 3);
+        assert_eq!(output.best_move, Some(capture));
+        searcher.set_search_moves(&[]);
+        let output = searcher.iterative_deepening(&mut position, 3, |_| {});
+        assert_eq!(output.best_move, Some(capture));
+    }
+
+    #[test]
+    fn test_search_scores_repetition_as_draw() {
+        // white is a queen down, but Kb1 repeats a position of the game
+        let mut position = Position::from_fen("7k/8/8/7q/8/8/8/K7 w - - 10 40").unwrap();
+        let repeat = Move::new(Square64::A1, Square64::B1, MoveFlag::Quiet);
+        let history = [position.make_move_new(repeat).key()];
+        let output = search(&mut position, 1);
+        assert_eq!(output.score, -1_000);
+        let output = Searcher::with_history(&history).search(&mut position, 1);
+        assert_eq!((output.best_move, output.score), (Some(repeat), 0));
+    }
+
+    #[test]
+    fn test_iterative_deepening_reports_every_depth() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut iterations = Vec::new();
+        let output =
+            exact_searcher(DEFAULT_HASH_MB).iterative_deepening(&mut position, 3, |result| {
+                iterations.push(result.clone());
+            });
+        let depths: Vec<u32> = iterations.iter().map(|result| result.depth).collect();
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(output, iterations[2]);
+        // the move order at the root doesn't change its score
+        for result in &iterations {
+            let expected = exact_searcher(DEFAULT_HASH_MB)
+                .search(&mut position, result.depth)
+                .score;
+            assert_eq!(result.score, expected, "depth {}", result.depth);
+        }
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+    }
+
+    #[test]
+    fn test_iterative_deepening_stops_without_legal_moves() {
+        let mut position = Position::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let mut calls = 0;
+        let output = Searcher::new().iterative_deepening(&mut position, 5, |_| calls += 1);
+        assert_eq!((output.best_move, output.score, calls), (None, -MATE, 1));
+    }
+
+    #[test]
+    fn test_seldepth() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        let mut output = Vec::new();
+        searcher.iterative_deepening(&mut position, 5, |result| output.push(result.seldepth));
+        // quiescence search goes past the horizon in a position full of captures
+        assert!(output
+            .iter()
+            .zip(1..)
+            .all(|(&seldepth, depth)| seldepth > depth));
+        assert!(output.iter().all(|&seldepth| seldepth < MAX_PLY));
+        // nothing's searched past the root at depth 0 or without moves
+        let output = searcher.search(&mut position, 0);
+        assert_eq!(output.seldepth, 0);
+        let mut position = Position::from_fen("7k/8/6QK/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(searcher.search(&mut position, 3).seldepth, 0);
+    }
+
+    #[test]
+    fn test_search_info_display() {
+        let e4 = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let e5 = Move::new(Square64::E7, Square64::E5, MoveFlag::DoublePawnPush);
+        let result = SearchResult {
+            best_move: Some(e4),
+            score: 30,
+            pv: vec![e4, e5],
+            depth: 2,
+            seldepth: 5,
+            nodes: 70,
+        };
+        let mut input = SearchInfo {
+            result: &result,
+            nodes: 100,
+            time: Some(Duration::from_millis(20)),
+            nps: Some(5_000),
+            hashfull: 3,
+        };
+        let output = input.to_string();
+        let expected =
+            "info depth 2 seldepth 5 score cp 30 nodes 100 nps 5000 time 20 hashfull 3 pv e2e4 e7e5";
+        assert_eq!(output, expected);
+        let result = SearchResult {
+            best_move: None,
+            score: -MATE,
+            pv: Vec::new(),
+            depth: 1,
+            seldepth: 0,
+            nodes: 1,
+        };
+        input = SearchInfo {
+            result: &result,
+            time: None,
+            nps: None,
+            ..input
+        };
+        let output = input.to_string();
+        let expected = "info depth 1 seldepth 0 score mate 0 nodes 100 hashfull 3";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(5),
+            movetime: Some(Duration::from_millis(1)),
+            ..SearchLimits::default()
+        };
+        let mut searcher = Searcher::new();
+        searcher.set_deterministic(true);
+        searcher.set_threads(4);
+        let mut expected = Vec::new();
+        searcher.search_with_limits(&mut position, limits, |result| {
+            expected.push(result.clone())
+        });
+        let expected_nodes = searcher.nodes();
+        // neither the clock nor the helpers cut it short
+        assert_eq!(expected.len(), 5);
+        // what other searches leave behind doesn't change the next one
+        searcher.search(&mut Position::start(), 4);
+        searcher.search_with_limits(&mut position, limits, |_| {});
+        let mut output = Vec::new();
+        searcher.search_with_limits(&mut position, limits, |result| output.push(result.clone()));
+        assert_eq!(output, expected);
+        assert_eq!(searcher.nodes(), expected_nodes);
+        let mut searcher = Searcher::new();
+        searcher.set_deterministic(true);
+        let output = searcher.search_with_limits(&mut position, limits, |_| {});
+        assert_eq!(Some(&output), expected.last());
+        assert_eq!(searcher.nodes(), expected_nodes);
+    }
+
+    #[test]
+    fn test_engine() {
+        let inputs = [
+            ("alpha_beta", EngineKind::AlphaBeta),
+            ("mcts", EngineKind::Mcts),
+        ];
+        for (name, expected) in inputs {
+            let kind: EngineKind = name.parse().unwrap();
+            assert_eq!(kind, expected);
+            assert_eq!(kind.to_string(), name);
+            let mut engine = Engine::new(kind, 1);
+            assert_eq!(engine.kind(), kind);
+            let limits = SearchLimits {
+                nodes: Some(1_000),
+                ..SearchLimits::default()
+            };
+            let mut position = Position::start();
+            let output = engine.search(&mut position, limits);
+            assert!(position.is_legal(output.best_move.unwrap()), "{}", name);
+            assert!(!engine.stop_handle().load(Ordering::Relaxed));
+        }
+        assert!("minimax".parse::<EngineKind>().is_err());
+        assert_eq!(Engine::default().kind(), EngineKind::AlphaBeta);
+        // the hash size goes to the tree of Mcts too
+        let Engine::Mcts(output) = Engine::new(EngineKind::Mcts, 1) else {
+            panic!("not an Mcts engine");
+        };
+        assert_eq!(output.max_nodes(), (1 << 20) / mcts::NODE_SIZE);
+        assert_ne!(output.max_nodes(), Mcts::default().max_nodes());
+    }
+
+    /// Observer keeping everything it's told
+    #[derive(Default)]
+    struct Recorder {
+        iterations: Vec<(SearchResult, u64, Option<u64>, u32)>,
+        current_moves: Vec<(u32, Move, usize)>,
+    }
+
+    impl SearchObserver for Recorder {
+        fn on_iteration(&mut self, info: &SearchInfo) {
+            let iteration = (info.result.clone(), info.nodes, info.nps, info.hashfull);
+            self.iterations.push(iteration);
+        }
+
+        fn on_current_move(&mut self, depth: u32, move_: Move, number: usize) {
+            self.current_moves.push((depth, move_, number));
+        }
+    }
+
+    #[test]
+    fn test_search_observer() {
+        let mut position = Position::start();
+        // a table of a single bucket fills up
+        let mut searcher = Searcher::with_hash_size(0);
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let mut recorder = Recorder::default();
+        let output = searcher.search_with_observer(&mut position, limits, &mut recorder);
+        let depths: Vec<u32> = recorder
+            .iterations
+            .iter()
+            .map(|(result, ..)| result.depth)
+            .collect();
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(recorder.iterations[2].0, output);
+        // the node count runs on across the depths
+        let nodes: Vec<u64> = recorder
+            .iterations
+            .iter()
+            .map(|&(_, nodes, ..)| nodes)
+            .collect();
+        assert!(nodes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(nodes[2], searcher.nodes());
+        let (_, _, nps, hashfull) = recorder.iterations[2];
+        assert!(hashfull > 0);
+        assert_eq!(nps.is_some(), cfg!(feature = "std"));
+        // without aspiration windows each depth goes through the 20 moves once
+        for depth in 1..=3 {
+            let output: Vec<(Move, usize)> = recorder
+                .current_moves
+                .iter()
+                .filter(|(move_depth, ..)| *move_depth == depth)
+                .map(|&(_, move_, number)| (move_, number))
+                .collect();
+            let numbers: Vec<usize> = output.iter().map(|&(_, number)| number).collect();
+            assert_eq!(numbers, (1..=20).collect::<Vec<_>>(), "{}", depth);
+            let mut moves: Vec<Move> = output.iter().map(|&(move_, _)| move_).collect();
+            moves.sort_by_key(|move_| move_.bits());
+            moves.dedup();
+            assert_eq!(moves.len(), 20);
+        }
+        // the first move of a depth is the best of the depth before
+        let first = recorder
+            .current_moves
+            .iter()
+            .find(|(depth, ..)| *depth == 3);
+        assert_eq!(
+            first.map(|&(_, move_, _)| move_),
+            recorder.iterations[1].0.best_move
+        );
+    }
+
+    #[test]
+    fn test_ponder_move() {
+        let e4 = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let e5 = Move::new(Square64::E7, Square64::E5, MoveFlag::DoublePawnPush);
+        let nf3 = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        let inputs = [
+            (vec![], None),
+            // a line ending in mate leaves nothing to ponder on
+            (vec![e4], None),
+            (vec![e4, e5], Some(e5)),
+            (vec![e4, e5, nf3], Some(e5)),
+        ];
+        for (pv, expected) in inputs {
+            let input = SearchResult {
+                best_move: pv.first().copied(),
+                score: 0,
+                pv,
+                depth: 3,
+                seldepth: 3,
+                nodes: 1,
+            };
+            assert_eq!(input.ponder_move(), expected, "{:?}", input.pv);
+        }
+    }
+
+    #[test]
+    fn test_quiescence_resolves_captures() {
+        let inputs = [
+            // the queen can take the pawn, but the rook recaptures, so white stands pat
+            ("3rk3/8/8/3p4/8/8/8/3QK3 w - - 0 1", 350),
+            // the knight on E5 hangs
+            ("4k3/8/8/4n3/8/8/8/4RK2 w - - 0 1", 550),
+            // in check white can't stand pat, it has to move and takes the checking queen
+            ("4k3/8/8/8/8/8/1q6/K1R5 w - - 0 1", 550),
+            ("8/8/8/8/8/2k5/1q6/K7 w - - 0 1", -MATE),
+        ];
+        for (fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = Searcher::new().quiescence(&mut position, 0, -INFINITY, INFINITY);
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_search_sees_past_the_horizon() {
+        // at depth 1 without quiescence search the queen would take the defended pawn
+        let mut input = Position::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let output = search(&mut input, 1);
+        let bad = Move::new(Square64::D1, Square64::D5, MoveFlag::Capture);
+        assert_ne!(output.best_move, Some(bad));
+        assert_eq!(output.score, 800);
+    }
+
+    #[test]
+    fn test_transposition_table_keeps_scores_and_saves_nodes() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(1);
+        let first = searcher.search(&mut position, 3);
+        let expected = exact_searcher(0).search(&mut position, 3);
+        assert_eq!(first.score, expected.score);
+        // searched again, the root's children are answered from the table
+        let second = searcher.search(&mut position, 3);
+        assert_eq!(second.score, expected.score);
+        assert!(second.nodes < first.nodes);
+    }
+
+    /// Plays the principal variation on a copy of the position, checking every move is legal
+    pub(super) fn play_pv(position: &Position, pv: &[Move]) -> Position {
+        let mut position = position.clone();
+        for &move_ in pv {
+            assert!(
+                position.is_legal(move_),
+                "{} in {}",
+                move_,
+                position.to_fen()
+            );
+            position.make_move(move_);
+        }
+        position
+    }
+
+    #[test]
+    fn test_principal_variation() {
+        let inputs = [
+            (START_FEN, 3),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                3,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+        ];
+        for (fen, depth) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = exact_searcher(DEFAULT_HASH_MB).search(&mut position, depth);
+            assert_eq!(output.pv.len(), depth as usize, "{}", fen);
+            assert_eq!(output.pv.first().copied(), output.best_move);
+            play_pv(&position, &output.pv);
+        }
+    }
+
+    #[test]
+    fn test_score_from() {
+        let inputs = [
+            (0, Score::Centipawns(0)),
+            (-550, Score::Centipawns(-550)),
+            (
+                MATE - MAX_PLY as i32 - 1,
+                Score::Centipawns(MATE - MAX_PLY as i32 - 1),
+            ),
+            (MATE - 1, Score::Mate(1)),
+            (MATE - 3, Score::Mate(2)),
+            (-(MATE - 2), Score::Mate(-1)),
+            (-(MATE - 4), Score::Mate(-2)),
+            (-MATE, Score::Mate(0)),
+        ];
+        for (score, expected) in inputs {
+            assert_eq!(Score::from(score), expected, "{}", score);
+        }
+    }
+
+    #[test]
+    fn test_score_display() {
+        assert_eq!(Score::Centipawns(-25).to_string(), "cp -25");
+        assert_eq!(Score::Mate(3).to_string(), "mate 3");
+        assert_eq!(Score::Mate(-2).to_string(), "mate -2");
+    }
+
+    #[test]
+    fn test_mate_distance_pruning() {
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        // a mate found 4 plies from the root is as quick as any through a node at ply 3
+        let output = searcher.negamax(&mut position, 5, 3, MATE - 4, MATE);
+        assert_eq!((output, searcher.thread.nodes), (MATE - 4, 1));
+        // a deeper search still finds the mate at its true distance
+        let output = Searcher::new().search(&mut position, 8);
+        assert_eq!(output.score, MATE - 3);
+        assert_eq!(Score::from(output.score), Score::Mate(2));
+    }
+
+    #[test]
+    fn test_principal_variation_ends_in_mate() {
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = search(&mut position, 4);
+        assert_eq!(output.score, MATE - 3);
+        assert_eq!(output.pv.len(), 3);
+        let mated = play_pv(&position, &output.pv);
+        assert!(legal_moves(&mated).is_empty() && mated.checkers().0 != 0);
+    }
+
+    #[test]
+    fn test_principal_variation_continues_from_transposition_table() {
+        // searched again, the root's children are answered from the table, which still knows
+        // the rest of the line
+        let mut position = Position::start();
+        let mut searcher = Searcher::new();
+        let first = searcher.search(&mut position, 3);
+        let second = searcher.search(&mut position, 3);
+        assert_eq!(second.pv.len(), 3);
+        assert_eq!(second.pv, first.pv);
+    }
+
+    #[test]
+    fn test_aspiration_windows_keep_scores() {
+        let inputs = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+        // a window this narrow fails on most iterations
+        let aspiration = Aspiration {
+            min_depth: 2,
+            margin: 1,
+            growth: 2,
+        };
+        for fen in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_aspiration(Some(aspiration));
+            let mut output = Vec::new();
+            searcher.iterative_deepening(&mut position, 3, |result| output.push(result.score));
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_aspiration(None);
+            let mut expected = Vec::new();
+            searcher.iterative_deepening(&mut position, 3, |result| expected.push(result.score));
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_search_root_fails_outside_window() {
+        let mut position = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        // the exact score is 550
+        let output = searcher.search_root(&mut position, 2, None, &[], 600, 700, &mut ());
+        assert!(output.score <= 600);
+        assert_eq!(output.pv.first().copied(), output.best_move);
+        let output = searcher.search_root(&mut position, 2, None, &[], 400, 500, &mut ());
+        assert!(output.score >= 500);
+    }
+
+    #[test]
+    fn test_pvs_matches_full_window_search() {
+        let inputs = [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        ];
+        for fen in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            let output = searcher.search(&mut position, 4);
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_pvs(false);
+            let expected = searcher.search(&mut position, 4);
+            assert_eq!(
+                (output.score, output.best_move),
+                (expected.score, expected.best_move),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_pvs_searches_reduced_move_again() {
+        // Ra7 leaves the back rank to Rd1#, which quiescence search doesn't look for
+        let mut position = Position::from_fen("3r2k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let move_ = Move::new(Square64::A1, Square64::A7, MoveFlag::Quiet);
+        let alpha = -100;
+        let mut searcher = exact_searcher(0);
+        // reduced to quiescence search the move fails high at a zero window node...
+        let reduced = -searcher.search_move(&mut position, move_, 0, 1, -alpha - 1, -alpha);
+        assert!(reduced > alpha);
+        // ...so it's searched again to the full depth, which sees the mate
+        let output = searcher.search_pvs(&mut position, move_, false, 1, 1, 1, alpha, alpha + 1);
+        assert_eq!(output, -MATE + 2);
+    }
+
+    #[test]
+    fn test_store_killer() {
+        let mut searcher = Searcher::new();
+        let a3 = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let h3 = Move::new(Square64::H2, Square64::H3, MoveFlag::Quiet);
+        let inputs = [
+            (a3, [Some(a3), None]),
+            // a killer isn't stored twice
+            (a3, [Some(a3), None]),
+            (h3, [Some(h3), Some(a3)]),
+            (a3, [Some(a3), Some(h3)]),
+        ];
+        for (input, expected) in inputs {
+            searcher.store_killer(3, input);
+            assert_eq!(searcher.thread.stack[3].killers, expected, "{}", input);
+        }
+        assert_eq!(searcher.thread.stack[2].killers, [None, None]);
+    }
+
+    #[test]
+    fn test_search_stores_quiet_killers() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        searcher.search(&mut position, 4);
+        let killers: Vec<Move> = searcher
+            .thread
+            .stack
+            .iter()
+            .flat_map(|frame| frame.killers.into_iter().flatten())
+            .collect();
+        assert!(!killers.is_empty());
+        assert!(killers
+            .iter()
+            .all(|killer| !killer.is_capture() && !killer.is_promotion()));
+        // the next search starts without them
+        searcher.search(&mut Position::start(), 1);
+        assert!(searcher
+            .thread
+            .stack
+            .iter()
+            .all(|frame| frame.killers == [None, None]));
+    }
+
+    #[test]
+    fn test_update_history() {
+        let position = Position::start();
+        let mut searcher = Searcher::new();
+        let cutoff = Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet);
+        let tried = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let mut quiets_tried = MoveList::new();
+        quiets_tried.push(tried);
+        searcher.update_history(&position, 4, cutoff, &quiets_tried);
+        let history = &searcher.thread.quiet_history;
+        let output = (
+            history.get(Color::White, cutoff),
+            history.get(Color::White, tried),
+        );
+        assert_eq!(output, (history_bonus(4), -history_bonus(4)));
+        assert_eq!(history.get(Color::Black, cutoff), 0);
+    }
+
+    #[test]
+    fn test_search_records_countermoves() {
+        let mut searcher = Searcher::new();
+        searcher.search(&mut Position::start(), 4);
+        let mut countermoves = Vec::new();
+        for color in [Color::White, Color::Black] {
+            for (from, to) in
+                Square64::iter().flat_map(|from| Square64::iter().map(move |to| (from, to)))
+            {
+                let previous = Move::new(from, to, MoveFlag::Quiet);
+                countermoves.extend(searcher.thread.countermoves.get(color, previous));
+            }
+        }
+        assert!(!countermoves.is_empty());
+        assert!(countermoves
+            .iter()
+            .all(|move_| !move_.is_capture() && !move_.is_promotion()));
+    }
+}
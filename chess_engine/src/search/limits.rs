@@ -0,0 +1,330 @@
+//! Limits of a search: what stops it, from the node and time limits of SearchLimits to the stop
+//! and ponder flags other threads set
+
+use super::{Score, SearchResult, Searcher, MAX_PLY};
+use alloc::sync::Arc;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Limits of Searcher::search_with_limits, each None to not limit by it. The search stops at
+/// the first limit it reaches and returns the last depth it completed, so the first depth is
+/// always searched in full. Without any limits it goes on until it's stopped through
+/// Searcher::stop_handle, as for UCI go infinite.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Deepest depth searched, at most MAX_PLY - 1
+    pub depth: Option<u32>,
+    /// Nodes the whole search may visit across its depths
+    pub nodes: Option<u64>,
+    /// Time the search may take. Telling the time needs std, so without it there's no limit.
+    /// A root with a single move to search has nothing to spend it on, so then the search
+    /// stops after its first depth.
+    pub movetime: Option<Duration>,
+    /// Moves to mate in: the search stops once the side to move mates within that many
+    pub mate: Option<u32>,
+    /// The search ponders, as for UCI go ponder: it searches the position after the expected
+    /// reply during the opponent's time and doesn't stop at any limit until Searcher's ponder
+    /// handle is cleared at ponderhit, from when on the limits apply and the movetime counts.
+    /// Stopping it returns the best move found so far as usual.
+    pub ponder: bool,
+}
+
+/// Nodes between two looks at the clock, since that costs more than a search node
+const NODES_PER_TIME_CHECK: u64 = 1_024;
+
+impl Searcher {
+    /// Flag that stops the running search once set, e.g. from the thread reading UCI stop. Like
+    /// a limit it only stops search_with_limits and multi_pv_search, and only once a first depth
+    /// is done, so there's always a best move. Each search clears it when it starts, and clones
+    /// share it, so it stops all of them.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Flag that's set while the search ponders, see SearchLimits::ponder. Clearing it, as for
+    /// UCI ponderhit, turns the ponder search into a search within its limits. Each search sets
+    /// it when it starts, so a ponderhit before that is lost.
+    pub fn ponder_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.ponder)
+    }
+
+    /// Whether the search still ponders. The clock of the limits starts at the ponderhit that
+    /// ends it.
+    pub(super) fn pondering(&mut self) -> bool {
+        if self.limits.ponder && !self.ponder.load(Ordering::Relaxed) {
+            self.limits.ponder = false;
+            #[cfg(feature = "std")]
+            {
+                self.started = Some(std::time::Instant::now());
+            }
+        }
+        self.limits.ponder
+    }
+
+    /// Waits for the ponderhit or stop that ends a ponder search, since the best move mustn't
+    /// be played before
+    pub(super) fn wait_while_pondering(&mut self) {
+        while self.pondering() && !self.stop.load(Ordering::Relaxed) {
+            #[cfg(feature = "std")]
+            std::thread::sleep(Duration::from_millis(1));
+            #[cfg(not(feature = "std"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Checks the stop flag and the node and time limits at a node, stopping the search if one
+    /// is reached
+    pub(super) fn check_limits(&mut self) {
+        if !self.thread.depth_completed || self.thread.stopped {
+            return;
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            self.thread.stopped = true;
+        }
+        if self.pondering() {
+            return;
+        }
+        let nodes = self.thread.nodes();
+        if self.limits.nodes.is_some_and(|limit| nodes >= limit) {
+            self.thread.stopped = true;
+        }
+        #[cfg(feature = "std")]
+        if let (Some(movetime), Some(started)) = (self.limits.movetime, self.started) {
+            if nodes % NODES_PER_TIME_CHECK == 0 && started.elapsed() >= movetime {
+                self.thread.stopped = true;
+            }
+        }
+    }
+
+    /// Whether iterative deepening is done after the result of a completed depth, which is
+    /// when it reached the depth limit, found a mate quick enough or has a single root move to
+    /// spend a movetime on. While pondering it only is once there's nothing left to deepen.
+    pub(super) fn limits_reached(&mut self, result: &SearchResult) -> bool {
+        if self.pondering() {
+            return result.depth >= MAX_PLY - 1
+                || result.best_move.is_none()
+                || self.thread.stopped;
+        }
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY - 1).min(MAX_PLY - 1);
+        let mated = match (self.limits.mate, Score::from(result.score)) {
+            (Some(limit), Score::Mate(moves)) => moves > 0 && moves as u32 <= limit,
+            _ => false,
+        };
+        let forced = self.only_move && self.limits.movetime.is_some();
+        result.depth >= max_depth
+            || mated
+            || forced
+            || result.best_move.is_none()
+            || self.thread.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::Position,
+        moves::{Move, MoveFlag},
+        search::{
+            tests::{exact_searcher, play_pv},
+            DEFAULT_HASH_MB,
+        },
+        squares::Square64,
+    };
+
+    #[test]
+    fn test_search_with_node_limit() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        let limits = SearchLimits {
+            nodes: Some(20_000),
+            ..SearchLimits::default()
+        };
+        let mut iterations = Vec::new();
+        let output = searcher.search_with_limits(&mut position, limits, |result| {
+            iterations.push(result.clone())
+        });
+        // the depth cut short isn't reported, the one before it is returned
+        assert!(iterations.len() < 6);
+        assert_eq!(Some(&output), iterations.last());
+        let nodes: u64 = iterations.iter().map(|result| result.nodes).sum();
+        assert!(nodes < 20_000);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        // and nothing it left in the table is wrong
+        let output = searcher.search(&mut position, 3);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 3);
+        assert_eq!(output.score, expected.score);
+        // the first depth is always searched in full
+        let limits = SearchLimits {
+            nodes: Some(1),
+            ..SearchLimits::default()
+        };
+        let output = searcher.search_with_limits(&mut position, limits, |_| {});
+        assert_eq!(output.depth, 1);
+        assert!(output.best_move.is_some());
+    }
+
+    #[test]
+    fn test_stop_handle() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        let stop = searcher.stop_handle();
+        // stopped after depth 3, the search returns it without finishing depth 4
+        let mut depths = Vec::new();
+        let output =
+            searcher.search_with_limits(&mut position, SearchLimits::default(), |result| {
+                depths.push(result.depth);
+                if result.depth == 3 {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(output.depth, 3);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        // the next search clears the flag
+        let output = searcher.iterative_deepening(&mut position, 4, |_| {});
+        assert_eq!(output.depth, 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_infinite_search_stops_from_another_thread() {
+        let mut searcher = Searcher::new();
+        let stop = searcher.stop_handle();
+        let search = std::thread::spawn(move || {
+            let mut position = Position::start();
+            searcher.search_with_limits(&mut position, SearchLimits::default(), |_| {})
+        });
+        // a stop before the search cleared the flag would be lost, so it's repeated
+        while !search.is_finished() {
+            stop.store(true, Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let output = search.join().unwrap();
+        assert!(output.best_move.is_some());
+        assert!(output.depth < MAX_PLY - 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ponder() {
+        let mut searcher = Searcher::new();
+        let ponder = searcher.ponder_handle();
+        let limits = SearchLimits {
+            depth: Some(2),
+            movetime: Some(Duration::from_millis(20)),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+        let search = std::thread::spawn(move || {
+            let mut position = Position::start();
+            let output = searcher.search_with_limits(&mut position, limits, |_| {});
+            (output, searcher.ponder_handle().load(Ordering::Relaxed))
+        });
+        // neither the depth nor the time limit ends the search before ponderhit
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!search.is_finished());
+        let started = std::time::Instant::now();
+        ponder.store(false, Ordering::Relaxed);
+        let (output, pondering) = search.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(output.best_move.is_some() && output.depth >= 2);
+        assert!(!pondering);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ponder_stops() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut searcher = Searcher::new();
+        searcher.set_threads(2);
+        let stop = searcher.stop_handle();
+        let limits = SearchLimits {
+            depth: Some(1),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+        // pondering searches past the depth limit, so it's stopped once it got a ply past it
+        let search = std::thread::spawn(move || {
+            let mut position = Position::from_fen(fen).unwrap();
+            searcher.search_with_limits(&mut position, limits, |result| {
+                if result.depth == 2 {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            })
+        });
+        // stop returns the best move and the reply to ponder on next
+        let output = search.join().unwrap();
+        let position = Position::from_fen(fen).unwrap();
+        assert_eq!(output.depth, 2);
+        assert!(position.is_legal(output.best_move.unwrap()));
+        assert!(output.ponder_move().is_some());
+        play_pv(&position, &output.pv);
+    }
+
+    #[test]
+    fn test_search_with_depth_and_mate_limits() {
+        // Rh7 and Rh8 mate in 2, found at depth 3
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let inputs = [
+            (Some(2), None, 3),
+            (Some(1), Some(5), 5),
+            (None, Some(4), 4),
+            (None, Some(0), 0),
+        ];
+        for (mate, depth, expected) in inputs {
+            let limits = SearchLimits {
+                depth,
+                mate,
+                ..SearchLimits::default()
+            };
+            let output = Searcher::new().search_with_limits(&mut position, limits, |_| {});
+            assert_eq!(output.depth, expected, "mate {:?} depth {:?}", mate, depth);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_search_with_movetime() {
+        let mut position = Position::start();
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(50)),
+            ..SearchLimits::default()
+        };
+        let started = std::time::Instant::now();
+        let output = Searcher::new().search_with_limits(&mut position, limits, |_| {});
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(output.best_move.is_some());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_search_with_movetime_only_move() {
+        // Kxb2 is the only legal move
+        let fen = "k7/8/8/8/8/8/1q6/K7 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_secs(30)),
+            ..SearchLimits::default()
+        };
+        let started = std::time::Instant::now();
+        let output = Searcher::new().search_with_limits(&mut position, limits, |_| {});
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(output.depth, 1);
+        assert_eq!(
+            output.best_move,
+            Some(Move::new(Square64::A1, Square64::B2, MoveFlag::Capture))
+        );
+        // a depth limit is still searched to
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let output = Searcher::new().search_with_limits(&mut position, limits, |_| {});
+        assert_eq!(output.depth, 4);
+    }
+}
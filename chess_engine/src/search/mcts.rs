@@ -0,0 +1,587 @@
+//! Monte Carlo tree search, an alternative to the alpha-beta Searcher for experimenting with
+//! neural network guided play: the tree grows one position per playout towards the moves that
+//! have scored best so far, with an Evaluator scoring the new positions and giving the prior
+//! probabilities of their moves instead of searching them out.
+
+use super::{
+    evaluate, SearchLimits, SearchResult, TerminalScoring, DEFAULT_HASH_MB, MATE, MAX_PLY,
+};
+use crate::{
+    board::Position,
+    movegen::{legal_moves, MAX_MOVES},
+    moves::Move,
+};
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Guides the tree search, e.g. the value and policy heads of a network. Values are from the
+/// point of view of the side to move, from -1.0 for a loss through 0.0 for a draw to 1.0 for a
+/// win.
+pub trait Evaluator {
+    /// Value of the position, which has legal moves and isn't a draw
+    fn value(&mut self, position: &Position) -> f32;
+
+    /// Prior probabilities of the moves of the position being best, in their order. By default
+    /// every move is as likely as any other.
+    fn policy(&mut self, position: &Position, moves: &[Move]) -> Vec<f32> {
+        vec![1.0 / moves.len() as f32; moves.len()]
+    }
+}
+
+/// Evaluator of the material balance with a uniform policy, which needs no network but plays no
+/// better than its idea of what's likely
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaterialEvaluator;
+
+impl Evaluator for MaterialEvaluator {
+    fn value(&mut self, position: &Position) -> f32 {
+        value_from_centipawns(evaluate(position))
+    }
+}
+
+/// Weight of the priors a search starts with, see Mcts::set_exploration
+pub const DEFAULT_EXPLORATION: f32 = 1.5;
+
+/// Centipawns a value is worth when it's 1.0 or -1.0 less than a half
+const VALUE_SCALE: f32 = 400.0;
+
+/// Value of a score in centipawns, which is a half at VALUE_SCALE and nears 1.0 the more there
+/// is to win
+pub fn value_from_centipawns(centipawns: i32) -> f32 {
+    let centipawns = centipawns as f32;
+    centipawns / (centipawns.abs() + VALUE_SCALE)
+}
+
+/// Score in centipawns of a value, the inverse of value_from_centipawns, kept below mate scores
+pub fn centipawns_from_value(value: f32) -> i32 {
+    let bound = (MATE - MAX_PLY as i32 - 1) as f32;
+    let value = value.clamp(-1.0, 1.0);
+    let magnitude = (1.0 - value.abs()).max(f32::EPSILON);
+    (VALUE_SCALE * value / magnitude).clamp(-bound, bound) as i32
+}
+
+/// Square root in 1/1024ths, as core has no floating point one
+fn sqrt(n: u32) -> f32 {
+    ((n as u64) << 20).isqrt() as f32 / 1_024.0
+}
+
+/// Bytes a node of the tree takes, which set_hash_size divides the hash size by
+pub const NODE_SIZE: usize = mem::size_of::<Node>();
+
+/// Position of the tree, reached by a move from its parent
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// Move::NULL at the root
+    move_: Move,
+    prior: f32,
+    visits: u32,
+    /// Sum of the values of the playouts through the node for the side that played move_
+    value: f32,
+    /// Children are stored next to each other from first_child on, and there are none until
+    /// the node is expanded
+    first_child: u32,
+    child_count: u32,
+    expanded: bool,
+    /// Value of a position that ends the game for its side to move, checkmate or a draw
+    terminal: Option<f32>,
+}
+
+impl Node {
+    fn new(move_: Move, prior: f32) -> Self {
+        Self {
+            move_,
+            prior,
+            visits: 0,
+            value: 0.0,
+            first_child: 0,
+            child_count: 0,
+            expanded: false,
+            terminal: None,
+        }
+    }
+
+    /// Mean value of the playouts through the node, 0.0 before there are any
+    fn mean(&self) -> f32 {
+        match self.visits {
+            0 => 0.0,
+            visits => self.value / visits as f32,
+        }
+    }
+
+    fn children(&self) -> core::ops::Range<usize> {
+        self.first_child as usize..(self.first_child + self.child_count) as usize
+    }
+}
+
+/// UCT search with the priors of an Evaluator's policy, as PUCT: each playout follows the
+/// child with the highest mean value plus exploration × prior × √visits of the parent ÷
+/// (1 + visits of the child) down to a leaf, expands it and backs the Evaluator's value of it
+/// up the path. The best move is the most visited root move.
+pub struct Mcts {
+    evaluator: Box<dyn Evaluator + Send>,
+    exploration: f32,
+    /// Keys of the positions before the current one, as for Searcher
+    history: Vec<u64>,
+    /// Set from another thread to stop the search, see stop_handle
+    stop: Arc<AtomicBool>,
+    /// Nodes of the last search, kept to reuse the allocation
+    tree: Vec<Node>,
+    /// Nodes the tree may grow to, see set_hash_size
+    max_nodes: usize,
+    /// Values of positions without legal moves, see set_terminal_scoring
+    terminal_scoring: TerminalScoring,
+}
+
+/// Leaves out the evaluator, which needn't be Debug
+impl fmt::Debug for Mcts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mcts")
+            .field("exploration", &self.exploration)
+            .field("history", &self.history)
+            .field("max_nodes", &self.max_nodes)
+            .field("terminal_scoring", &self.terminal_scoring)
+            .finish()
+    }
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Self::new(MaterialEvaluator)
+    }
+}
+
+impl Mcts {
+    /// Search with a tree of at most DEFAULT_HASH_MB
+    pub fn new(evaluator: impl Evaluator + Send + 'static) -> Self {
+        let mut mcts = Self {
+            evaluator: Box::new(evaluator),
+            exploration: DEFAULT_EXPLORATION,
+            history: Vec::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            tree: Vec::new(),
+            max_nodes: 0,
+            terminal_scoring: TerminalScoring::Standard,
+        };
+        mcts.set_hash_size(DEFAULT_HASH_MB);
+        mcts
+    }
+
+    /// Search for a game with the given earlier positions, oldest first, e.g. Game::history
+    pub fn with_history(evaluator: impl Evaluator + Send + 'static, history: &[u64]) -> Self {
+        Self {
+            history: history.to_vec(),
+            ..Self::new(evaluator)
+        }
+    }
+
+    /// Sets the weight of the priors against the mean values, the higher the wider the tree
+    pub fn set_exploration(&mut self, exploration: f32) {
+        self.exploration = exploration;
+    }
+
+    /// Limits the tree to about size_mb megabytes, as the UCI Hash option does the
+    /// transposition table of a Searcher. There's always room for the two playouts that expand the
+    /// root and visit a root move, so a position with legal moves gets a best move.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        let bytes = size_mb.saturating_mul(1 << 20);
+        // children are found by u32 indices
+        self.max_nodes = (bytes / NODE_SIZE).clamp(1 + 2 * MAX_MOVES, u32::MAX as usize);
+        self.tree = Vec::new();
+    }
+
+    /// Sets what positions without legal moves are worth, like Searcher::set_terminal_scoring
+    pub fn set_terminal_scoring(&mut self, terminal_scoring: TerminalScoring) {
+        self.terminal_scoring = terminal_scoring;
+    }
+
+    /// Nodes the tree may grow to
+    pub fn max_nodes(&self) -> usize {
+        self.max_nodes
+    }
+
+    /// Flag that stops the running search once set, like Searcher::stop_handle
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Runs playouts from the position until one of the limits is reached and returns the most
+    /// visited root move. Only the node limit, which counts playouts, the movetime and the stop
+    /// flag apply, there's always at least one playout, and without them the search goes on
+    /// until it's stopped or the tree has no room left for another expansion. The score is the mean value of the best move in centipawns, or a mate
+    /// if it mates at once, the principal variation follows the most visited moves, its length
+    /// is the depth and the deepest playout the selective depth. The position is left as it was.
+    pub fn search(&mut self, position: &mut Position, limits: SearchLimits) -> SearchResult {
+        self.stop.store(false, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        let started = std::time::Instant::now();
+        let mut tree = mem::take(&mut self.tree);
+        tree.clear();
+        tree.reserve_exact(self.max_nodes);
+        tree.push(Node::new(Move::NULL, 1.0));
+        let (mut playouts, mut seldepth) = (0, 0);
+        loop {
+            seldepth = seldepth.max(self.playout(&mut tree, position));
+            playouts += 1;
+            if limits.nodes.is_some_and(|limit| playouts >= limit)
+                || self.stop.load(Ordering::Relaxed)
+                || tree[0].terminal.is_some()
+                || tree.len() + MAX_MOVES > self.max_nodes
+            {
+                break;
+            }
+            #[cfg(feature = "std")]
+            if limits
+                .movetime
+                .is_some_and(|movetime| started.elapsed() >= movetime)
+            {
+                break;
+            }
+        }
+        let result = self.result(&tree, seldepth, playouts);
+        self.tree = tree;
+        result
+    }
+
+    /// Goes down the tree to a leaf, expands it and backs its value up, returning its ply
+    fn playout(&mut self, tree: &mut Vec<Node>, position: &mut Position) -> u32 {
+        let mut path = vec![0];
+        let mut undos = Vec::new();
+        let mut index = 0;
+        while tree[index].expanded && tree[index].terminal.is_none() {
+            index = self.select(tree, index);
+            self.history.push(position.key());
+            undos.push(position.make_move(tree[index].move_));
+            path.push(index);
+        }
+        let value = match tree[index].terminal {
+            Some(value) => value,
+            None => self.expand(tree, index, position),
+        };
+        // each node keeps the value for the side that moved into it, the opponent of its own
+        let mut value = -value;
+        for &index in path.iter().rev() {
+            tree[index].visits += 1;
+            tree[index].value += value;
+            value = -value;
+        }
+        for undo in undos.into_iter().rev() {
+            position.unmake_move(undo);
+            self.history.pop();
+        }
+        path.len() as u32 - 1
+    }
+
+    /// Child of the node a playout goes on to, the first one of the highest PUCT score
+    fn select(&self, tree: &[Node], index: usize) -> usize {
+        let parent = &tree[index];
+        let weight = self.exploration * sqrt(parent.visits);
+        let score = |child: &Node| child.mean() + weight * child.prior / (1 + child.visits) as f32;
+        parent
+            .children()
+            .fold(None, |best: Option<(usize, f32)>, child| {
+                let child_score = score(&tree[child]);
+                match best {
+                    Some((_, best_score)) if best_score >= child_score => best,
+                    _ => Some((child, child_score)),
+                }
+            })
+            .map(|(child, _)| child)
+            .expect("an expanded node that doesn't end the game should have children")
+    }
+
+    /// Adds the children of a leaf and returns its value for its side to move, which ends the
+    /// game if the leaf is checkmate or a draw
+    fn expand(&mut self, tree: &mut Vec<Node>, index: usize, position: &Position) -> f32 {
+        tree[index].expanded = true;
+        let moves = legal_moves(position);
+        let drawn = position.repetitions(&self.history) > 0
+            || position.is_insufficient_material()
+            || position.is_fifty_move_draw();
+        let terminal = match moves.is_empty() {
+            true => Some(self.terminal_scoring.value(position.checkers().0 != 0)),
+            // a position repeated at the root is the one to find a move for
+            false if drawn && index != 0 => Some(0.0),
+            false => None,
+        };
+        if let Some(value) = terminal {
+            tree[index].terminal = Some(value);
+            return value;
+        }
+        let priors = self.evaluator.policy(position, &moves);
+        tree[index].first_child = tree.len() as u32;
+        tree[index].child_count = moves.len() as u32;
+        for (index, &move_) in moves.iter().enumerate() {
+            let prior = priors.get(index).copied().unwrap_or(0.0);
+            tree.push(Node::new(move_, prior));
+        }
+        self.evaluator.value(position).clamp(-1.0, 1.0)
+    }
+
+    /// Most visited child of the node, the one with the higher mean value of equally visited
+    /// ones
+    fn best_child(tree: &[Node], index: usize) -> Option<usize> {
+        tree[index].children().reduce(|best, child| {
+            let key = |node: &Node| (node.visits, node.mean());
+            match key(&tree[child]).partial_cmp(&key(&tree[best])) {
+                Some(core::cmp::Ordering::Greater) => child,
+                _ => best,
+            }
+        })
+    }
+
+    fn result(&self, tree: &[Node], seldepth: u32, playouts: u64) -> SearchResult {
+        let mut pv = Vec::new();
+        let mut index = 0;
+        while let Some(child) =
+            Self::best_child(tree, index).filter(|&child| tree[child].visits > 0)
+        {
+            pv.push(tree[child].move_);
+            index = child;
+        }
+        let score = match Self::best_child(tree, 0) {
+            // the move mates at once
+            Some(child) if tree[child].terminal == Some(-1.0) => MATE - 1,
+            Some(child) => centipawns_from_value(tree[child].mean()),
+            None => match tree[0].terminal {
+                Some(value) if value < 0.0 => -MATE,
+                Some(value) if value > 0.0 => MATE - 1,
+                _ => 0,
+            },
+        };
+        SearchResult {
+            best_move: pv.first().copied(),
+            score,
+            depth: pv.len() as u32,
+            pv,
+            seldepth,
+            nodes: playouts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{moves::MoveFlag, squares::Square64};
+
+    fn playouts(nodes: u64) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..SearchLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_value_from_centipawns() {
+        let inputs = [(0, 0.0), (400, 0.5), (-400, -0.5), (1_200, 0.75)];
+        for (centipawns, expected) in inputs {
+            let output = value_from_centipawns(centipawns);
+            assert!((output - expected).abs() < 1e-6, "{}", centipawns);
+            assert_eq!(centipawns_from_value(output), centipawns);
+        }
+        // a sure win is still no mate
+        assert_eq!(centipawns_from_value(1.0), MATE - MAX_PLY as i32 - 1);
+        assert_eq!(centipawns_from_value(-2.0), -(MATE - MAX_PLY as i32 - 1));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let inputs = [
+            (0, 0.0),
+            (1, 1.0),
+            (4, 2.0),
+            (2, 1.414),
+            (1_000_000, 1_000.0),
+        ];
+        for (n, expected) in inputs {
+            assert!((sqrt(n) - expected).abs() < 1e-3, "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_mcts_finds_the_best_move() {
+        let inputs = [
+            // back rank mate
+            (
+                "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+                Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet),
+            ),
+            // the queen hangs
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                Move::new(Square64::D1, Square64::D5, MoveFlag::Capture),
+            ),
+        ];
+        for (fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = Mcts::default().search(&mut position, playouts(2_000));
+            assert_eq!(output.best_move, Some(expected), "{}", fen);
+            assert_eq!(output.nodes, 2_000);
+            assert_eq!(output.depth, output.pv.len() as u32);
+            assert!(output.seldepth >= output.depth);
+            assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        }
+        let mut position = Position::from_fen(inputs[0].0).unwrap();
+        let output = Mcts::default().search(&mut position, playouts(2_000));
+        assert_eq!(output.score, MATE - 1);
+    }
+
+    #[test]
+    fn test_mcts_without_legal_moves() {
+        let inputs = [
+            ("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", -MATE),
+            ("7k/8/6QK/8/8/8/8/8 b - - 0 1", 0),
+        ];
+        for (fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = Mcts::default().search(&mut position, SearchLimits::default());
+            let expected = SearchResult {
+                best_move: None,
+                score: expected,
+                pv: Vec::new(),
+                depth: 0,
+                seldepth: 0,
+                nodes: 1,
+            };
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_mcts_terminal_scoring() {
+        let inputs = [
+            (
+                TerminalScoring::StalemateLoses,
+                "7k/8/6QK/8/8/8/8/8 b - - 0 1",
+                -MATE,
+            ),
+            (
+                TerminalScoring::NoMovesWins,
+                "7k/8/6QK/8/8/8/8/8 b - - 0 1",
+                MATE - 1,
+            ),
+            (
+                TerminalScoring::NoMovesWins,
+                "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1",
+                MATE - 1,
+            ),
+        ];
+        for (terminal_scoring, fen, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut mcts = Mcts::default();
+            mcts.set_terminal_scoring(terminal_scoring);
+            let output = mcts.search(&mut position, SearchLimits::default());
+            assert_eq!(output.score, expected, "{:?} {}", terminal_scoring, fen);
+        }
+        // white's only mate is now the move that loses
+        let mut position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mut mcts = Mcts::default();
+        mcts.set_terminal_scoring(TerminalScoring::NoMovesWins);
+        let output = mcts.search(&mut position, playouts(2_000));
+        assert_ne!(
+            output.best_move,
+            Some(Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet))
+        );
+    }
+
+    /// Policy that's sure of one move, and a value that knows nothing
+    struct Hunch(Move);
+
+    impl Evaluator for Hunch {
+        fn value(&mut self, _: &Position) -> f32 {
+            0.0
+        }
+
+        fn policy(&mut self, _: &Position, moves: &[Move]) -> Vec<f32> {
+            moves
+                .iter()
+                .map(|&move_| if move_ == self.0 { 1.0 } else { 0.0 })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_mcts_follows_the_policy() {
+        let mut position = Position::start();
+        let input = Move::new(Square64::B1, Square64::A3, MoveFlag::Quiet);
+        let output = Mcts::new(Hunch(input)).search(&mut position, playouts(100));
+        assert_eq!(output.best_move, Some(input));
+    }
+
+    #[test]
+    fn test_mcts_sees_repetitions() {
+        // black is a rook up, but Ng1 repeats the position before Ra8
+        let mut position = Position::from_fen("4k3/r7/8/8/8/8/8/4K1N1 b - - 0 1").unwrap();
+        let ng1 = Move::new(Square64::F3, Square64::G1, MoveFlag::Quiet);
+        let moves = [
+            Move::new(Square64::A7, Square64::A8, MoveFlag::Quiet),
+            Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet),
+            Move::new(Square64::A8, Square64::A7, MoveFlag::Quiet),
+        ];
+        let mut history = Vec::new();
+        for move_ in moves {
+            history.push(position.key());
+            position.make_move(move_);
+        }
+        let mut mcts = Mcts::with_history(MaterialEvaluator, &history);
+        let output = mcts.search(&mut position, playouts(1_000));
+        assert_eq!((output.best_move, output.score), (Some(ng1), 0));
+        assert_eq!(mcts.history, history);
+        // a root that repeats a position of the game is still searched
+        let history = [history, vec![position.key()]].concat();
+        let mut position = position.make_move_new(ng1);
+        let output =
+            Mcts::with_history(MaterialEvaluator, &history).search(&mut position, playouts(10));
+        assert!(output.best_move.is_some());
+    }
+
+    #[test]
+    fn test_mcts_set_hash_size() {
+        let mut mcts = Mcts::default();
+        mcts.set_hash_size(1);
+        assert_eq!(mcts.max_nodes, (1 << 20) / mem::size_of::<Node>());
+        // without limits the search stops once the tree is full, and stays under the cap
+        let mut position = Position::start();
+        let output = mcts.search(&mut position, SearchLimits::default());
+        assert!(output.best_move.is_some());
+        assert!(mcts.tree.len() <= mcts.max_nodes);
+        assert!(mcts.tree.len() + MAX_MOVES > mcts.max_nodes);
+        assert!(position.strict_eq(&Position::start()));
+        // the smallest tree still has room for a root move
+        mcts.set_hash_size(0);
+        assert_eq!(mcts.max_nodes, 1 + 2 * MAX_MOVES);
+        let output = mcts.search(&mut position, SearchLimits::default());
+        assert!(output.nodes >= 2);
+        assert!(output.best_move.is_some());
+    }
+
+    #[test]
+    fn test_mcts_stop_handle() {
+        let mut mcts = Mcts::default();
+        let stop = mcts.stop_handle();
+        stop.store(true, Ordering::Relaxed);
+        // the search clears the flag when it starts
+        let output = mcts.search(&mut Position::start(), playouts(10));
+        assert_eq!(output.nodes, 10);
+        assert!(!stop.load(Ordering::Relaxed));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mcts_stops_from_another_thread() {
+        let mut mcts = Mcts::default();
+        let stop = mcts.stop_handle();
+        let search = std::thread::spawn(move || {
+            mcts.search(&mut Position::start(), SearchLimits::default())
+        });
+        while !search.is_finished() {
+            stop.store(true, Ordering::Relaxed);
+            std::thread::sleep(core::time::Duration::from_millis(10));
+        }
+        let output = search.join().unwrap();
+        assert!(output.best_move.is_some());
+    }
+}
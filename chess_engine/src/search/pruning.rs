@@ -0,0 +1,1426 @@
+//! Pruning, reductions and extensions of negamax and quiescence search, each with the settings
+//! a Searcher has it on with, see e.g. Searcher::set_null_move
+
+use super::{is_mate_score, Bound, Searcher, TtEntry, MAX_PLY};
+use crate::{
+    board::Position,
+    movegen::{legal_moves, MovePicker},
+    moves::Move,
+    pieces::{Piece, PieceType},
+};
+use strum::IntoEnumIterator;
+
+/// Null-move pruning: when a node is at or above beta by static evaluation, the side to move
+/// passes, and if a shallow search of the opponent's replies still fails high the node is taken
+/// to fail high as well, since moving is nearly always better than passing. That's wrong in
+/// zugzwang, so it's never tried in check, where passing is illegal, or with only pawns left
+/// beside the king, where zugzwang is common. Only zero window nodes prune, a principal
+/// variation is always searched in full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullMove {
+    /// Shallowest remaining depth a pass is tried at
+    pub min_depth: u32,
+    /// Plies the search after the pass is reduced by on top of the pass itself, R, which grows
+    /// by one every depth_divisor plies of depth so deep nodes save more
+    pub reduction: u32,
+    pub depth_divisor: u32,
+    /// From this depth on a fail high is only trusted if a shallow search of the node's own
+    /// moves, reduced by R as well and without passing, fails high too, which catches the
+    /// zugzwangs the exclusions miss where a wrong cutoff would cost the most
+    pub verification_depth: u32,
+}
+
+impl Default for NullMove {
+    fn default() -> Self {
+        Self {
+            min_depth: 3,
+            reduction: 2,
+            depth_divisor: 4,
+            verification_depth: 8,
+        }
+    }
+}
+
+/// Late move reductions: with good move ordering a move tried late rarely turns out best, so the
+/// quiet moves after the first few are searched less deep, the more so the later they come and
+/// the deeper the node is. A reduced move that raises alpha anyway is searched again to the full
+/// depth. Captures, promotions, checks and check evasions are never reduced, they're too likely
+/// to matter, and principal variation nodes reduce a ply less.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lmr {
+    /// Shallowest remaining depth moves are reduced at
+    pub min_depth: u32,
+    /// Moves of a node searched to full depth before the reductions start
+    pub full_depth_moves: u32,
+    /// The n-th move at depth d is reduced by base + ln(d) ln(n) / divisor plies, rounded down,
+    /// with base and divisor in hundredths of a ply
+    pub base: u32,
+    pub divisor: u32,
+}
+
+impl Default for Lmr {
+    fn default() -> Self {
+        Self {
+            min_depth: 3,
+            full_depth_moves: 3,
+            base: 75,
+            divisor: 225,
+        }
+    }
+}
+
+/// History pruning: at shallow zero window nodes a quiet move with a history score far below zero
+/// has been tried without a cutoff many times while other moves cut off, so once a move of the
+/// node has been searched it's skipped. Checks and evasions are always searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryPruning {
+    /// Deepest remaining depth moves are pruned at
+    pub max_depth: u32,
+    /// A move is pruned if its history score is below -margin times the depth
+    pub margin: i32,
+}
+
+impl Default for HistoryPruning {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            margin: 2_048,
+        }
+    }
+}
+
+/// Late move pruning: at shallow zero window nodes the moves are ordered well enough that once
+/// a number of quiet moves growing with the depth has been searched without a cutoff, the quiet
+/// moves left are skipped. Checks and evasions are always searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LateMovePruning {
+    /// Deepest remaining depth moves are pruned at
+    pub max_depth: u32,
+    /// Quiet moves searched at every depth before the rest are pruned, base plus factor times
+    /// the depth squared
+    pub base: usize,
+    pub factor: usize,
+}
+
+impl Default for LateMovePruning {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            base: 8,
+            factor: 2,
+        }
+    }
+}
+
+/// Futility pruning: at frontier nodes a quiet move can't change the material balance, so if
+/// the static evaluation plus a margin for what the move could still gain positionally doesn't
+/// reach alpha, the move is skipped once a move of the node has been searched. Checks, evasions,
+/// captures and promotions are always searched, as are principal variation nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Futility {
+    /// Deepest remaining depth moves are pruned at
+    pub max_depth: u32,
+    /// Margin per ply of remaining depth
+    pub margin: i32,
+}
+
+impl Default for Futility {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            margin: 125,
+        }
+    }
+}
+
+/// Reverse futility pruning, or static null-move pruning: at a shallow zero window node whose
+/// static evaluation beats beta even after a margin for what the opponent could still win back
+/// by the horizon, the node fails high without searching a move. It's never done in check, or
+/// against a mate score for beta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseFutility {
+    /// Deepest remaining depth nodes are pruned at
+    pub max_depth: u32,
+    /// Margin per ply of remaining depth
+    pub margin: i32,
+}
+
+impl Default for ReverseFutility {
+    fn default() -> Self {
+        Self {
+            max_depth: 6,
+            margin: 100,
+        }
+    }
+}
+
+/// Razoring: at a shallow zero window node whose static evaluation is so far below alpha that
+/// no quiet move is likely to make up for it, only quiescence search is tried, and if even the
+/// captures don't raise alpha the node fails low with their score instead of being searched in
+/// full. It's never done in check. A quiet move that's far below alpha by material can still
+/// start an attack, so the margins are wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Razoring {
+    /// Deepest remaining depth nodes are razored at
+    pub max_depth: u32,
+    /// The static evaluation has to be below alpha by this much, plus margin_per_ply per ply of
+    /// remaining depth
+    pub margin: i32,
+    pub margin_per_ply: i32,
+}
+
+impl Default for Razoring {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            margin: 700,
+            margin_per_ply: 300,
+        }
+    }
+}
+
+/// Delta pruning: quiescence search skips a capture if even winning the captured piece, and the
+/// promotion if it is one, for free plus a margin wouldn't lift the static evaluation to alpha.
+/// With little material left the static evaluation misses too much, e.g. a pawn about to queen,
+/// so endgames aren't pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaPruning {
+    pub margin: i32,
+    /// Positions with at most this much material besides the kings and pawns, both sides
+    /// together, count as endgames
+    pub endgame_material: i32,
+}
+
+impl Default for DeltaPruning {
+    fn default() -> Self {
+        Self {
+            margin: 200,
+            endgame_material: 2_600,
+        }
+    }
+}
+
+/// Check extensions: a node in check is searched a ply deeper, so a line of checks isn't cut off
+/// at the horizon in the middle of it, where quiescence search only resolves the last check.
+/// Checks can go on for long, so each line has a budget of extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckExtensions {
+    /// Most plies a line from the root is extended by
+    pub max_per_line: u32,
+}
+
+impl Default for CheckExtensions {
+    fn default() -> Self {
+        Self { max_per_line: 16 }
+    }
+}
+
+/// Single reply extensions: a node in check with only one legal evasion is searched a ply deeper
+/// on top of the check extension, since nothing at it can be pruned or reduced away and the line
+/// through it is forced. Counting the legal moves takes a full move generation, so nodes not in
+/// check, which hardly ever have a single legal move, aren't extended. Like checks, each line
+/// has a budget of extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SingleReplyExtensions {
+    /// Most plies a line from the root is extended by, counting the other extensions too
+    pub max_per_line: u32,
+}
+
+impl Default for SingleReplyExtensions {
+    fn default() -> Self {
+        Self { max_per_line: 16 }
+    }
+}
+
+/// Singular extensions: a hash move that scored a lower bound is searched a ply deeper if it's
+/// singular, i.e. every other move fails low against a bound some margin below its score in a
+/// shallower search of the node without it. Such a node hangs on the one move, so a mistake in
+/// its line costs the most. The shallower search stores its results under a key of its own, see
+/// tt::excluded_key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SingularExtensions {
+    pub min_depth: u32,
+    /// Plies the hash move may have been searched shallower than the node at most, past which
+    /// its score is too unsure to go by
+    pub tt_depth_margin: u32,
+    /// Centipawns per ply of depth the other moves have to stay below the hash move's score by
+    pub margin_per_ply: i32,
+    /// Most plies a line from the root is extended by, check extensions included
+    pub max_per_line: u32,
+}
+
+impl Default for SingularExtensions {
+    fn default() -> Self {
+        Self {
+            min_depth: 6,
+            tt_depth_margin: 3,
+            margin_per_ply: 2,
+            max_per_line: 8,
+        }
+    }
+}
+
+/// ProbCut: a zero window node deep enough is expected to fail high if a capture that wins
+/// material by static exchange evaluation beats beta by a margin in a search some plies
+/// shallower. The capture is first tried with quiescence search, which refutes most of them for
+/// little, and its shallower result is kept in the transposition table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbCut {
+    pub min_depth: u32,
+    /// Centipawns above beta the shallower search has to score
+    pub margin: i32,
+    /// Plies shallower than the node the captures are searched
+    pub reduction: u32,
+}
+
+impl Default for ProbCut {
+    fn default() -> Self {
+        Self {
+            min_depth: 5,
+            margin: 150,
+            reduction: 3,
+        }
+    }
+}
+
+/// Internal iterative deepening and reduction of nodes without a hash move. A PV node, whose
+/// score is needed exactly, is first searched some plies shallower, so the full search can start
+/// with the best move that one found, as iterative deepening does at the root. A zero window
+/// node is searched a ply shallower instead: without a hash move nothing searched it before, so
+/// it's unlikely to matter, and its next search finds the move this one stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InternalIterativeDeepening {
+    pub min_depth: u32,
+    /// Plies shallower than the node the first search of a PV node goes
+    pub reduction: u32,
+    /// Zero window nodes are reduced from this depth on
+    pub reduction_min_depth: u32,
+}
+
+impl Default for InternalIterativeDeepening {
+    fn default() -> Self {
+        Self {
+            min_depth: 4,
+            reduction: 2,
+            reduction_min_depth: 6,
+        }
+    }
+}
+
+/// Depths and move numbers past the reduction table are reduced like its last row and column
+pub(super) const REDUCTION_TABLE_SIZE: usize = 64;
+
+pub(super) type ReductionTable = [[u8; REDUCTION_TABLE_SIZE]; REDUCTION_TABLE_SIZE];
+
+impl Searcher {
+    /// Returns true if late move pruning skips the quiet move at depth after searched quiet moves
+    pub(super) fn late_move_prunes(
+        &self,
+        position: &Position,
+        move_: Move,
+        depth: u32,
+        searched: usize,
+    ) -> bool {
+        let Some(pruning) = self.late_move_pruning else {
+            return false;
+        };
+        let depth = depth as usize;
+        depth <= pruning.max_depth as usize
+            && searched >= pruning.base + pruning.factor * depth * depth
+            && !position.gives_check(move_)
+    }
+
+    /// Returns true if history pruning skips the quiet move at depth
+    pub(super) fn history_prunes(&self, position: &Position, move_: Move, depth: u32) -> bool {
+        let Some(pruning) = self.history_pruning else {
+            return false;
+        };
+        depth <= pruning.max_depth
+            && self
+                .thread
+                .quiet_history
+                .get(position.side_to_move(), move_)
+                < -pruning.margin * depth as i32
+            && !position.gives_check(move_)
+    }
+
+    /// Plies to extend a node at depth and ply by, 1 if it's in check and its line still has
+    /// extensions left, as long as the search stays within MAX_PLY
+    pub(super) fn check_extension(&self, in_check: bool, depth: u32, ply: u32) -> u32 {
+        let Some(check_extensions) = self.check_extensions else {
+            return 0;
+        };
+        let extend = in_check
+            && self.thread.stack[ply as usize - 1].extensions < check_extensions.max_per_line
+            && ply + depth < MAX_PLY - 1;
+        extend as u32
+    }
+
+    /// Plies to extend a node at depth, check extension included, and ply by on top of it, 1 if
+    /// it's in check with a single legal move and its line still has extensions left, as long as
+    /// the search stays within MAX_PLY
+    pub(super) fn single_reply_extension(
+        &self,
+        position: &Position,
+        in_check: bool,
+        depth: u32,
+        ply: u32,
+    ) -> u32 {
+        let Some(single_reply_extensions) = self.single_reply_extensions else {
+            return 0;
+        };
+        let extend = in_check
+            && self.thread.stack[ply as usize - 1].extensions
+                < single_reply_extensions.max_per_line
+            && ply + depth < MAX_PLY - 1
+            && legal_moves(position).len() == 1;
+        extend as u32
+    }
+
+    /// Plies to extend the hash move of a node at depth and ply by, 1 if the transposition table
+    /// entry is deep enough to go by and a search without the move shows it's singular
+    pub(super) fn singular_extension(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        entry: Option<TtEntry>,
+    ) -> u32 {
+        let Some(singular_extensions) = self.singular_extensions else {
+            return 0;
+        };
+        let Some((entry, hash_move)) =
+            entry.and_then(|entry| entry.best_move.map(|move_| (entry, move_)))
+        else {
+            return 0;
+        };
+        let worth_trying = depth >= singular_extensions.min_depth.max(3)
+            && entry.depth + singular_extensions.tt_depth_margin >= depth
+            && entry.bound != Bound::Upper
+            && !is_mate_score(entry.score)
+            && self.thread.stack[ply as usize].extensions < singular_extensions.max_per_line
+            && ply + depth < MAX_PLY - 1
+            && position.is_legal(hash_move);
+        if !worth_trying {
+            return 0;
+        }
+        let singular_beta = entry.score - singular_extensions.margin_per_ply * depth as i32;
+        let extensions = self.thread.stack[ply as usize].extensions;
+        self.thread.stack[ply as usize].excluded_move = Some(hash_move);
+        let score = self.negamax(
+            position,
+            (depth - 1) / 2,
+            ply,
+            singular_beta - 1,
+            singular_beta,
+        );
+        // the search of the node without the move left its own line and extensions behind
+        self.thread.pv_len[ply as usize] = ply as usize;
+        self.thread.stack[ply as usize].extensions = extensions;
+        (score < singular_beta) as u32
+    }
+
+    /// Score to fail high with at a zero window node by its static evaluation, if reverse futility
+    /// pruning applies
+    pub(super) fn reverse_futility_score(
+        &self,
+        depth: u32,
+        static_eval: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        let reverse_futility = self.reverse_futility?;
+        let score = static_eval - reverse_futility.margin * depth as i32;
+        (depth <= reverse_futility.max_depth && score >= beta && !is_mate_score(beta))
+            .then_some(score)
+    }
+
+    /// Razors a zero window node of negamax around alpha, returning the quiescence score to fail
+    /// low with if it doesn't raise alpha
+    pub(super) fn razor(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        alpha: i32,
+        static_eval: i32,
+    ) -> Option<i32> {
+        let razoring = self.razoring?;
+        let margin = razoring.margin + razoring.margin_per_ply * depth as i32;
+        if depth > razoring.max_depth || static_eval + margin >= alpha || is_mate_score(alpha) {
+            return None;
+        }
+        let score = self.quiescence(position, ply, alpha, alpha + 1);
+        (score <= alpha).then_some(score)
+    }
+
+    /// Best score futility pruning expects the quiet move at depth to reach, if the move is
+    /// shallow enough to be pruned and doesn't give check
+    pub(super) fn futility_value(
+        &self,
+        position: &Position,
+        move_: Move,
+        depth: u32,
+        static_eval: i32,
+    ) -> Option<i32> {
+        let futility = self.futility?;
+        (depth <= futility.max_depth && !position.gives_check(move_))
+            .then(|| static_eval + futility.margin * depth as i32)
+    }
+
+    /// Plies to reduce the move with the given index among a node's moves by, at most to a depth
+    /// 0 search of it. The side to move mustn't be in check.
+    pub(super) fn late_move_reduction(
+        &self,
+        position: &Position,
+        move_: Move,
+        depth: u32,
+        index: usize,
+        pv_node: bool,
+    ) -> u32 {
+        let Some(lmr) = self.lmr else {
+            return 0;
+        };
+        if depth < lmr.min_depth
+            || index < lmr.full_depth_moves as usize
+            || move_.is_capture()
+            || move_.is_promotion()
+            || position.gives_check(move_)
+        {
+            return 0;
+        }
+        let last = REDUCTION_TABLE_SIZE - 1;
+        let reduction = self.reductions[(depth as usize).min(last)][index.min(last)] as u32;
+        reduction.saturating_sub(pv_node as u32).min(depth - 1)
+    }
+
+    /// Tries ProbCut at a zero window node of negamax, returning the score to fail high with if a
+    /// good capture beats beta by the margin in the shallower search
+    pub(super) fn probcut(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        beta: i32,
+        static_eval: i32,
+        hash_move: Option<Move>,
+    ) -> Option<i32> {
+        let probcut = self.probcut?;
+        if depth < probcut.min_depth.max(probcut.reduction + 1) || is_mate_score(beta) {
+            return None;
+        }
+        let probcut_beta = beta + probcut.margin;
+        let child_depth = depth - probcut.reduction;
+        // the capture has to win at least what the static evaluation is short of probcut_beta
+        let picker_position = position.clone();
+        let captures = MovePicker::probcut(&picker_position, hash_move, probcut_beta - static_eval);
+        for move_ in captures {
+            let window = (-probcut_beta, -probcut_beta + 1);
+            let mut score = -self.search_move(position, move_, 0, ply + 1, window.0, window.1);
+            if score >= probcut_beta {
+                score =
+                    -self.search_move(position, move_, child_depth, ply + 1, window.0, window.1);
+            }
+            if score >= probcut_beta && !self.thread.stopped {
+                self.tt.store(
+                    position.key(),
+                    ply,
+                    child_depth + 1,
+                    Bound::Lower,
+                    score,
+                    Some(move_),
+                );
+                return Some(score);
+            }
+        }
+        None
+    }
+
+    /// Searches a PV node of negamax without a hash move shallower if internal iterative
+    /// deepening applies, returning the transposition table entry that search left for the node
+    pub(super) fn internal_iterative_deepening(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+    ) -> Option<TtEntry> {
+        let iid = self.iid?;
+        if depth < iid.min_depth.max(iid.reduction + 1) {
+            return None;
+        }
+        let extensions = self.thread.stack[ply as usize].extensions;
+        self.negamax(position, depth - iid.reduction, ply, alpha, beta);
+        // like the search without the excluded move, see singular_extension
+        self.thread.pv_len[ply as usize] = ply as usize;
+        self.thread.stack[ply as usize].extensions = extensions;
+        self.tt.probe(position.key(), ply)
+    }
+
+    /// Plies to reduce a zero window node of negamax without a hash move at depth by
+    pub(super) fn internal_iterative_reduction(&self, depth: u32) -> u32 {
+        let Some(iid) = self.iid else {
+            return 0;
+        };
+        (depth >= iid.reduction_min_depth.max(2)) as u32
+    }
+
+    /// Tries null-move pruning at a node of negamax, returning the score to fail high with if
+    /// passing doesn't bring the node below beta
+    pub(super) fn null_move_search(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        let null_move = self.null_move?;
+        if depth < null_move.min_depth.max(2)
+            || beta - alpha != 1
+            || is_mate_score(beta)
+            || self.thread.stack[ply as usize].null_move
+            || position.checkers().0 != 0
+            || !has_non_pawn_material(position)
+            || self.thread.stack[ply as usize]
+                .static_eval
+                .is_none_or(|static_eval| static_eval < beta)
+        {
+            return None;
+        }
+        let reduction =
+            (null_move.reduction + depth / null_move.depth_divisor.max(1)).min(depth - 1);
+        self.thread.stack[ply as usize].current_move = None;
+        self.thread.history.push(position.key());
+        let undo = position.make_null_move();
+        self.thread.stack[ply as usize + 1].null_move = true;
+        let score = match depth - 1 - reduction {
+            0 => -self.quiescence(position, ply + 1, -beta, -alpha),
+            depth => -self.negamax(position, depth, ply + 1, -beta, -alpha),
+        };
+        self.thread.stack[ply as usize + 1].null_move = false;
+        position.unmake_null_move(undo);
+        self.thread.history.pop();
+        if score < beta {
+            return None;
+        }
+        if depth >= null_move.verification_depth {
+            self.thread.stack[ply as usize].verifying = true;
+            let verified = self.negamax(position, depth - reduction, ply, alpha, beta);
+            // the node is searched in full after a failed verification, which starts a new line
+            self.thread.pv_len[ply as usize] = ply as usize;
+            if verified < beta {
+                return None;
+            }
+        }
+        // a mate found after passing isn't a mate the node can force
+        Some(match is_mate_score(score) {
+            true => beta,
+            false => score,
+        })
+    }
+}
+
+/// Late move reductions of Lmr by depth and move number, the first move being number 1 at index
+/// 0, since ln(0) isn't defined
+pub(super) fn reduction_table(lmr: &Lmr) -> ReductionTable {
+    let mut table = [[0; REDUCTION_TABLE_SIZE]; REDUCTION_TABLE_SIZE];
+    for (depth, row) in table.iter_mut().enumerate().skip(1) {
+        for (index, reduction) in row.iter_mut().enumerate() {
+            let product = ln_fixed(depth as u32) * ln_fixed(index as u32 + 1) / 1_024;
+            let hundredths = lmr.base + product * 10_000 / (1_024 * lmr.divisor.max(1));
+            *reduction = (hundredths / 100).min(u8::MAX as u32) as u8;
+        }
+    }
+    table
+}
+
+/// Natural logarithm of n > 0 in 1/1024ths. Without std there's no floating point logarithm, so
+/// log2 is taken as the index of the highest bit plus the rest of n as a linear fraction of the
+/// power of two below it, which is off by at most 0.09.
+fn ln_fixed(n: u32) -> u32 {
+    let bits = n.ilog2();
+    let log2 = (bits << 10) + (((n - (1 << bits)) << 10) >> bits);
+    // ln 2 is 0.6931...
+    log2 * 710 / 1_024
+}
+
+/// Material of both sides besides the kings and pawns
+pub(super) fn non_pawn_material(position: &Position) -> i32 {
+    Piece::iter()
+        .filter(|piece| !matches!(piece.piece_type(), PieceType::Pawn | PieceType::King))
+        .map(|piece| (position.pieces(piece).0.count_ones() * piece.get_value()) as i32)
+        .sum()
+}
+
+/// Material the move wins before any recapture: the captured piece and what a promotion adds to
+/// the pawn
+pub(super) fn material_gain(position: &Position, move_: Move) -> i32 {
+    let us = position.side_to_move();
+    let value = |piece_type| Piece::new(us, piece_type).get_value() as i32;
+    let captured = match move_.is_en_passant() {
+        true => value(PieceType::Pawn),
+        false => position
+            .piece_on(move_.to_square())
+            .map_or(0, |piece| piece.get_value() as i32),
+    };
+    let promotion = move_
+        .promotion()
+        .map_or(0, |promotion| value(promotion) - value(PieceType::Pawn));
+    captured + promotion
+}
+
+/// Returns true if the side to move has a piece other than its king and pawns
+fn has_non_pawn_material(position: &Position) -> bool {
+    let us = position.side_to_move();
+    [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .into_iter()
+    .any(|piece_type| position.pieces(Piece::new(us, piece_type)).0 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::START_FEN,
+        moves::MoveFlag,
+        search::{evaluate, tests::exact_searcher, DEFAULT_HASH_MB, INFINITY, MATE},
+        selftest::{run_suite, DEFAULT_SELFTEST_DEPTH, WAC},
+        squares::Square64,
+        util::Color,
+    };
+
+    #[test]
+    fn test_has_non_pawn_material() {
+        let inputs = [
+            (START_FEN, true),
+            ("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1", false),
+            // only the side to move's pieces count
+            ("4k3/pppp4/8/8/8/8/4PPPP/3QK3 b - - 0 1", false),
+            ("4k1n1/pppp4/8/8/8/8/4PPPP/4K3 b - - 0 1", true),
+        ];
+        for (fen, expected) in inputs {
+            let output = has_non_pawn_material(&Position::from_fen(fen).unwrap());
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_null_move_search() {
+        let queen_up = "4k3/pppp4/8/8/8/8/4PPPP/3QK3 w - - 0 1";
+        // (fen, depth, zero window, reached by passing, fails high)
+        let inputs = [
+            (queen_up, 4, true, false, true),
+            // too shallow
+            (queen_up, 2, true, false, false),
+            // principal variation nodes are searched in full
+            (queen_up, 4, false, false, false),
+            // no passing straight back after a pass
+            (queen_up, 4, true, true, false),
+            // passing is illegal in check
+            (
+                "4k3/pppp4/8/8/8/8/4PPPP/3QK2r w - - 0 1",
+                4,
+                true,
+                false,
+                false,
+            ),
+            // zugzwang is common with only pawns left
+            (
+                "8/k7/3p4/p2P1p2/P2P1P2/8/8/K7 w - - 0 1",
+                4,
+                true,
+                false,
+                false,
+            ),
+        ];
+        for (fen, depth, zero_window, passed, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = exact_searcher(0);
+            searcher.set_null_move(Some(NullMove::default()));
+            let static_eval = evaluate(&position);
+            searcher.thread.stack[1].static_eval = Some(static_eval);
+            searcher.thread.stack[1].null_move = passed;
+            // the static evaluation is well above beta
+            let beta = static_eval - 100;
+            let alpha = match zero_window {
+                true => beta - 1,
+                false => -INFINITY,
+            };
+            let output = searcher.null_move_search(&mut position, depth, 1, alpha, beta);
+            assert_eq!(
+                output.is_some_and(|score| score >= beta),
+                expected,
+                "{} at depth {}",
+                fen,
+                depth
+            );
+            assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        }
+        // the cutoffs save nodes
+        let mut position = Position::from_fen(queen_up).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_null_move(Some(NullMove::default()));
+        let output = searcher.search(&mut position, 5);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 5);
+        assert!(output.nodes < expected.nodes);
+    }
+
+    #[test]
+    fn test_null_move_pruning_skips_pawn_endgames() {
+        // zugzwang decides pawn endgames, so passing is never tried and nothing changes
+        let mut position = Position::from_fen("8/k7/3p4/p2P1p2/P2P1P2/8/8/K7 w - - 0 1").unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_null_move(Some(NullMove::default()));
+        let output = searcher.search(&mut position, 6);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 6);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_null_move_pruning_finds_mates() {
+        // white is a rook up at every node, but passing mustn't hide the mate in 2
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = Searcher::new().iterative_deepening(&mut position, 5, |_| {});
+        assert_eq!(output.score, MATE - 3);
+    }
+
+    #[test]
+    fn test_ln_fixed() {
+        let inputs = [(1, 0), (2, 710), (3, 1_065), (8, 2_130), (63, 4_237)];
+        for (input, expected) in inputs {
+            assert_eq!(ln_fixed(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_reduction_table() {
+        let table = reduction_table(&Lmr::default());
+        // the first move and depth 1 have a logarithm of 0, leaving only the base
+        assert_eq!((table[1][40], table[40][0]), (0, 0));
+        assert_eq!((table[3][2], table[8][15], table[63][63]), (1, 3, 8));
+        for depth in 1..REDUCTION_TABLE_SIZE {
+            for index in 1..REDUCTION_TABLE_SIZE {
+                assert!(table[depth][index] >= table[depth - 1][index]);
+                assert!(table[depth][index] >= table[depth][index - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_late_move_reductions() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let inputs = [
+            // quiet, but among the first moves
+            (
+                kiwipete,
+                (Square64::A2, Square64::A3, MoveFlag::Quiet),
+                2,
+                0,
+            ),
+            (
+                kiwipete,
+                (Square64::A2, Square64::A3, MoveFlag::Quiet),
+                20,
+                3,
+            ),
+            // captures and checks are never reduced
+            (
+                kiwipete,
+                (Square64::E5, Square64::F7, MoveFlag::Capture),
+                20,
+                0,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+                (Square64::A1, Square64::A8, MoveFlag::Quiet),
+                20,
+                0,
+            ),
+        ];
+        let searcher = Searcher::new();
+        for (fen, (from, to, flag), index, expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let move_ = Move::new(from, to, flag);
+            let output = searcher.late_move_reduction(&position, move_, 8, index, false);
+            assert_eq!(output, expected, "{} in {}", move_, fen);
+        }
+        // principal variation nodes reduce a ply less
+        let position = Position::from_fen(kiwipete).unwrap();
+        let move_ = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        assert_eq!(
+            searcher.late_move_reduction(&position, move_, 8, 20, true),
+            2
+        );
+    }
+
+    #[test]
+    fn test_late_move_reductions_bounds() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(kiwipete).unwrap();
+        let move_ = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let mut searcher = Searcher::new();
+        // too shallow to reduce
+        assert_eq!(
+            searcher.late_move_reduction(&position, move_, 2, 20, false),
+            0
+        );
+        // promotions are never reduced
+        let promotion_position = Position::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = Move::new(Square64::A7, Square64::A8, MoveFlag::KnightPromotion);
+        assert_eq!(
+            searcher.late_move_reduction(&promotion_position, promotion, 8, 20, false),
+            0
+        );
+        // a reduced move keeps at least a ply of depth however large the reduction
+        searcher.set_lmr(Some(Lmr {
+            base: 500,
+            ..Lmr::default()
+        }));
+        assert_eq!(
+            searcher.late_move_reduction(&position, move_, 3, 20, false),
+            2
+        );
+        searcher.set_lmr(None);
+        assert_eq!(
+            searcher.late_move_reduction(&position, move_, 8, 20, false),
+            0
+        );
+        // the reductions save nodes, and with every reduction the tactics of the first plies
+        // are still found
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_lmr(Some(Lmr::default()));
+        let output = searcher.search(&mut position, 5);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 5);
+        assert!(output.nodes < expected.nodes);
+        assert!(position.strict_eq(&Position::from_fen(kiwipete).unwrap()));
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = Searcher::new().iterative_deepening(&mut position, 5, |_| {});
+        assert_eq!(output.score, MATE - 3);
+    }
+
+    #[test]
+    fn test_history_prunes() {
+        let position = Position::from_fen("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let quiet = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let check = Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet);
+        searcher
+            .thread
+            .quiet_history
+            .update(Color::White, quiet, -5_000);
+        searcher
+            .thread
+            .quiet_history
+            .update(Color::White, check, -5_000);
+        let inputs = [(1, true), (2, true), (3, false)];
+        for (depth, expected) in inputs {
+            let output = searcher.history_prunes(&position, quiet, depth);
+            assert_eq!(output, expected, "depth {}", depth);
+        }
+        // checks are always searched
+        assert!(!searcher.history_prunes(&position, check, 1));
+        // the threshold grows with the depth
+        searcher.thread.quiet_history.clear();
+        searcher
+            .thread
+            .quiet_history
+            .update(Color::White, quiet, -3_000);
+        assert!(searcher.history_prunes(&position, quiet, 1));
+        assert!(!searcher.history_prunes(&position, quiet, 2));
+        searcher.set_history_pruning(None);
+        assert!(!searcher.history_prunes(&position, quiet, 1));
+    }
+
+    #[test]
+    fn test_late_move_prunes() {
+        let position = Position::from_fen("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let quiet = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let check = Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet);
+        let inputs = [
+            (1, 9, false),
+            (1, 10, true),
+            (2, 15, false),
+            (2, 16, true),
+            (3, 26, true),
+            (4, 50, false),
+        ];
+        for (depth, searched, expected) in inputs {
+            let output = searcher.late_move_prunes(&position, quiet, depth, searched);
+            assert_eq!(output, expected, "depth {} searched {}", depth, searched);
+        }
+        // checks are always searched
+        assert!(!searcher.late_move_prunes(&position, check, 1, 20));
+        searcher.set_late_move_pruning(None);
+        assert!(!searcher.late_move_prunes(&position, quiet, 1, 20));
+    }
+
+    #[test]
+    fn test_late_move_pruning() {
+        // every move fails low, so the node gets through all 15 quiet moves without it
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(0);
+        let expected = searcher.negamax(&mut position, 1, 1, 1_000, 1_001);
+        let mut pruning = exact_searcher(0);
+        pruning.set_late_move_pruning(Some(LateMovePruning::default()));
+        let output = pruning.negamax(&mut position, 1, 1, 1_000, 1_001);
+        assert_eq!(output, expected);
+        // at depth 1 the 4 quiet moves after the first 10 are skipped, Ra8+ is still searched
+        assert_eq!(pruning.thread.nodes, searcher.thread.nodes - 4);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        // so the mate among the late quiet moves isn't pruned away
+        let mut position = Position::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = pruning.negamax(&mut position, 1, 1, 1_000, 1_001);
+        assert_eq!(output, MATE - 2);
+    }
+
+    #[test]
+    fn test_futility_value() {
+        let position = Position::from_fen("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let quiet = Move::new(Square64::A2, Square64::A3, MoveFlag::Quiet);
+        let check = Move::new(Square64::A1, Square64::A8, MoveFlag::Quiet);
+        let inputs = [
+            (quiet, 1, Some(225)),
+            (quiet, 3, Some(475)),
+            (quiet, 4, None),
+            // checks are always searched
+            (check, 1, None),
+        ];
+        for (move_, depth, expected) in inputs {
+            let output = searcher.futility_value(&position, move_, depth, 100);
+            assert_eq!(output, expected, "{} at depth {}", move_, depth);
+        }
+        searcher.set_futility(None);
+        assert_eq!(searcher.futility_value(&position, quiet, 1, 100), None);
+    }
+
+    #[test]
+    fn test_futility_pruning() {
+        // black is a queen and a rook up, far more than the margin of a ply makes up for
+        let fen = "6k1/5ppp/8/8/8/8/qr6/4K3 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let static_eval = evaluate(&position);
+        let mut searcher = exact_searcher(0);
+        searcher.set_futility(Some(Futility::default()));
+        // only the first of the two king moves is searched, the other scores its futility value
+        let output = searcher.negamax(&mut position, 1, 1, 0, 1);
+        assert_eq!(output, static_eval + Futility::default().margin);
+        // the same material down, the back rank mate is a quiet move that's still searched
+        let mut position = Position::from_fen("6k1/5ppp/8/8/8/8/qr6/3R2K1 w - - 0 1").unwrap();
+        let output = searcher.negamax(&mut position, 1, 1, 0, 1);
+        assert_eq!(output, MATE - 2);
+        // and leaving the moves out saves nodes
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_futility(Some(Futility::default()));
+        let output = searcher.search(&mut position, 4);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 4);
+        assert!(output.nodes < expected.nodes);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+    }
+
+    #[test]
+    fn test_reverse_futility_score() {
+        let mut searcher = Searcher::new();
+        let inputs = [
+            (1, 500, 300, Some(400)),
+            (2, 500, 300, Some(300)),
+            (3, 500, 300, None),
+            // too deep to trust the static evaluation
+            (7, 2_000, 300, None),
+            (1, 500, MATE - 10, None),
+        ];
+        for (depth, static_eval, beta, expected) in inputs {
+            let output = searcher.reverse_futility_score(depth, static_eval, beta);
+            assert_eq!(output, expected, "depth {} beta {}", depth, beta);
+        }
+        searcher.set_reverse_futility(None);
+        assert_eq!(searcher.reverse_futility_score(1, 500, 300), None);
+    }
+
+    #[test]
+    fn test_reverse_futility_pruning() {
+        let fen = "4k3/pppp4/8/8/8/8/4PPPP/3QK3 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let static_eval = evaluate(&position);
+        let beta = static_eval - 300;
+        let mut searcher = exact_searcher(0);
+        searcher.set_reverse_futility(Some(ReverseFutility::default()));
+        // a zero window node fails high by its static evaluation without searching a move
+        let output = searcher.negamax(&mut position, 2, 1, beta - 1, beta);
+        assert_eq!(output, static_eval - 2 * ReverseFutility::default().margin);
+        assert_eq!(searcher.thread.nodes, 1);
+        // a principal variation node is searched
+        let output = searcher.negamax(&mut position, 2, 1, beta - 300, beta);
+        assert_ne!(output, static_eval - 2 * ReverseFutility::default().margin);
+        assert!(searcher.thread.nodes > 2);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        // so is a node in check, which has no static evaluation
+        let mut position = Position::from_fen("4k3/pppp4/8/8/8/8/4PPPP/3QK2r w - - 0 1").unwrap();
+        let static_eval = evaluate(&position);
+        let beta = static_eval - 300;
+        let output = searcher.negamax(&mut position, 2, 1, beta - 1, beta);
+        assert_ne!(output, static_eval - 2 * ReverseFutility::default().margin);
+        // the node count goes down, and a mate isn't pruned away by the material white is up
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_reverse_futility(Some(ReverseFutility::default()));
+        let output = searcher.search(&mut position, 4);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 4);
+        assert!(output.nodes < expected.nodes);
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = searcher.iterative_deepening(&mut position, 5, |_| {});
+        assert_eq!(output.score, MATE - 3);
+    }
+
+    /// Ids of the WAC positions the searcher finds a best move of at the self-test depth
+    fn solved_tactics(searcher: impl Fn() -> Searcher) -> Vec<String> {
+        run_suite(&WAC, DEFAULT_SELFTEST_DEPTH, searcher)
+            .unwrap()
+            .solved
+    }
+
+    #[test]
+    fn test_razor() {
+        let mut searcher = Searcher::new();
+        // white is a queen and a rook down and can't capture anything
+        let mut position = Position::from_fen("r2qk3/8/8/8/8/8/P7/4K3 w - - 0 1").unwrap();
+        let static_eval = evaluate(&position);
+        let inputs = [
+            (1, 0, Some(static_eval)),
+            (2, 0, Some(static_eval)),
+            (3, 0, None),
+            // close enough to alpha to search in full
+            (1, static_eval + 900, None),
+            (1, MATE - 10, None),
+        ];
+        for (depth, alpha, expected) in inputs {
+            let output = searcher.razor(&mut position, depth, 1, alpha, static_eval);
+            assert_eq!(output, expected, "depth {} alpha {}", depth, alpha);
+        }
+        searcher.set_razoring(None);
+        assert_eq!(searcher.razor(&mut position, 1, 1, 0, static_eval), None);
+    }
+
+    #[test]
+    fn test_razoring_keeps_tactics() {
+        let output = solved_tactics(Searcher::new);
+        let expected = solved_tactics(|| {
+            let mut searcher = Searcher::new();
+            searcher.set_razoring(None);
+            searcher
+        });
+        assert_eq!(output, expected);
+        assert_eq!(output.len(), WAC.len());
+    }
+
+    #[test]
+    fn test_non_pawn_material() {
+        let inputs = [
+            (START_FEN, 6_800),
+            ("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1", 0),
+            ("4k1n1/pppp4/8/8/8/8/4PPPP/3QK3 w - - 0 1", 1_325),
+        ];
+        for (fen, expected) in inputs {
+            let output = non_pawn_material(&Position::from_fen(fen).unwrap());
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_material_gain() {
+        let inputs = [
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                (Square64::D1, Square64::D5, MoveFlag::Capture),
+                1_000,
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                (Square64::E5, Square64::D6, MoveFlag::EnPassant),
+                100,
+            ),
+            (
+                "4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                (Square64::A7, Square64::A8, MoveFlag::QueenPromotion),
+                900,
+            ),
+            (
+                "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                (Square64::A7, Square64::B8, MoveFlag::KnightPromotionCapture),
+                550,
+            ),
+        ];
+        for (fen, (from, to, flag), expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let output = material_gain(&position, Move::new(from, to, flag));
+            assert_eq!(output, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_delta_pruning() {
+        // no capture wins enough to lift white to alpha, so none is searched
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        let output = searcher.quiescence(&mut position, 0, 1_000, 1_001);
+        assert_eq!((output, searcher.thread.nodes), (525, 1));
+        searcher.set_delta_pruning(None);
+        let expected = searcher.quiescence(&mut position, 0, 1_000, 1_001);
+        assert!(expected <= 1_000 && searcher.thread.nodes > 2);
+        // endgames aren't pruned
+        let mut position = Position::from_fen("4k3/8/8/3p4/4P3/8/8/R3K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.quiescence(&mut position, 0, 1_000, 1_001);
+        assert_eq!(searcher.thread.nodes, 2);
+    }
+
+    #[test]
+    fn test_check_extension() {
+        let mut searcher = Searcher::new();
+        let inputs = [
+            (true, 3, 1, 0, 1),
+            (false, 3, 1, 0, 0),
+            // the line has used up its extensions
+            (true, 3, 20, 16, 0),
+            (true, 3, 20, 15, 1),
+            // one more ply would go past MAX_PLY
+            (true, 3, MAX_PLY - 4, 0, 0),
+        ];
+        for (in_check, depth, ply, extensions, expected) in inputs {
+            searcher.thread.stack[ply as usize - 1].extensions = extensions;
+            let output = searcher.check_extension(in_check, depth, ply);
+            assert_eq!(output, expected, "depth {} ply {}", depth, ply);
+        }
+        searcher.set_check_extensions(None);
+        searcher.thread.stack[0].extensions = 0;
+        assert_eq!(searcher.check_extension(true, 3, 1), 0);
+    }
+
+    #[test]
+    fn test_single_reply_extension() {
+        let mut searcher = Searcher::new();
+        let inputs = [
+            // Kxb2 is the only way out of check
+            ("k7/8/8/8/8/8/1q6/K7 w - - 0 1", 3, 1, 0, 1),
+            ("k7/8/8/8/8/8/1q6/K7 w - - 0 1", 3, 20, 16, 0),
+            ("k7/8/8/8/8/8/1q6/K7 w - - 0 1", 3, MAX_PLY - 4, 0, 0),
+            // Ka1 and Kxc2
+            ("k7/8/8/8/8/8/2q5/1K6 w - - 0 1", 3, 1, 0, 0),
+            // h3 is the only legal move, but not in check
+            ("k7/8/8/8/7p/8/2q4P/K7 w - - 0 1", 3, 1, 0, 0),
+        ];
+        for (fen, depth, ply, extensions, expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            let in_check = position.checkers().0 != 0;
+            searcher.thread.stack[ply as usize - 1].extensions = extensions;
+            let output = searcher.single_reply_extension(&position, in_check, depth, ply);
+            assert_eq!(output, expected, "{} ply {}", fen, ply);
+        }
+        searcher.set_single_reply_extensions(None);
+        searcher.thread.stack[0].extensions = 0;
+        let position = Position::from_fen("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+        assert_eq!(searcher.single_reply_extension(&position, true, 3, 1), 0);
+    }
+
+    #[test]
+    fn test_check_extensions_find_deeper_mates() {
+        // Qg8+ Rxg8 Nf7# is a smothered mate 3 plies deep, which the extension of black's
+        // reply in check lets a search to depth 2 see
+        let fen = "2r4k/6pp/8/4N3/8/1Q6/B5PP/7K w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let output = Searcher::new().search(&mut position, 2);
+        let expected = exact_searcher(DEFAULT_HASH_MB).search(&mut position, 2);
+        assert_eq!(output.score, MATE - 3);
+        assert!(!is_mate_score(expected.score));
+    }
+
+    #[test]
+    fn test_singular_extension() {
+        let mut searcher = Searcher::new();
+        let inputs = [
+            // taking the queen is the only move that doesn't lose
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                (Square64::D1, Square64::D5, MoveFlag::Capture),
+                Bound::Lower,
+                550,
+                1,
+            ),
+            // the king has to take the queen, with no other move at all
+            (
+                "k7/8/8/8/8/8/1q6/K7 w - - 0 1",
+                (Square64::A1, Square64::B2, MoveFlag::Capture),
+                Bound::Exact,
+                0,
+                1,
+            ),
+            // plenty of moves are as good as the first
+            (
+                START_FEN,
+                (Square64::E2, Square64::E4, MoveFlag::DoublePawnPush),
+                Bound::Exact,
+                0,
+                0,
+            ),
+            // an upper bound doesn't show the move is any good
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                (Square64::D1, Square64::D5, MoveFlag::Capture),
+                Bound::Upper,
+                550,
+                0,
+            ),
+        ];
+        for (fen, (from, to, flag), bound, score, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let move_ = Move::new(from, to, flag);
+            searcher
+                .tt
+                .store(position.key(), 1, 8, bound, score, Some(move_));
+            let entry = searcher.tt.probe(position.key(), 1);
+            let output = searcher.singular_extension(&mut position, 8, 1, entry);
+            assert_eq!(output, expected, "{}", fen);
+            // the search without the move keeps its entries apart from the position's own
+            assert_eq!(searcher.tt.probe(position.key(), 1), entry, "{}", fen);
+        }
+        let mut position = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let entry = searcher.tt.probe(position.key(), 1);
+        // too shallow to be worth a second search, or the line is out of extensions
+        assert_eq!(searcher.singular_extension(&mut position, 5, 1, entry), 0);
+        searcher.thread.stack[1].extensions = 8;
+        assert_eq!(searcher.singular_extension(&mut position, 8, 1, entry), 0);
+        searcher.thread.stack[1].extensions = 0;
+        assert_eq!(searcher.singular_extension(&mut position, 8, 1, None), 0);
+        searcher.set_singular_extensions(None);
+        assert_eq!(searcher.singular_extension(&mut position, 8, 1, entry), 0);
+    }
+
+    #[test]
+    fn test_singular_extensions_keep_scores() {
+        // the hash moves of a deep search get extended, which mustn't change the outcome
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        let output = searcher.iterative_deepening(&mut position, 7, |_| {});
+        let mut searcher = Searcher::new();
+        searcher.set_singular_extensions(None);
+        let expected = searcher.iterative_deepening(&mut position, 7, |_| {});
+        assert_eq!(output.best_move, expected.best_move);
+        assert_eq!(position.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_probcut() {
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_probcut(Some(ProbCut::default()));
+        // taking the queen wins a rook's worth, far above a beta of 0 but not of 500
+        let mut position = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let static_eval = evaluate(&position);
+        let inputs = [(5, 0, Some(550)), (4, 0, None), (5, 500, None)];
+        for (depth, beta, expected) in inputs {
+            let output = searcher.probcut(&mut position, depth, 1, beta, static_eval, None);
+            assert_eq!(output, expected, "depth {} beta {}", depth, beta);
+        }
+        // the cutoff is stored as a lower bound a ply deeper than the captures were searched
+        let entry = searcher.tt.probe(position.key(), 1).unwrap();
+        let capture = Move::new(Square64::D1, Square64::D5, MoveFlag::Capture);
+        assert_eq!((entry.depth, entry.bound), (3, Bound::Lower));
+        assert_eq!((entry.score, entry.best_move), (550, Some(capture)));
+        // nothing to capture in the start position
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        assert_eq!(searcher.probcut(&mut position, 8, 1, -300, 0, None), None);
+        searcher.set_probcut(None);
+        let mut position = Position::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let output = searcher.probcut(&mut position, 5, 1, 0, static_eval, None);
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn test_probcut_only_at_zero_window_nodes() {
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        // (alpha, depth of the node's entry): ProbCut's cutoff leaves the entry of its shallower
+        // search, a principal variation node is searched to the full depth
+        let inputs = [(-1, 3), (-300, 5)];
+        for (alpha, expected) in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_probcut(Some(ProbCut::default()));
+            let output = searcher.negamax(&mut position, 5, 1, alpha, 0);
+            assert!(output >= 0, "alpha {}", alpha);
+            let entry = searcher.tt.probe(position.key(), 1).unwrap();
+            assert_eq!(entry.depth, expected, "alpha {}", alpha);
+        }
+        // the cutoff saves nodes
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_probcut(Some(ProbCut::default()));
+        searcher.negamax(&mut position, 5, 1, -1, 0);
+        let mut expected = exact_searcher(DEFAULT_HASH_MB);
+        expected.negamax(&mut position, 5, 1, -1, 0);
+        assert!(searcher.thread.nodes < expected.thread.nodes);
+    }
+
+    #[test]
+    fn test_internal_iterative_deepening() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+        searcher.set_iid(Some(InternalIterativeDeepening::default()));
+        let output =
+            searcher.internal_iterative_deepening(&mut position, 3, 1, -INFINITY, INFINITY);
+        assert_eq!(output, None);
+        let output =
+            searcher.internal_iterative_deepening(&mut position, 5, 1, -INFINITY, INFINITY);
+        let entry = output.unwrap();
+        assert_eq!(entry.depth, 3);
+        assert!(position.is_legal(entry.best_move.unwrap()));
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        searcher.set_iid(None);
+        let output =
+            searcher.internal_iterative_deepening(&mut position, 8, 1, -INFINITY, INFINITY);
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn test_internal_iterative_reduction() {
+        let mut searcher = Searcher::new();
+        let inputs = [(1, 0), (5, 0), (6, 1), (20, 1)];
+        for (depth, expected) in inputs {
+            assert_eq!(searcher.internal_iterative_reduction(depth), expected);
+        }
+        searcher.set_iid(None);
+        assert_eq!(searcher.internal_iterative_reduction(20), 0);
+    }
+
+    #[test]
+    fn test_internal_iterative_reduction_without_hash_move() {
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        // a zero window node new to the table is searched and stored a ply shallower
+        let inputs = [(Some(InternalIterativeDeepening::default()), 5), (None, 6)];
+        let mut nodes = Vec::new();
+        for (iid, expected) in inputs {
+            let mut searcher = exact_searcher(DEFAULT_HASH_MB);
+            searcher.set_iid(iid);
+            searcher.negamax(&mut position, 6, 1, -1, 0);
+            let output = searcher.tt.probe(position.key(), 1).unwrap();
+            assert_eq!(output.depth, expected, "{:?}", iid);
+            nodes.push(searcher.thread.nodes);
+        }
+        // and searching it a ply shallower takes fewer nodes
+        assert!(nodes[0] < nodes[1]);
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+    }
+}
@@ -0,0 +1,293 @@
+//! Lazy SMP: the state each thread of a search keeps to itself, and the helper threads searching
+//! along with the main one
+
+use super::{Frame, SearchLimits, SearchObserver, SearchResult, Searcher, MAX_PLY, PV_SIZE};
+use crate::{
+    board::Position,
+    movegen::{CountermoveTable, HistoryTable},
+    moves::Move,
+};
+use alloc::{vec, vec::Vec};
+use core::{mem, sync::atomic::Ordering};
+
+/// State of the search on one thread that the thread keeps to itself: the line it's on, what it
+/// learned about ordering moves and how many nodes it visited. Searcher keeps those of its
+/// threads between searches, so a helper thread's history carries over like the main thread's.
+#[derive(Debug, Clone)]
+pub struct SearchThread {
+    /// Keys of the positions before the current one, from the start of the game through the
+    /// moves searched so far
+    pub(super) history: Vec<u64>,
+    /// Triangular principal variation table: row ply holds the best line found from ply on in
+    /// its columns ply to pv_len[ply], which a node copies from the row below when a move
+    /// raises alpha
+    pub(super) pv: Vec<[Move; PV_SIZE]>,
+    pub(super) pv_len: [usize; PV_SIZE],
+    /// History scores of the quiet moves, which order them and prune by. Later searches keep
+    /// them, aged.
+    pub(super) quiet_history: HistoryTable,
+    /// Quiet refutations of the opponent's moves, also kept between searches
+    pub(super) countermoves: CountermoveTable,
+    pub(super) stack: [Frame; PV_SIZE],
+    /// Nodes of the current call of search_root
+    pub(super) nodes: u64,
+    /// Nodes of the calls of search_root before it in the search
+    pub(super) searched_nodes: u64,
+    /// Deepest ply the current call of search_root reached
+    pub(super) seldepth: u32,
+    /// A depth was completed, so the search may stop at its limits
+    pub(super) depth_completed: bool,
+    /// A limit was reached, so the search unwinds without trusting or storing any score
+    pub(super) stopped: bool,
+}
+
+impl SearchThread {
+    pub(super) fn new(history: Vec<u64>) -> Self {
+        Self {
+            history,
+            pv: vec![[Move::NULL; PV_SIZE]; PV_SIZE],
+            pv_len: [0; PV_SIZE],
+            quiet_history: HistoryTable::new(),
+            countermoves: CountermoveTable::new(),
+            stack: [Frame::default(); PV_SIZE],
+            nodes: 0,
+            searched_nodes: 0,
+            seldepth: 0,
+            depth_completed: false,
+            stopped: false,
+        }
+    }
+
+    /// Starts a search: the history ages its scores, the killers of earlier searches are
+    /// forgotten and the node count starts over
+    pub(super) fn new_search(&mut self) {
+        self.quiet_history.age();
+        self.stack = [Frame::default(); PV_SIZE];
+        self.nodes = 0;
+        self.searched_nodes = 0;
+        self.depth_completed = false;
+        self.stopped = false;
+    }
+
+    /// Forgets the history and countermoves of earlier searches
+    pub(super) fn clear_history(&mut self) {
+        self.quiet_history.clear();
+        self.countermoves.clear();
+    }
+
+    /// Nodes the thread visited in the last search
+    pub fn nodes(&self) -> u64 {
+        self.searched_nodes + self.nodes
+    }
+}
+
+impl Searcher {
+    /// Sets the number of threads search_with_limits searches with, at least 1. Helper threads
+    /// need std, without it there's only ever one. Helpers that are still needed keep their
+    /// state, new ones start with an empty history.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.helpers
+            .resize_with(threads.max(1) - 1, || SearchThread::new(Vec::new()));
+    }
+
+    /// Threads search_with_limits searches with
+    pub fn threads(&self) -> usize {
+        self.helpers.len() + 1
+    }
+
+    /// States of the threads of the last search, this one first, e.g. to sum their stats
+    pub fn search_threads(&self) -> impl Iterator<Item = &SearchThread> {
+        core::iter::once(&self.thread).chain(&self.helpers)
+    }
+
+    /// Nodes the last search visited on all its threads and at all its depths
+    pub fn nodes(&self) -> u64 {
+        self.search_threads().map(SearchThread::nodes).sum()
+    }
+
+    /// Lazy SMP: helper threads run iterative deepening of the same root on clones of the
+    /// searcher with their own SearchThread, which share the transposition table, so they fill
+    /// it with entries this thread then finds. Every other helper starts a ply deeper and each
+    /// has its own history and killers, so they don't all search the same nodes in the same
+    /// order. Only this thread's result counts, and once it's done the stop flag ends the
+    /// helpers' searches. The helpers' states are kept for the next search.
+    #[cfg(feature = "std")]
+    pub(super) fn lazy_smp(
+        &mut self,
+        position: &mut Position,
+        first_depth: u32,
+        observer: &mut dyn SearchObserver,
+    ) -> SearchResult {
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY - 1);
+        // only this thread keeps to the node and time limits and reports iterations, and while
+        // pondering, when it doesn't apply yet, the helpers don't keep to the depth limit either
+        let helper_limits = SearchLimits {
+            depth: self.limits.depth.filter(|_| !self.limits.ponder),
+            ..SearchLimits::default()
+        };
+        let threads = mem::take(&mut self.helpers);
+        let helpers: Vec<Searcher> = threads
+            .into_iter()
+            .map(|mut thread| {
+                thread.history.clone_from(&self.thread.history);
+                let mut helper = self.clone();
+                helper.thread = thread;
+                helper.limits = helper_limits;
+                helper
+            })
+            .collect();
+        let root = position.clone();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = helpers
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut helper)| {
+                    let mut position = root.clone();
+                    let first_depth = (first_depth + index as u32 % 2).min(max_depth);
+                    scope.spawn(move || {
+                        helper.deepen(&mut position, first_depth, &mut ());
+                        helper.thread
+                    })
+                })
+                .collect();
+            let result = self.deepen(position, first_depth, observer);
+            self.stop.store(true, Ordering::Relaxed);
+            for handle in handles {
+                let thread = handle.join().expect("a helper thread panicked");
+                self.helpers.push(thread);
+            }
+            result
+        })
+    }
+}
+
+/// Time to depth of a search on some number of threads, see time_to_depth
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalingResult {
+    pub threads: usize,
+    /// Time the searches of all the positions took together
+    pub time: std::time::Duration,
+    /// Nodes they visited on all threads
+    pub nodes: u64,
+    /// Time on the first thread count divided by this one's
+    pub speedup: f64,
+    /// Speedup per thread compared with the first thread count, 1 for perfect scaling
+    pub efficiency: f64,
+}
+
+/// Searches each position to depth on each of the thread counts with a fresh Searcher, so no
+/// search reuses the transposition table of another, and reports how much faster each count
+/// gets there than the first. Lazy SMP helps by filling the table rather than by splitting the
+/// work, so the speedup is usually well below the thread count.
+#[cfg(feature = "std")]
+pub fn time_to_depth(fens: &[&str], depth: u32, thread_counts: &[usize]) -> Vec<ScalingResult> {
+    let mut results: Vec<ScalingResult> = Vec::with_capacity(thread_counts.len());
+    for &threads in thread_counts {
+        let (mut time, mut nodes) = (std::time::Duration::ZERO, 0);
+        for fen in fens {
+            let mut position = Position::from_fen(fen).expect("bench positions should be valid");
+            let mut searcher = Searcher::new();
+            searcher.set_threads(threads);
+            let started = std::time::Instant::now();
+            searcher.iterative_deepening(&mut position, depth, |_| {});
+            time += started.elapsed();
+            nodes += searcher.nodes();
+        }
+        let (base_threads, base_time) = results
+            .first()
+            .map_or((threads, time), |first| (first.threads, first.time));
+        let speedup = base_time.as_secs_f64() / time.as_secs_f64().max(f64::MIN_POSITIVE);
+        results.push(ScalingResult {
+            threads,
+            time,
+            nodes,
+            speedup,
+            efficiency: speedup * base_threads as f64 / threads.max(1) as f64,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::tests::play_pv;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_lazy_smp() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_threads(4);
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let mut depths = Vec::new();
+        let output =
+            searcher.search_with_limits(&mut position, limits, |result| depths.push(result.depth));
+        // the iterations reported are this thread's, and the helpers' nodes count too
+        assert_eq!(depths, [1, 2, 3, 4, 5]);
+        assert!(position.is_legal(output.best_move.unwrap()));
+        play_pv(&position, &output.pv);
+        let nodes: Vec<u64> = searcher.search_threads().map(SearchThread::nodes).collect();
+        assert_eq!(nodes.len(), 4);
+        assert!(nodes.iter().all(|&nodes| nodes > 0));
+        assert_eq!(searcher.nodes(), nodes.iter().sum());
+        assert!(position.strict_eq(&Position::from_fen(fen).unwrap()));
+        // the helpers stop with the infinite search they help
+        let stop = searcher.stop_handle();
+        let output =
+            searcher.search_with_limits(&mut position, SearchLimits::default(), |result| {
+                if result.depth == 3 {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+        assert_eq!(output.depth, 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_set_threads() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        assert_eq!(searcher.threads(), 1);
+        searcher.set_threads(3);
+        searcher.iterative_deepening(&mut position, 5, |_| {});
+        // each helper searches at least its first depth in full, however soon this thread is done
+        assert_eq!(searcher.helpers.len(), 2);
+        assert!(searcher.search_threads().all(|thread| thread.nodes() > 0));
+        // helpers are kept when the thread count changes, new ones start empty
+        let helper = searcher.helpers[0].clone();
+        searcher.set_threads(2);
+        assert_eq!(searcher.threads(), 2);
+        assert_eq!(searcher.helpers[0].nodes(), helper.nodes());
+        assert!(searcher.helpers[0].quiet_history == helper.quiet_history);
+        searcher.set_threads(0);
+        assert_eq!(searcher.threads(), 1);
+        searcher.set_threads(2);
+        assert_eq!(searcher.helpers[0].nodes(), 0);
+        assert!(searcher.helpers[0].quiet_history == HistoryTable::new());
+        // a search on fewer threads leaves none of the old helpers' nodes in the count
+        searcher.set_threads(1);
+        searcher.iterative_deepening(&mut position, 3, |_| {});
+        assert_eq!(searcher.nodes(), searcher.thread.nodes());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_time_to_depth() {
+        let fens = [crate::board::START_FEN, "4k3/8/8/8/8/8/8/4K2R w K - 0 1"];
+        let output = time_to_depth(&fens, 4, &[1, 2]);
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].threads, 1);
+        assert_eq!(output[1].threads, 2);
+        assert_eq!((output[0].speedup, output[0].efficiency), (1.0, 1.0));
+        assert!(output.iter().all(|result| result.nodes > 0));
+        let expected = output[1].speedup / 2.0;
+        assert!((output[1].efficiency - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,451 @@
+use super::is_mate_score;
+use crate::moves::Move;
+use alloc::vec::Vec;
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+/// Size of the table a Searcher starts with
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// How a stored score relates to the exact score of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact, it was inside the search window
+    Exact,
+    /// The search failed high: the exact score is at least this
+    Lower,
+    /// The search failed low: the exact score is at most this
+    Upper,
+}
+
+/// What the search learned about a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    /// Zobrist key of the position, checked on probing so another position mapping to the same
+    /// slot isn't mistaken for this one
+    pub key: u64,
+    /// Plies the position was searched to, stored as at most 255
+    pub depth: u32,
+    pub bound: Bound,
+    /// Score relative to the position itself rather than the root, see TranspositionTable::store
+    pub score: i32,
+    /// Move that scored best, or caused the cutoff, if the search found one
+    pub best_move: Option<Move>,
+    /// Generation of the table when the entry was stored, see TranspositionTable::new_search
+    pub generation: u8,
+}
+
+/// Entries a key can be stored in, four 16 byte slots filling one 64 byte cache line
+const BUCKET_SIZE: usize = 4;
+
+/// One entry as two words: the data packed into the second, and the key XORed with the data in
+/// the first. Threads store both words without taking a lock, so another thread may see one
+/// word of a new entry with the other of an old one, but then the key it recovers from them
+/// doesn't match and the torn entry reads as a miss.
+#[derive(Debug, Default)]
+struct Slot {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+type Bucket = [Slot; BUCKET_SIZE];
+
+// bit layout of Slot::data; a bound of 0 marks an empty slot
+const MOVE_SHIFT: u32 = 0;
+const SCORE_SHIFT: u32 = 16;
+const DEPTH_SHIFT: u32 = 32;
+const GENERATION_SHIFT: u32 = 40;
+const BOUND_SHIFT: u32 = 48;
+
+impl Slot {
+    fn load(&self) -> Option<TtEntry> {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key_xor_data.load(Ordering::Relaxed) ^ data;
+        let bound = match (data >> BOUND_SHIFT) as u8 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        Some(TtEntry {
+            key,
+            depth: (data >> DEPTH_SHIFT) as u8 as u32,
+            bound,
+            score: (data >> SCORE_SHIFT) as u16 as i16 as i32,
+            best_move: Move::try_from((data >> MOVE_SHIFT) as u16)
+                .ok()
+                .filter(|&move_| move_ != Move::NULL),
+            generation: (data >> GENERATION_SHIFT) as u8,
+        })
+    }
+
+    fn save(&self, entry: TtEntry) {
+        let bound: u64 = match entry.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        let data = (entry.best_move.unwrap_or(Move::NULL).bits() as u64) << MOVE_SHIFT
+            | (entry.score as i16 as u16 as u64) << SCORE_SHIFT
+            | (entry.depth.min(u8::MAX as u32) as u64) << DEPTH_SHIFT
+            | (entry.generation as u64) << GENERATION_SHIFT
+            | bound << BOUND_SHIFT;
+        self.key_xor_data.store(entry.key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.key_xor_data.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Cache of search results by Zobrist key, so a position reached again through a transposition
+/// or in the next iteration of iterative deepening doesn't have to be searched again, and a
+/// search that has to repeat it at least tries the best move first.
+///
+/// Each key maps onto a bucket of BUCKET_SIZE entries, found with a mask since the number of
+/// buckets is a power of two. Once a bucket is full a new entry replaces the one that's worth
+/// least: entries from earlier searches age, so a long analysis isn't left with a table full of
+/// deep results for positions that can no longer come up, while among entries of the same age
+/// the deeper ones, which took longer to find, are kept.
+///
+/// Every method takes &self, so search threads can share one table, e.g. behind an Arc, without
+/// a mutex. A probe can still return an entry stored for another position whose key maps onto
+/// the same bucket and collides in all 64 bits, so a best move from the table has to be checked
+/// for legality before it's played.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// Table of at most size_mb megabytes, with the number of buckets rounded down to a power of
+    /// two. There's always at least one bucket.
+    pub fn new(size_mb: usize) -> Self {
+        let bytes = size_mb.saturating_mul(1 << 20);
+        let buckets = (bytes / mem::size_of::<Bucket>()).max(1);
+        Self {
+            buckets: (0..1usize << buckets.ilog2())
+                .map(|_| Bucket::default())
+                .collect(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    /// Number of entries the table has room for
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// Forgets every entry, e.g. before searching an unrelated game
+    pub fn clear(&self) {
+        self.buckets.iter().flatten().for_each(Slot::clear);
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Starts a new generation, which makes every entry stored so far one search older. Called
+    /// once per search rather than per iteration of iterative deepening.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Per mille of the entries holding results of the current search, as UCI hashfull reports
+    /// it. Only the first thousand entries are looked at, keys spread evenly over all of them.
+    pub fn hashfull(&self) -> u32 {
+        let generation = self.generation();
+        let sample = self.capacity().min(1_000);
+        let filled = self
+            .buckets
+            .iter()
+            .flatten()
+            .take(sample)
+            .filter_map(Slot::load)
+            .filter(|entry| entry.generation == generation)
+            .count();
+        (filled * 1_000 / sample) as u32
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+
+    /// Entry for the position with the given key, with a mate score turned back into the
+    /// distance from the root of the search probing it, ply plies above the position
+    pub fn probe(&self, key: u64, ply: u32) -> Option<TtEntry> {
+        self.bucket(key)
+            .iter()
+            .filter_map(Slot::load)
+            .find(|entry| entry.key == key)
+            .map(|entry| TtEntry {
+                score: score_from_tt(entry.score, ply),
+                ..entry
+            })
+    }
+
+    /// How much an entry is worth keeping: its depth, less a few plies for every search since
+    /// it was stored
+    fn worth(&self, entry: &TtEntry) -> i32 {
+        let age = self.generation().wrapping_sub(entry.generation) as i32;
+        entry.depth as i32 - 8 * age
+    }
+
+    /// Stores what a search ply plies from its root found out about the position with the given
+    /// key. Mate scores count plies from the root, so they're stored as the distance from the
+    /// position instead, which is the same wherever the position is reached.
+    pub fn store(
+        &self,
+        key: u64,
+        ply: u32,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+    ) {
+        let bucket = self.bucket(key);
+        let entries = bucket.each_ref().map(Slot::load);
+        // the position's own entry, then an empty one, then the one worth least
+        let index = entries
+            .iter()
+            .position(|entry| entry.is_some_and(|entry| entry.key == key))
+            .or_else(|| entries.iter().position(Option::is_none))
+            .unwrap_or_else(|| {
+                (0..BUCKET_SIZE)
+                    .min_by_key(|&index| {
+                        entries[index].map_or(i32::MIN, |entry| self.worth(&entry))
+                    })
+                    .expect("a bucket should have entries")
+            });
+        // a search that didn't find a best move keeps the one found before
+        let best_move = best_move.or_else(|| {
+            entries[index]
+                .filter(|entry| entry.key == key)
+                .and_then(|entry| entry.best_move)
+        });
+        bucket[index].save(TtEntry {
+            key,
+            depth,
+            bound,
+            score: score_to_tt(score, ply),
+            best_move,
+            generation: self.generation(),
+        });
+    }
+}
+
+/// Only shows the size, not the entries
+impl fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("capacity", &self.capacity())
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+/// Key a search of the position with key that leaves out one move stores its results under, so
+/// they're kept apart from those of the full position
+pub(super) fn excluded_key(key: u64, excluded: Move) -> u64 {
+    // any odd multiplier spreads the move's bits over the whole key
+    key ^ (excluded.bits() as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    match is_mate_score(score) {
+        true => score + score.signum() * ply as i32,
+        false => score,
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    match is_mate_score(score) {
+        true => score - score.signum() * ply as i32,
+        false => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{moves::MoveFlag, search::MATE, squares::Square64};
+
+    #[test]
+    fn test_new_rounds_down_to_power_of_two() {
+        let bucket_size = mem::size_of::<Bucket>();
+        let inputs = [0, 1, 3];
+        for size_mb in inputs {
+            let output = TranspositionTable::new(size_mb).capacity() / BUCKET_SIZE;
+            assert!(output.is_power_of_two());
+            assert!(output * bucket_size <= (size_mb << 20).max(bucket_size));
+            assert!(2 * output * bucket_size > size_mb << 20);
+        }
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let input = TranspositionTable::new(1);
+        let best_move = Some(Move::new(
+            Square64::E2,
+            Square64::E4,
+            MoveFlag::DoublePawnPush,
+        ));
+        input.store(42, 3, 5, Bound::Lower, 120, best_move);
+        let output = input.probe(42, 3);
+        let expected = Some(TtEntry {
+            key: 42,
+            depth: 5,
+            bound: Bound::Lower,
+            score: 120,
+            best_move,
+            generation: 0,
+        });
+        assert_eq!(output, expected);
+        // another key mapping to the same slot
+        assert_eq!(input.probe(42 + input.capacity() as u64, 3), None);
+        input.clear();
+        assert_eq!(input.probe(42, 3), None);
+    }
+
+    #[test]
+    fn test_mate_scores_are_relative_to_the_position() {
+        let input = TranspositionTable::new(1);
+        // mate in 5 plies from the root, found 2 plies down, is a mate in 3 from the position
+        input.store(7, 2, 3, Bound::Exact, MATE - 5, None);
+        let output = (input.probe(7, 2), input.probe(7, 4));
+        assert_eq!(output.0.unwrap().score, MATE - 5);
+        assert_eq!(output.1.unwrap().score, MATE - 7);
+        input.store(7, 4, 3, Bound::Exact, -(MATE - 6), None);
+        assert_eq!(input.probe(7, 1).unwrap().score, -(MATE - 3));
+    }
+
+    #[test]
+    fn test_store_keeps_best_move_of_same_position() {
+        let input = TranspositionTable::new(1);
+        let best_move = Some(Move::new(Square64::G1, Square64::F3, MoveFlag::Quiet));
+        input.store(42, 0, 2, Bound::Exact, 10, best_move);
+        input.store(42, 0, 3, Bound::Upper, -5, None);
+        let output = input.probe(42, 0).unwrap();
+        assert_eq!((output.depth, output.best_move), (3, best_move));
+    }
+
+    #[test]
+    fn test_full_bucket_replaces_shallowest_or_oldest() {
+        let input = TranspositionTable::new(0);
+        // keys a whole number of buckets apart all map onto the same bucket
+        let stride = (input.capacity() / BUCKET_SIZE) as u64;
+        let keys: [u64; 7] = core::array::from_fn(|i| 1 + i as u64 * stride);
+        for (&key, depth) in keys.iter().zip([5, 2, 7, 4, 3]) {
+            input.store(key, 0, depth, Bound::Exact, 0, None);
+        }
+        // the bucket was full, so the depth 2 entry made room
+        let output = keys.map(|key| input.probe(key, 0).is_some());
+        assert_eq!(output, [true, false, true, true, true, false, false]);
+        // a search later every entry has aged the same, so the shallowest one goes
+        input.new_search();
+        input.store(keys[5], 0, 1, Bound::Exact, 0, None);
+        let output = keys.map(|key| input.probe(key, 0).is_some());
+        assert_eq!(output, [true, false, true, true, false, true, false]);
+        // but the new depth 1 entry is now worth more than the older depth 4 one
+        input.store(keys[6], 0, 1, Bound::Exact, 0, None);
+        let output = keys.map(|key| input.probe(key, 0).is_some());
+        assert_eq!(output, [true, false, true, false, false, true, true]);
+        assert_eq!(input.probe(keys[6], 0).unwrap().generation, 1);
+    }
+
+    #[test]
+    fn test_slot_round_trips_entries() {
+        let slot = Slot::default();
+        assert_eq!(slot.load(), None);
+        let inputs = [
+            TtEntry {
+                key: u64::MAX,
+                depth: 255,
+                bound: Bound::Upper,
+                score: -(MATE - 3),
+                best_move: Some(Move::new(
+                    Square64::H7,
+                    Square64::H8,
+                    MoveFlag::QueenPromotion,
+                )),
+                generation: 200,
+            },
+            TtEntry {
+                key: 0,
+                depth: 0,
+                bound: Bound::Exact,
+                score: 0,
+                best_move: None,
+                generation: 0,
+            },
+        ];
+        for input in inputs {
+            slot.save(input);
+            assert_eq!(slot.load(), Some(input));
+        }
+    }
+
+    #[test]
+    fn test_torn_entry_reads_as_miss() {
+        let input = TranspositionTable::new(0);
+        input.store(42, 0, 3, Bound::Exact, 10, None);
+        // another thread's store gets as far as the data word
+        input.bucket(42)[0]
+            .data
+            .fetch_xor(1 << SCORE_SHIFT, Ordering::Relaxed);
+        assert_eq!(input.probe(42, 0), None);
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let input = TranspositionTable::new(1);
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let input = &input;
+                scope.spawn(move || {
+                    for key in (thread * 1_000..(thread + 1) * 1_000).map(|key| key * 7) {
+                        input.store(key, 0, 1, Bound::Exact, key as i32 % 100, None);
+                    }
+                });
+            }
+        });
+        for key in (0..4_000).map(|key: u64| key * 7) {
+            let output = input.probe(key, 0).map(|entry| entry.score);
+            assert_eq!(output, Some(key as i32 % 100));
+        }
+    }
+
+    #[test]
+    fn test_hashfull() {
+        let input = TranspositionTable::new(1);
+        assert_eq!(input.hashfull(), 0);
+        // one entry in each of the buckets of the first thousand entries
+        for key in 0..(1_000 / BUCKET_SIZE) as u64 {
+            input.store(key, 0, 1, Bound::Exact, 0, None);
+        }
+        assert_eq!(input.hashfull(), (1_000 / BUCKET_SIZE) as u32);
+        // entries of earlier searches don't count
+        input.new_search();
+        assert_eq!(input.hashfull(), 0);
+        let input = TranspositionTable::new(0);
+        input.store(0, 0, 1, Bound::Exact, 0, None);
+        assert_eq!(input.hashfull(), (1_000 / BUCKET_SIZE) as u32);
+    }
+
+    #[test]
+    fn test_excluded_key() {
+        let key = 0x0123_4567_89AB_CDEF;
+        let input = Move::new(Square64::E2, Square64::E4, MoveFlag::DoublePawnPush);
+        let output = excluded_key(key, input);
+        assert_ne!(output, key);
+        assert_eq!(output, excluded_key(key, input));
+        let other = Move::new(Square64::E2, Square64::E3, MoveFlag::Quiet);
+        assert_ne!(excluded_key(key, other), output);
+        assert_ne!(excluded_key(key, Move::NULL), key);
+    }
+}
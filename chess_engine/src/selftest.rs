@@ -0,0 +1,252 @@
+//! Strength self-test: searches the positions of a bundled test suite and reports the share of
+//! them whose best move it finds, which a log of earlier runs turns into a trend. A quick sanity
+//! check after building, e.g. with `cargo run --release --example selftest`.
+
+use crate::{epd::Epd, error::EpdParseError, search::Searcher};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// Depth the suites are searched to unless asked otherwise. The bundled positions are solved at
+/// it, so a run that misses one points at a search that got weaker.
+pub const DEFAULT_SELFTEST_DEPTH: u32 = 5;
+
+/// Positions of the Win at Chess suite iterative deepening solves at DEFAULT_SELFTEST_DEPTH
+/// without any of the pruning that could miss their tactics
+pub const WAC: [&str; 16] = [
+    "5rk1/1ppb3p/p1pb4/6q1/3P1p1r/2P1R2P/PP1BQ1P1/5RKN w - - bm Rg3; id \"WAC.003\";",
+    "r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - bm Qxh7+; id \"WAC.004\";",
+    "5k2/6pp/p1qN4/1p1p4/3P4/2PKP2Q/PP3r2/3R4 b - - bm Qc4+; id \"WAC.005\";",
+    "7k/p7/1R5K/6r1/6p1/6P1/8/8 w - - bm Rb7; id \"WAC.006\";",
+    "rnbqkb1r/pppp1ppp/8/4P3/6n1/7P/PPPNPPP1/R1BQKBNR b KQkq - bm Ne3; id \"WAC.007\";",
+    "r4q1k/p2bR1rp/2p2Q1N/5p2/5p2/2P5/PP3PPP/R5K1 w - - bm Rf7; id \"WAC.008\";",
+    "3q1rk1/p4pp1/2pb3p/3p4/6Pr/1PNQ4/P1PB1PP1/4RRK1 b - - bm Bh2+; id \"WAC.009\";",
+    "2br2k1/2q3rn/p2NppQ1/2p1P3/Pp5R/4P3/1P3PPP/3R2K1 w - - bm Rxh7; id \"WAC.010\";",
+    "r1b1kb1r/3q1ppp/pBp1pn2/8/Np3P2/5B2/PPP3PP/R2Q1RK1 w kq - bm Bxc6; id \"WAC.011\";",
+    "4k1r1/2p3r1/1pR1p3/3pP2p/3P2qP/P4N2/1PQ4P/5R1K b - - bm Qxf3+; id \"WAC.012\";",
+    "5rk1/pp4p1/2n1p2p/2Npq3/2p5/6P1/P3P1BP/R4Q1K w - - bm Qxf8+; id \"WAC.013\";",
+    "r2rb1k1/pp1q1p1p/2n1p1p1/2bp4/5P2/PP1BPR1Q/1BPN2PP/R5K1 w - - bm Qxh7+; id \"WAC.014\";",
+    "1R6/1brk2p1/4p2p/p1P1Pp2/P7/6P1/1P4P1/2R3K1 w - - bm Rxb7; id \"WAC.015\";",
+    "r4rk1/ppp2ppp/2n5/2bqp3/8/P2PB3/1PP1NPPP/R2Q1RK1 w - - bm Nc3; id \"WAC.016\";",
+    "r1b2rk1/ppbn1ppp/4p3/1QP4q/3P4/N4N2/5PPP/R1B2RK1 w - - bm c6; id \"WAC.019\";",
+    "r2qkb1r/1ppb1ppp/p7/4p3/P1Q1P3/2P5/5PPP/R1B2KNR b kq - bm Bb5; id \"WAC.020\";",
+];
+
+/// Outcome of a run of a suite, with the ids of the records, or their position in the suite
+/// counting from 1 if they have none
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelftestRun {
+    pub depth: u32,
+    /// Records the search found one of the best moves of
+    pub solved: Vec<String>,
+    /// Records it found a move to avoid or none of the best moves of
+    pub failed: Vec<String>,
+}
+
+impl SelftestRun {
+    pub fn summary(&self) -> RunSummary {
+        RunSummary {
+            depth: self.depth,
+            solved: self.solved.len(),
+            total: self.solved.len() + self.failed.len(),
+        }
+    }
+}
+
+/// Searches every record of the suite depth plies deep with a searcher of its own, so no run
+/// depends on what the records before it left in the transposition table. A record is solved if
+/// the search finds one of its best moves and none of the moves to avoid.
+pub fn run_suite(
+    suite: &[&str],
+    depth: u32,
+    searcher: impl Fn() -> Searcher,
+) -> Result<SelftestRun, EpdParseError> {
+    let mut run = SelftestRun {
+        depth,
+        solved: Vec::new(),
+        failed: Vec::new(),
+    };
+    for (index, record) in suite.iter().enumerate() {
+        let epd = Epd::try_from(*record)?;
+        let mut position = epd.position.clone();
+        let output = searcher().iterative_deepening(&mut position, depth, |_| {});
+        let san = output.best_move.map(|move_| move_.to_san(&epd.position));
+        let solved = san.is_some_and(|san| {
+            (epd.best_moves.is_empty() || epd.best_moves.contains(&san))
+                && !epd.avoid_moves.contains(&san)
+        });
+        let id = epd.id.unwrap_or_else(|| alloc::format!("{}", index + 1));
+        match solved {
+            true => run.solved.push(id),
+            false => run.failed.push(id),
+        }
+    }
+    Ok(run)
+}
+
+/// How a run went, as the log of earlier runs keeps it: one line of depth, solved and total,
+/// e.g. `5 15 16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSummary {
+    pub depth: u32,
+    pub solved: usize,
+    pub total: usize,
+}
+
+impl RunSummary {
+    /// Share of the suite solved, from 0 to 100
+    pub fn percentage(&self) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => self.solved as f64 * 100.0 / total as f64,
+        }
+    }
+
+    /// Reads a line of the log, None if it isn't one
+    pub fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace().map(str::parse::<usize>);
+        let (Some(Ok(depth)), Some(Ok(solved)), Some(Ok(total)), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return None;
+        };
+        (solved <= total).then_some(Self {
+            depth: u32::try_from(depth).ok()?,
+            solved,
+            total,
+        })
+    }
+
+    /// Change in percentage points since the latest of the earlier runs, oldest first, that
+    /// searched the same suite as deep. None if there's none to compare with.
+    pub fn trend(&self, earlier: &[RunSummary]) -> Option<f64> {
+        earlier
+            .iter()
+            .rev()
+            .find(|run| run.depth == self.depth && run.total == self.total)
+            .map(|run| self.percentage() - run.percentage())
+    }
+}
+
+/// Writes the line of the log of earlier runs
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.depth, self.solved, self.total)
+    }
+}
+
+/// Earlier runs logged at path, oldest first, skipping lines that aren't runs. A missing log
+/// has none.
+#[cfg(feature = "std")]
+pub fn load_runs(path: &std::path::Path) -> std::io::Result<Vec<RunSummary>> {
+    match std::fs::read_to_string(path) {
+        Ok(log) => Ok(log.lines().filter_map(RunSummary::from_line).collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Appends the run to the log at path, creating it if needed
+#[cfg(feature = "std")]
+pub fn save_run(path: &std::path::Path, run: RunSummary) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(log, "{}", run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::borrow::ToOwned;
+
+    #[test]
+    fn test_run_suite() {
+        let suite = [
+            WAC[3],
+            // the rook can take a free pawn, but not if the bm says it shouldn't
+            "4k3/8/8/8/8/8/p7/R3K3 w - - bm Kd2;",
+            "4k3/8/8/8/8/8/p7/R3K3 w - - am Rxa2;",
+        ];
+        let output = run_suite(&suite, 3, Searcher::new).unwrap();
+        let expected = SelftestRun {
+            depth: 3,
+            solved: ["WAC.006".to_owned()].into(),
+            failed: ["2".to_owned(), "3".to_owned()].into(),
+        };
+        assert_eq!(output, expected);
+        assert_eq!(
+            output.summary(),
+            RunSummary {
+                depth: 3,
+                solved: 1,
+                total: 3,
+            }
+        );
+
+        let output = run_suite(&["4k3/8/8/8/8/8/8/4K3 w"], 3, Searcher::new);
+        assert!(matches!(output, Err(EpdParseError::MissingFields(_))));
+    }
+
+    #[test]
+    fn test_run_summary() {
+        let input = RunSummary {
+            depth: 5,
+            solved: 12,
+            total: 16,
+        };
+        assert_eq!(input.percentage(), 75.0);
+        let output = RunSummary::from_line(&input.to_string());
+        assert_eq!(output, Some(input));
+        for line in ["", "5 12", "5 12 16 1", "5 x 16", "5 17 16"] {
+            assert_eq!(RunSummary::from_line(line), None, "{}", line);
+        }
+
+        let earlier = [
+            RunSummary { solved: 8, ..input },
+            RunSummary {
+                solved: 4,
+                depth: 4,
+                ..input
+            },
+            RunSummary {
+                solved: 4,
+                total: 8,
+                ..input
+            },
+        ];
+        assert_eq!(input.trend(&earlier), Some(25.0));
+        assert_eq!(input.trend(&earlier[1..]), None);
+        assert_eq!(input.trend(&[]), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_run_log() {
+        let path = std::env::temp_dir().join(alloc::format!(
+            "chess_engine_selftest_{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(load_runs(&path).unwrap(), []);
+        let runs = [
+            RunSummary {
+                depth: 5,
+                solved: 15,
+                total: 16,
+            },
+            RunSummary {
+                depth: 5,
+                solved: 16,
+                total: 16,
+            },
+        ];
+        for run in runs {
+            save_run(&path, run).unwrap();
+        }
+        let output = load_runs(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(output.unwrap(), runs);
+    }
+}
@@ -1,20 +1,21 @@
 use crate::{
     board::bitboard::BitBoard,
     error::{Square64ConversionError, SquareConversionError},
-    util::{Color, File, Rank, FILES_BOARD, RANKS_BOARD, SQUARE_120_TO_64, SQUARE_64_TO_120},
+    util::{Color, File, Rank, FILES_BOARD, RANKS_BOARD, SQUARE_64_BY_INDEX, SQUARE_64_TO_120},
 };
-use num::Integer;
-use std::{
+use core::{
     fmt,
     ops::{Add, AddAssign, Sub},
     str::FromStr,
 };
+use num::Integer;
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter, EnumString};
 
 #[derive(Display, Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, EnumCountMacro)]
 #[rustfmt::skip]
-#[strum(use_phf)]
+// phf lookups need std, plain matching is used otherwise
+#[cfg_attr(feature = "std", strum(use_phf))]
 pub enum Square64 {
     A1, B1, C1, D1, E1, F1, G1, H1,
     A2, B2, C2, D2, E2, F2, G2, H2,
@@ -28,8 +29,7 @@ pub enum Square64 {
 
 impl From<Square> for Square64 {
     fn from(square_120: Square) -> Self {
-        SQUARE_120_TO_64[square_120 as usize]
-            .expect("10x12 Square should have a corresponding 8x8 Square64")
+        square_120.to_square64()
     }
 }
 
@@ -72,6 +72,30 @@ impl TryFrom<usize> for Square64 {
 }
 
 impl Square64 {
+    /// Corresponding square on the 10x12 board. Every Square64 is on the board so this can't fail.
+    pub const fn to_square120(self) -> Square {
+        SQUARE_64_TO_120[self as usize]
+    }
+
+    /// Mirrors the square across the horizontal midline, e.g. A1 <-> A8
+    pub const fn flip_vertical(self) -> Self {
+        SQUARE_64_BY_INDEX[self as usize ^ 56]
+    }
+
+    /// Mirrors the square across the vertical midline, e.g. A1 <-> H1
+    pub const fn flip_horizontal(self) -> Self {
+        SQUARE_64_BY_INDEX[self as usize ^ 7]
+    }
+
+    /// Square as seen from the given color's side of the board, so tables written from white's
+    /// point of view can be looked up for black too
+    pub const fn relative_to(self, color: Color) -> Self {
+        match color {
+            Color::White => self,
+            Color::Black => self.flip_vertical(),
+        }
+    }
+
     pub fn from_file_and_rank(file: File, rank: Rank) -> Self {
         let index_64 = (file as u8) + (rank as u8) * 8;
         index_64.try_into().expect(
@@ -104,7 +128,7 @@ impl Square64 {
 
 #[derive(Display, Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, EnumCountMacro)]
 #[rustfmt::skip]
-#[strum(use_phf)]
+#[cfg_attr(feature = "std", strum(use_phf))]
 pub enum Square {
     A1 = 21, B1, C1, D1, E1, F1, G1, H1,
     A2 = 31, B2, C2, D2, E2, F2, G2, H2,
@@ -118,24 +142,21 @@ pub enum Square {
 
 impl From<Square64> for Square {
     fn from(square_64: Square64) -> Self {
-        SQUARE_64_TO_120[square_64 as usize]
-            .expect("8x8 Square64 should have a corresponding 10x12 Square")
+        square_64.to_square120()
     }
 }
 
 impl Add<i8> for Square {
     type Output = Result<Self, SquareConversionError>;
     fn add(self, rhs: i8) -> Self::Output {
-        let result = (self as i8 + rhs).try_into();
-        result
+        (self as i8 + rhs).try_into()
     }
 }
 
 impl Sub<i8> for Square {
     type Output = Result<Self, SquareConversionError>;
     fn sub(self, rhs: i8) -> Self::Output {
-        let result = (self as i8 - rhs).try_into();
-        result
+        (self as i8 - rhs).try_into()
     }
 }
 
@@ -180,6 +201,14 @@ impl TryFrom<usize> for Square {
 }
 
 impl Square {
+    /// Corresponding square on the 8x8 board. Square has no variants for the 10x12 border,
+    /// so this can't fail.
+    pub const fn to_square64(self) -> Square64 {
+        // A1 is at index 21 and each rank is 10 wide, of which the first and last are border
+        let offset = self as usize - Square::A1 as usize;
+        SQUARE_64_BY_INDEX[(offset / 10) * 8 + offset % 10]
+    }
+
     pub fn from_file_and_rank(file: File, rank: Rank) -> Self {
         let index_120 = (21 + (file as u8) + (10 * (rank as u8)));
         index_120.try_into().expect(
@@ -224,6 +253,45 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_square_conversion_round_trip() {
+        for square_64 in Square64::iter() {
+            let index = square_64 as usize;
+            let file = File::try_from(index % 8).unwrap();
+            let rank = Rank::try_from(index / 8).unwrap();
+            let square = square_64.to_square120();
+            assert_eq!(square, Square::from_file_and_rank(file, rank));
+            assert_eq!(square.to_square64(), square_64);
+        }
+    }
+
+    #[test]
+    fn test_square_64_flip_vertical() {
+        let input = [Square64::A1, Square64::E2, Square64::H8];
+        let output = input.map(Square64::flip_vertical);
+        let expected = [Square64::A8, Square64::E7, Square64::H1];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_square_64_flip_horizontal() {
+        let input = [Square64::A1, Square64::E2, Square64::H8];
+        let output = input.map(Square64::flip_horizontal);
+        let expected = [Square64::H1, Square64::D2, Square64::A8];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_square_64_relative_to() {
+        let input = Square64::C2;
+        let output = (
+            input.relative_to(Color::White),
+            input.relative_to(Color::Black),
+        );
+        let expected = (Square64::C2, Square64::C7);
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_square_64_to_square_120() {
         let input = Square64::A6;
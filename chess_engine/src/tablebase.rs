@@ -0,0 +1,691 @@
+//! Win/draw/loss tables of the endings of a king and up to two pieces against a lone king,
+//! generated by retrograde analysis rather than read from files: the mates are found first, and
+//! the results are walked back from them a move at a time until nothing changes. The tables see
+//! positions from the side of the pieces, so one table serves both colors.
+//!
+//! None of the endings needs more than fifty moves to win, so the tables don't have to know the
+//! fifty move rule. They don't know how far the win is either, which is left to the search.
+
+use crate::{
+    board::{
+        attacks::{
+            between, bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks,
+            rook_attacks,
+        },
+        bitboard::BitBoard,
+        Position,
+    },
+    pieces::{Piece, PieceType},
+    util::{Color, SQUARE_64_BY_INDEX},
+};
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::ops::Neg;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+/// Result of a position with perfect play, for its side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+/// Endings there are tables of, named after the pieces of the side that has them
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Ending {
+    #[strum(serialize = "KQK")]
+    Kqk,
+    #[strum(serialize = "KRK")]
+    Krk,
+    #[strum(serialize = "KPK")]
+    Kpk,
+    #[strum(serialize = "KBNK")]
+    Kbnk,
+}
+
+impl Ending {
+    /// Pieces besides the king, in the order a Setup keeps their squares
+    fn pieces(self) -> &'static [PieceType] {
+        match self {
+            Ending::Kqk => &[PieceType::Queen],
+            Ending::Krk => &[PieceType::Rook],
+            Ending::Kpk => &[PieceType::Pawn],
+            Ending::Kbnk => &[PieceType::Bishop, PieceType::Knight],
+        }
+    }
+
+    /// Endings a pawn can promote into without throwing the win away
+    fn promotions(self) -> &'static [Ending] {
+        match self {
+            Ending::Kpk => &[Ending::Kqk, Ending::Krk],
+            _ => &[],
+        }
+    }
+
+    /// Squares the king of the side with the pieces is mirrored onto: a quarter of the board
+    /// without pawns, which can be mirrored either way, and half of it with them, which only
+    /// allow mirroring files
+    fn king_squares(self) -> usize {
+        match self {
+            Ending::Kpk => 32,
+            _ => 16,
+        }
+    }
+
+    /// Positions in the table, including the illegal ones its indexing can't avoid
+    fn len(self) -> usize {
+        self.king_squares() * 64usize.pow(self.pieces().len() as u32 + 1) * 2
+    }
+}
+
+/// Squares of the pieces of a position of an ending, as indices of Square64, with the side
+/// that has the pieces as white
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Setup {
+    /// King of the side with the pieces
+    strong_king: u8,
+    /// The lone king
+    weak_king: u8,
+    /// Squares of the pieces of Ending::pieces
+    pieces: [u8; 2],
+    strong_to_move: bool,
+}
+
+impl Setup {
+    fn occupancy(&self, ending: Ending) -> u64 {
+        let pieces = &self.pieces[..ending.pieces().len()];
+        pieces.iter().fold(
+            bit(self.strong_king) | bit(self.weak_king),
+            |occupancy, &square| occupancy | bit(square),
+        )
+    }
+
+    /// Same position with every square mirrored by flip, which is 7 to mirror files and 56 to
+    /// mirror ranks
+    fn mirrored(mut self, ending: Ending, flip: u8) -> Self {
+        self.strong_king ^= flip;
+        self.weak_king ^= flip;
+        for square in &mut self.pieces[..ending.pieces().len()] {
+            *square ^= flip;
+        }
+        self
+    }
+
+    /// Index of the position in the table of the ending, the same for all its mirror images
+    fn index(mut self, ending: Ending) -> usize {
+        if self.strong_king % 8 >= 4 {
+            self = self.mirrored(ending, 7);
+        }
+        if ending.king_squares() == 16 && self.strong_king / 8 >= 4 {
+            self = self.mirrored(ending, 56);
+        }
+        let king = self.strong_king / 8 * 4 + self.strong_king % 8;
+        let squares = self.pieces[..ending.pieces().len()].iter().fold(
+            king as usize * 64 + self.weak_king as usize,
+            |index, &square| index * 64 + square as usize,
+        );
+        squares * 2 + usize::from(!self.strong_to_move)
+    }
+
+    /// Position at the index of the table of the ending
+    fn from_index(ending: Ending, mut index: usize) -> Self {
+        let strong_to_move = index % 2 == 0;
+        index /= 2;
+        let mut pieces = [0; 2];
+        for square in pieces[..ending.pieces().len()].iter_mut().rev() {
+            *square = (index % 64) as u8;
+            index /= 64;
+        }
+        let weak_king = (index % 64) as u8;
+        let king = (index / 64) as u8;
+        Self {
+            strong_king: king / 4 * 8 + king % 4,
+            weak_king,
+            pieces,
+            strong_to_move,
+        }
+    }
+}
+
+fn bit(square: u8) -> u64 {
+    1 << square
+}
+
+/// Iterates over the squares of a set of them
+fn squares(mut set: u64) -> impl Iterator<Item = u8> {
+    core::iter::from_fn(move || {
+        let square = set.trailing_zeros();
+        set &= set.wrapping_sub(1);
+        (square < 64).then_some(square as u8)
+    })
+}
+
+fn attacks(piece_type: PieceType, square: u8, occupancy: u64) -> u64 {
+    let square = SQUARE_64_BY_INDEX[square as usize];
+    let occupancy = BitBoard(occupancy);
+    let attacks = match piece_type {
+        PieceType::Pawn => pawn_attacks(Color::White, square),
+        PieceType::Knight => knight_attacks(square),
+        PieceType::Bishop => bishop_attacks(square, occupancy),
+        PieceType::Rook => rook_attacks(square, occupancy),
+        PieceType::Queen => queen_attacks(square, occupancy),
+        PieceType::King => king_attacks(square),
+    };
+    attacks.0
+}
+
+/// Whether a piece on from attacks to, cheaper than its attacks when only one square matters
+fn attacks_square(piece_type: PieceType, from: u8, to: u8, occupancy: u64) -> bool {
+    let (files, ranks) = ((to % 8).abs_diff(from % 8), (to / 8).abs_diff(from / 8));
+    let aligned = match piece_type {
+        PieceType::Bishop => files == ranks,
+        PieceType::Rook => files == 0 || ranks == 0,
+        PieceType::Queen => files == ranks || files == 0 || ranks == 0,
+        piece_type => return attacks(piece_type, from, 0) & bit(to) != 0,
+    };
+    let squares = (
+        SQUARE_64_BY_INDEX[from as usize],
+        SQUARE_64_BY_INDEX[to as usize],
+    );
+    aligned && from != to && between(squares.0, squares.1).0 & occupancy == 0
+}
+
+/// Win/draw/loss of every position of an ending, two bits each
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WdlTable {
+    ending: Ending,
+    entries: Vec<u8>,
+}
+
+impl WdlTable {
+    fn get(&self, index: usize) -> Wdl {
+        match (self.entries[index / 4] >> (index % 4 * 2)) & 0b11 {
+            1 => Wdl::Win,
+            2 => Wdl::Loss,
+            _ => Wdl::Draw,
+        }
+    }
+
+    fn set(&mut self, index: usize, wdl: Wdl) {
+        let value = match wdl {
+            Wdl::Draw => 0,
+            Wdl::Win => 1,
+            Wdl::Loss => 2,
+        };
+        let shift = index % 4 * 2;
+        self.entries[index / 4] = (self.entries[index / 4] & !(0b11 << shift)) | (value << shift);
+    }
+
+    fn probe(&self, setup: Setup) -> Wdl {
+        self.get(setup.index(self.ending))
+    }
+
+    /// Squares the side with the pieces attacks, leaving out the piece at skip if it was just
+    /// taken
+    fn strong_attacks(&self, setup: &Setup, occupancy: u64, skip: Option<usize>) -> u64 {
+        let pieces = self.ending.pieces().iter().zip(setup.pieces).enumerate();
+        pieces.filter(|(index, _)| Some(*index) != skip).fold(
+            attacks(PieceType::King, setup.strong_king, occupancy),
+            |attacked, (_, (&piece_type, square))| {
+                attacked | attacks(piece_type, square, occupancy)
+            },
+        )
+    }
+
+    /// Whether the side with the pieces gives check
+    fn gives_check(&self, setup: &Setup, occupancy: u64) -> bool {
+        let pieces = self.ending.pieces().iter().zip(setup.pieces);
+        pieces.into_iter().any(|(&piece_type, square)| {
+            attacks_square(piece_type, square, setup.weak_king, occupancy)
+        })
+    }
+
+    fn is_legal(&self, setup: &Setup) -> bool {
+        let pieces = &setup.pieces[..self.ending.pieces().len()];
+        let occupancy = setup.occupancy(self.ending);
+        occupancy.count_ones() as usize == pieces.len() + 2
+            && attacks(PieceType::King, setup.strong_king, 0) & bit(setup.weak_king) == 0
+            && self
+                .ending
+                .pieces()
+                .iter()
+                .zip(pieces)
+                .all(|(&piece_type, square)| {
+                    piece_type != PieceType::Pawn || (8..56).contains(square)
+                })
+            && !(setup.strong_to_move && self.gives_check(setup, occupancy))
+    }
+
+    /// Legal moves of the lone king to move, and whether it's in check. Taking a piece counts
+    /// as a move that doesn't lose, as what's left can't mate in any of the endings.
+    fn weak_moves(&self, setup: &Setup) -> (u8, bool) {
+        let occupancy = setup.occupancy(self.ending);
+        let without_king = occupancy & !bit(setup.weak_king);
+        let attacked = self.strong_attacks(setup, without_king, None);
+        let targets = attacks(PieceType::King, setup.weak_king, 0)
+            & !attacks(PieceType::King, setup.strong_king, 0);
+        let pieces = &setup.pieces[..self.ending.pieces().len()];
+        let moves = squares(targets)
+            .filter(|&to| {
+                let attacked = match pieces.iter().position(|&square| square == to) {
+                    Some(taken) => self.strong_attacks(setup, without_king, Some(taken)),
+                    None => attacked,
+                };
+                attacked & bit(to) == 0
+            })
+            .count();
+        (moves as u8, self.gives_check(setup, occupancy))
+    }
+
+    /// What the side with the pieces to move gets from its moves with the table as far as it is
+    /// known, and the finished tables of the endings its pawn can promote into: a win if any
+    /// move leaves the lone king lost
+    fn strong_value(&self, setup: &Setup, promotions: &[&WdlTable]) -> Wdl {
+        let occupancy = setup.occupancy(self.ending);
+        let mut wins = false;
+        let king_moves = attacks(PieceType::King, setup.strong_king, 0)
+            & !occupancy
+            & !attacks(PieceType::King, setup.weak_king, 0);
+        for to in squares(king_moves) {
+            let next = Setup {
+                strong_king: to,
+                strong_to_move: false,
+                ..*setup
+            };
+            wins |= self.probe(next) == Wdl::Loss;
+        }
+        for (index, &piece_type) in self.ending.pieces().iter().enumerate() {
+            let from = setup.pieces[index];
+            let targets = match piece_type {
+                PieceType::Pawn => pawn_pushes(from, occupancy),
+                piece_type => attacks(piece_type, from, occupancy) & !occupancy,
+            };
+            for to in squares(targets) {
+                let mut next = Setup {
+                    strong_to_move: false,
+                    ..*setup
+                };
+                next.pieces[index] = to;
+                wins |= match to >= 56 {
+                    true => promotions
+                        .iter()
+                        .any(|table| table.probe(next) == Wdl::Loss),
+                    false => self.probe(next) == Wdl::Loss,
+                };
+            }
+        }
+        match wins {
+            true => Wdl::Win,
+            false => Wdl::Draw,
+        }
+    }
+
+    /// Positions with the side with the pieces to move that reach the one of setup, with the
+    /// lone king to move, by a move of the king or a piece which isn't a promotion
+    fn strong_predecessors(&self, setup: &Setup, predecessors: &mut Vec<Setup>) {
+        let occupancy = setup.occupancy(self.ending);
+        predecessors.clear();
+        let king_moves = attacks(PieceType::King, setup.strong_king, 0)
+            & !occupancy
+            & !attacks(PieceType::King, setup.weak_king, 0);
+        for from in squares(king_moves) {
+            predecessors.push(Setup {
+                strong_king: from,
+                strong_to_move: true,
+                ..*setup
+            });
+        }
+        for (index, &piece_type) in self.ending.pieces().iter().enumerate() {
+            let to = setup.pieces[index];
+            let origins = match piece_type {
+                PieceType::Pawn => pawn_origins(to, occupancy),
+                piece_type => attacks(piece_type, to, occupancy) & !occupancy,
+            };
+            for from in squares(origins) {
+                let mut predecessor = Setup {
+                    strong_to_move: true,
+                    ..*setup
+                };
+                predecessor.pieces[index] = from;
+                predecessors.push(predecessor);
+            }
+        }
+        predecessors.retain(|predecessor| self.is_legal(predecessor));
+    }
+
+    /// Positions with the lone king to move that reach the one of setup by a king move
+    fn weak_predecessors(&self, setup: &Setup) -> impl Iterator<Item = Setup> {
+        let occupancy = setup.occupancy(self.ending);
+        let origins = attacks(PieceType::King, setup.weak_king, 0)
+            & !occupancy
+            & !attacks(PieceType::King, setup.strong_king, 0);
+        let setup = *setup;
+        squares(origins).map(move |from| Setup {
+            weak_king: from,
+            strong_to_move: false,
+            ..setup
+        })
+    }
+
+    /// Retrograde analysis of the ending, given the finished tables of the endings its pawn
+    /// can promote into
+    fn generate(ending: Ending, promotions: &[&WdlTable]) -> Self {
+        let mut table = Self {
+            ending,
+            entries: vec![0; ending.len().div_ceil(4)],
+        };
+        // moves of each position with the lone king to move not known to lose yet, indexed by
+        // half its index
+        let mut escapes = vec![0u8; ending.len() / 2];
+        let mut queue = VecDeque::new();
+        let mut predecessors = Vec::new();
+        // the mates, and the wins by promoting from the seventh rank, which no move within the
+        // table leads back to. Everything else is walked back to from them.
+        for index in 0..ending.len() {
+            let setup = Setup::from_index(ending, index);
+            let promotes = !promotions.is_empty() && setup.pieces[0] >= 48;
+            if (setup.strong_to_move && !promotes) || !table.is_legal(&setup) {
+                continue;
+            }
+            let wdl = match setup.strong_to_move {
+                true => table.strong_value(&setup, promotions),
+                false => {
+                    let (moves, in_check) = table.weak_moves(&setup);
+                    escapes[index / 2] = moves;
+                    match moves == 0 && in_check {
+                        true => Wdl::Loss,
+                        false => Wdl::Draw,
+                    }
+                }
+            };
+            if wdl != Wdl::Draw && table.get(index) == Wdl::Draw {
+                table.set(index, wdl);
+                queue.push_back(index);
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            let setup = Setup::from_index(ending, index);
+            match setup.strong_to_move {
+                // every move into a lost position wins
+                false => {
+                    table.strong_predecessors(&setup, &mut predecessors);
+                    for &predecessor in &predecessors {
+                        let index = predecessor.index(ending);
+                        if table.get(index) == Wdl::Draw {
+                            table.set(index, Wdl::Win);
+                            queue.push_back(index);
+                        }
+                    }
+                }
+                // the lone king loses once the last of its moves turns out to lose. No square is
+                // its own mirror image, so no two moves of a position lead to mirror images of
+                // each other through the same move back, and each move is counted off once.
+                true => {
+                    for predecessor in table.weak_predecessors(&setup) {
+                        let index = predecessor.index(ending);
+                        if table.get(index) == Wdl::Draw {
+                            escapes[index / 2] -= 1;
+                            if escapes[index / 2] == 0 {
+                                table.set(index, Wdl::Loss);
+                                queue.push_back(index);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        table
+    }
+}
+
+/// Squares a white pawn can push to
+fn pawn_pushes(from: u8, occupancy: u64) -> u64 {
+    let single = bit(from + 8) & !occupancy;
+    match from < 16 && single != 0 {
+        true => single | (bit(from + 16) & !occupancy),
+        false => single,
+    }
+}
+
+/// Squares a white pawn can have been pushed to `to` from
+fn pawn_origins(to: u8, occupancy: u64) -> u64 {
+    if to < 16 {
+        return 0;
+    }
+    let single = bit(to - 8) & !occupancy;
+    match (24..32).contains(&to) && single != 0 {
+        true => single | (bit(to - 16) & !occupancy),
+        false => single & !0xFF,
+    }
+}
+
+/// Tables of some of the endings, generated when they're made. KQK and KRK are generated along
+/// with KPK whether they're asked for or not, as that's where its pawn promotes to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tablebases {
+    tables: Vec<WdlTable>,
+}
+
+impl Tablebases {
+    pub fn generate(endings: &[Ending]) -> Self {
+        let mut tablebases = Self::default();
+        for ending in Ending::iter() {
+            let needed = endings
+                .iter()
+                .any(|&asked| asked == ending || asked.promotions().contains(&ending));
+            if !needed {
+                continue;
+            }
+            let promotions: Vec<&WdlTable> = ending
+                .promotions()
+                .iter()
+                .filter_map(|&promotion| tablebases.table(promotion))
+                .collect();
+            let table = WdlTable::generate(ending, &promotions);
+            tablebases.tables.push(table);
+        }
+        tablebases
+    }
+
+    /// Tables of every ending
+    pub fn all() -> Self {
+        Self::generate(&Ending::iter().collect::<Vec<_>>())
+    }
+
+    fn table(&self, ending: Ending) -> Option<&WdlTable> {
+        self.tables.iter().find(|table| table.ending == ending)
+    }
+
+    /// Endings there are tables of
+    pub fn endings(&self) -> impl Iterator<Item = Ending> + '_ {
+        self.tables.iter().map(|table| table.ending)
+    }
+
+    /// Bytes the tables take
+    pub fn size(&self) -> usize {
+        self.tables.iter().map(|table| table.entries.len()).sum()
+    }
+
+    /// Result of the position for its side to move, None if it isn't one of the endings of the
+    /// tables or either side can still castle
+    pub fn probe(&self, position: &Position) -> Option<Wdl> {
+        if position.occupancy().0.count_ones() > 4 || !position.castling_rights().is_empty() {
+            return None;
+        }
+        let strong = match position.color_occupancy(Color::White).0.count_ones() {
+            1 => Color::Black,
+            _ => Color::White,
+        };
+        let weak = !strong;
+        if position.color_occupancy(weak).0.count_ones() != 1 {
+            return None;
+        }
+        // the side with the pieces plays up the board in the tables
+        let flip = match strong {
+            Color::White => 0,
+            Color::Black => 56,
+        };
+        let square = |piece_type: PieceType| {
+            let mut pieces = position.pieces(Piece::new(strong, piece_type));
+            pieces.pop_bit().map(|square| square as u8 ^ flip)
+        };
+        let table = self.tables.iter().find(|table| {
+            let pieces = table.ending.pieces();
+            pieces.len() + 2 == position.occupancy().0.count_ones() as usize
+                && pieces
+                    .iter()
+                    .all(|&piece_type| square(piece_type).is_some())
+        })?;
+        let mut pieces = [0; 2];
+        for (square_of, &piece_type) in pieces.iter_mut().zip(table.ending.pieces()) {
+            *square_of = square(piece_type)?;
+        }
+        let setup = Setup {
+            strong_king: square(PieceType::King)?,
+            weak_king: position
+                .pieces(Piece::new(weak, PieceType::King))
+                .pop_bit()? as u8
+                ^ flip,
+            pieces,
+            strong_to_move: position.side_to_move() == strong,
+        };
+        Some(table.probe(setup))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Searcher, MATE};
+
+    #[test]
+    fn test_setup_index() {
+        for ending in Ending::iter().filter(|&ending| ending != Ending::Kbnk) {
+            for index in 0..ending.len() {
+                let setup = Setup::from_index(ending, index);
+                assert_eq!(setup.index(ending), index, "{} {:?}", ending, setup);
+                for flip in [7, 56, 63] {
+                    let mirrored = setup.mirrored(ending, flip);
+                    match ending {
+                        Ending::Kpk if flip != 7 => continue,
+                        _ => assert_eq!(mirrored.index(ending), index, "{} {:?}", ending, setup),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pawn_moves() {
+        // e2 pushes to e3 and e4, e4 came from e3 or e2, e3 only from e2
+        assert_eq!(pawn_pushes(12, 0), bit(20) | bit(28));
+        assert_eq!(pawn_pushes(12, bit(28)), bit(20));
+        assert_eq!(pawn_pushes(12, bit(20)), 0);
+        assert_eq!(pawn_origins(28, 0), bit(20) | bit(12));
+        assert_eq!(pawn_origins(28, bit(20)), 0);
+        assert_eq!(pawn_origins(20, 0), bit(12));
+        assert_eq!(pawn_origins(12, 0), 0);
+    }
+
+    #[test]
+    fn test_probe() {
+        let tablebases = Tablebases::generate(&[Ending::Kqk, Ending::Krk, Ending::Kpk]);
+        assert_eq!(
+            tablebases.endings().collect::<Vec<_>>(),
+            [Ending::Kqk, Ending::Krk, Ending::Kpk]
+        );
+        assert_eq!(tablebases.size(), (131_072 * 2 + 262_144) / 4);
+        let inputs = [
+            ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", Some(Wdl::Win)),
+            ("4k3/8/8/8/8/8/8/3QK3 b - - 0 1", Some(Wdl::Loss)),
+            // the lone king can only take the queen if it isn't defended
+            ("8/8/8/8/2K5/kQ6/8/8 b - - 0 1", Some(Wdl::Loss)),
+            ("8/8/8/8/8/kQ6/8/7K b - - 0 1", Some(Wdl::Draw)),
+            // stalemate and mate
+            ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Some(Wdl::Draw)),
+            ("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", Some(Wdl::Loss)),
+            ("8/8/8/8/4k3/8/8/R3K3 w - - 0 1", Some(Wdl::Win)),
+            ("8/8/8/8/4k3/8/8/R3K3 w Q - 0 1", None),
+            ("8/8/8/8/3qk3/8/8/4K3 b - - 0 1", Some(Wdl::Win)),
+            ("8/8/8/8/3rk3/8/8/4K3 w - - 0 1", Some(Wdl::Loss)),
+            // with the opposition the pawn queens, without it the king holds
+            ("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1", Some(Wdl::Loss)),
+            ("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1", Some(Wdl::Draw)),
+            // the king takes the pawn, or is stalemated in front of it
+            ("4k3/8/8/8/8/8/4p3/4K3 w - - 0 1", Some(Wdl::Draw)),
+            ("8/8/8/8/8/4k3/4p3/4K3 w - - 0 1", Some(Wdl::Draw)),
+            // the king is outside of the square of the pawn, and can't stop a rook pawn in time
+            ("7k/8/8/8/8/8/P7/K7 w - - 0 1", Some(Wdl::Win)),
+            ("k7/8/8/8/8/8/P7/K7 w - - 0 1", Some(Wdl::Draw)),
+            ("4k3/8/8/8/8/8/8/2BNK3 w - - 0 1", None),
+            ("4k3/8/8/8/8/8/8/2QQK3 w - - 0 1", None),
+            ("4k3/8/8/8/8/8/4p3/3QK3 w - - 0 1", None),
+        ];
+        for (fen, expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            assert_eq!(tablebases.probe(&position), expected, "{}", fen);
+            assert_eq!(
+                tablebases.probe(&position.mirrored()),
+                expected,
+                "mirrored {}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_probe_matches_search() {
+        // positions a few plies from mate or from the lone king taking the pawn, which the search
+        // sees to the end of
+        let tablebases = Tablebases::generate(&[Ending::Kpk]);
+        let inputs = [
+            "7k/8/6K1/8/8/8/8/1R6 w - - 0 1",
+            "7k/5Q2/6K1/8/8/8/8/8 w - - 0 1",
+            "7k/8/5QK1/8/8/8/8/8 b - - 0 1",
+            "k7/8/1K6/8/8/8/8/7R b - - 0 1",
+            "8/8/8/8/8/8/1kP5/7K b - - 0 1",
+        ];
+        for fen in inputs {
+            let mut position = Position::from_fen(fen).unwrap();
+            let output = Searcher::new().search(&mut position, 5);
+            let expected = match output.score {
+                score if score > MATE / 2 => Wdl::Win,
+                score if score < -MATE / 2 => Wdl::Loss,
+                _ => Wdl::Draw,
+            };
+            assert_eq!(tablebases.probe(&position), Some(expected), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_kbnk() {
+        let tablebases = Tablebases::generate(&[Ending::Kbnk]);
+        assert_eq!(tablebases.size(), 8_388_608 / 4);
+        let inputs = [
+            ("8/8/3k4/8/8/8/8/4KBN1 w - - 0 1", Wdl::Win),
+            ("8/8/3k4/8/8/8/8/4KBN1 b - - 0 1", Wdl::Loss),
+            // the king takes the knight, or is stalemated in the corner
+            ("8/8/8/8/8/8/6k1/4KBN1 b - - 0 1", Wdl::Draw),
+            ("k7/8/1KN5/8/8/8/7B/8 b - - 0 1", Wdl::Draw),
+            // mated in the corner of the color of the bishop
+            ("7k/8/5BKN/8/8/8/8/8 b - - 0 1", Wdl::Loss),
+        ];
+        for (fen, expected) in inputs {
+            let position = Position::from_fen(fen).unwrap();
+            assert_eq!(tablebases.probe(&position), Some(expected), "{}", fen);
+        }
+    }
+}
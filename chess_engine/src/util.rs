@@ -4,6 +4,7 @@ use crate::{
     pieces::Piece,
     squares::{Square, Square64},
 };
+use core::ops::Not;
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter, EnumString};
 
@@ -30,6 +31,20 @@ impl TryFrom<usize> for File {
     }
 }
 
+impl File {
+    /// File one step towards the H file, or None on the H file
+    pub fn east(self) -> Option<File> {
+        File::try_from(self as usize + 1).ok()
+    }
+
+    /// File one step towards the A file, or None on the A file
+    pub fn west(self) -> Option<File> {
+        (self as usize)
+            .checked_sub(1)
+            .and_then(|file| File::try_from(file).ok())
+    }
+}
+
 impl From<File> for char {
     fn from(value: File) -> Self {
         match value {
@@ -66,6 +81,33 @@ impl TryFrom<usize> for Rank {
     }
 }
 
+impl Rank {
+    /// Rank one step forward from the point of view of the given color, or None on the
+    /// opponent's back rank
+    pub fn up(self, color: Color) -> Option<Rank> {
+        match color {
+            Color::White => Rank::try_from(self as usize + 1).ok(),
+            Color::Black => (self as usize)
+                .checked_sub(1)
+                .and_then(|rank| Rank::try_from(rank).ok()),
+        }
+    }
+
+    /// Rank as seen by the given color, so that e.g. Rank2 is the pawn starting rank for both sides
+    pub fn relative(self, color: Color) -> Rank {
+        match color {
+            Color::White => self,
+            Color::Black => Rank::try_from(Rank::COUNT - 1 - self as usize)
+                .expect("rank should be in range 0..=7"),
+        }
+    }
+
+    /// Iterates over the ranks starting from the given color's back rank
+    pub fn iter_from(color: Color) -> impl DoubleEndedIterator<Item = Rank> {
+        Rank::iter().map(move |rank| rank.relative(color))
+    }
+}
+
 // impl Add<usize> for Rank {
 //     type Output = Result<Self, ConversionError>;
 //     fn add(self, rhs: usize) -> Self::Output {
@@ -80,6 +122,17 @@ pub enum Color {
     Black,
 }
 
+impl Not for Color {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 impl From<Color> for char {
     fn from(value: Color) -> Self {
         match value {
@@ -90,31 +143,27 @@ impl From<Color> for char {
 }
 
 #[rustfmt::skip]
-pub const SQUARE_120_TO_64: [Option<Square64>; NUM_BOARD_SQUARES] = [
-    None, None,                None,                None,                None,                None,                None,                None,                None,               None,
-    None, None,                None,                None,                None,                None,                None,                None,                None,               None,
-    None, Some(Square64::A1),  Some(Square64::B1),  Some(Square64::C1),  Some(Square64::D1),  Some(Square64::E1),  Some(Square64::F1),  Some(Square64::G1),  Some(Square64::H1), None,
-    None, Some(Square64::A2),  Some(Square64::B2),  Some(Square64::C2),  Some(Square64::D2),  Some(Square64::E2),  Some(Square64::F2),  Some(Square64::G2),  Some(Square64::H2), None,
-    None, Some(Square64::A3),  Some(Square64::B3),  Some(Square64::C3),  Some(Square64::D3),  Some(Square64::E3),  Some(Square64::F3),  Some(Square64::G3),  Some(Square64::H3), None,
-    None, Some(Square64::A4),  Some(Square64::B4),  Some(Square64::C4),  Some(Square64::D4),  Some(Square64::E4),  Some(Square64::F4),  Some(Square64::G4),  Some(Square64::H4), None,
-    None, Some(Square64::A5),  Some(Square64::B5),  Some(Square64::C5),  Some(Square64::D5),  Some(Square64::E5),  Some(Square64::F5),  Some(Square64::G5),  Some(Square64::H5), None,
-    None, Some(Square64::A6),  Some(Square64::B6),  Some(Square64::C6),  Some(Square64::D6),  Some(Square64::E6),  Some(Square64::F6),  Some(Square64::G6),  Some(Square64::H6), None,
-    None, Some(Square64::A7),  Some(Square64::B7),  Some(Square64::C7),  Some(Square64::D7),  Some(Square64::E7),  Some(Square64::F7),  Some(Square64::G7),  Some(Square64::H7), None,
-    None, Some(Square64::A8),  Some(Square64::B8),  Some(Square64::C8),  Some(Square64::D8),  Some(Square64::E8),  Some(Square64::F8),  Some(Square64::G8),  Some(Square64::H8), None,
-    None, None,                None,                None,                None,                None,                None,                None,                None,               None,
-    None, None,                None,                None,                None,                None,                None,                None,                None,               None,
+pub const SQUARE_64_TO_120: [Square; 64] = [
+    Square::A1, Square::B1, Square::C1, Square::D1, Square::E1, Square::F1, Square::G1, Square::H1,
+    Square::A2, Square::B2, Square::C2, Square::D2, Square::E2, Square::F2, Square::G2, Square::H2,
+    Square::A3, Square::B3, Square::C3, Square::D3, Square::E3, Square::F3, Square::G3, Square::H3,
+    Square::A4, Square::B4, Square::C4, Square::D4, Square::E4, Square::F4, Square::G4, Square::H4,
+    Square::A5, Square::B5, Square::C5, Square::D5, Square::E5, Square::F5, Square::G5, Square::H5,
+    Square::A6, Square::B6, Square::C6, Square::D6, Square::E6, Square::F6, Square::G6, Square::H6,
+    Square::A7, Square::B7, Square::C7, Square::D7, Square::E7, Square::F7, Square::G7, Square::H7,
+    Square::A8, Square::B8, Square::C8, Square::D8, Square::E8, Square::F8, Square::G8, Square::H8
 ];
 
 #[rustfmt::skip]
-pub const SQUARE_64_TO_120: [Option<Square>; 64] = [
-    Some(Square::A1), Some(Square::B1), Some(Square::C1), Some(Square::D1), Some(Square::E1), Some(Square::F1), Some(Square::G1), Some(Square::H1),
-    Some(Square::A2), Some(Square::B2), Some(Square::C2), Some(Square::D2), Some(Square::E2), Some(Square::F2), Some(Square::G2), Some(Square::H2),
-    Some(Square::A3), Some(Square::B3), Some(Square::C3), Some(Square::D3), Some(Square::E3), Some(Square::F3), Some(Square::G3), Some(Square::H3),
-    Some(Square::A4), Some(Square::B4), Some(Square::C4), Some(Square::D4), Some(Square::E4), Some(Square::F4), Some(Square::G4), Some(Square::H4),
-    Some(Square::A5), Some(Square::B5), Some(Square::C5), Some(Square::D5), Some(Square::E5), Some(Square::F5), Some(Square::G5), Some(Square::H5),
-    Some(Square::A6), Some(Square::B6), Some(Square::C6), Some(Square::D6), Some(Square::E6), Some(Square::F6), Some(Square::G6), Some(Square::H6),
-    Some(Square::A7), Some(Square::B7), Some(Square::C7), Some(Square::D7), Some(Square::E7), Some(Square::F7), Some(Square::G7), Some(Square::H7),
-    Some(Square::A8), Some(Square::B8), Some(Square::C8), Some(Square::D8), Some(Square::E8), Some(Square::F8), Some(Square::G8), Some(Square::H8)
+pub const SQUARE_64_BY_INDEX: [Square64; 64] = [
+    Square64::A1, Square64::B1, Square64::C1, Square64::D1, Square64::E1, Square64::F1, Square64::G1, Square64::H1,
+    Square64::A2, Square64::B2, Square64::C2, Square64::D2, Square64::E2, Square64::F2, Square64::G2, Square64::H2,
+    Square64::A3, Square64::B3, Square64::C3, Square64::D3, Square64::E3, Square64::F3, Square64::G3, Square64::H3,
+    Square64::A4, Square64::B4, Square64::C4, Square64::D4, Square64::E4, Square64::F4, Square64::G4, Square64::H4,
+    Square64::A5, Square64::B5, Square64::C5, Square64::D5, Square64::E5, Square64::F5, Square64::G5, Square64::H5,
+    Square64::A6, Square64::B6, Square64::C6, Square64::D6, Square64::E6, Square64::F6, Square64::G6, Square64::H6,
+    Square64::A7, Square64::B7, Square64::C7, Square64::D7, Square64::E7, Square64::F7, Square64::G7, Square64::H7,
+    Square64::A8, Square64::B8, Square64::C8, Square64::D8, Square64::E8, Square64::F8, Square64::G8, Square64::H8
 ];
 
 #[rustfmt::skip]
@@ -148,3 +197,59 @@ pub const RANKS_BOARD: [Option<Rank>; NUM_BOARD_SQUARES] = [
     None,  None,               None,               None,               None,               None,               None,               None,               None,              None,
     None,  None,               None,               None,               None,               None,               None,               None,               None,              None,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_east() {
+        let output: Vec<Option<File>> = File::iter().map(File::east).collect();
+        let mut expected: Vec<Option<File>> = File::iter().skip(1).map(Some).collect();
+        expected.push(None);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_file_west() {
+        let output: Vec<Option<File>> = File::iter().map(File::west).collect();
+        let mut expected: Vec<Option<File>> = vec![None];
+        expected.extend(File::iter().take(File::COUNT - 1).map(Some));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rank_up() {
+        let input = [
+            (Rank::Rank2, Color::White),
+            (Rank::Rank8, Color::White),
+            (Rank::Rank7, Color::Black),
+            (Rank::Rank1, Color::Black),
+        ];
+        let output: Vec<Option<Rank>> = input.iter().map(|&(r, c)| r.up(c)).collect();
+        let expected = vec![Some(Rank::Rank3), None, Some(Rank::Rank6), None];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rank_relative() {
+        let input = [
+            (Rank::Rank2, Color::White),
+            (Rank::Rank7, Color::Black),
+            (Rank::Rank1, Color::Black),
+        ];
+        let output: Vec<Rank> = input.iter().map(|&(r, c)| r.relative(c)).collect();
+        let expected = vec![Rank::Rank2, Rank::Rank2, Rank::Rank8];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rank_iter_from() {
+        let output: Vec<Rank> = Rank::iter_from(Color::Black).collect();
+        let expected: Vec<Rank> = Rank::iter().rev().collect();
+        assert_eq!(output, expected);
+        let output: Vec<Rank> = Rank::iter_from(Color::White).collect();
+        let expected: Vec<Rank> = Rank::iter().collect();
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,167 @@
+//! Zobrist keys for hashing a Position into a u64.
+//!
+//! Every feature of a position (a piece on a square, each castling right, the en passant file and
+//! black to move) gets its own pseudo random key, and a position's hash is the xor of the keys of
+//! every feature it has. Since xor is its own inverse, making a move only has to xor out the
+//! features it removes and xor in the ones it adds.
+
+use crate::{castle_perms::CastlingRights, pieces::Piece, squares::Square64};
+use strum::EnumCount;
+
+/// Seed for the key generator, the same one Gamestate uses for its own Zobrist keys
+// NOTE: the seed data comes from this article: https://www.pcg-random.org/posts/simple-portable-cpp-seed-entropy.html
+const ZOBRIST_SEED: [u8; 32] = [
+    0x67, 0x0e, 0x5a, 0x45, 0x9a, 0xc9, 0xea, 0x9c, 0x88, 0x85, 0x36, 0x20, 0xc4, 0xc8, 0x36, 0xf9,
+    0x07, 0xab, 0x56, 0x40, 0xb2, 0x0b, 0x31, 0x3e, 0x7b, 0x94, 0x50, 0x51, 0x37, 0xf5, 0x0e, 0x84,
+];
+const PCG_MULTIPLIER: u128 = 0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645;
+const NUM_CASTLING_KEYS: usize = 4;
+const NUM_EN_PASSANT_KEYS: usize = 8;
+
+struct ZobristKeys {
+    pieces: [[u64; Square64::COUNT]; Piece::COUNT],
+    castling: [u64; NUM_CASTLING_KEYS],
+    en_passant: [u64; NUM_EN_PASSANT_KEYS],
+    black_to_move: u64,
+}
+
+/// Keys are generated at compile time, so hashing needs no setup and works without std
+static KEYS: ZobristKeys = ZobristKeys::generate();
+
+/// Const version of rand_pcg's Lcg128Xsl64, so the keys can be built at compile time
+struct Pcg {
+    state: u128,
+    increment: u128,
+}
+
+impl Pcg {
+    const fn from_seed(seed: [u8; 32]) -> Self {
+        let mut state: u128 = 0;
+        let mut increment: u128 = 0;
+        let mut i = 0;
+        while i < 16 {
+            state |= (seed[i] as u128) << (8 * i);
+            increment |= (seed[i + 16] as u128) << (8 * i);
+            i += 1;
+        }
+        let mut pcg = Self {
+            state,
+            increment: increment | 1,
+        };
+        pcg.state = pcg.state.wrapping_add(pcg.increment);
+        pcg.step();
+        pcg
+    }
+
+    const fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    const fn next_u64(&mut self) -> u64 {
+        self.step();
+        let rotation = (self.state >> 122) as u32;
+        let xsl = ((self.state >> 64) as u64) ^ (self.state as u64);
+        xsl.rotate_right(rotation)
+    }
+}
+
+impl ZobristKeys {
+    const fn generate() -> Self {
+        let mut rng = Pcg::from_seed(ZOBRIST_SEED);
+        let mut pieces = [[0u64; Square64::COUNT]; Piece::COUNT];
+        let mut piece = 0;
+        while piece < Piece::COUNT {
+            let mut square = 0;
+            while square < Square64::COUNT {
+                pieces[piece][square] = rng.next_u64();
+                square += 1;
+            }
+            piece += 1;
+        }
+        let mut castling = [0u64; NUM_CASTLING_KEYS];
+        let mut i = 0;
+        while i < NUM_CASTLING_KEYS {
+            castling[i] = rng.next_u64();
+            i += 1;
+        }
+        let mut en_passant = [0u64; NUM_EN_PASSANT_KEYS];
+        let mut i = 0;
+        while i < NUM_EN_PASSANT_KEYS {
+            en_passant[i] = rng.next_u64();
+            i += 1;
+        }
+        Self {
+            pieces,
+            castling,
+            en_passant,
+            black_to_move: rng.next_u64(),
+        }
+    }
+}
+
+/// Key for the given piece standing on the given square
+pub fn piece_key(piece: Piece, square: Square64) -> u64 {
+    KEYS.pieces[piece as usize][square as usize]
+}
+
+/// Xor of the keys of every right in castling_rights, so having no rights hashes to 0
+pub fn castling_key(castling_rights: CastlingRights) -> u64 {
+    let mut key = 0;
+    for (i, castling_key) in KEYS.castling.iter().enumerate() {
+        if castling_rights.bits() & (1 << i) != 0 {
+            key ^= castling_key;
+        }
+    }
+    key
+}
+
+/// Key for an en passant square. Only the file is hashed, since the rank follows from the side
+/// to move
+pub fn en_passant_key(square: Square64) -> u64 {
+    KEYS.en_passant[square as usize % 8]
+}
+
+/// Key xored in when black is to move
+pub fn side_key() -> u64 {
+    KEYS.black_to_move
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_distinct() {
+        let mut keys: alloc::vec::Vec<u64> = KEYS.pieces.iter().flatten().copied().collect();
+        keys.extend(KEYS.castling);
+        keys.extend(KEYS.en_passant);
+        keys.push(KEYS.black_to_move);
+        let output = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        let expected = keys.len();
+        assert_eq!(output, expected);
+        assert!(!keys.contains(&0));
+    }
+
+    #[test]
+    fn test_castling_key_combines_rights() {
+        let input = CastlingRights::WHITE_KING | CastlingRights::BLACK_QUEEN;
+        let output = castling_key(input);
+        let expected =
+            castling_key(CastlingRights::WHITE_KING) ^ castling_key(CastlingRights::BLACK_QUEEN);
+        assert_eq!(output, expected);
+        assert_eq!(castling_key(CastlingRights::empty()), 0);
+    }
+
+    #[test]
+    fn test_en_passant_key_only_depends_on_file() {
+        let output = en_passant_key(Square64::E3);
+        let expected = en_passant_key(Square64::E6);
+        assert_eq!(output, expected);
+        assert_ne!(en_passant_key(Square64::D6), expected);
+    }
+}
@@ -0,0 +1,96 @@
+//! Minimal spectator server: plays a scripted game and broadcasts every GameEvent to all
+//! connected websocket clients as nanoserde binary messages.
+
+use std::time::{Duration, Instant};
+
+use futures_util::SinkExt;
+use log::{debug, info};
+use nanoserde::SerBin;
+use tokio::{
+    net::TcpListener,
+    sync::broadcast::{self, error::RecvError, Sender},
+    time::sleep,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use chess_client::{
+    game::{Clock, Game, GameEvent},
+    types::{Move, PlayerColor, Square},
+};
+
+const URL: &str = "127.0.0.1:8092";
+const MOVE_DELAY: Duration = Duration::from_secs(2);
+
+// Fool's mate, in the client's coordinates where rank 0 is black's back rank
+const SCRIPT: [((u32, u32), (u32, u32)); 4] = [
+    ((6, 5), (5, 5)),
+    ((1, 4), (3, 4)),
+    ((6, 6), (4, 6)),
+    ((0, 3), (4, 7)),
+];
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::init();
+    let (events_tx, _) = broadcast::channel::<GameEvent>(64);
+
+    tokio::spawn(play_scripted_games(events_tx.clone()));
+
+    let listener = TcpListener::bind(URL).await?;
+    info!("Broadcasting live game on {}", URL);
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        debug!("spectator connected from {:#?}", addr);
+        let mut events_rx = events_tx.subscribe();
+        tokio::spawn(async move {
+            let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            loop {
+                match events_rx.recv().await {
+                    Ok(event) => {
+                        if socket.send(Message::Binary(event.serialize_bin())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+async fn play_scripted_games(events_tx: Sender<GameEvent>) {
+    let epoch = Instant::now();
+    let now_ms = || epoch.elapsed().as_millis() as u64;
+    loop {
+        let mut game = Game::new(Clock::new(60_000, 1_000), now_ms());
+        let tx = events_tx.clone();
+        game.add_observer(move |event: &GameEvent| {
+            // Sending only fails when nobody is watching
+            let _ = tx.send(event.clone());
+        });
+
+        let mut player = PlayerColor::White;
+        for (from, to) in SCRIPT {
+            sleep(MOVE_DELAY).await;
+            let move_ = Move {
+                from: Square {
+                    rank: from.0,
+                    file: from.1,
+                },
+                to: Square {
+                    rank: to.0,
+                    file: to.1,
+                },
+                promotion: None,
+            };
+            game.play_move(player, move_, now_ms()).unwrap();
+            player = !player;
+        }
+        sleep(MOVE_DELAY).await;
+        game.resign(player, now_ms()).unwrap();
+        sleep(MOVE_DELAY).await;
+    }
+}